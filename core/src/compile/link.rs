@@ -9,6 +9,8 @@ use crate::graph::prelude::Rng;
 pub struct Slice {
     generation: usize,
     start: usize,
+    /// Whether the slice keeps everything recorded since it was created when reset
+    persistent: bool,
 }
 
 pub struct Tape<Y, R> {
@@ -34,10 +36,21 @@ impl<Y, R> Tape<Y, R> {
 
     fn reset(&mut self, idx: usize) {
         let slice = self.slices.get_mut(idx).unwrap();
+        if slice.persistent {
+            return;
+        }
         slice.generation += 1;
         slice.start = self.buffer.len();
     }
 
+    /// Forgets everything recorded after the first `len` states.
+    fn truncate(&mut self, len: usize) {
+        self.buffer.truncate(len);
+        self.slices
+            .iter_mut()
+            .for_each(|slice| slice.start = slice.start.min(len));
+    }
+
     fn get_generation(&self, idx: usize) -> usize {
         self.slices.get(idx).unwrap().generation
     }
@@ -49,6 +62,8 @@ impl<Y, R> Tape<Y, R> {
 
 pub type SharedTape<Y, R> = Rc<RefCell<Tape<Y, R>>>;
 
+pub(super) type GeneratorTape<G: Generator> = SharedTape<G::Yield, G::Return>;
+
 pub(super) struct SliceRef<Y, R> {
     index: usize,
     tape: SharedTape<Y, R>,
@@ -70,6 +85,12 @@ impl<Y, R> SliceRef<Y, R> {
         (*self.tape).borrow_mut().reset(self.index)
     }
 
+    /// Makes the views of this slice read everything ever recorded, instead of only what was
+    /// recorded since the last reset.
+    pub(super) fn persist(&self) {
+        (*self.tape).borrow_mut().slices[self.index].persistent = true
+    }
+
     pub(super) fn new_view(&self) -> TapeView<Y, R> {
         TapeView(TapeViewImpl {
             slice: self.clone(),
@@ -99,6 +120,7 @@ impl<G, Y, R> RecorderImpl<G, Y, R> {
             let slice = Slice {
                 generation: 0,
                 start: tape.buffer.len(),
+                persistent: false,
             };
             tape.slices.push(slice);
             tape.slices.len() - 1
@@ -154,6 +176,10 @@ impl<G, Y, R> Recorder<G, Y, R> {
         self.0.new_slice()
     }
 
+    pub(super) fn tape(&self) -> SharedTape<Y, R> {
+        self.0.tape.clone()
+    }
+
     pub(super) fn wrap(inner: G) -> Self {
         Self(RecorderImpl {
             inner,
@@ -216,6 +242,34 @@ where
     }
 }
 
+/// The buffers of a set of [`Recorder`](Recorder)s, which can be rewound to forget what was
+/// recorded since a [`mark`](Rewind::mark).
+pub struct Rewind<Y, R>(Vec<SharedTape<Y, R>>);
+
+pub type GeneratorRewind<G: Generator> = Rewind<G::Yield, G::Return>;
+
+impl<Y, R> Rewind<Y, R> {
+    pub(super) fn new(tapes: Vec<SharedTape<Y, R>>) -> Self {
+        Self(tapes)
+    }
+
+    /// The current length of every buffer.
+    pub fn mark(&self) -> Vec<usize> {
+        self.0
+            .iter()
+            .map(|tape| tape.borrow().buffer.len())
+            .collect()
+    }
+
+    /// Forgets everything recorded since `mark` was taken.
+    pub fn rewind(&self, mark: &[usize]) {
+        self.0
+            .iter()
+            .zip(mark)
+            .for_each(|(tape, len)| tape.borrow_mut().truncate(*len));
+    }
+}
+
 /// A generator that reads its output from a slice into the buffer managed by a
 /// [`Recorder`](Recorder).
 pub struct TapeView<Y, R>(pub(super) TapeViewImpl<Y, R>);
@@ -20,7 +20,9 @@
 //! to compile both [`Namespace`](crate::schema::Namespace) and [`Content`](crate::schema::Content)
 //! into [`Graph`](crate::graph::Graph).
 
+use std::cell::RefCell;
 use std::collections::{BTreeMap, BTreeSet};
+use std::rc::Rc;
 
 use anyhow::{Context, Result};
 
@@ -33,10 +35,10 @@ pub use address::Address;
 
 pub mod link;
 pub use link::{FromLink, Link};
-use link::{GeneratorRecorder, GeneratorSliceRef, Ordered, Recorder};
+use link::{GeneratorRecorder, GeneratorSliceRef, GeneratorTape, Ordered, Recorder, Rewind};
 
-use crate::graph::Graph;
-use crate::schema::{Content, Namespace};
+use crate::graph::{Graph, IterNode, UniqueNode, Value};
+use crate::schema::{Content, Namespace, SameAsContent};
 
 /// A trait for visitors of the [`Content`](crate::schema::Content) tree.
 ///
@@ -49,21 +51,94 @@ pub trait Compiler<'a> {
 
     /// Access the built value of the node at address `field`.
     fn get<S: Into<Address>>(&mut self, field: S) -> Result<Graph>;
+
+    /// The [`Shard`](Shard) the graph being built will generate for.
+    fn shard(&self) -> Shard {
+        Shard::default()
+    }
+}
+
+/// One of `count` graphs compiled from the same [`Namespace`](crate::schema::Namespace) to
+/// generate data side by side.
+///
+/// Nodes which must not repeat values (such as `id` numbers and `unique` content) use it to
+/// generate values disjoint from those of every other shard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+}
+
+impl Shard {
+    pub fn new(index: usize, count: usize) -> Result<Self> {
+        if index >= count {
+            return Err(anyhow!(
+                "shard index {} is out of range for {} shards",
+                index,
+                count
+            ));
+        }
+        Ok(Self { index, count })
+    }
+
+    pub fn is_single(&self) -> bool {
+        self.count == 1
+    }
+}
+
+impl Default for Shard {
+    fn default() -> Self {
+        Self { index: 0, count: 1 }
+    }
 }
 
 pub trait Compile {
     fn compile<'a, C: Compiler<'a>>(&'a self, compiler: C) -> Result<Graph>;
 }
 
+/// The address of the array of which the field at `address` gives the `cardinality`, if any.
+fn cardinality_array(state: &CompilerState<Graph>, address: &Address) -> Option<Address> {
+    let mut array = address.clone();
+    let field = array.shallower()?;
+    if array.shallower()? != "content" {
+        return None;
+    }
+    match state.project(array.clone()).ok()?.source() {
+        Source::Content(Content::Array(content))
+            if content.cardinality().map(|(name, ..)| name) == Some(field.as_str()) =>
+        {
+            Some(array)
+        }
+        _ => None,
+    }
+}
+
 pub struct NamespaceCompiler<'a> {
     state: CompilerState<'a, Graph>,
     vtable: Symbols,
+    shard: Shard,
+    overrides: BTreeMap<Address, Graph>,
+    /// The parent value of the elements being generated by each array with a `cardinality`,
+    /// which the field with the `cardinality` reads.
+    parents: BTreeMap<Address, Rc<RefCell<Value>>>,
+    /// The buffers of the nodes built so far whose values are referenced
+    tapes: BTreeMap<Address, GeneratorTape<Graph>>,
+    /// The collections whose values referenced by other collections are kept across completions
+    persistent: BTreeSet<String>,
 }
 
 impl<'a> NamespaceCompiler<'a> {
     fn new_at(state: CompilerState<'a, Graph>) -> Self {
         let vtable = Symbols::new();
-        Self { state, vtable }
+        Self {
+            state,
+            vtable,
+            shard: Shard::default(),
+            overrides: BTreeMap::new(),
+            parents: BTreeMap::new(),
+            tapes: BTreeMap::new(),
+            persistent: BTreeSet::new(),
+        }
     }
 
     pub fn new(namespace: &'a Namespace) -> Self {
@@ -76,14 +151,57 @@ impl<'a> NamespaceCompiler<'a> {
         Self::new_at(state)
     }
 
-    pub fn compile(mut self) -> Result<Graph> {
+    pub fn with_shard(mut self, shard: Shard) -> Self {
+        self.shard = shard;
+        self
+    }
+
+    /// Use `graph` in place of whatever the content at `address` compiles to.
+    pub fn with_override(mut self, address: Address, graph: Graph) -> Self {
+        self.overrides.insert(address, graph);
+        self
+    }
+
+    /// Let other collections refer to every value of `collection` generated so far, instead of
+    /// only to those generated by the same completion of the graph. The values of `collection`
+    /// referred to in later completions are then those of the first ones.
+    pub fn with_persistent(mut self, collection: &str) -> Self {
+        self.persistent.insert(collection.to_string());
+        self
+    }
+
+    /// The collections of the namespace whose values are referred to from other collections.
+    pub fn referenced_collections(mut self) -> Result<BTreeSet<String>> {
+        self.crawl()?;
+        Ok(self
+            .vtable
+            .get(&Address::new_root())
+            .into_iter()
+            .flat_map(|table| table.targets())
+            .filter_map(|target| target.iter().next().map(str::to_string))
+            .collect())
+    }
+
+    fn crawl(&mut self) -> Result<()> {
         let crawler = Crawler {
             state: &mut self.state,
             symbols: &mut self.vtable,
             position: Address::new_root(),
         };
 
-        crawler.compile()?;
+        crawler.compile()
+    }
+
+    pub fn compile(mut self) -> Result<Graph> {
+        self.crawl()?;
+
+        if let Some(address) = self
+            .overrides
+            .keys()
+            .find(|address| self.state.project((*address).clone()).is_err())
+        {
+            return Err(anyhow!("cannot override `{}`: it does not exist", address));
+        }
 
         let mut visits = vec![Address::new_root()];
 
@@ -178,6 +296,27 @@ impl<'a> NamespaceCompiler<'a> {
                 }
             }
 
+            let parent = cardinality_array(&self.state, &address).map(|array| {
+                self.parents
+                    .entry(array)
+                    .or_insert_with(|| Rc::new(RefCell::new(Value::Null(()))))
+                    .clone()
+            });
+
+            if let (
+                Source::Content(Content::SameAs(SameAsContent {
+                    cardinality: Some(_),
+                    ..
+                })),
+                None,
+            ) = (self.state.project(address.clone())?.source(), &parent)
+            {
+                return Err(anyhow!(
+                    "the `same_as` at `{}` has a `cardinality`, which only a field of the object content of an array can have",
+                    address
+                ));
+            }
+
             let state = self.state.project_mut(address.clone())?;
             let vtable = &mut self.vtable;
             let mut children = BTreeMap::new();
@@ -186,11 +325,24 @@ impl<'a> NamespaceCompiler<'a> {
                 state,
                 children: &mut children,
                 vtable,
+                shard: self.shard,
+            };
+
+            let mut node = match (self.overrides.remove(&address), parent) {
+                (Some(node), _) => node,
+                (None, Some(parent)) => Graph::Iter(IterNode {
+                    iter: Box::new(std::iter::repeat_with(move || parent.borrow().clone())),
+                }),
+                (None, None) => content_compiler
+                    .compile()
+                    .with_context(|| format!("while trying to build `{}`", &address))?,
             };
 
-            let mut node = content_compiler
-                .compile()
-                .with_context(|| format!("while trying to build `{}`", &address))?;
+            if let (Graph::OneToMany(one_to_many), Some(parent)) =
+                (&mut node, self.parents.get(&address))
+            {
+                one_to_many.share_current(parent.clone());
+            }
 
             if let Some(local_table) = vtable.get(&address) {
                 // `node` must be wrapped in `Ordered`
@@ -211,14 +363,40 @@ impl<'a> NamespaceCompiler<'a> {
                 node = Graph::from_link(Link::Ordered(Ordered::new(scope, ordered_children, node)));
             }
 
+            if let Source::Content(Content::Object(object)) = state.source() {
+                if !object.unique_together.is_empty() {
+                    // Objects are generated again as a whole, including the children they record
+                    // beforehand when wrapped in `Ordered`. What the references into a rejected
+                    // object recorded is forgotten.
+                    let tapes = self
+                        .tapes
+                        .iter()
+                        .filter(|(at, _)| at.as_in(&address).is_some_and(|at| !at.is_root()))
+                        .map(|(_, tape)| tape.clone())
+                        .collect();
+                    node = Graph::Unique(UniqueNode::rows(
+                        node,
+                        object.unique_keys()?,
+                        Rewind::new(tapes),
+                        self.shard,
+                    ));
+                }
+            }
+
             let artifact = if vtable.targetted(&address) {
                 let recorder = Recorder::wrap(node);
+                self.tapes.insert(address.clone(), recorder.tape());
                 vtable
                     .paths(&address)
                     .into_iter()
                     .try_for_each(|(root, tail)| {
                         debug!("setting source root=`{}` tail=`{}`", root, tail);
-                        vtable.set_source(&root, &tail, recorder.new_slice())
+                        let slice = recorder.new_slice();
+                        let collection = tail.iter().next().unwrap_or_default();
+                        if root.is_root() && self.persistent.contains(collection) {
+                            slice.persist()
+                        }
+                        vtable.set_source(&root, &tail, slice)
                     })?;
                 Artifact::from_recorder(recorder)
             } else {
@@ -237,6 +415,7 @@ pub struct ContentCompiler<'c, 'a: 'c> {
     state: &'c mut CompilerState<'a, Graph>,
     children: &'c mut BTreeMap<String, (GeneratorRecorder<Graph>, GeneratorSliceRef<Graph>)>,
     vtable: &'c mut Symbols,
+    shard: Shard,
 }
 
 impl<'c, 'a: 'c> ContentCompiler<'c, 'a> {
@@ -281,6 +460,10 @@ impl<'c, 'a: 'c> Compiler<'a> for ContentCompiler<'c, 'a> {
         })?;
         Ok(Graph::from_link(Link::View(view)))
     }
+
+    fn shard(&self) -> Shard {
+        self.shard
+    }
 }
 
 pub struct Crawler<'t, 'a> {
@@ -332,6 +515,7 @@ impl<'t, 'a: 't> Compiler<'a> for Crawler<'t, 'a> {
 #[cfg(test)]
 pub mod tests {
     use crate::graph::Value;
+    use crate::schema::{ChronoValue, ChronoValueAndFormat};
     use crate::tests::complete;
 
     use synth_gen::prelude::*;
@@ -407,6 +591,435 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn compile_linked_bounds() {
+        let mut generator = generator!({
+            "type": "object",
+            "shipped_at": {
+                "type": "date_time",
+                "subtype": "naive_date",
+                "format": "%Y-%m-%d",
+                "begin": {"ref": "ordered_at", "offset": "1d"},
+                "end": {"ref": "ordered_at", "offset": "5d"}
+            },
+            "ordered_at": {
+                "type": "date_time",
+                "format": "%Y-%m-%d",
+                "begin": "2020-01-01",
+                "end": "2020-12-31"
+            },
+            "price": {
+                "type": "number",
+                "subtype": "u64",
+                "range": {"low": 1, "high": 100}
+            },
+            "discount": {
+                "type": "number",
+                "subtype": "u64",
+                "range": {"high": "@price", "include_high": true}
+            }
+        });
+
+        let mut rng = crate::tests::rng();
+        for _ in 0..100 {
+            let value = generator.complete(&mut rng).unwrap();
+            let object = value.as_object().unwrap();
+            let date = |field: &str| match object.get(field).unwrap() {
+                Value::DateTime(ChronoValueAndFormat {
+                    value: ChronoValue::NaiveDate(date),
+                    ..
+                }) => *date,
+                otherwise => panic!("{otherwise:?} is not a naive date"),
+            };
+            let days = (date("shipped_at") - date("ordered_at")).num_days();
+            assert!((1..=5).contains(&days));
+
+            let number = |field: &str| match object.get(field).unwrap() {
+                Value::Number(Number::U64(n)) => *n,
+                otherwise => panic!("{otherwise:?} is not a u64"),
+            };
+            assert!(number("discount") <= number("price"));
+        }
+
+        let mut generator = generator!({
+            "type": "object",
+            "begin": {"type": "date_time", "format": "%Y-%m-%d", "begin": "2020-01-01", "end": "2020-12-31"},
+            "end": {"type": "date_time", "format": "%Y-%m-%d", "begin": "2021-01-01", "end": "@begin"}
+        });
+        assert!(generator.complete(&mut rng).is_err());
+    }
+
+    #[test]
+    fn compile_expression() {
+        let mut generator = generator!({
+            "type": "object",
+            "total": {
+                "type": "expression",
+                "expression": "round(quantity * unit_price, 2)",
+                "arguments": {"quantity": "@quantity", "unit_price": "@unit_price"}
+            },
+            "size": {
+                "type": "expression",
+                "expression": "quantity >= 5 ? upper('bulk') : 'single'",
+                "arguments": {"quantity": "@quantity"}
+            },
+            "quantity": {
+                "type": "number",
+                "subtype": "u64",
+                "range": {"low": 1, "high": 10}
+            },
+            "unit_price": {
+                "type": "number",
+                "subtype": "f64",
+                "range": {"low": 1.0, "high": 20.0}
+            }
+        });
+
+        let mut rng = crate::tests::rng();
+        for _ in 0..100 {
+            let value = generator.complete(&mut rng).unwrap();
+            let object = value.as_object().unwrap();
+            let quantity = match object.get("quantity").unwrap() {
+                Value::Number(Number::U64(n)) => *n,
+                otherwise => panic!("{otherwise:?} is not a u64"),
+            };
+            let unit_price = match object.get("unit_price").unwrap() {
+                Value::Number(Number::F64(n)) => n.into_inner(),
+                otherwise => panic!("{otherwise:?} is not a f64"),
+            };
+            match object.get("total").unwrap() {
+                Value::Number(Number::F64(total)) => {
+                    assert!((total.into_inner() - quantity as f64 * unit_price).abs() <= 0.005)
+                }
+                otherwise => panic!("{otherwise:?} is not a f64"),
+            }
+            let size = if quantity >= 5 { "BULK" } else { "single" };
+            assert_eq!(
+                object.get("size").unwrap(),
+                &Value::String(size.to_string())
+            );
+        }
+
+        assert!(try_generator!({
+            "type": "expression",
+            "expression": "quantity * 2",
+            "arguments": {"quantiy": 1}
+        })
+        .is_err());
+        assert!(try_generator!({
+            "type": "expression",
+            "expression": "uper('a')"
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn compile_switch() {
+        let mut generator = generator!({
+            "type": "object",
+            "skip_when_null": true,
+            "card_last4": {
+                "type": "switch",
+                "on": "@payment_method",
+                "cases": {
+                    "card": {"type": "string", "pattern": "[0-9]{4}"},
+                    "transfer": "@reference"
+                }
+            },
+            "payment_method": {
+                "type": "string",
+                "categorical": {"card": 1, "cash": 1, "transfer": 1}
+            },
+            "reference": {"type": "string", "pattern": "REF[0-9]{6}"},
+            "size": {
+                "type": "switch",
+                "on": {
+                    "type": "expression",
+                    "expression": "quantity >= 5",
+                    "arguments": {"quantity": "@quantity"}
+                },
+                "cases": {"true": "bulk"},
+                "default": {"type": "number", "subtype": "u64", "constant": 1}
+            },
+            "quantity": {"type": "number", "subtype": "u64", "range": {"low": 1, "high": 10}}
+        });
+
+        let mut rng = crate::tests::rng();
+        let mut methods = std::collections::BTreeSet::new();
+        for _ in 0..100 {
+            let value = generator.complete(&mut rng).unwrap();
+            let object = value.as_object().unwrap();
+            let method = object
+                .get("payment_method")
+                .unwrap()
+                .as_string()
+                .unwrap()
+                .as_str();
+            methods.insert(method.to_string());
+            match (method, object.get("card_last4")) {
+                ("card", Some(Value::String(last4))) => {
+                    assert!(last4.len() == 4 && last4.chars().all(|c| c.is_ascii_digit()))
+                }
+                ("transfer", Some(reference)) => {
+                    assert_eq!(Some(reference), object.get("reference"))
+                }
+                ("cash", None) => {}
+                (method, last4) => panic!("unexpected {last4:?} for {method}"),
+            }
+
+            let size = match object.get("quantity").unwrap() {
+                Value::Number(Number::U64(quantity)) if *quantity >= 5 => {
+                    Value::String("bulk".to_string())
+                }
+                _ => Value::Number(Number::U64(1)),
+            };
+            assert_eq!(object.get("size"), Some(&size));
+        }
+        assert_eq!(methods.len(), 3);
+
+        let mut generator = generator!({
+            "type": "switch",
+            "on": {"type": "object", "a": 1},
+            "cases": {}
+        });
+        assert!(generator.complete(&mut rng).is_err());
+    }
+
+    #[test]
+    fn compile_cardinality() {
+        let generator = generator!({
+            "type": "object",
+            "users": {
+                "type": "array",
+                "length": 20,
+                "content": {
+                    "type": "object",
+                    "id": {"type": "number", "subtype": "u64", "id": {}}
+                }
+            },
+            "orders": {
+                "type": "array",
+                "content": {
+                    "type": "object",
+                    "id": {"type": "number", "subtype": "u64", "id": {}},
+                    "user_id": {
+                        "type": "same_as",
+                        "ref": "users.content.id",
+                        "cardinality": {"type": "number", "subtype": "u64", "range": {"low": 1, "high": 4}}
+                    },
+                    "user_ref": {
+                        "type": "expression",
+                        "expression": "user_id",
+                        "arguments": {"user_id": "@orders.content.user_id"}
+                    }
+                }
+            }
+        });
+        let value = complete(generator).unwrap();
+        let orders = value.as_object().unwrap().get("orders").unwrap();
+        let mut counts = std::collections::BTreeMap::new();
+        for (index, order) in orders.as_array().unwrap().iter().enumerate() {
+            let order = order.as_object().unwrap();
+            assert_eq!(
+                order.get("id"),
+                Some(&Value::Number(Number::U64(index as u64 + 1)))
+            );
+            match order.get("user_id") {
+                Some(Value::Number(Number::U64(user_id))) => {
+                    assert_eq!(
+                        order.get("user_ref"),
+                        Some(&Value::Number(Number::I64(*user_id as i64)))
+                    );
+                    *counts.entry(*user_id).or_insert(0) += 1
+                }
+                otherwise => panic!("unexpected user_id {otherwise:?}"),
+            }
+        }
+        assert_eq!(
+            counts.keys().copied().collect::<Vec<_>>(),
+            (1..=20).collect::<Vec<_>>()
+        );
+        assert!(counts.values().all(|count| (1..4).contains(count)));
+        assert!(counts.values().any(|count| *count != counts[&1]));
+    }
+
+    #[test]
+    fn compile_misplaced_cardinality() {
+        let users = serde_json::json!({
+            "type": "array",
+            "length": 5,
+            "content": {
+                "type": "object",
+                "id": {"type": "number", "subtype": "u64", "id": {}}
+            }
+        });
+        let same_as = serde_json::json!({
+            "type": "same_as",
+            "ref": "users.content.id",
+            "cardinality": 2
+        });
+        let misplaced = [
+            // In a nested object
+            serde_json::json!({
+                "type": "array",
+                "length": 5,
+                "content": {
+                    "type": "object",
+                    "user": {"type": "object", "id": same_as}
+                }
+            }),
+            // As the content of an array
+            serde_json::json!({"type": "array", "length": 5, "content": same_as}),
+            // In a one_of
+            serde_json::json!({
+                "type": "array",
+                "length": 5,
+                "content": {
+                    "type": "object",
+                    "user_id": {"type": "one_of", "variants": [same_as, {"type": "null"}]}
+                }
+            }),
+        ];
+        for orders in misplaced {
+            let content = serde_json::from_value(serde_json::json!({
+                "type": "object",
+                "users": users,
+                "orders": orders
+            }))
+            .unwrap();
+            let err = crate::Graph::from_content(&content).err().unwrap();
+            assert!(format!("{err:#}").contains("cardinality"), "{err:#}");
+        }
+    }
+
+    #[test]
+    fn compile_unique_together() {
+        let generator = generator!({
+            "type": "object",
+            "stocks": {
+                "type": "array",
+                "length": 8,
+                "content": {
+                    "type": "object",
+                    "unique_together": [["product_id", "warehouse.code"]],
+                    "product_id": {
+                        "type": "number",
+                        "subtype": "u64",
+                        "range": {"low": 1, "high": 5}
+                    },
+                    "warehouse": {
+                        "type": "object",
+                        "code": {"type": "string", "categorical": {"a": 1, "b": 1, "c": 1}}
+                    },
+                    "product": {
+                        "type": "expression",
+                        "expression": "id",
+                        "arguments": {"id": "@stocks.content.product_id"}
+                    }
+                }
+            }
+        });
+        let value = complete(generator).unwrap();
+        let stocks = value.as_object().unwrap().get("stocks").unwrap();
+        let mut keys = std::collections::BTreeSet::new();
+        for stock in stocks.as_array().unwrap() {
+            let stock = stock.as_object().unwrap();
+            let product_id = match stock.get("product_id") {
+                Some(Value::Number(Number::U64(product_id))) => *product_id,
+                otherwise => panic!("unexpected product_id {otherwise:?}"),
+            };
+            assert_eq!(
+                stock.get("product"),
+                Some(&Value::Number(Number::I64(product_id as i64)))
+            );
+            let warehouse = stock.get("warehouse").unwrap().as_object().unwrap();
+            let code = warehouse.get("code").unwrap().clone();
+            assert!(keys.insert((product_id, code.to_string())));
+        }
+        assert_eq!(keys.len(), 8);
+    }
+
+    #[test]
+    fn compile_unique_together_with_cardinality() {
+        let generator = generator!({
+            "type": "object",
+            "users": {
+                "type": "array",
+                "length": 10,
+                "content": {
+                    "type": "object",
+                    "id": {"type": "number", "subtype": "u64", "id": {}}
+                }
+            },
+            "favourites": {
+                "type": "array",
+                "content": {
+                    "type": "object",
+                    "unique_together": [["user_id", "colour"]],
+                    "user_id": {
+                        "type": "same_as",
+                        "ref": "users.content.id",
+                        "cardinality": {"type": "number", "subtype": "u64", "constant": 3}
+                    },
+                    "colour": {"type": "string", "categorical": {"red": 1, "green": 1, "blue": 1}}
+                }
+            }
+        });
+        let value = complete(generator).unwrap();
+        let favourites = value.as_object().unwrap().get("favourites").unwrap();
+        let mut keys = std::collections::BTreeSet::new();
+        for favourite in favourites.as_array().unwrap() {
+            let favourite = favourite.as_object().unwrap();
+            assert!(keys.insert((
+                favourite.get("user_id").unwrap().to_string(),
+                favourite.get("colour").unwrap().to_string()
+            )));
+        }
+        assert_eq!(keys.len(), 30);
+    }
+
+    #[test]
+    fn compile_with_override() {
+        use super::{Address, NamespaceCompiler};
+        use crate::graph::{Graph, IterNode};
+
+        let content = schema!({
+            "type": "object",
+            "users": {
+                "type": "array",
+                "length": 1,
+                "content": {
+                    "type": "bool",
+                    "constant": true
+                }
+            }
+        });
+        let lengths = [3u64, 2].into_iter().map(|len| Value::Number(len.into()));
+        let length_address = Address::new_root().into_at("users").into_at("length");
+
+        let mut generator = NamespaceCompiler::new_flat(&content)
+            .with_override(
+                length_address,
+                Graph::Iter(IterNode {
+                    iter: Box::new(lengths),
+                }),
+            )
+            .compile()
+            .unwrap();
+
+        let mut rng = crate::tests::rng();
+        for expected in [3, 2] {
+            let value = generator.complete(&mut rng).unwrap();
+            let users = value.as_object().unwrap().get("users").unwrap();
+            assert_eq!(users.as_array().unwrap().len(), expected);
+        }
+
+        let missing = NamespaceCompiler::new_flat(&content)
+            .with_override(Address::new_root().into_at("orders"), Graph::null())
+            .compile();
+        assert!(missing.is_err());
+    }
+
     #[test]
     fn compile_circle() {
         let generator = try_generator!({
@@ -471,6 +471,11 @@ where
         self.locals.values()
     }
 
+    /// The addresses of the nodes referred to, relative to the local root.
+    pub(super) fn targets(&self) -> impl Iterator<Item = &Address> {
+        self.locals.keys()
+    }
+
     fn get_mut(&mut self, to: &Address) -> Option<&mut ReferenceFactory<G>> {
         self.locals.get_mut(to)
     }
@@ -45,3 +45,90 @@ impl ArrayNode {
         Self(inner)
     }
 }
+
+/// Generates, for each value at `path` in the value of `parent`, as many elements as given by
+/// `cardinality`. The value is `current` while they are generated.
+pub struct OneToManyNode {
+    parent: Box<Graph>,
+    path: Vec<String>,
+    cardinality: SizeGenerator,
+    content: Box<Graph>,
+    current: Rc<RefCell<Value>>,
+}
+
+impl OneToManyNode {
+    pub fn new(
+        parent: Graph,
+        path: Vec<String>,
+        cardinality: SizeGenerator,
+        content: Graph,
+    ) -> Self {
+        Self {
+            parent: Box::new(parent),
+            path,
+            cardinality,
+            content: Box::new(content),
+            current: Rc::new(RefCell::new(Value::Null(()))),
+        }
+    }
+
+    /// Sets the value of each element's parent in `current` instead.
+    pub fn share_current(&mut self, current: Rc<RefCell<Value>>) {
+        self.current = current;
+    }
+
+    /// The values at `path` in `value`, where `content` stands for every element of an array.
+    fn values_at(value: Value, path: &[String], values: &mut Vec<Value>) -> Result<(), Error> {
+        match (path.split_first(), value) {
+            (None, value) => values.push(value),
+            (Some((next, rest)), Value::Array(elements)) if next == "content" => {
+                for element in elements {
+                    Self::values_at(element, rest, values)?;
+                }
+            }
+            (Some((next, rest)), Value::Object(mut fields)) => match fields.remove(next) {
+                Some(field) => Self::values_at(field, rest, values)?,
+                None => {
+                    return Err(failed_crate!(
+                        target: Release,
+                        "the referenced value has no field '{}'",
+                        next
+                    ))
+                }
+            },
+            (Some((next, _)), value) => {
+                return Err(failed_crate!(
+                    target: Release,
+                    "cannot find '{}' in a value of type {}",
+                    next,
+                    value.type_().to_lowercase()
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    fn generate<R: Rng>(&mut self, rng: &mut R) -> Result<Value, Error> {
+        let mut parents = Vec::new();
+        Self::values_at(self.parent.complete(rng)?, &self.path, &mut parents)?;
+
+        let mut elements = Vec::new();
+        for parent in parents.into_iter().filter(|parent| !parent.is_null()) {
+            *self.current.borrow_mut() = parent;
+            for _ in 0..self.cardinality.complete(rng)? {
+                elements.push(self.content.complete(rng)?);
+            }
+        }
+        Ok(elements.into())
+    }
+}
+
+impl Generator for OneToManyNode {
+    type Yield = Token;
+
+    type Return = Result<Value, Error>;
+
+    fn next<R: Rng>(&mut self, rng: &mut R) -> GeneratorState<Self::Yield, Self::Return> {
+        GeneratorState::Complete(self.generate(rng))
+    }
+}
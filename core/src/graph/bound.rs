@@ -0,0 +1,140 @@
+use super::prelude::*;
+
+use num::NumCast;
+
+/// Values that can be read from the value of another field to bound the values of a node, and
+/// shifted by an offset.
+pub trait BoundValue: Sized {
+    type Offset;
+
+    fn from_value(value: Value) -> Result<Self, Error>;
+
+    fn offset_by(self, offset: &Self::Offset) -> Result<Self, Error>;
+}
+
+/// The bound of a node, resolved again from the value of the field it references (if any) every
+/// time the node generates.
+pub enum BoundNode<T: BoundValue> {
+    Value(T),
+    Field(Box<Graph>, Option<T::Offset>),
+}
+
+impl<T: BoundValue + Clone> BoundNode<T> {
+    pub fn resolve<R: Rng>(&mut self, rng: &mut R) -> Result<T, Error> {
+        match self {
+            Self::Value(value) => Ok(value.clone()),
+            Self::Field(graph, offset) => {
+                let value = T::from_value(graph.complete(rng)?)?;
+                match offset {
+                    Some(offset) => value.offset_by(offset),
+                    None => Ok(value),
+                }
+            }
+        }
+    }
+}
+
+fn number_as<N: NumCast>(number: Number) -> Option<N> {
+    match number {
+        Number::I8(n) => N::from(n),
+        Number::I16(n) => N::from(n),
+        Number::I32(n) => N::from(n),
+        Number::I64(n) => N::from(n),
+        Number::I128(n) => N::from(n),
+        Number::U8(n) => N::from(n),
+        Number::U16(n) => N::from(n),
+        Number::U32(n) => N::from(n),
+        Number::U64(n) => N::from(n),
+        Number::U128(n) => N::from(n),
+        Number::F32(n) => N::from(n.into_inner()),
+        Number::F64(n) => N::from(n.into_inner()),
+    }
+}
+
+macro_rules! bound_value_number_impl {
+    (@from_value $ty:ty) => {
+        fn from_value(value: Value) -> Result<Self, Error> {
+            match value {
+                Value::Number(number) => number_as(number).ok_or_else(|| {
+                    failed_crate!(target: Release, "the bound {} does not fit in a {}", number, stringify!($ty))
+                }),
+                otherwise => Err(failed_crate!(
+                    target: Release,
+                    "could not use the value '{}' as a {} bound",
+                    otherwise,
+                    stringify!($ty)
+                )),
+            }
+        }
+    };
+    (float $($ty:ty),*) => {
+        $(
+            impl BoundValue for $ty {
+                type Offset = $ty;
+
+                bound_value_number_impl!(@from_value $ty);
+
+                fn offset_by(self, offset: &$ty) -> Result<Self, Error> {
+                    Ok(self + offset)
+                }
+            }
+        )*
+    };
+    ($($ty:ty),*) => {
+        $(
+            impl BoundValue for $ty {
+                type Offset = $ty;
+
+                bound_value_number_impl!(@from_value $ty);
+
+                fn offset_by(self, offset: &$ty) -> Result<Self, Error> {
+                    self.checked_add(*offset).ok_or_else(|| {
+                        failed_crate!(target: Release, "the bound {} + {} overflows a {}", self, offset, stringify!($ty))
+                    })
+                }
+            }
+        )*
+    };
+}
+
+bound_value_number_impl!(i16, i32, u32, i64, u64);
+bound_value_number_impl!(float f32, f64);
+
+impl BoundValue for ChronoValue {
+    type Offset = DateTimeOffset;
+
+    fn from_value(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::DateTime(date_time) => Ok(date_time.value),
+            otherwise => Err(failed_crate!(
+                target: Release,
+                "could not use the value '{}' as a date/time bound",
+                otherwise
+            )),
+        }
+    }
+
+    fn offset_by(self, offset: &DateTimeOffset) -> Result<Self, Error> {
+        Ok(self + offset.0)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_as_bounds() {
+        assert_eq!(u64::from_value(Value::Number(Number::I32(4))).unwrap(), 4);
+        assert_eq!(
+            i64::from_value(Value::Number(Number::F64(2.0.into()))).unwrap(),
+            2
+        );
+        assert_eq!(f32::from_value(Value::Number(Number::U64(7))).unwrap(), 7.);
+        assert!(u64::from_value(Value::Number(Number::I64(-1))).is_err());
+        assert!(i16::from_value(Value::String("1".to_string())).is_err());
+
+        assert_eq!(5u64.offset_by(&2).unwrap(), 7);
+        assert!(u64::MAX.offset_by(&1).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 use super::prelude::*;
 
+use rand::distributions::WeightedIndex;
+
 use std::{ops::Range as StdRange, sync::Arc};
 
 derive_generator! {
@@ -18,20 +20,145 @@ impl From<RandomDateTime> for DateTimeNode {
     }
 }
 
+derive_generator! {
+    yield ChronoValue,
+    return Result<ChronoValue, Error>,
+    enum DateTimeRange {
+        Fixed(OnceInfallible<Random<ChronoValue, Uniform<ChronoValue>>>),
+        Linked(TryOnce<LinkedDateTime>),
+        Histogram(OnceInfallible<Random<ChronoValue, HistogramDateTime>>),
+    }
+}
+
 pub struct RandomDateTime {
-    inner: OnceInfallible<Random<ChronoValue, Uniform<ChronoValue>>>,
+    inner: DateTimeRange,
     format: Arc<str>,
 }
 
 impl RandomDateTime {
     pub fn new(range: StdRange<ChronoValue>, format: &str) -> Self {
         Self {
-            inner: Random::new_with(Uniform::new_inclusive(range.start, range.end))
-                .infallible()
-                .try_once(),
+            inner: DateTimeRange::Fixed(
+                Random::new_with(Uniform::new_inclusive(range.start, range.end))
+                    .infallible()
+                    .try_once(),
+            ),
             format: Arc::from(format.to_owned()),
         }
     }
+
+    /// A date/time in one of `buckets`, picked by weight.
+    pub fn new_histogram(buckets: &[DateTimeBucket], format: &str) -> anyhow::Result<Self> {
+        let fmt = ChronoValueFormatter::new(format);
+        let ranges = buckets
+            .iter()
+            .map(|bucket| {
+                if bucket.begin.type_() != bucket.end.type_() || bucket.begin > bucket.end {
+                    return Err(anyhow!(
+                        "histogram bucket begin is after end: begin={}, end={}",
+                        fmt.format(&bucket.begin)?,
+                        fmt.format(&bucket.end)?
+                    ));
+                }
+                Ok(Uniform::new_inclusive(
+                    bucket.begin.clone(),
+                    bucket.end.clone(),
+                ))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        let index = WeightedIndex::new(buckets.iter().map(|bucket| bucket.weight))
+            .map_err(|err| anyhow!("date time histogram has invalid weights: {}", err))?;
+        Ok(Self {
+            inner: DateTimeRange::Histogram(
+                Random::new_with(HistogramDateTime { index, ranges })
+                    .infallible()
+                    .try_once(),
+            ),
+            format: Arc::from(format.to_owned()),
+        })
+    }
+
+    /// A date/time between `begin` and `end`, where either may reference the value of another
+    /// field.
+    pub fn new_linked(
+        begin: BoundNode<ChronoValue>,
+        end: BoundNode<ChronoValue>,
+        type_: ChronoValueType,
+        format: &str,
+    ) -> Self {
+        let format: Arc<str> = Arc::from(format.to_owned());
+        Self {
+            inner: DateTimeRange::Linked(
+                LinkedDateTime {
+                    begin,
+                    end,
+                    type_,
+                    format: Arc::clone(&format),
+                }
+                .try_once(),
+            ),
+            format,
+        }
+    }
+}
+
+pub struct HistogramDateTime {
+    index: WeightedIndex<f64>,
+    ranges: Vec<Uniform<ChronoValue>>,
+}
+
+impl Distribution<ChronoValue> for HistogramDateTime {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> ChronoValue {
+        self.ranges[self.index.sample(rng)].sample(rng)
+    }
+}
+
+pub struct LinkedDateTime {
+    begin: BoundNode<ChronoValue>,
+    end: BoundNode<ChronoValue>,
+    type_: ChronoValueType,
+    format: Arc<str>,
+}
+
+impl LinkedDateTime {
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> Result<ChronoValue, Error> {
+        let begin = self.begin.resolve(rng)?;
+        let end = self.end.resolve(rng)?;
+        for bound in [&begin, &end] {
+            if bound.type_() != self.type_ {
+                return Err(failed_crate!(
+                    target: Release,
+                    "expected a {} bound but got a {}: try setting the 'subtype' to {}",
+                    self.type_,
+                    bound.type_(),
+                    serde_json::to_string(&bound.type_()).unwrap()
+                ));
+            }
+        }
+        if begin > end {
+            let fmt = ChronoValueFormatter::new(&self.format);
+            return Err(failed_crate!(
+                target: Release,
+                "begin is after end: begin={}, end={}",
+                fmt.format(&begin)?,
+                fmt.format(&end)?
+            ));
+        }
+        Ok(Uniform::new_inclusive(begin, end).sample(rng))
+    }
+}
+
+impl Generator for LinkedDateTime {
+    type Yield = ChronoValue;
+
+    type Return = Result<Never, Error>;
+
+    fn next<R: Rng>(&mut self, rng: &mut R) -> GeneratorState<Self::Yield, Self::Return> {
+        match self.sample(rng) {
+            Ok(value) => GeneratorState::Yielded(value),
+            Err(err) => GeneratorState::Complete(Err(err)),
+        }
+    }
 }
 
 pub struct UniformChronoValue(ChronoValue, UniformDuration);
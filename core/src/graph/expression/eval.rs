@@ -0,0 +1,701 @@
+use super::super::prelude::*;
+use super::parser::{BinaryOp, Expr, UnaryOp};
+
+use chrono::{Datelike, Duration, NaiveDateTime};
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// The format of the date/times returned by `now()`.
+const NOW_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%z";
+
+/// The default format of the dates parsed by `date(s)`.
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+const SECONDS_PER_YEAR: f64 = 365.2425 * 86400.;
+
+/// The functions expressions can call, with their minimum and maximum number of arguments.
+pub const FUNCTIONS: [(&str, usize, usize); 30] = [
+    ("abs", 1, 1),
+    ("ceil", 1, 1),
+    ("coalesce", 1, usize::MAX),
+    ("concat", 1, usize::MAX),
+    ("date", 1, 2),
+    ("day", 1, 1),
+    ("days", 1, 1),
+    ("duration", 1, 1),
+    ("float", 1, 1),
+    ("floor", 1, 1),
+    ("hours", 1, 1),
+    ("int", 1, 1),
+    ("is_null", 1, 1),
+    ("len", 1, 1),
+    ("lower", 1, 1),
+    ("max", 1, usize::MAX),
+    ("min", 1, usize::MAX),
+    ("minutes", 1, 1),
+    ("month", 1, 1),
+    ("now", 0, 0),
+    ("pow", 2, 2),
+    ("replace", 3, 3),
+    ("round", 1, 2),
+    ("seconds", 1, 1),
+    ("str", 1, 1),
+    ("substr", 2, 3),
+    ("trim", 1, 1),
+    ("upper", 1, 1),
+    ("year", 1, 1),
+    ("years", 1, 1),
+];
+
+/// The values expressions compute with. Integers and floats are kept apart so that integer
+/// arithmetic stays exact, and durations only exist in between date/time arithmetic.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    DateTime(ChronoValueAndFormat),
+    Duration(Duration),
+}
+
+impl Operand {
+    pub fn from_value(value: Value) -> Result<Self, Error> {
+        Ok(match value {
+            Value::Null(()) => Self::Null,
+            Value::Bool(boolean) => Self::Bool(boolean),
+            Value::Number(number) => match number {
+                Number::I8(n) => Self::Int(n.into()),
+                Number::I16(n) => Self::Int(n.into()),
+                Number::I32(n) => Self::Int(n.into()),
+                Number::I64(n) => Self::Int(n),
+                Number::U8(n) => Self::Int(n.into()),
+                Number::U16(n) => Self::Int(n.into()),
+                Number::U32(n) => Self::Int(n.into()),
+                Number::U64(n) => Self::Int(int_from(n)?),
+                Number::I128(n) => Self::Int(int_from(n)?),
+                Number::U128(n) => Self::Int(int_from(n)?),
+                Number::F32(n) => Self::Float(n.into_inner().into()),
+                Number::F64(n) => Self::Float(n.into_inner()),
+            },
+            Value::String(string) => Self::String(string),
+            Value::DateTime(date_time) => Self::DateTime(date_time),
+            otherwise => {
+                return Err(failed_crate!(
+                    target: Release,
+                    "expressions can only use scalar values, not an {}",
+                    otherwise.type_().to_lowercase()
+                ))
+            }
+        })
+    }
+
+    pub fn into_value(self) -> Result<Value, Error> {
+        Ok(match self {
+            Self::Null => Value::Null(()),
+            Self::Bool(boolean) => Value::Bool(boolean),
+            Self::Int(int) => Value::Number(int.into()),
+            Self::Float(float) => Value::Number(float.into()),
+            Self::String(string) => Value::String(string),
+            Self::DateTime(date_time) => Value::DateTime(date_time),
+            Self::Duration(_) => {
+                return Err(failed_crate!(
+                    target: Release,
+                    "an expression cannot evaluate to a duration: convert it to a number with `years`, `days`, `hours`, `minutes` or `seconds`"
+                ))
+            }
+        })
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Bool(_) => "bool",
+            Self::Int(_) => "int",
+            Self::Float(_) => "float",
+            Self::String(_) => "string",
+            Self::DateTime(_) => "date_time",
+            Self::Duration(_) => "duration",
+        }
+    }
+
+    fn to_text(&self) -> String {
+        match self {
+            Self::Null => String::new(),
+            Self::Bool(boolean) => boolean.to_string(),
+            Self::Int(int) => int.to_string(),
+            Self::Float(float) => float.to_string(),
+            Self::String(string) => string.clone(),
+            Self::DateTime(date_time) => date_time.format_to_string(),
+            Self::Duration(duration) => DateTimeOffset(*duration).to_string(),
+        }
+    }
+}
+
+fn int_from<N: TryInto<i64> + Display + Copy>(n: N) -> Result<i64, Error> {
+    n.try_into()
+        .map_err(|_| failed_crate!(target: Release, "the number {} does not fit in an int", n))
+}
+
+fn float_to_int(float: f64) -> Result<i64, Error> {
+    // `as` saturates, so anything that does not round trip was out of range
+    let int = float as i64;
+    if float.is_finite() && (int as f64 - float).abs() < 1. {
+        Ok(int)
+    } else {
+        Err(failed_crate!(
+            target: Release,
+            "the number {} does not fit in an int",
+            float
+        ))
+    }
+}
+
+fn finite(float: f64) -> Result<Operand, Error> {
+    if float.is_finite() {
+        Ok(Operand::Float(float))
+    } else {
+        Err(failed_crate!(
+            target: Release,
+            "the expression evaluated to {}, which is not a finite number",
+            float
+        ))
+    }
+}
+
+fn overflow(op: impl Display) -> Error {
+    failed_crate!(target: Release, "overflow when evaluating '{}'", op)
+}
+
+fn division_by_zero() -> Error {
+    failed_crate!(target: Release, "division by zero")
+}
+
+/// Date/times of different types are compared and subtracted as naive UTC date/times, with dates
+/// at midnight.
+fn naive(value: &ChronoValue) -> Option<NaiveDateTime> {
+    match value {
+        ChronoValue::DateTime(dt) => Some(dt.naive_utc()),
+        ChronoValue::NaiveDateTime(dt) => Some(*dt),
+        ChronoValue::NaiveDate(d) => d.and_hms_opt(0, 0, 0),
+        ChronoValue::NaiveTime(_) => None,
+    }
+}
+
+fn since(left: &ChronoValue, right: &ChronoValue) -> Result<Duration, Error> {
+    match (left, right) {
+        (ChronoValue::DateTime(left), ChronoValue::DateTime(right)) => Ok(*left - *right),
+        (ChronoValue::NaiveTime(left), ChronoValue::NaiveTime(right)) => Ok(*left - *right),
+        _ => match (naive(left), naive(right)) {
+            (Some(left), Some(right)) => Ok(left - right),
+            _ => Err(failed_crate!(
+                target: Release,
+                "cannot combine a {} with a {}",
+                left.type_(),
+                right.type_()
+            )),
+        },
+    }
+}
+
+fn shift(date_time: ChronoValueAndFormat, by: Duration) -> Result<Operand, Error> {
+    let shifted = match date_time.value {
+        ChronoValue::NaiveDate(d) => d.checked_add_signed(by).map(ChronoValue::NaiveDate),
+        ChronoValue::NaiveTime(t) => Some(ChronoValue::NaiveTime(t + by)),
+        ChronoValue::NaiveDateTime(dt) => dt.checked_add_signed(by).map(ChronoValue::NaiveDateTime),
+        ChronoValue::DateTime(dt) => dt.checked_add_signed(by).map(ChronoValue::DateTime),
+    };
+    shifted
+        .map(|value| {
+            Operand::DateTime(ChronoValueAndFormat {
+                value,
+                format: date_time.format,
+            })
+        })
+        .ok_or_else(|| failed_crate!(target: Release, "date/time out of range"))
+}
+
+fn compare(left: &Operand, right: &Operand) -> Result<Ordering, Error> {
+    use Operand::*;
+    let ordering = match (left, right) {
+        (Int(left), Int(right)) => Some(left.cmp(right)),
+        (Int(left), Float(right)) => (*left as f64).partial_cmp(right),
+        (Float(left), Int(right)) => left.partial_cmp(&(*right as f64)),
+        (Float(left), Float(right)) => left.partial_cmp(right),
+        (String(left), String(right)) => Some(left.cmp(right)),
+        (Bool(left), Bool(right)) => Some(left.cmp(right)),
+        (DateTime(left), DateTime(right)) => {
+            Some(since(&left.value, &right.value)?.cmp(&chrono::Duration::zero()))
+        }
+        (Duration(left), Duration(right)) => Some(left.cmp(right)),
+        (Null, Null) => Some(Ordering::Equal),
+        _ => None,
+    };
+    ordering.ok_or_else(|| {
+        failed_crate!(
+            target: Release,
+            "cannot compare a {} with a {}",
+            left.type_name(),
+            right.type_name()
+        )
+    })
+}
+
+fn equals(left: &Operand, right: &Operand) -> Result<bool, Error> {
+    match (left, right) {
+        (Operand::Null, other) | (other, Operand::Null) => Ok(matches!(other, Operand::Null)),
+        _ => compare(left, right).map(Ordering::is_eq),
+    }
+}
+
+fn arithmetic(op: BinaryOp, left: Operand, right: Operand) -> Result<Operand, Error> {
+    use BinaryOp::*;
+    use Operand::{DateTime, Float, Int};
+    match (op, left, right) {
+        (Div, Int(left), Int(right)) => arithmetic(Div, Float(left as f64), Float(right as f64)),
+        (op, Int(left), Int(right)) => match op {
+            Add => left.checked_add(right),
+            Sub => left.checked_sub(right),
+            Mul => left.checked_mul(right),
+            Rem if right == 0 => return Err(division_by_zero()),
+            _ => left.checked_rem(right),
+        }
+        .map(Int)
+        .ok_or_else(|| overflow(format!("{left} {op} {right}"))),
+        (op, left @ (Int(_) | Float(_)), right @ (Int(_) | Float(_))) => {
+            let as_float = |operand| match operand {
+                Int(int) => int as f64,
+                Float(float) => float,
+                _ => unreachable!(),
+            };
+            let (left, right) = (as_float(left), as_float(right));
+            match op {
+                Add => finite(left + right),
+                Sub => finite(left - right),
+                Mul => finite(left * right),
+                _ if right == 0. => Err(division_by_zero()),
+                Div => finite(left / right),
+                _ => finite(left % right),
+            }
+        }
+        (Add, Operand::String(left), Operand::String(right)) => Ok(Operand::String(left + &right)),
+        (Add, DateTime(date_time), Operand::Duration(by))
+        | (Add, Operand::Duration(by), DateTime(date_time)) => shift(date_time, by),
+        (Sub, DateTime(date_time), Operand::Duration(by)) => shift(date_time, -by),
+        (Sub, DateTime(left), DateTime(right)) => {
+            since(&left.value, &right.value).map(Operand::Duration)
+        }
+        (Add, Operand::Duration(left), Operand::Duration(right)) => left
+            .checked_add(&right)
+            .map(Operand::Duration)
+            .ok_or_else(|| overflow("duration + duration")),
+        (Sub, Operand::Duration(left), Operand::Duration(right)) => left
+            .checked_sub(&right)
+            .map(Operand::Duration)
+            .ok_or_else(|| overflow("duration - duration")),
+        (Mul, Operand::Duration(duration), Int(times))
+        | (Mul, Int(times), Operand::Duration(duration)) => duration
+            .num_microseconds()
+            .and_then(|micros| micros.checked_mul(times))
+            .map(|micros| Operand::Duration(Duration::microseconds(micros)))
+            .ok_or_else(|| overflow("duration * int")),
+        (op, left, right) => Err(failed_crate!(
+            target: Release,
+            "cannot apply '{}' to a {} and a {}",
+            op,
+            left.type_name(),
+            right.type_name()
+        )),
+    }
+}
+
+fn expect_bool(operand: Operand) -> Result<bool, Error> {
+    match operand {
+        Operand::Bool(boolean) => Ok(boolean),
+        otherwise => Err(failed_crate!(
+            target: Release,
+            "expected a bool but got a {}",
+            otherwise.type_name()
+        )),
+    }
+}
+
+fn bad_argument(function: &str, argument: &Operand) -> Error {
+    failed_crate!(
+        target: Release,
+        "`{}` cannot be applied to a {}",
+        function,
+        argument.type_name()
+    )
+}
+
+/// Evaluates expressions, reading variables from the values of the `arguments` of the content.
+pub struct Scope<'a> {
+    pub variables: &'a BTreeMap<String, Operand>,
+}
+
+impl<'a> Scope<'a> {
+    pub fn eval(&self, expr: &Expr) -> Result<Operand, Error> {
+        Ok(match expr {
+            Expr::Null => Operand::Null,
+            Expr::Bool(boolean) => Operand::Bool(*boolean),
+            Expr::Int(int) => Operand::Int(*int),
+            Expr::Float(float) => Operand::Float(*float),
+            Expr::String(string) => Operand::String(string.clone()),
+            Expr::Variable(name) => self
+                .variables
+                .get(name)
+                .cloned()
+                .ok_or_else(|| failed_crate!(target: Release, "unknown variable '{}'", name))?,
+            Expr::Unary(UnaryOp::Not, expr) => Operand::Bool(!expect_bool(self.eval(expr)?)?),
+            Expr::Unary(UnaryOp::Neg, expr) => match self.eval(expr)? {
+                Operand::Int(int) => int
+                    .checked_neg()
+                    .map(Operand::Int)
+                    .ok_or_else(|| overflow(format!("-{int}")))?,
+                Operand::Float(float) => Operand::Float(-float),
+                Operand::Duration(duration) => Operand::Duration(-duration),
+                otherwise => return Err(bad_argument("-", &otherwise)),
+            },
+            Expr::Binary(BinaryOp::And, left, right) => {
+                Operand::Bool(expect_bool(self.eval(left)?)? && expect_bool(self.eval(right)?)?)
+            }
+            Expr::Binary(BinaryOp::Or, left, right) => {
+                Operand::Bool(expect_bool(self.eval(left)?)? || expect_bool(self.eval(right)?)?)
+            }
+            Expr::Binary(op, left, right) => {
+                let (left, right) = (self.eval(left)?, self.eval(right)?);
+                match op {
+                    BinaryOp::Eq => Operand::Bool(equals(&left, &right)?),
+                    BinaryOp::Ne => Operand::Bool(!equals(&left, &right)?),
+                    BinaryOp::Lt => Operand::Bool(compare(&left, &right)?.is_lt()),
+                    BinaryOp::Le => Operand::Bool(compare(&left, &right)?.is_le()),
+                    BinaryOp::Gt => Operand::Bool(compare(&left, &right)?.is_gt()),
+                    BinaryOp::Ge => Operand::Bool(compare(&left, &right)?.is_ge()),
+                    _ => arithmetic(*op, left, right)?,
+                }
+            }
+            Expr::Conditional(cond, then, otherwise) => {
+                if expect_bool(self.eval(cond)?)? {
+                    self.eval(then)?
+                } else {
+                    self.eval(otherwise)?
+                }
+            }
+            Expr::Call(function, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.eval(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                call(function, args)?
+            }
+        })
+    }
+}
+
+fn call(function: &str, args: Vec<Operand>) -> Result<Operand, Error> {
+    use Operand::*;
+    let mut args = args.into_iter();
+    let mut arg = || args.next().unwrap_or(Null);
+    Ok(match function {
+        "now" => DateTime(ChronoValueAndFormat {
+            value: ChronoValue::DateTime(ChronoValue::now()),
+            format: Arc::from(NOW_FORMAT),
+        }),
+        "date" => match (arg(), arg()) {
+            (String(s), format @ (String(_) | Null)) => {
+                let format = match format {
+                    String(format) => format,
+                    _ => DATE_FORMAT.to_string(),
+                };
+                let value = ChronoValueFormatter::new(&format)
+                    .parse(&s)
+                    .map_err(|err| failed_crate!(target: Release, "{}", err))?;
+                DateTime(ChronoValueAndFormat {
+                    value,
+                    format: Arc::from(format),
+                })
+            }
+            (String(_), otherwise) | (otherwise, _) => {
+                return Err(bad_argument(function, &otherwise))
+            }
+        },
+        "duration" => match arg() {
+            String(s) => Duration(
+                s.parse::<DateTimeOffset>()
+                    .map_err(|err| failed_crate!(target: Release, "{}", err))?
+                    .0,
+            ),
+            otherwise => return Err(bad_argument(function, &otherwise)),
+        },
+        "years" | "days" | "hours" | "minutes" | "seconds" => match arg() {
+            Duration(duration) => Int(match function {
+                "years" => (duration.num_seconds() as f64 / SECONDS_PER_YEAR) as i64,
+                "days" => duration.num_days(),
+                "hours" => duration.num_hours(),
+                "minutes" => duration.num_minutes(),
+                _ => duration.num_seconds(),
+            }),
+            otherwise => return Err(bad_argument(function, &otherwise)),
+        },
+        "year" | "month" | "day" => match arg() {
+            DateTime(date_time) => {
+                let date = match date_time.value {
+                    ChronoValue::NaiveDate(d) => d,
+                    ChronoValue::NaiveDateTime(dt) => dt.date(),
+                    ChronoValue::DateTime(dt) => dt.date_naive(),
+                    ChronoValue::NaiveTime(_) => {
+                        return Err(bad_argument(function, &DateTime(date_time)))
+                    }
+                };
+                Int(match function {
+                    "year" => date.year().into(),
+                    "month" => date.month().into(),
+                    _ => date.day().into(),
+                })
+            }
+            otherwise => return Err(bad_argument(function, &otherwise)),
+        },
+        "upper" | "lower" | "trim" | "len" => match arg() {
+            String(s) => match function {
+                "upper" => String(s.to_uppercase()),
+                "lower" => String(s.to_lowercase()),
+                "trim" => String(s.trim().to_string()),
+                _ => Int(s.chars().count() as i64),
+            },
+            otherwise => return Err(bad_argument(function, &otherwise)),
+        },
+        "substr" => match (arg(), arg(), arg()) {
+            (String(s), Int(start), len @ (Int(_) | Null)) => {
+                let start = usize::try_from(start).unwrap_or(0);
+                let chars = s.chars().skip(start);
+                String(match len {
+                    Int(len) => chars.take(usize::try_from(len).unwrap_or(0)).collect(),
+                    _ => chars.collect(),
+                })
+            }
+            (s, start, len) => {
+                let otherwise = [s, start, len]
+                    .into_iter()
+                    .find(|arg| !matches!(arg, String(_) | Int(_)))
+                    .unwrap_or(Null);
+                return Err(bad_argument(function, &otherwise));
+            }
+        },
+        "replace" => match (arg(), arg(), arg()) {
+            (String(s), String(from), String(to)) => String(s.replace(&from, &to)),
+            (String(_), String(_), otherwise) | (String(_), otherwise, _) | (otherwise, _, _) => {
+                return Err(bad_argument(function, &otherwise))
+            }
+        },
+        "concat" => String(args.map(|arg| arg.to_text()).collect()),
+        "str" => String(arg().to_text()),
+        "int" => match arg() {
+            Int(int) => Int(int),
+            Float(float) => Int(float_to_int(float.trunc())?),
+            String(s) => Int(s
+                .trim()
+                .parse()
+                .map_err(|_| failed_crate!(target: Release, "cannot convert '{}' to an int", s))?),
+            otherwise => return Err(bad_argument(function, &otherwise)),
+        },
+        "float" => match arg() {
+            Int(int) => Float(int as f64),
+            Float(float) => Float(float),
+            String(s) => finite(s.trim().parse().map_err(
+                |_| failed_crate!(target: Release, "cannot convert '{}' to a float", s),
+            )?)?,
+            otherwise => return Err(bad_argument(function, &otherwise)),
+        },
+        "abs" => match arg() {
+            Int(int) => Int(int
+                .checked_abs()
+                .ok_or_else(|| overflow(format!("abs({int})")))?),
+            Float(float) => Float(float.abs()),
+            Duration(duration) => Duration(duration.abs()),
+            otherwise => return Err(bad_argument(function, &otherwise)),
+        },
+        "floor" | "ceil" => match arg() {
+            Int(int) => Int(int),
+            Float(float) if function == "floor" => Int(float_to_int(float.floor())?),
+            Float(float) => Int(float_to_int(float.ceil())?),
+            otherwise => return Err(bad_argument(function, &otherwise)),
+        },
+        "round" => match (arg(), arg()) {
+            (Int(int), Null) => Int(int),
+            (Float(float), Null) => Int(float_to_int(float.round())?),
+            (number @ (Int(_) | Float(_)), Int(digits)) => {
+                let float = match number {
+                    Int(int) => int as f64,
+                    Float(float) => float,
+                    _ => unreachable!(),
+                };
+                let scale = 10f64.powi(digits.clamp(-308, 308) as i32);
+                finite((float * scale).round() / scale)?
+            }
+            (Int(_) | Float(_), otherwise) | (otherwise, _) => {
+                return Err(bad_argument(function, &otherwise))
+            }
+        },
+        "pow" => match (arg(), arg()) {
+            (Int(base), Int(exp)) if exp >= 0 => Int(u32::try_from(exp)
+                .ok()
+                .and_then(|exp| base.checked_pow(exp))
+                .ok_or_else(|| overflow(format!("pow({base}, {exp})")))?),
+            (Int(base), Int(exp)) => finite((base as f64).powf(exp as f64))?,
+            (Float(base), Int(exp)) => finite(base.powf(exp as f64))?,
+            (Int(base), Float(exp)) => finite((base as f64).powf(exp))?,
+            (Float(base), Float(exp)) => finite(base.powf(exp))?,
+            (Int(_) | Float(_), otherwise) | (otherwise, _) => {
+                return Err(bad_argument(function, &otherwise))
+            }
+        },
+        "min" | "max" => {
+            let mut best = arg();
+            for arg in args {
+                let ordering = compare(&arg, &best)?;
+                if (function == "min" && ordering.is_lt())
+                    || (function == "max" && ordering.is_gt())
+                {
+                    best = arg;
+                }
+            }
+            best
+        }
+        "coalesce" => args.find(|arg| *arg != Null).unwrap_or(Null),
+        "is_null" => Bool(arg() == Null),
+        _ => {
+            return Err(failed_crate!(
+                target: Release,
+                "unknown function '{}'",
+                function
+            ))
+        }
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::super::parser::Parser;
+    use super::*;
+
+    use chrono::NaiveDate;
+
+    fn eval(src: &str) -> Result<Operand, Error> {
+        let variables = BTreeMap::from([
+            ("quantity".to_string(), Operand::Int(3)),
+            ("unit_price".to_string(), Operand::Float(2.5)),
+            ("first".to_string(), Operand::String("ada".to_string())),
+            ("last".to_string(), Operand::String("Lovelace".to_string())),
+            (
+                "born".to_string(),
+                Operand::DateTime(ChronoValueAndFormat {
+                    value: ChronoValue::NaiveDate(NaiveDate::from_ymd_opt(1990, 6, 15).unwrap()),
+                    format: Arc::from("%Y-%m-%d"),
+                }),
+            ),
+            ("missing".to_string(), Operand::Null),
+        ]);
+        Scope {
+            variables: &variables,
+        }
+        .eval(&Parser::parse(src).unwrap())
+    }
+
+    fn date(s: &str) -> Operand {
+        Operand::DateTime(ChronoValueAndFormat {
+            value: ChronoValue::NaiveDate(NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()),
+            format: Arc::from("%Y-%m-%d"),
+        })
+    }
+
+    #[test]
+    fn eval_numbers() {
+        assert_eq!(eval("quantity * unit_price").unwrap(), Operand::Float(7.5));
+        assert_eq!(eval("quantity * 2 - 1").unwrap(), Operand::Int(5));
+        assert_eq!(eval("7 / 2").unwrap(), Operand::Float(3.5));
+        assert_eq!(eval("7 % 2").unwrap(), Operand::Int(1));
+        assert_eq!(eval("round(10 / 3, 2)").unwrap(), Operand::Float(3.33));
+        assert_eq!(eval("floor(-1.5)").unwrap(), Operand::Int(-2));
+        assert_eq!(eval("pow(2, 10)").unwrap(), Operand::Int(1024));
+        assert_eq!(eval("max(1, 2.5, quantity)").unwrap(), Operand::Int(3));
+        assert_eq!(eval("int('42') + float(1)").unwrap(), Operand::Float(43.));
+
+        assert!(eval("1 / 0").is_err());
+        assert!(eval("quantity % 0").is_err());
+        assert!(eval("9223372036854775807 + 1").is_err());
+        assert!(eval("quantity + first").is_err());
+    }
+
+    #[test]
+    fn eval_strings() {
+        assert_eq!(
+            eval("upper(first) + ' ' + last").unwrap(),
+            Operand::String("ADA Lovelace".to_string())
+        );
+        assert_eq!(
+            eval("concat(last, '-', quantity, '-', born)").unwrap(),
+            Operand::String("Lovelace-3-1990-06-15".to_string())
+        );
+        assert_eq!(eval("len(last)").unwrap(), Operand::Int(8));
+        assert_eq!(
+            eval("substr(replace(last, 'Love', 'LOVE'), 1, 4)").unwrap(),
+            Operand::String("OVEl".to_string())
+        );
+    }
+
+    #[test]
+    fn eval_dates() {
+        assert_eq!(
+            eval("born + duration('2 days')").unwrap(),
+            date("1990-06-17")
+        );
+        assert_eq!(
+            eval("days(date('1990-07-01') - born)").unwrap(),
+            Operand::Int(16)
+        );
+        assert_eq!(
+            eval("years(date('2020-06-14') - born)").unwrap(),
+            Operand::Int(29)
+        );
+        assert_eq!(
+            eval("year(born) * 100 + month(born)").unwrap(),
+            Operand::Int(199006)
+        );
+        assert_eq!(
+            eval("date('15/06/1990', '%d/%m/%Y') == born").unwrap(),
+            Operand::Bool(true)
+        );
+        assert_eq!(
+            eval("years(now() - born) > 30").unwrap(),
+            Operand::Bool(true)
+        );
+
+        assert!(eval("now() - born").unwrap().into_value().is_err());
+        assert!(eval("born + 1").is_err());
+        assert!(eval("date('yesterday')").is_err());
+    }
+
+    #[test]
+    fn eval_logic() {
+        assert_eq!(
+            eval("quantity > 2 && !(last == 'Babbage') ? 'bulk' : 'single'").unwrap(),
+            Operand::String("bulk".to_string())
+        );
+        assert_eq!(eval("missing == null").unwrap(), Operand::Bool(true));
+        assert_eq!(eval("quantity != null").unwrap(), Operand::Bool(true));
+        assert_eq!(
+            eval("coalesce(missing, first)").unwrap(),
+            eval("first").unwrap()
+        );
+        // Short circuits before the type error on the right
+        assert_eq!(eval("false && quantity").unwrap(), Operand::Bool(false));
+
+        assert!(eval("quantity ? 1 : 2").is_err());
+        assert!(eval("first < quantity").is_err());
+        assert!(eval("'a' == 1").is_err());
+    }
+}
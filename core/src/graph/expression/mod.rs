@@ -0,0 +1,117 @@
+use super::prelude::*;
+
+use std::collections::BTreeMap;
+
+mod eval;
+mod parser;
+mod typing;
+
+use eval::{Operand, Scope, FUNCTIONS};
+use parser::{Expr, Parser};
+
+pub use typing::ExpressionType;
+
+/// The types of the values `expression` can evaluate to when its variables have the types of
+/// `arguments`.
+pub fn expression_types(
+    expression: &str,
+    arguments: &BTreeMap<String, Vec<ExpressionType>>,
+) -> Result<Vec<ExpressionType>, Error> {
+    Ok(typing::value_types(&Parser::parse(expression)?, arguments))
+}
+
+/// Computes a value from the values of its `arguments` every time it generates.
+pub struct ExpressionNode {
+    expr: Expr,
+    arguments: BTreeMap<String, Graph>,
+    value: Option<Value>,
+}
+
+impl ExpressionNode {
+    pub fn new(expression: &str, arguments: BTreeMap<String, Graph>) -> Result<Self, Error> {
+        let expr = Parser::parse(expression)?;
+
+        let mut errors = Vec::new();
+        expr.walk(&mut |expr| match expr {
+            Expr::Variable(name) if !arguments.contains_key(name) => errors.push(format!(
+                "unknown variable '{}'{}",
+                name,
+                suggest_closest(arguments.keys(), name).unwrap_or_default()
+            )),
+            Expr::Call(function, args) => {
+                match FUNCTIONS.iter().find(|(name, ..)| name == function) {
+                    None => errors.push(format!(
+                        "unknown function '{}'{}",
+                        function,
+                        suggest_closest(FUNCTIONS.iter().map(|(name, ..)| name), function)
+                            .unwrap_or_default()
+                    )),
+                    Some((_, min, max)) if args.len() < *min || args.len() > *max => {
+                        errors.push(format!(
+                            "wrong number of arguments to '{}': expected {}, got {}",
+                            function,
+                            match (min, max) {
+                                (min, max) if min == max => min.to_string(),
+                                (min, &usize::MAX) => format!("at least {min}"),
+                                (min, max) => format!("{min} to {max}"),
+                            },
+                            args.len()
+                        ))
+                    }
+                    Some(_) => {}
+                }
+            }
+            _ => {}
+        });
+
+        match errors.first() {
+            Some(error) => Err(failed_crate!(target: Release, "{}", error)),
+            None => Ok(Self {
+                expr,
+                arguments,
+                value: None,
+            }),
+        }
+    }
+
+    fn evaluate<R: Rng>(&mut self, rng: &mut R) -> Result<Value, Error> {
+        let variables = self
+            .arguments
+            .iter_mut()
+            .map(|(name, graph)| Ok((name.clone(), Operand::from_value(graph.complete(rng)?)?)))
+            .collect::<Result<BTreeMap<_, _>, Error>>()?;
+        Scope {
+            variables: &variables,
+        }
+        .eval(&self.expr)?
+        .into_value()
+    }
+}
+
+impl Generator for ExpressionNode {
+    type Yield = Token;
+
+    type Return = Result<Value, Error>;
+
+    fn next<R: Rng>(&mut self, rng: &mut R) -> GeneratorState<Self::Yield, Self::Return> {
+        if let Some(value) = self.value.take() {
+            return GeneratorState::Complete(Ok(value));
+        }
+
+        match self.evaluate(rng) {
+            Ok(value) => {
+                let primitive = match &value {
+                    Value::Null(()) => Primitive::Null(()),
+                    Value::Bool(boolean) => Primitive::Bool(*boolean),
+                    Value::Number(number) => Primitive::Number(*number),
+                    Value::String(string) => Primitive::String(string.clone()),
+                    Value::DateTime(date_time) => Primitive::String(date_time.format_to_string()),
+                    Value::Object(_) | Value::Array(_) => unreachable!("expressions are scalars"),
+                };
+                self.value = Some(value);
+                GeneratorState::Yielded(Token::Primitive(primitive))
+            }
+            Err(err) => GeneratorState::Complete(Err(err)),
+        }
+    }
+}
@@ -0,0 +1,537 @@
+//! Parser for the expression language of `expression` content.
+//!
+//! The grammar, from the lowest to the highest precedence:
+//!
+//! ```text
+//! expr       := or ("?" expr ":" expr)?
+//! or         := and ("||" and)*
+//! and        := equality ("&&" equality)*
+//! equality   := comparison (("==" | "!=") comparison)*
+//! comparison := term (("<" | "<=" | ">" | ">=") term)*
+//! term       := factor (("+" | "-") factor)*
+//! factor     := unary (("*" | "/" | "%") unary)*
+//! unary      := ("-" | "!") unary | primary
+//! primary    := number | string | "true" | "false" | "null"
+//!             | identifier | identifier "(" (expr ("," expr)*)? ")" | "(" expr ")"
+//! ```
+use super::super::prelude::*;
+
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+impl std::fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Rem => "%",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::And => "&&",
+            Self::Or => "||",
+        };
+        write!(f, "{op}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Variable(String),
+    Call(String, Vec<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Calls `f` with this expression and all of its subexpressions, parents first.
+    pub fn walk<F: FnMut(&Expr)>(&self, f: &mut F) {
+        f(self);
+        match self {
+            Self::Call(_, args) => args.iter().for_each(|arg| arg.walk(f)),
+            Self::Unary(_, expr) => expr.walk(f),
+            Self::Binary(_, left, right) => {
+                left.walk(f);
+                right.walk(f);
+            }
+            Self::Conditional(cond, then, otherwise) => {
+                cond.walk(f);
+                then.walk(f);
+                otherwise.walk(f);
+            }
+            Self::Null
+            | Self::Bool(_)
+            | Self::Int(_)
+            | Self::Float(_)
+            | Self::String(_)
+            | Self::Variable(_) => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Int(i64),
+    Float(f64),
+    String(String),
+    Ident(String),
+    Op(&'static str),
+    Eof,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(int) => write!(f, "{int}"),
+            Self::Float(float) => write!(f, "{float}"),
+            Self::String(string) => write!(f, "'{string}'"),
+            Self::Ident(ident) => write!(f, "{ident}"),
+            Self::Op(op) => write!(f, "{op}"),
+            Self::Eof => write!(f, "the end of the expression"),
+        }
+    }
+}
+
+/// Operators, longest first so that `<=` is not read as `<`.
+const OPERATORS: [&str; 19] = [
+    "==", "!=", "<=", ">=", "&&", "||", "+", "-", "*", "/", "%", "<", ">", "!", "?", ":", "(", ")",
+    ",",
+];
+
+struct Lexer<'a> {
+    src: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            chars: src.char_indices().peekable(),
+        }
+    }
+
+    fn tokenize(mut self) -> Result<Vec<(usize, Token)>, Error> {
+        let mut tokens = Vec::new();
+        loop {
+            while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+
+            let (pos, c) = match self.chars.peek() {
+                Some(&next) => next,
+                None => {
+                    tokens.push((self.src.len(), Token::Eof));
+                    return Ok(tokens);
+                }
+            };
+
+            let token = if c.is_ascii_digit() {
+                self.number(pos)?
+            } else if c == '\'' || c == '"' {
+                self.string(pos, c)?
+            } else if c.is_alphabetic() || c == '_' {
+                let mut end = pos;
+                while let Some((i, c)) = self
+                    .chars
+                    .next_if(|(_, c)| c.is_alphanumeric() || *c == '_')
+                {
+                    end = i + c.len_utf8();
+                }
+                Token::Ident(self.src[pos..end].to_string())
+            } else {
+                let op = OPERATORS
+                    .iter()
+                    .find(|op| self.src[pos..].starts_with(*op))
+                    .ok_or_else(|| {
+                        failed_crate!(
+                            target: Release,
+                            "unexpected character '{}' at position {}",
+                            c,
+                            pos
+                        )
+                    })?;
+                for _ in 0..op.len() {
+                    self.chars.next();
+                }
+                Token::Op(op)
+            };
+            tokens.push((pos, token));
+        }
+    }
+
+    fn number(&mut self, start: usize) -> Result<Token, Error> {
+        let mut end = start;
+        let mut is_float = false;
+        while let Some(&(i, c)) = self.chars.peek() {
+            let is_fraction = c == '.'
+                && !is_float
+                && self.src[i + 1..].starts_with(|c: char| c.is_ascii_digit());
+            if !(c.is_ascii_digit() || c == '_' || is_fraction) {
+                break;
+            }
+            is_float |= is_fraction;
+            end = i + 1;
+            self.chars.next();
+        }
+
+        let literal = self.src[start..end].replace('_', "");
+        let invalid = |err: &dyn std::fmt::Display| {
+            failed_crate!(
+                target: Release,
+                "invalid number '{}' at position {}: {}",
+                literal,
+                start,
+                err
+            )
+        };
+        if is_float {
+            literal
+                .parse()
+                .map(Token::Float)
+                .map_err(|err| invalid(&err))
+        } else {
+            literal.parse().map(Token::Int).map_err(|err| invalid(&err))
+        }
+    }
+
+    fn string(&mut self, start: usize, quote: char) -> Result<Token, Error> {
+        self.chars.next();
+        let mut string = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, c)) if c == quote => return Ok(Token::String(string)),
+                Some((_, '\\')) => match self.chars.next() {
+                    Some((_, 'n')) => string.push('\n'),
+                    Some((_, 't')) => string.push('\t'),
+                    Some((_, c)) => string.push(c),
+                    None => break,
+                },
+                Some((_, c)) => string.push(c),
+                None => break,
+            }
+        }
+        Err(failed_crate!(
+            target: Release,
+            "unterminated string starting at position {}",
+            start
+        ))
+    }
+}
+
+pub struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+}
+
+impl Parser {
+    pub fn parse(src: &str) -> Result<Expr, Error> {
+        let mut parser = Self {
+            tokens: Lexer::new(src).tokenize()?,
+            pos: 0,
+        };
+        let expr = parser.expr()?;
+        match parser.peek() {
+            Token::Eof => Ok(expr),
+            _ => Err(parser.unexpected()),
+        }
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].1
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.pos].1.clone();
+        if token != Token::Eof {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        if matches!(self.peek(), Token::Op(next) if *next == op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), Error> {
+        if self.eat(op) {
+            Ok(())
+        } else {
+            let (pos, token) = &self.tokens[self.pos];
+            Err(failed_crate!(
+                target: Release,
+                "expected '{}' but found {} at position {}",
+                op,
+                token,
+                pos
+            ))
+        }
+    }
+
+    fn unexpected(&self) -> Error {
+        let (pos, token) = &self.tokens[self.pos];
+        failed_crate!(
+            target: Release,
+            "unexpected {} at position {}",
+            token,
+            pos
+        )
+    }
+
+    fn expr(&mut self) -> Result<Expr, Error> {
+        let cond = self.or()?;
+        if self.eat("?") {
+            let then = self.expr()?;
+            self.expect(":")?;
+            let otherwise = self.expr()?;
+            Ok(Expr::Conditional(
+                Box::new(cond),
+                Box::new(then),
+                Box::new(otherwise),
+            ))
+        } else {
+            Ok(cond)
+        }
+    }
+
+    /// Parses a left associative chain of the binary operators `ops` between operands parsed by
+    /// `operand`.
+    fn binary(
+        &mut self,
+        ops: &[(&str, BinaryOp)],
+        operand: fn(&mut Self) -> Result<Expr, Error>,
+    ) -> Result<Expr, Error> {
+        let mut left = operand(self)?;
+        'chain: loop {
+            for (symbol, op) in ops {
+                if self.eat(symbol) {
+                    left = Expr::Binary(*op, Box::new(left), Box::new(operand(self)?));
+                    continue 'chain;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, Error> {
+        self.binary(&[("||", BinaryOp::Or)], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Expr, Error> {
+        self.binary(&[("&&", BinaryOp::And)], Self::equality)
+    }
+
+    fn equality(&mut self) -> Result<Expr, Error> {
+        self.binary(
+            &[("==", BinaryOp::Eq), ("!=", BinaryOp::Ne)],
+            Self::comparison,
+        )
+    }
+
+    fn comparison(&mut self) -> Result<Expr, Error> {
+        self.binary(
+            &[
+                ("<=", BinaryOp::Le),
+                (">=", BinaryOp::Ge),
+                ("<", BinaryOp::Lt),
+                (">", BinaryOp::Gt),
+            ],
+            Self::term,
+        )
+    }
+
+    fn term(&mut self) -> Result<Expr, Error> {
+        self.binary(&[("+", BinaryOp::Add), ("-", BinaryOp::Sub)], Self::factor)
+    }
+
+    fn factor(&mut self) -> Result<Expr, Error> {
+        self.binary(
+            &[
+                ("*", BinaryOp::Mul),
+                ("/", BinaryOp::Div),
+                ("%", BinaryOp::Rem),
+            ],
+            Self::unary,
+        )
+    }
+
+    fn unary(&mut self) -> Result<Expr, Error> {
+        if self.eat("-") {
+            Ok(Expr::Unary(UnaryOp::Neg, Box::new(self.unary()?)))
+        } else if self.eat("!") {
+            Ok(Expr::Unary(UnaryOp::Not, Box::new(self.unary()?)))
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, Error> {
+        match self.peek().clone() {
+            Token::Int(int) => {
+                self.advance();
+                Ok(Expr::Int(int))
+            }
+            Token::Float(float) => {
+                self.advance();
+                Ok(Expr::Float(float))
+            }
+            Token::String(string) => {
+                self.advance();
+                Ok(Expr::String(string))
+            }
+            Token::Ident(ident) => {
+                self.advance();
+                match ident.as_str() {
+                    "true" => Ok(Expr::Bool(true)),
+                    "false" => Ok(Expr::Bool(false)),
+                    "null" => Ok(Expr::Null),
+                    _ if self.eat("(") => {
+                        let mut args = Vec::new();
+                        if !self.eat(")") {
+                            loop {
+                                args.push(self.expr()?);
+                                if self.eat(")") {
+                                    break;
+                                }
+                                self.expect(",")?;
+                            }
+                        }
+                        Ok(Expr::Call(ident, args))
+                    }
+                    _ => Ok(Expr::Variable(ident)),
+                }
+            }
+            Token::Op("(") => {
+                self.advance();
+                let expr = self.expr()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn var(name: &str) -> Box<Expr> {
+        Box::new(Expr::Variable(name.to_string()))
+    }
+
+    #[test]
+    fn parse_precedence() {
+        assert_eq!(
+            Parser::parse("a + b * 2").unwrap(),
+            Expr::Binary(
+                BinaryOp::Add,
+                var("a"),
+                Box::new(Expr::Binary(
+                    BinaryOp::Mul,
+                    var("b"),
+                    Box::new(Expr::Int(2))
+                ))
+            )
+        );
+        assert_eq!(
+            Parser::parse("a - b - c").unwrap(),
+            Expr::Binary(
+                BinaryOp::Sub,
+                Box::new(Expr::Binary(BinaryOp::Sub, var("a"), var("b"))),
+                var("c")
+            )
+        );
+        assert_eq!(
+            Parser::parse("!done && -x <= 1.5 ? 'yes' : null").unwrap(),
+            Expr::Conditional(
+                Box::new(Expr::Binary(
+                    BinaryOp::And,
+                    Box::new(Expr::Unary(UnaryOp::Not, var("done"))),
+                    Box::new(Expr::Binary(
+                        BinaryOp::Le,
+                        Box::new(Expr::Unary(UnaryOp::Neg, var("x"))),
+                        Box::new(Expr::Float(1.5))
+                    ))
+                )),
+                Box::new(Expr::String("yes".to_string())),
+                Box::new(Expr::Null)
+            )
+        );
+    }
+
+    #[test]
+    fn parse_calls_and_literals() {
+        assert_eq!(
+            Parser::parse(r#"concat(upper(first), " it\'s ", 1_000)"#).unwrap(),
+            Expr::Call(
+                "concat".to_string(),
+                vec![
+                    Expr::Call("upper".to_string(), vec![*var("first")]),
+                    Expr::String(" it's ".to_string()),
+                    Expr::Int(1000)
+                ]
+            )
+        );
+        assert_eq!(
+            Parser::parse("now()").unwrap(),
+            Expr::Call("now".to_string(), vec![])
+        );
+
+        let mut variables = Vec::new();
+        Parser::parse("a > 1 ? f(b, (c)) : d")
+            .unwrap()
+            .walk(&mut |expr| {
+                if let Expr::Variable(name) = expr {
+                    variables.push(name.clone())
+                }
+            });
+        assert_eq!(variables, vec!["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        for src in [
+            "", "a +", "(a", "f(a b)", "'open", "a # b", "a b", "a ? b", "1.2.3",
+        ] {
+            assert!(Parser::parse(src).is_err(), "parsed '{src}'");
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! Tells the types of the values an expression can evaluate to from the types of its arguments,
+//! without evaluating it, mirroring how [`Scope::eval`](super::eval::Scope::eval) computes them.
+use super::parser::{BinaryOp, Expr, UnaryOp};
+use crate::schema::ChronoValueType;
+
+use std::collections::BTreeMap;
+
+/// The type of a value an expression computes with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExpressionType {
+    Null,
+    Bool,
+    Int,
+    Float,
+    String,
+    /// A date/time, of a type which cannot be told when it is parsed with a given format
+    DateTime(Option<ChronoValueType>),
+    Duration,
+}
+
+/// Adds the types of `from` missing in `to`.
+fn union(to: &mut Vec<ExpressionType>, from: impl IntoIterator<Item = ExpressionType>) {
+    for type_ in from {
+        if !to.contains(&type_) {
+            to.push(type_);
+        }
+    }
+}
+
+/// The types `expr` can evaluate to when its variables have the types of `arguments`. Operations
+/// that always fail on some types contribute nothing for them.
+pub fn value_types(
+    expr: &Expr,
+    arguments: &BTreeMap<String, Vec<ExpressionType>>,
+) -> Vec<ExpressionType> {
+    use ExpressionType::*;
+    let types_of = |expr: &Expr| value_types(expr, arguments);
+    match expr {
+        Expr::Null => vec![Null],
+        Expr::Bool(_) => vec![Bool],
+        Expr::Int(_) => vec![Int],
+        Expr::Float(_) => vec![Float],
+        Expr::String(_) => vec![String],
+        Expr::Variable(name) => arguments.get(name).cloned().unwrap_or_default(),
+        Expr::Unary(UnaryOp::Not, _) => vec![Bool],
+        Expr::Unary(UnaryOp::Neg, expr) => types_of(expr)
+            .into_iter()
+            .filter(|type_| matches!(type_, Int | Float | Duration))
+            .collect(),
+        Expr::Binary(
+            BinaryOp::And
+            | BinaryOp::Or
+            | BinaryOp::Eq
+            | BinaryOp::Ne
+            | BinaryOp::Lt
+            | BinaryOp::Le
+            | BinaryOp::Gt
+            | BinaryOp::Ge,
+            _,
+            _,
+        ) => vec![Bool],
+        Expr::Binary(op, left, right) => {
+            let right = types_of(right);
+            let mut types = Vec::new();
+            for left in types_of(left) {
+                union(
+                    &mut types,
+                    right
+                        .iter()
+                        .filter_map(|right| arithmetic_type(*op, left, *right)),
+                );
+            }
+            types
+        }
+        Expr::Conditional(_, then, otherwise) => {
+            let mut types = types_of(then);
+            union(&mut types, types_of(otherwise));
+            types
+        }
+        Expr::Call(function, args) => {
+            let args = args.iter().map(types_of).collect::<Vec<_>>();
+            call_types(function, args)
+        }
+    }
+}
+
+fn arithmetic_type(
+    op: BinaryOp,
+    left: ExpressionType,
+    right: ExpressionType,
+) -> Option<ExpressionType> {
+    use BinaryOp::*;
+    use ExpressionType::{DateTime, Duration, Float, Int};
+    match (op, left, right) {
+        (Div, Int, Int) => Some(Float),
+        (_, Int, Int) => Some(Int),
+        (_, Int | Float, Int | Float) => Some(Float),
+        (Add, ExpressionType::String, ExpressionType::String) => Some(ExpressionType::String),
+        (Add, DateTime(type_), Duration)
+        | (Add, Duration, DateTime(type_))
+        | (Sub, DateTime(type_), Duration) => Some(DateTime(type_)),
+        (Sub, DateTime(_), DateTime(_)) => Some(Duration),
+        (Add | Sub, Duration, Duration) | (Mul, Duration, Int) | (Mul, Int, Duration) => {
+            Some(Duration)
+        }
+        _ => None,
+    }
+}
+
+fn call_types(function: &str, args: Vec<Vec<ExpressionType>>) -> Vec<ExpressionType> {
+    use ExpressionType::*;
+    let numbers = |index: usize| {
+        args.get(index)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|type_| matches!(type_, Int | Float))
+            .collect::<Vec<_>>()
+    };
+    match function {
+        "now" => vec![DateTime(Some(ChronoValueType::DateTime))],
+        "date" if args.len() == 1 => vec![DateTime(Some(ChronoValueType::NaiveDate))],
+        "date" => vec![DateTime(None)],
+        "duration" => vec![Duration],
+        "years" | "days" | "hours" | "minutes" | "seconds" | "year" | "month" | "day" | "len"
+        | "int" | "floor" | "ceil" => vec![Int],
+        "upper" | "lower" | "trim" | "substr" | "replace" | "concat" | "str" => vec![String],
+        "float" => vec![Float],
+        "round" if args.len() == 1 => vec![Int],
+        "round" => vec![Float],
+        "abs" => args
+            .first()
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|type_| matches!(type_, Int | Float | Duration))
+            .collect(),
+        // A negative integer exponent gives a float, which is not told apart here
+        "pow" if numbers(0) == [Int] && numbers(1) == [Int] => vec![Int],
+        "pow" => vec![Float],
+        "min" | "max" => {
+            let mut types = Vec::new();
+            for arg in args {
+                union(&mut types, arg);
+            }
+            types
+        }
+        "coalesce" => {
+            let nullable = args.iter().all(|arg| arg.contains(&Null));
+            let mut types = Vec::new();
+            for arg in args {
+                union(&mut types, arg.into_iter().filter(|type_| *type_ != Null));
+            }
+            if nullable {
+                union(&mut types, [Null]);
+            }
+            types
+        }
+        "is_null" => vec![Bool],
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::super::parser::Parser;
+    use super::*;
+
+    fn types(src: &str) -> Vec<ExpressionType> {
+        let arguments = BTreeMap::from([
+            ("quantity".to_string(), vec![ExpressionType::Int]),
+            ("unit_price".to_string(), vec![ExpressionType::Float]),
+            ("name".to_string(), vec![ExpressionType::String]),
+            (
+                "nickname".to_string(),
+                vec![ExpressionType::String, ExpressionType::Null],
+            ),
+            (
+                "born".to_string(),
+                vec![ExpressionType::DateTime(Some(ChronoValueType::NaiveDate))],
+            ),
+        ]);
+        value_types(&Parser::parse(src).unwrap(), &arguments)
+    }
+
+    #[test]
+    fn types_of_expressions() {
+        use ExpressionType::*;
+        assert_eq!(types("quantity * 2"), vec![Int]);
+        assert_eq!(types("quantity / 2"), vec![Float]);
+        assert_eq!(types("round(quantity * unit_price, 2)"), vec![Float]);
+        assert_eq!(types("upper(name) + ' ' + name"), vec![String]);
+        assert_eq!(types("quantity > 5 ? 'bulk' : null"), vec![String, Null]);
+        assert_eq!(types("coalesce(nickname, name)"), vec![String]);
+        assert_eq!(types("coalesce(nickname)"), vec![String, Null]);
+        assert_eq!(
+            types("born + duration('365d')"),
+            vec![DateTime(Some(ChronoValueType::NaiveDate))]
+        );
+        assert_eq!(types("years(now() - born)"), vec![Int]);
+        assert_eq!(types("date(name, '%d/%m/%Y')"), vec![DateTime(None)]);
+        assert_eq!(types("name * 2"), vec![]);
+    }
+}
@@ -6,12 +6,13 @@ use anyhow::{Context, Result};
 use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use sqlx::mysql::MySqlTypeInfo;
 use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo};
+use sqlx::sqlite::{SqliteArgumentValue, SqliteTypeInfo};
 use sqlx::{encode::IsNull, Encode, Type};
-use sqlx::{MySql, Postgres};
+use sqlx::{MySql, Postgres, Sqlite};
 
 use synth_gen::prelude::*;
 
-use crate::compile::{Link, NamespaceCompiler};
+use crate::compile::{Link, NamespaceCompiler, Shard};
 
 pub mod prelude;
 use prelude::*;
@@ -22,13 +23,16 @@ pub use null::NullNode;
 pub mod string;
 pub use string::{Format, FormatArgs, RandFaker, RandomString, StringNode, Truncated, UuidGen};
 
+pub mod bound;
+pub use bound::{BoundNode, BoundValue};
+
 pub mod date_time;
 pub use date_time::{DateTimeNode, RandomDateTime};
 
 pub mod number;
 pub use number::{
-    Incrementing, NumberNode, RandomF64, RandomI64, RandomU64, StandardFloatRangeStep,
-    StandardIntRangeStep,
+    Incrementing, LinkedRangeStep, NumberNode, RandomF64, RandomI64, RandomU64,
+    StandardFloatRangeStep, StandardIntRangeStep,
 };
 
 pub mod boolean;
@@ -37,8 +41,14 @@ pub use boolean::{BoolNode, RandomBool};
 pub mod iter;
 pub use iter::IterNode;
 
+pub mod expression;
+pub use expression::{expression_types, ExpressionNode, ExpressionType};
+
+pub mod switch;
+pub use switch::SwitchNode;
+
 pub mod array;
-pub use array::ArrayNode;
+pub use array::{ArrayNode, OneToManyNode};
 
 pub mod object;
 pub use object::{KeyValueOrNothing, ObjectNode};
@@ -365,8 +375,19 @@ impl Type<MySql> for Value {
     }
 }
 
+impl Type<Sqlite> for Value {
+    fn type_info() -> SqliteTypeInfo {
+        <String as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(_ty: &SqliteTypeInfo) -> bool {
+        unreachable!("This should never happen. Please reach out to https://github.com/getsynth/synth/issues if it does.")
+    }
+}
+
 impl Value {
-    fn to_postgres_string(&self) -> String {
+    /// The text representation Postgres parses array values from.
+    pub fn to_postgres_string(&self) -> String {
         match self {
             Self::Array(arr) => {
                 let (typ, _) = self.get_postgres_type();
@@ -389,7 +410,7 @@ impl Value {
                 Number::F64(f64) => (*f64).to_string(),
                 _ => num.to_string(),
             },
-            Self::String(str) => format!("\"{str}\""),
+            Self::String(str) => format!("\"{}\"", str.replace('\\', "\\\\").replace('"', "\\\"")),
             Self::DateTime(date) => date.format_to_string(),
             Self::Object(_) => {
                 serde_json::to_string(&json::synth_val_to_json(self.clone())).unwrap()
@@ -594,6 +615,54 @@ impl Encode<'_, MySql> for Value {
     }
 }
 
+/// SQLite only has integer, real, text and blob storage classes, so anything that does not fit an
+/// `i64` or `f64` is stored as text.
+impl<'q> Encode<'q, Sqlite> for Value {
+    fn encode_by_ref(&self, buf: &mut Vec<SqliteArgumentValue<'q>>) -> IsNull {
+        match self {
+            Value::Null(_) => IsNull::Yes,
+            Value::Bool(b) => <bool as Encode<'_, Sqlite>>::encode_by_ref(b, buf),
+            Value::Number(num) => match *num {
+                Number::I8(i) => <i64 as Encode<'_, Sqlite>>::encode(i.into(), buf),
+                Number::I16(i) => <i64 as Encode<'_, Sqlite>>::encode(i.into(), buf),
+                Number::I32(i) => <i64 as Encode<'_, Sqlite>>::encode(i.into(), buf),
+                Number::I64(i) => <i64 as Encode<'_, Sqlite>>::encode(i, buf),
+                Number::U8(i) => <i64 as Encode<'_, Sqlite>>::encode(i.into(), buf),
+                Number::U16(i) => <i64 as Encode<'_, Sqlite>>::encode(i.into(), buf),
+                Number::U32(i) => <i64 as Encode<'_, Sqlite>>::encode(i.into(), buf),
+                Number::U64(i) => match i64::try_from(i) {
+                    Ok(i) => <i64 as Encode<'_, Sqlite>>::encode(i, buf),
+                    Err(_) => <String as Encode<'_, Sqlite>>::encode(i.to_string(), buf),
+                },
+                Number::I128(_) | Number::U128(_) => {
+                    <String as Encode<'_, Sqlite>>::encode(num.to_string(), buf)
+                }
+                Number::F32(f) => <f64 as Encode<'_, Sqlite>>::encode(f.into_inner().into(), buf),
+                Number::F64(f) => <f64 as Encode<'_, Sqlite>>::encode(f.into_inner(), buf),
+            },
+            Value::String(s) => <String as Encode<'_, Sqlite>>::encode(s.clone(), buf),
+            Value::DateTime(ChronoValueAndFormat { value, .. }) => match value {
+                ChronoValue::NaiveDate(nd) => {
+                    <NaiveDate as Encode<'_, Sqlite>>::encode_by_ref(nd, buf)
+                }
+                ChronoValue::NaiveTime(nt) => {
+                    <NaiveTime as Encode<'_, Sqlite>>::encode_by_ref(nt, buf)
+                }
+                ChronoValue::NaiveDateTime(ndt) => {
+                    <NaiveDateTime as Encode<'_, Sqlite>>::encode_by_ref(ndt, buf)
+                }
+                ChronoValue::DateTime(dt) => {
+                    <DateTime<FixedOffset> as Encode<'_, Sqlite>>::encode_by_ref(dt, buf)
+                }
+            },
+            Value::Object(_) | Value::Array(_) => <String as Encode<'_, Sqlite>>::encode(
+                json::synth_val_to_json(self.clone()).to_string(),
+                buf,
+            ),
+        }
+    }
+}
+
 #[allow(unused)]
 impl Value {
     pub fn is_null(&self) -> bool {
@@ -774,12 +843,15 @@ derive_generator!(
         DateTime(DateTimeNode),
         Object(ObjectNode),
         Array(ArrayNode),
+        OneToMany(OneToManyNode),
         OneOf(OneOfNode),
         Series(SeriesNode),
         Unique(UniqueNode),
         Link(Box<LinkNode>),
         Hidden(Box<Graph>),
         Iter(IterNode),
+        Expression(ExpressionNode),
+        Switch(SwitchNode),
     }
 );
 
@@ -860,7 +932,12 @@ impl Graph {
     }
 
     pub fn from_namespace(ns: &Namespace) -> Result<Self> {
+        Self::from_namespace_sharded(ns, Shard::default())
+    }
+
+    pub fn from_namespace_sharded(ns: &Namespace, shard: Shard) -> Result<Self> {
         NamespaceCompiler::new(ns)
+            .with_shard(shard)
             .compile()
             .context("cannot compile the namespace")
     }
@@ -5,6 +5,8 @@ use rand::distributions::uniform::SampleRange;
 
 use num::{CheckedAdd, One, Zero};
 
+use crate::compile::Shard;
+
 use std::ops::{Bound, RangeBounds};
 
 /// A custom extension of all the range types that uses
@@ -293,6 +295,191 @@ macro_rules! standard_float_range_step_impl {
 standard_float_range_step_impl! { f32 }
 standard_float_range_step_impl! { f64 }
 
+/// The distributions of [`NumberDistribution`](crate::schema::NumberDistribution), sampled as
+/// `f64`s.
+enum StandardDistribution {
+    Normal(rand_distr::Normal<f64>),
+    LogNormal(rand_distr::LogNormal<f64>),
+    Exponential(rand_distr::Exp<f64>),
+    Poisson(rand_distr::Poisson<f64>),
+    Zipf(rand_distr::Zipf<f64>),
+    /// Scaled from `[0, 1]` to `[low, high]`
+    Beta(rand_distr::Beta<f64>, f64, f64),
+    /// Picks one of the `[low, high)` buckets by weight, rounding down for integers
+    Histogram {
+        index: rand::distributions::WeightedIndex<f64>,
+        buckets: Vec<(f64, f64)>,
+        integers: bool,
+    },
+}
+
+impl Distribution<f64> for StandardDistribution {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        match self {
+            Self::Normal(dist) => dist.sample(rng),
+            Self::LogNormal(dist) => dist.sample(rng),
+            Self::Exponential(dist) => dist.sample(rng),
+            Self::Poisson(dist) => dist.sample(rng),
+            Self::Zipf(dist) => dist.sample(rng),
+            Self::Beta(dist, low, high) => low + dist.sample(rng) * (high - low),
+            Self::Histogram {
+                index,
+                buckets,
+                integers,
+            } => {
+                let (low, high) = buckets[index.sample(rng)];
+                let num = if low < high {
+                    rng.gen_range(low..high)
+                } else {
+                    low
+                };
+                if *integers {
+                    num.floor()
+                } else {
+                    num
+                }
+            }
+        }
+    }
+}
+
+pub struct ClampedDistribution<N> {
+    dist: StandardDistribution,
+    low: Option<N>,
+    high: Option<N>,
+}
+
+/// Macroed implementation of [`Distribution`](rand::distributions::Distribution) for the
+/// distributions of [`NumberDistribution`](crate::schema::NumberDistribution).
+///
+/// Samples are rounded with `$round` and then cast to `$target`, which saturates at the bounds of
+/// `$target`, before being clamped to `low` and `high`. Histograms of `$integers` round down
+/// instead, so that every bucket holds the integers up to but excluding its `high`.
+macro_rules! clamped_distribution_impl {
+    { $target:ty, $round:expr, $integers:literal } => {
+        impl ClampedDistribution<$target> {
+            pub fn try_from_distribution(dist: NumberDistribution<$target>) -> anyhow::Result<Self> {
+                let (low, high) = (dist.low().cloned(), dist.high().cloned());
+                if let (Some(low), Some(high)) = (low, high) {
+                    if low > high {
+                        return Err(anyhow!("{} distribution cannot have 'low'={} > 'high'={}", stringify!($target), low, high));
+                    }
+                }
+
+                let invalid = |name: &str, err: &dyn std::fmt::Display| {
+                    anyhow!("{} distribution has invalid parameters: {}", name, err)
+                };
+
+                let dist = match dist {
+                    NumberDistribution::Normal(normal) => StandardDistribution::Normal(
+                        rand_distr::Normal::new(normal.mean, normal.sd).map_err(|err| invalid("normal", &err))?,
+                    ),
+                    NumberDistribution::LogNormal(log_normal) => StandardDistribution::LogNormal(
+                        rand_distr::LogNormal::new(log_normal.mean, log_normal.sd).map_err(|err| invalid("log_normal", &err))?,
+                    ),
+                    NumberDistribution::Exponential(exponential) => StandardDistribution::Exponential(
+                        rand_distr::Exp::new(exponential.rate).map_err(|err| invalid("exponential", &err))?,
+                    ),
+                    NumberDistribution::Poisson(poisson) => StandardDistribution::Poisson(
+                        rand_distr::Poisson::new(poisson.lambda).map_err(|err| invalid("poisson", &err))?,
+                    ),
+                    NumberDistribution::Zipf(zipf) => StandardDistribution::Zipf(
+                        rand_distr::Zipf::new(zipf.n, zipf.s).map_err(|err| invalid("zipf", &err))?,
+                    ),
+                    NumberDistribution::Beta(beta) => StandardDistribution::Beta(
+                        rand_distr::Beta::new(beta.alpha, beta.beta).map_err(|err| invalid("beta", &err))?,
+                        low.map(|low| low as f64).unwrap_or(0.),
+                        high.map(|high| high as f64).unwrap_or(1.),
+                    ),
+                    NumberDistribution::Histogram(histogram) => {
+                        let unbounded = |bucket: &&HistogramBucket| {
+                            !bucket.low.is_finite() || !bucket.high.is_finite() || bucket.low > bucket.high
+                        };
+                        if let Some(bucket) = histogram.buckets.iter().find(unbounded) {
+                            return Err(invalid("histogram", &format!("bucket ['low'={}, 'high'={}) is not a range", bucket.low, bucket.high)));
+                        }
+                        let index = rand::distributions::WeightedIndex::new(
+                            histogram.buckets.iter().map(|bucket| bucket.weight),
+                        )
+                        .map_err(|err| invalid("histogram", &err))?;
+                        StandardDistribution::Histogram {
+                            index,
+                            buckets: histogram.buckets.iter().map(|bucket| (bucket.low, bucket.high)).collect(),
+                            integers: $integers,
+                        }
+                    }
+                };
+
+                Ok(Self { dist, low, high })
+            }
+        }
+
+        impl Distribution<$target> for ClampedDistribution<$target> {
+            fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> $target {
+                let round: fn(f64) -> f64 = $round;
+                let mut num = round(self.dist.sample(rng)) as $target;
+                if let Some(low) = self.low {
+                    num = num.max(low);
+                }
+                if let Some(high) = self.high {
+                    num = num.min(high);
+                }
+                num
+            }
+        }
+    }
+}
+
+clamped_distribution_impl! { u32, f64::round, true }
+clamped_distribution_impl! { u64, f64::round, true }
+clamped_distribution_impl! { i16, f64::round, true }
+clamped_distribution_impl! { i32, f64::round, true }
+clamped_distribution_impl! { i64, f64::round, true }
+clamped_distribution_impl! { f32, |num| num, false }
+clamped_distribution_impl! { f64, |num| num, false }
+
+/// A [`RangeStep`](crate::schema::RangeStep) whose `low` and `high` are resolved again from the
+/// fields they reference every time it generates.
+pub struct LinkedRangeStep<N: BoundValue> {
+    low: Option<BoundNode<N>>,
+    high: Option<BoundNode<N>>,
+    step: Option<N>,
+    include_low: bool,
+    include_high: bool,
+}
+
+impl<N: BoundValue + Clone> LinkedRangeStep<N> {
+    pub fn new(
+        low: Option<BoundNode<N>>,
+        high: Option<BoundNode<N>>,
+        step: Option<N>,
+        include_low: bool,
+        include_high: bool,
+    ) -> Self {
+        Self {
+            low,
+            high,
+            step,
+            include_low,
+            include_high,
+        }
+    }
+
+    fn resolve<R: Rng>(&mut self, rng: &mut R) -> Result<RangeStep<N>, Error> {
+        Ok(RangeStep {
+            low: self.low.as_mut().map(|low| low.resolve(rng)).transpose()?,
+            high: self
+                .high
+                .as_mut()
+                .map(|high| high.resolve(rng))
+                .transpose()?,
+            step: self.step.clone(),
+            include_low: self.include_low,
+            include_high: self.include_high,
+        })
+    }
+}
+
 pub struct Incrementing<N = i64> {
     count: N,
     step: N,
@@ -335,6 +522,33 @@ impl<N> Incrementing<N> {
     }
 }
 
+impl<N> Incrementing<N>
+where
+    N: CheckedAdd + TryFrom<usize> + Copy,
+{
+    /// Starts counting for one of several [`Shard`](crate::compile::Shard)s: each shard is
+    /// offset from `start_at` by its index and steps over the values of all the others.
+    pub fn new_at_sharded(start_at: N, shard: Shard) -> anyhow::Result<Self> {
+        let cast = |n: usize| {
+            N::try_from(n).map_err(|_| {
+                failed!(
+                    target: Release,
+                    "cannot split an id across {} shards: try specifying the 'subtype' parameter with a larger numerical primitive (e.g. u64)",
+                    shard.count
+                )
+            })
+        };
+        let start_at = start_at.checked_add(&cast(shard.index)?).ok_or_else(|| {
+            failed!(
+                target: Release,
+                "id overflowed when split across {} shards",
+                shard.count
+            )
+        })?;
+        Ok(Self::new_at_by(start_at, cast(shard.count)?))
+    }
+}
+
 impl<N> Default for Incrementing<N>
 where
     N: Zero + One,
@@ -380,6 +594,8 @@ macro_rules! number_node {
             $rand:ident (
                 $range:ident<$dist:ty> as $new_range:ident,
                 $constant:ident as $new_constant:ident,
+                $distribution:ident as $new_distribution:ident,
+                $linked:ident as $new_linked:ident,
                 $(
                     $categorical:ident as $new_categorical:ident
                 )?,
@@ -404,6 +620,8 @@ macro_rules! number_node {
                 pub enum $rand {
                     $range(OnceInfallible<Random<$ty, $dist>>),
                     $constant(OnceInfallible<Yield<$ty>>),
+                    $distribution(OnceInfallible<Random<$ty, ClampedDistribution<$ty>>>),
+                    $linked(TryOnce<LinkedRangeStep<$ty>>),
                     $($categorical(OnceInfallible<Random<$ty, Categorical<$ty>>>),)?
                     $($incrementing(TryOnce<Incrementing<$ty>>),)?
                 }
@@ -419,6 +637,15 @@ macro_rules! number_node {
                     Self::$constant(Yield::wrap(value).infallible().try_once())
                 }
 
+                pub fn $new_distribution(dist: NumberDistribution<$ty>) -> Result<Self, anyhow::Error> {
+                    let dist = ClampedDistribution::<$ty>::try_from_distribution(dist)?;
+                    Ok(Self::$distribution(Random::new_with(dist).infallible().try_once()))
+                }
+
+                pub fn $new_linked(range: LinkedRangeStep<$ty>) -> Self {
+                    Self::$linked(range.try_once())
+                }
+
                 $(
                     pub fn $new_categorical(cat: Categorical<$ty>) -> Self {
                         Self::$categorical(Random::new_with(cat).infallible().try_once())
@@ -432,6 +659,23 @@ macro_rules! number_node {
                 )?
             }
 
+            impl Generator for LinkedRangeStep<$ty> {
+                type Yield = $ty;
+
+                type Return = Result<Never, Error>;
+
+                fn next<R: Rng>(&mut self, rng: &mut R) -> GeneratorState<Self::Yield, Self::Return> {
+                    let dist = self.resolve(rng).and_then(|range| {
+                        <$dist>::try_from_range(range)
+                            .map_err(|err| failed_crate!(target: Release, "{}", err))
+                    });
+                    match dist {
+                        Ok(dist) => GeneratorState::Yielded(dist.sample(rng)),
+                        Err(err) => GeneratorState::Complete(Err(err)),
+                    }
+                }
+            }
+
             impl From<$rand> for NumberNode {
                 fn from(value: $rand) -> Self {
                     Self::$rand(value.into_token().map_complete(value_from_ok_number::<$ty>))
@@ -445,38 +689,52 @@ number_node!(
     RandomU64 (
         U64Range<StandardIntRangeStep<u64, u64>> as range,
         U64Constant as constant,
+        U64Distribution as distribution,
+        U64Linked as linked,
         U64Categorical as categorical,
         Incrementing as incrementing,
     ) for u64,
     RandomI64 (
         I64Range<StandardIntRangeStep<u64, i128>> as range,
         I64Constant as constant,
+        I64Distribution as distribution,
+        I64Linked as linked,
         I64Categorical as categorical,
         Incrementing as incrementing,
     ) for i64,
     RandomF64 (
         F64Range<StandardFloatRangeStep<f64>> as range,
-        F64Constant as constant,,,
+        F64Constant as constant,
+        F64Distribution as distribution,
+        F64Linked as linked,,,
     ) for f64,
     RandomU32 (
         U32Range<StandardIntRangeStep<u32, u32>> as range,
         U32Constant as constant,
+        U32Distribution as distribution,
+        U32Linked as linked,
         U32Categorical as categorical,
         Incrementing as incrementing,
     ) for u32,
     RandomI32 (
         I32Range<StandardIntRangeStep<u32, i64>> as range,
         I32Constant as constant,
+        I32Distribution as distribution,
+        I32Linked as linked,
         I32Categorical as categorical,
         Incrementing as incrementing,
     ) for i32,
     RandomF32 (
         F32Range<StandardFloatRangeStep<f32>> as range,
-        F32Constant as constant,,,
+        F32Constant as constant,
+        F32Distribution as distribution,
+        F32Linked as linked,,,
     ) for f32,
     RandomI16 (
         I16Range<StandardIntRangeStep<u16, i32>> as range,
         I16Constant as constant,
+        I16Distribution as distribution,
+        I16Linked as linked,
         I16Categorical as categorical,
         Incrementing as incrementing,
     ) for i16,
@@ -504,4 +762,102 @@ pub mod test {
 
         assert!(incrementing.next(&mut rng).into_complete().is_err())
     }
+
+    #[test]
+    fn clamped_distributions() {
+        let mut rng = <rand::rngs::StdRng as rand::SeedableRng>::seed_from_u64(0);
+
+        let normal =
+            ClampedDistribution::<i32>::try_from_distribution(NumberDistribution::Normal(Normal {
+                mean: 40.,
+                sd: 12.,
+                low: Some(30),
+                high: Some(45),
+            }))
+            .unwrap();
+        let samples: Vec<i32> = (0..1000).map(|_| normal.sample(&mut rng)).collect();
+        assert!(samples.iter().all(|n| (30..=45).contains(n)));
+        assert!(samples.contains(&30) && samples.contains(&45));
+
+        let beta =
+            ClampedDistribution::<f64>::try_from_distribution(NumberDistribution::Beta(Beta {
+                alpha: 2.,
+                beta: 5.,
+                low: Some(10.),
+                high: Some(20.),
+            }))
+            .unwrap();
+        let mean = (0..1000).map(|_| beta.sample(&mut rng)).sum::<f64>() / 1000.;
+        assert!((12.5..13.2).contains(&mean), "{}", mean);
+
+        let zipf =
+            ClampedDistribution::<u64>::try_from_distribution(NumberDistribution::Zipf(Zipf {
+                n: 5,
+                s: 1.,
+                low: None,
+                high: None,
+            }))
+            .unwrap();
+        assert!((0..1000).all(|_| (1..=5).contains(&zipf.sample(&mut rng))));
+
+        assert!(
+            ClampedDistribution::<u32>::try_from_distribution(NumberDistribution::Poisson(
+                Poisson {
+                    lambda: -1.,
+                    low: None,
+                    high: None,
+                }
+            ))
+            .is_err()
+        );
+        assert!(ClampedDistribution::<u32>::try_from_distribution(
+            NumberDistribution::Exponential(Exponential {
+                rate: 1.,
+                low: Some(2),
+                high: Some(1),
+            })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn histograms() {
+        let mut rng = <rand::rngs::StdRng as rand::SeedableRng>::seed_from_u64(0);
+        fn histogram<N>(buckets: &[(f64, f64, f64)]) -> NumberDistribution<N> {
+            NumberDistribution::Histogram(Histogram {
+                buckets: buckets
+                    .iter()
+                    .map(|&(low, high, weight)| HistogramBucket { low, high, weight })
+                    .collect(),
+                low: None,
+                high: None,
+            })
+        }
+
+        let dist = ClampedDistribution::<u64>::try_from_distribution(histogram(&[
+            (0., 10., 1.),
+            (10., 20., 0.),
+            (100., 102., 3.),
+        ]))
+        .unwrap();
+        let samples: Vec<u64> = (0..1000).map(|_| dist.sample(&mut rng)).collect();
+        assert!(samples
+            .iter()
+            .all(|n| (0..10).contains(n) || (100..102).contains(n)));
+        assert!(samples.contains(&0) && samples.contains(&9) && samples.contains(&101));
+        let high = samples.iter().filter(|n| **n >= 100).count();
+        assert!((700..800).contains(&high), "{}", high);
+
+        let dist = ClampedDistribution::<f64>::try_from_distribution(histogram(&[(0.5, 0.75, 1.)]))
+            .unwrap();
+        assert!((0..1000).all(|_| (0.5..0.75).contains(&dist.sample(&mut rng))));
+
+        assert!(ClampedDistribution::<f64>::try_from_distribution(histogram(&[])).is_err());
+        assert!(
+            ClampedDistribution::<f64>::try_from_distribution(histogram(&[(1., 0., 1.)])).is_err()
+        );
+        assert!(
+            ClampedDistribution::<f64>::try_from_distribution(histogram(&[(0., 1., -1.)])).is_err()
+        );
+    }
 }
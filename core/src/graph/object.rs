@@ -8,6 +8,8 @@ pub struct KeyValueOrNothing {
     p: f64,
     active: bool,
     pub hidden: bool,
+    /// Whether the key is left out when the value is `null`
+    pub skip_null: bool,
     pub key: String,
 }
 
@@ -41,10 +43,18 @@ impl KeyValueOrNothing {
             p: freq,
             active: false,
             hidden: is_hidden,
+            skip_null: false,
             key: key.to_string(),
         }
     }
 
+    pub fn skipping_null(self) -> Self {
+        Self {
+            skip_null: true,
+            ..self
+        }
+    }
+
     pub fn always(key: &str, content: Graph, is_hidden: bool) -> Self {
         Self::new_with(key, content, 1.0, is_hidden)
     }
@@ -68,20 +78,23 @@ impl Generator for ObjectNode {
     type Return = Result<Value, Error>;
 
     fn next<R: Rng>(&mut self, rng: &mut R) -> GeneratorState<Self::Yield, Self::Return> {
-        let hidden_fields: std::collections::HashSet<String> = self
-            .0
-            .inner
-            .inner
-            .inners
+        let inners = &self.0.inner.inner.inners;
+        let hidden_fields: std::collections::HashSet<String> = inners
             .iter()
             .filter(|&p| p.hidden)
             .map(|p| p.key.clone())
             .collect();
+        let skip_null_fields: std::collections::HashSet<String> = inners
+            .iter()
+            .filter(|&p| p.skip_null)
+            .map(|p| p.key.clone())
+            .collect();
 
         self.0.next(rng).map_complete(|kv| {
             kv.into_iter()
                 .filter(|p| match p {
-                    Some((k, _)) => !hidden_fields.contains(k),
+                    Some((k, _)) if hidden_fields.contains(k) => false,
+                    Some((k, Ok(Value::Null(())))) => !skip_null_fields.contains(k),
                     _ => true,
                 })
                 .filter_map(|m_kv| m_kv.map(|(k, vr)| vr.map(|v| (k, v))))
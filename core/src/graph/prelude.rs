@@ -21,6 +21,7 @@ pub use crate::Error;
 pub use anyhow::Error as AnyhowError;
 
 pub use super::{
-    number_from_ok, value_from_ok, value_from_ok_number, Devaluize, Graph, JustToken,
-    OnceInfallible, OwnedDevaluize, SizeGenerator, StringGenerator, TokenOnce, Value, Valuize,
+    number_from_ok, value_from_ok, value_from_ok_number, BoundNode, BoundValue, Devaluize, Graph,
+    JustToken, OnceInfallible, OwnedDevaluize, SizeGenerator, StringGenerator, TokenOnce, Value,
+    Valuize,
 };
@@ -0,0 +1,70 @@
+use super::prelude::*;
+
+/// Generates from the case matching the value generated by `on`, or from the default.
+pub struct SwitchNode {
+    on: Box<Graph>,
+    keys: HashMap<String, usize>,
+    /// The cases, followed by the default
+    branches: Vec<Graph>,
+    active: Option<usize>,
+}
+
+impl SwitchNode {
+    pub fn new(on: Graph, cases: Vec<(String, Graph)>, default: Graph) -> Self {
+        let (keys, mut branches): (HashMap<_, _>, Vec<_>) = cases
+            .into_iter()
+            .enumerate()
+            .map(|(index, (key, graph))| ((key, index), graph))
+            .unzip();
+        branches.push(default);
+        Self {
+            on: Box::new(on),
+            keys,
+            branches,
+            active: None,
+        }
+    }
+
+    /// The case a value selects, which is the value as it would be written in JSON for strings,
+    /// numbers and date/times, and `true`, `false` or `null` otherwise.
+    fn case_of(value: Value) -> Result<String, Error> {
+        match value {
+            Value::Null(()) => Ok("null".to_string()),
+            Value::Bool(boolean) => Ok(boolean.to_string()),
+            Value::Object(_) | Value::Array(_) => Err(failed_crate!(
+                target: Release,
+                "cannot switch on the value of an {}",
+                value.type_().to_lowercase()
+            )),
+            value => value.try_into(),
+        }
+    }
+}
+
+impl Generator for SwitchNode {
+    type Yield = Token;
+
+    type Return = Result<Value, Error>;
+
+    fn next<R: Rng>(&mut self, rng: &mut R) -> GeneratorState<Self::Yield, Self::Return> {
+        let index = match self.active {
+            Some(index) => index,
+            None => match self.on.complete(rng).and_then(Self::case_of) {
+                Ok(case) => self
+                    .keys
+                    .get(&case)
+                    .copied()
+                    .unwrap_or(self.branches.len() - 1),
+                Err(err) => return GeneratorState::Complete(Err(err)),
+            },
+        };
+
+        let state = self.branches[index].next(rng);
+        self.active = if state.is_complete() {
+            None
+        } else {
+            Some(index)
+        };
+        state
+    }
+}
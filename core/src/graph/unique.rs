@@ -1,11 +1,22 @@
-use crate::graph::prelude::{Error, Rng, Token, TryFilterMap, TryGeneratorExt, Value};
+use crate::compile::link::GeneratorRewind;
+use crate::compile::Shard;
+use crate::graph::prelude::{
+    Error, Generator, GeneratorState, Number, Rng, Token, TryFilterMap, TryGeneratorExt, Value,
+};
 use crate::Graph;
 
-use std::collections::HashMap;
-use std::hash::BuildHasher;
+use bloomfilter::Bloom;
+
+use std::collections::hash_map::{DefaultHasher, RandomState};
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasher, Hash, Hasher};
 
 const MAX_RETRIES: usize = 64;
 
+/// The keys of the hashes of bloom filters, fixed so that the values they wrongly take to have
+/// been generated already, and so the values generated, are given by the seed.
+const BLOOM_SEED: [u8; 32] = [0; 32];
+
 type ValueFilter =
     TryFilterMap<Box<Graph>, Box<dyn FnMut(Value) -> Result<Option<Value>, Error>>, Value>;
 
@@ -14,13 +25,73 @@ derive_generator! {
     return Result<Value, Error>,
     pub enum UniqueNode {
         Hash(ValueFilter),
+        Bloom(ValueFilter),
+        Rows(UniqueRows),
+        Sequence(Sequence),
+    }
+}
+
+/// Lets through the values belonging to `shard`, giving up after `retries` times as many values
+/// as there are shards in a row belong to other shards.
+struct ShardFilter {
+    shard: Shard,
+    retries: usize,
+    foreign: usize,
+}
+
+impl ShardFilter {
+    fn new(shard: Shard, retries: usize) -> Self {
+        Self {
+            shard,
+            retries,
+            foreign: 0,
+        }
+    }
+
+    fn admits(&mut self, value: &Value) -> Result<bool, Error> {
+        if self.shard.is_single() {
+            return Ok(true);
+        }
+
+        // Values are assigned to shards with a fixed hasher so that every shard agrees
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        if hasher.finish() % self.shard.count as u64 == self.shard.index as u64 {
+            self.foreign = 0;
+            return Ok(true);
+        }
+
+        self.foreign += 1;
+        if self.foreign < self.retries * self.shard.count {
+            Ok(false)
+        } else {
+            Err(failed_crate!(
+                target: Release,
+                "Could not generate enough unique values for shard {} of {}: \
+                try reducing the number of threads or values generated",
+                self.shard.index,
+                self.shard.count
+            ))
+        }
     }
 }
 
 impl UniqueNode {
     pub fn hash(inner: Graph, retries: Option<usize>) -> Self {
+        Self::hash_sharded(inner, retries, Shard::default())
+    }
+
+    /// Like [`hash`](Self::hash), but only lets through the values belonging to `shard`, so that
+    /// no two shards can generate the same value.
+    pub fn hash_sharded(inner: Graph, retries: Option<usize>, shard: Shard) -> Self {
+        let retries = retries.unwrap_or(MAX_RETRIES);
         let mut seen: HashMap<u64, usize> = HashMap::new();
+        let mut shard = ShardFilter::new(shard, retries);
         let filter = move |value: Value| {
+            if !shard.admits(&value)? {
+                return Ok(None);
+            }
+
             let hash = seen.hasher().hash_one(&value);
 
             let count = seen
@@ -32,7 +103,7 @@ impl UniqueNode {
 
             match *count {
                 0 => Ok(Some(value)),
-                x if x < retries.unwrap_or(MAX_RETRIES) => Ok(None),
+                x if x < retries => Ok(None),
                 _ => Err(failed_crate!(
                     target: Release,
                     "Could not generate enough unique values from generator: \
@@ -42,6 +113,206 @@ impl UniqueNode {
         };
         Self::Hash(Box::new(inner).try_filter_map(Box::new(filter)))
     }
+
+    /// Like [`hash_sharded`](Self::hash_sharded), but remembers the values generated in a bloom
+    /// filter sized for `capacity` values, which takes a fixed amount of memory. Values are
+    /// wrongly taken to have been generated already at a rate of about `false_positive_rate`,
+    /// until more than `capacity` values are generated, after which the rate goes up.
+    pub fn bloom_sharded(
+        inner: Graph,
+        capacity: usize,
+        false_positive_rate: f64,
+        retries: Option<usize>,
+        shard: Shard,
+    ) -> Self {
+        let retries = retries.unwrap_or(MAX_RETRIES);
+        let mut seen = Bloom::new_for_fp_rate_with_seed(capacity, false_positive_rate, &BLOOM_SEED);
+        let mut shard = ShardFilter::new(shard, retries);
+        let mut collisions = 0;
+        let filter = move |value: Value| {
+            if !shard.admits(&value)? {
+                return Ok(None);
+            }
+
+            if !seen.check_and_set(&value) {
+                collisions = 0;
+                return Ok(Some(value));
+            }
+
+            collisions += 1;
+            if collisions < retries {
+                Ok(None)
+            } else {
+                Err(failed_crate!(
+                    target: Release,
+                    "Could not generate enough unique values from generator: \
+                    try reducing the number of values generated or increasing the `capacity`"
+                ))
+            }
+        };
+        Self::Bloom(Box::new(inner).try_filter_map(Box::new(filter)))
+    }
+
+    /// Generates the integers from `first` to `last` (included) every `step`, in order and each
+    /// only once, as numbers made by `number`. Each of the shards starts at a different integer
+    /// and steps over the integers of the others.
+    pub fn sequence(
+        first: i128,
+        last: i128,
+        step: i128,
+        number: fn(i128) -> Number,
+        shard: Shard,
+    ) -> Self {
+        Self::Sequence(Sequence {
+            next: first + step * shard.index as i128,
+            last,
+            step: step * shard.count as i128,
+            number,
+        })
+    }
+}
+
+/// The integers of [`UniqueNode::sequence`](UniqueNode::sequence), one at a time.
+pub struct Sequence {
+    next: i128,
+    last: i128,
+    step: i128,
+    number: fn(i128) -> Number,
+}
+
+impl Generator for Sequence {
+    type Yield = Token;
+
+    type Return = Result<Value, Error>;
+
+    fn next<R: Rng>(&mut self, _rng: &mut R) -> GeneratorState<Self::Yield, Self::Return> {
+        if self.next > self.last {
+            return GeneratorState::Complete(Err(failed_crate!(
+                target: Release,
+                "Could not generate enough unique values from the range: \
+                try reducing the number of values generated or widening the range"
+            )));
+        }
+        let next = self.next;
+        self.next += self.step;
+        GeneratorState::Complete(Ok(Value::Number((self.number)(next))))
+    }
+}
+
+/// Generates the objects of `inner`, generating a whole object again when the values of the
+/// fields of any of `keys` were already generated together, unless one of them is `null`.
+pub struct UniqueRows {
+    inner: Box<Graph>,
+    keys: Vec<Vec<Vec<String>>>,
+    seen: Vec<HashSet<u64>>,
+    hasher: RandomState,
+    retries: usize,
+    rewind: GeneratorRewind<Graph>,
+    shard: Shard,
+}
+
+impl UniqueRows {
+    fn value_at<'v>(value: &'v Value, path: &[String]) -> &'v Value {
+        path.iter()
+            .try_fold(value, |value, field| value.as_object()?.get(field))
+            .unwrap_or(&Value::Null(()))
+    }
+
+    fn generate<R: Rng>(&mut self, rng: &mut R) -> Result<Value, Error> {
+        // Rows must belong to this shard for every key, so they are rejected more often
+        let attempts = self.retries * self.shard.count.pow(self.keys.len() as u32);
+        for _ in 0..attempts {
+            let mark = self.rewind.mark();
+            let row = self.inner.complete(rng)?;
+
+            let mut hashes = Vec::with_capacity(self.keys.len());
+            let mut is_unique = true;
+            for (key, seen) in self.keys.iter().zip(&self.seen) {
+                let values = key
+                    .iter()
+                    .map(|path| Self::value_at(&row, path))
+                    .collect::<Vec<_>>();
+                if values.iter().any(|value| value.is_null()) {
+                    hashes.push(None);
+                    continue;
+                }
+
+                if !self.shard.is_single() {
+                    // Rows are assigned to shards with a fixed hasher so that every shard agrees
+                    let mut hasher = DefaultHasher::new();
+                    values.hash(&mut hasher);
+                    if hasher.finish() % self.shard.count as u64 != self.shard.index as u64 {
+                        is_unique = false;
+                        break;
+                    }
+                }
+
+                let hash = self.hasher.hash_one(&values);
+                is_unique &= !seen.contains(&hash);
+                hashes.push(Some(hash));
+            }
+
+            if is_unique {
+                for (seen, hash) in self.seen.iter_mut().zip(hashes) {
+                    seen.extend(hash);
+                }
+                return Ok(row);
+            }
+
+            // The fields of the rejected row that are referenced elsewhere must not be seen there
+            self.rewind.rewind(&mark);
+        }
+
+        Err(failed_crate!(
+            target: Release,
+            "Could not generate enough rows with unique {}: \
+            try reducing the number of values generated",
+            self.keys
+                .iter()
+                .map(|key| format!(
+                    "({})",
+                    key.iter()
+                        .map(|path| path.join("."))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+                .collect::<Vec<_>>()
+                .join(" and ")
+        ))
+    }
+}
+
+impl Generator for UniqueRows {
+    type Yield = Token;
+
+    type Return = Result<Value, Error>;
+
+    fn next<R: Rng>(&mut self, rng: &mut R) -> GeneratorState<Self::Yield, Self::Return> {
+        GeneratorState::Complete(self.generate(rng))
+    }
+}
+
+impl UniqueNode {
+    /// Generates the objects of `inner` such that the fields of each of `keys`, given as paths of
+    /// field names, never take the same values together twice.
+    ///
+    /// Everything recorded in `rewind` while generating a rejected object is forgotten.
+    pub fn rows(
+        inner: Graph,
+        keys: Vec<Vec<Vec<String>>>,
+        rewind: GeneratorRewind<Graph>,
+        shard: Shard,
+    ) -> Self {
+        Self::Rows(UniqueRows {
+            inner: Box::new(inner),
+            seen: vec![HashSet::new(); keys.len()],
+            keys,
+            hasher: RandomState::new(),
+            retries: MAX_RETRIES,
+            rewind,
+            shard,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -84,4 +355,103 @@ pub mod tests {
 
         assert!(output.iter().any(Result::is_err));
     }
+
+    #[test]
+    fn unique_node_sharded() {
+        let mut rng = rand::thread_rng();
+        let shards = (0..4)
+            .map(|index| {
+                let numbers = Graph::Number(NumberNode::from(
+                    RandomU64::range(RangeStep::new(0, NUM_GENERATED as u64, 1)).unwrap(),
+                ));
+                UniqueNode::hash_sharded(numbers, None, Shard::new(index, 4).unwrap())
+                    .repeat(NUM_GENERATED / 8)
+                    .complete(&mut rng)
+                    .into_iter()
+                    .collect::<Result<Vec<_>, _>>()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let all = shards
+            .iter()
+            .flatten()
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(all.len(), NUM_GENERATED / 2);
+    }
+
+    #[test]
+    fn unique_node_bloom() {
+        let mut rng = rand::thread_rng();
+        let numbers = Graph::Number(NumberNode::from(
+            RandomU64::range(RangeStep::new(0, 4 * NUM_GENERATED as u64, 1)).unwrap(),
+        ));
+        let output =
+            UniqueNode::bloom_sharded(numbers, NUM_GENERATED, 0.01, None, Shard::default())
+                .repeat(NUM_GENERATED)
+                .complete(&mut rng)
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+        let all = output.iter().collect::<std::collections::HashSet<_>>();
+        assert_eq!(all.len(), NUM_GENERATED);
+
+        let constant = Graph::Number(NumberNode::from(RandomU64::constant(44)));
+        let output = UniqueNode::bloom_sharded(constant, 16, 0.01, None, Shard::default())
+            .repeat(2)
+            .complete(&mut rng);
+        assert!(output[0].is_ok());
+        assert!(output[1].is_err());
+    }
+
+    #[test]
+    fn unique_node_bloom_seeded() {
+        // A filter this small takes many values to have been generated already
+        let generate = || {
+            let numbers = Graph::Number(NumberNode::from(
+                RandomU64::range(RangeStep::new(0, 4 * NUM_GENERATED as u64, 1)).unwrap(),
+            ));
+            let mut rng = <rand::rngs::StdRng as rand::SeedableRng>::seed_from_u64(0);
+            UniqueNode::bloom_sharded(numbers, NUM_GENERATED / 8, 0.2, None, Shard::default())
+                .repeat(NUM_GENERATED / 8)
+                .complete(&mut rng)
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        };
+        assert_eq!(generate(), generate());
+    }
+
+    #[test]
+    fn unique_node_sequence() {
+        let mut rng = rand::thread_rng();
+        let number = |n: i128| Number::I32(n as i32);
+        let output = UniqueNode::sequence(-3, 4, 2, number, Shard::default())
+            .repeat(5)
+            .complete(&mut rng);
+        assert_eq!(
+            output[..4]
+                .iter()
+                .map(|value| value.as_ref().unwrap().clone())
+                .collect::<Vec<_>>(),
+            [-3, -1, 1, 3].map(|n| Value::Number(Number::I32(n)))
+        );
+        assert!(output[4].is_err());
+
+        let shards = (0..3)
+            .map(|index| {
+                UniqueNode::sequence(0, 8, 1, number, Shard::new(index, 3).unwrap())
+                    .repeat(3)
+                    .complete(&mut rng)
+                    .into_iter()
+                    .collect::<Result<Vec<_>, _>>()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let all = shards
+            .iter()
+            .flatten()
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(all.len(), 9);
+    }
 }
@@ -1,6 +1,6 @@
 use super::prelude::*;
 use crate::graph::prelude::content::number::number_content::U64;
-use crate::graph::prelude::VariantContent;
+use crate::graph::prelude::{FieldRef, SameAsContent, VariantContent};
 use crate::schema::{number_content, NumberContent, RangeStep};
 use serde::de;
 use std::fmt;
@@ -10,6 +10,7 @@ use std::fmt;
 #[serde(deny_unknown_fields)]
 pub struct ArrayContent {
     #[serde(default)]
+    #[serde(skip_serializing_if = "Content::is_null")]
     pub length: Box<Content>,
     pub content: Box<Content>,
 }
@@ -66,8 +67,22 @@ impl<'de> Deserialize<'de> for ArrayContent {
                     }
                 }
 
+                let content: Content =
+                    content.ok_or_else(|| de::Error::missing_field("content"))?;
+
+                match (cardinalities(&content).count(), length.is_some()) {
+                    (0, _) => {}
+                    (1, false) => {
+                        return Ok(ArrayContent {
+                            length: Box::new(Content::null()),
+                            content: Box::new(content),
+                        });
+                    }
+                    (1, true) => return Err(de::Error::custom("an array whose content has a field with a `cardinality` cannot have a `length`, as its length is given by the `cardinality`. Try removing the '\"length\"' of the array")),
+                    _ => return Err(de::Error::custom("only one field of the content of an array can have a `cardinality`")),
+                }
+
                 let length = length.ok_or_else(|| de::Error::missing_field("length"))?;
-                let content = content.ok_or_else(|| de::Error::missing_field("content"))?;
 
                 match length {
                     // Default for positive constants
@@ -152,10 +167,51 @@ impl ArrayContent {
             content: Box::new(content),
         }
     }
+
+    /// The field of the content, if any, whose `same_as` has a `cardinality`.
+    ///
+    /// The array then generates, for each value of the referenced field, as many elements as
+    /// given by the `cardinality`, setting the field to that value in each of them. Its `length`
+    /// is unused.
+    pub fn cardinality(&self) -> Option<(&str, &SameAsContent, &Content)> {
+        cardinalities(&self.content).next()
+    }
+}
+
+fn cardinalities(content: &Content) -> impl Iterator<Item = (&str, &SameAsContent, &Content)> {
+    let fields = match content {
+        Content::Object(object) => Some(object.fields.iter()),
+        _ => None,
+    };
+    fields
+        .into_iter()
+        .flatten()
+        .filter_map(|(field, content)| match content {
+            Content::SameAs(same_as) => same_as
+                .cardinality
+                .as_deref()
+                .map(|cardinality| (field.as_str(), same_as, cardinality)),
+            _ => None,
+        })
 }
 
 impl Compile for ArrayContent {
     fn compile<'a, C: Compiler<'a>>(&'a self, mut compiler: C) -> Result<Graph> {
+        // The field with the cardinality reads the value the node sets, see `NamespaceCompiler`
+        if let Some((_, same_as, cardinality)) = self.cardinality() {
+            let collection = FieldRef::from_collection_name(same_as.ref_.collection().to_string())?;
+            let parent = compiler.get(collection)?;
+            let path = same_as.ref_.iter_fields().map(str::to_string).collect();
+            let cardinality = compiler.build("cardinality", cardinality)?.into_size();
+            let content = compiler.build("content", &self.content)?;
+            return Ok(Graph::OneToMany(OneToManyNode::new(
+                parent,
+                path,
+                cardinality,
+                content,
+            )));
+        }
+
         let length = compiler.build("length", self.length.as_ref())?.into_size();
         let content = compiler.build("content", &self.content)?;
         Ok(Graph::Array(ArrayNode::new_with(length, content)))
@@ -220,6 +276,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cardinality_replaces_length() {
+        let content: Content = schema!({
+            "type": "array",
+            "content": {
+                "type": "object",
+                "user_id": {
+                    "type": "same_as",
+                    "ref": "users.content.id",
+                    "cardinality": {"type": "number", "subtype": "u64", "range": {"low": 0, "high": 5}}
+                }
+            }
+        });
+        let array = match &content {
+            Content::Array(array) => array,
+            otherwise => panic!("expected an array, got {otherwise}"),
+        };
+        assert_eq!(
+            array.cardinality().map(|(field, ..)| field),
+            Some("user_id")
+        );
+        assert!(array.length.is_null());
+        let serialized = serde_json::to_value(&content).unwrap();
+        assert!(serialized.get("length").is_none());
+        assert_eq!(
+            serde_json::from_value::<Content>(serialized).unwrap(),
+            content
+        );
+
+        let with_length = serde_json::json!({
+            "type": "array",
+            "length": 3,
+            "content": {
+                "type": "object",
+                "user_id": {"type": "same_as", "ref": "users.content.id", "cardinality": 1}
+            }
+        });
+        assert!(serde_json::from_value::<Content>(with_length).is_err());
+    }
+
     supported_length_tests! {
         default: {
             "type": "array",
@@ -0,0 +1,213 @@
+use super::distribution::HashBound;
+use super::prelude::*;
+use super::FieldRef;
+
+use std::hash::Hasher;
+
+/// A bound that is either a fixed value or the value of another field, e.g. the `begin` of a
+/// `date_time` or the `low` of a number `range`.
+///
+/// Fields are referenced like `same_as`, as `"@orders.content.ordered_at"`, or with an `offset`
+/// added to their value as `{"ref": "orders.content.ordered_at", "offset": "1d"}`.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub enum LinkedBound<T, O> {
+    Value(T),
+    Field { ref_: FieldRef, offset: Option<O> },
+}
+
+impl<T, O> LinkedBound<T, O> {
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Self::Value(value) => Some(value),
+            Self::Field { .. } => None,
+        }
+    }
+
+    pub fn try_map<F, U, E>(self, f: F) -> Result<LinkedBound<U, O>, E>
+    where
+        F: FnOnce(T) -> Result<U, E>,
+    {
+        Ok(match self {
+            Self::Value(value) => LinkedBound::Value(f(value)?),
+            Self::Field { ref_, offset } => LinkedBound::Field { ref_, offset },
+        })
+    }
+
+    pub fn compile<'a, C: Compiler<'a>>(&self, compiler: &mut C) -> Result<BoundNode<T>>
+    where
+        T: BoundValue<Offset = O> + Clone,
+        O: Clone,
+    {
+        Ok(match self {
+            Self::Value(value) => BoundNode::Value(value.clone()),
+            Self::Field { ref_, offset } => {
+                BoundNode::Field(Box::new(compiler.get(ref_.clone())?), offset.clone())
+            }
+        })
+    }
+}
+
+impl<T: HashBound, O: HashBound> HashBound for LinkedBound<T, O> {
+    fn hash_bound<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::Value(value) => value.hash_bound(state),
+            Self::Field { ref_, offset } => {
+                ref_.hash(state);
+                offset.hash_bound(state);
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SerdeFieldBound<O> {
+    #[serde(rename = "ref")]
+    ref_: FieldRef,
+    #[serde(default = "Option::default")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    offset: Option<O>,
+}
+
+impl<T: Serialize, O: Serialize> Serialize for LinkedBound<T, O> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::Value(value) => value.serialize(serializer),
+            Self::Field { ref_, offset: None } => format!("@{ref_}").serialize(serializer),
+            Self::Field { ref_, offset } => SerdeFieldBound {
+                ref_: ref_.clone(),
+                offset: offset.as_ref(),
+            }
+            .serialize(serializer),
+        }
+    }
+}
+
+impl<'de, T, O> Deserialize<'de> for LinkedBound<T, O>
+where
+    T: serde::de::DeserializeOwned,
+    O: serde::de::DeserializeOwned,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let v = Value::deserialize(deserializer)?;
+        match v {
+            Value::String(s) if s.starts_with('@') => FieldRef::from_str(&s[1..])
+                .map(|ref_| Self::Field { ref_, offset: None })
+                .map_err(D::Error::custom),
+            Value::Object(_) => SerdeFieldBound::deserialize(v)
+                .map(|bound| Self::Field {
+                    ref_: bound.ref_,
+                    offset: bound.offset,
+                })
+                .map_err(D::Error::custom),
+            v => T::deserialize(v).map(Self::Value).map_err(D::Error::custom),
+        }
+    }
+}
+
+/// A signed offset added to the value of a referenced `date_time`, e.g. `"1d 12h"` or `"-30m"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DateTimeOffset(pub Duration);
+
+impl Display for DateTimeOffset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let abs = self.0.abs().to_std().map_err(|_| std::fmt::Error)?;
+        let sign = if self.0 < Duration::zero() { "-" } else { "" };
+        write!(f, "{sign}{}", humantime::format_duration(abs))
+    }
+}
+
+impl FromStr for DateTimeOffset {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (negative, abs) = match s.trim().strip_prefix('-') {
+            Some(abs) => (true, abs),
+            None => (false, s.trim()),
+        };
+        let duration = humantime::parse_duration(abs)
+            .map_err(|err| anyhow!("invalid offset '{s}': {err}"))
+            .and_then(|duration| Duration::from_std(duration).map_err(anyhow::Error::from))?;
+        Ok(Self(if negative { -duration } else { duration }))
+    }
+}
+
+impl Serialize for DateTimeOffset {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for DateTimeOffset {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn linked_bounds() {
+        let bound: LinkedBound<i64, i64> = serde_json::from_value(json!(3)).unwrap();
+        assert_eq!(bound, LinkedBound::Value(3));
+
+        let bound: LinkedBound<i64, i64> =
+            serde_json::from_value(json!("@orders.content.price")).unwrap();
+        assert_eq!(
+            bound,
+            LinkedBound::Field {
+                ref_: "orders.content.price".parse().unwrap(),
+                offset: None
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(&bound).unwrap(),
+            json!("@orders.content.price")
+        );
+
+        let bound: LinkedBound<String, DateTimeOffset> = serde_json::from_value(
+            json!({"ref": "orders.content.ordered_at", "offset": "-1day 2h"}),
+        )
+        .unwrap();
+        assert_eq!(
+            bound,
+            LinkedBound::Field {
+                ref_: "orders.content.ordered_at".parse().unwrap(),
+                offset: Some(DateTimeOffset(-Duration::hours(26)))
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(&bound).unwrap(),
+            json!({"ref": "orders.content.ordered_at", "offset": "-1day 2h"})
+        );
+
+        assert!(serde_json::from_value::<LinkedBound<i64, i64>>(json!("3")).is_err());
+        assert!(
+            serde_json::from_value::<LinkedBound<String, DateTimeOffset>>(
+                json!({"ref": "orders.content.ordered_at", "offset": "soon"})
+            )
+            .is_err()
+        );
+        assert!(serde_json::from_value::<LinkedBound<i64, i64>>(
+            json!({"ref": "orders.content.price", "shift": 1})
+        )
+        .is_err());
+    }
+}
@@ -1,6 +1,8 @@
 #![allow(clippy::derive_partial_eq_without_eq)]
 use super::prelude::*;
 
+use super::{DateTimeOffset, LinkedBound};
+
 use chrono::format::{parse as strptime, StrftimeItems};
 
 use std::sync::Arc;
@@ -128,8 +130,28 @@ impl Default for ChronoValueType {
 pub struct DateTimeContent {
     pub format: String,
     pub type_: ChronoValueType,
-    pub begin: Option<ChronoValue>,
-    pub end: Option<ChronoValue>,
+    pub begin: Option<LinkedBound<ChronoValue, DateTimeOffset>>,
+    pub end: Option<LinkedBound<ChronoValue, DateTimeOffset>>,
+    /// The buckets date times are sampled from by weight, instead of uniformly between `begin`
+    /// and `end`
+    pub histogram: Option<Vec<DateTimeBucket>>,
+}
+
+/// A bucket of the histogram of a [`DateTimeContent`], holding the date times from `begin` to
+/// `end`, like the content itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateTimeBucket {
+    pub begin: ChronoValue,
+    pub end: ChronoValue,
+    pub weight: f64,
+}
+
+impl std::hash::Hash for DateTimeBucket {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.begin.hash(state);
+        self.end.hash(state);
+        self.weight.to_bits().hash(state);
+    }
 }
 
 #[derive(Debug)]
@@ -210,9 +232,26 @@ struct SerdeDateTimeContent {
     #[serde(rename = "subtype")]
     type_: Option<ChronoValueType>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    begin: Option<String>,
+    begin: Option<LinkedBound<String, DateTimeOffset>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end: Option<LinkedBound<String, DateTimeOffset>>,
+    #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
-    end: Option<String>,
+    histogram: Option<SerdeDateTimeHistogram>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SerdeDateTimeHistogram {
+    buckets: Vec<SerdeDateTimeBucket>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SerdeDateTimeBucket {
+    begin: String,
+    end: String,
+    weight: f64,
 }
 
 impl SerdeDateTimeContent {
@@ -223,16 +262,59 @@ impl SerdeDateTimeContent {
 
         let begin = self
             .begin
-            .map(|begin| fmt.parse(begin.as_str()))
+            .map(|begin| begin.try_map(|begin| fmt.parse(begin.as_str())))
             .transpose()?;
 
-        let end = self.end.map(|end| fmt.parse(end.as_str())).transpose()?;
+        let end = self
+            .end
+            .map(|end| end.try_map(|end| fmt.parse(end.as_str())))
+            .transpose()?;
+
+        let histogram = self
+            .histogram
+            .map(|histogram| {
+                histogram
+                    .buckets
+                    .into_iter()
+                    .map(|bucket| {
+                        Ok(DateTimeBucket {
+                            begin: fmt.parse(&bucket.begin)?,
+                            end: fmt.parse(&bucket.end)?,
+                            weight: bucket.weight,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .transpose()?;
+
+        let first_bucket = histogram.as_ref().and_then(|buckets| buckets.first());
+        let type_ = infer_date_time_type(
+            self.type_,
+            begin
+                .as_ref()
+                .and_then(LinkedBound::value)
+                .or(first_bucket.map(|bucket| &bucket.begin)),
+            end.as_ref()
+                .and_then(LinkedBound::value)
+                .or(first_bucket.map(|bucket| &bucket.end)),
+        )?;
+        let buckets = histogram.iter().flatten();
+        if buckets
+            .flat_map(|bucket| [&bucket.begin, &bucket.end])
+            .any(|value| value.type_() != type_)
+        {
+            return Err(anyhow!(
+                "The date/time types of the histogram buckets do not match the 'subtype' {}",
+                type_
+            ));
+        }
 
         Ok(DateTimeContent {
             format: self.format,
-            type_: infer_date_time_type(self.type_, &begin, &end)?,
+            type_,
             begin,
             end,
+            histogram,
         })
     }
 
@@ -243,10 +325,31 @@ impl SerdeDateTimeContent {
             type_: Some(c.type_),
             begin: c
                 .begin
+                .clone()
+                .map(|begin| begin.try_map(|begin| fmt.format(&begin)))
+                .transpose()?,
+            end: c
+                .end
+                .clone()
+                .map(|end| end.try_map(|end| fmt.format(&end)))
+                .transpose()?,
+            histogram: c
+                .histogram
                 .as_ref()
-                .map(|begin| fmt.format(begin))
+                .map(|buckets| {
+                    let buckets = buckets
+                        .iter()
+                        .map(|bucket| {
+                            Ok(SerdeDateTimeBucket {
+                                begin: fmt.format(&bucket.begin)?,
+                                end: fmt.format(&bucket.end)?,
+                                weight: bucket.weight,
+                            })
+                        })
+                        .collect::<Result<_>>()?;
+                    Ok::<_, anyhow::Error>(SerdeDateTimeHistogram { buckets })
+                })
                 .transpose()?,
-            end: c.end.as_ref().map(|end| fmt.format(end)).transpose()?,
         })
     }
 }
@@ -273,15 +376,25 @@ impl<'de> Deserialize<'de> for DateTimeContent {
 }
 
 impl Compile for DateTimeContent {
-    fn compile<'a, C: Compiler<'a>>(&'a self, _compiler: C) -> Result<Graph> {
-        let begin = self
-            .begin
-            .clone()
-            .unwrap_or_else(|| ChronoValue::default_of(ChronoValue::now(), self.type_));
-        let end = self
-            .end
-            .clone()
-            .unwrap_or_else(|| ChronoValue::default_of(ChronoValue::now(), self.type_));
+    fn compile<'a, C: Compiler<'a>>(&'a self, mut compiler: C) -> Result<Graph> {
+        if let Some(histogram) = self.histogram.as_ref() {
+            let date_time_node = RandomDateTime::new_histogram(histogram, &self.format)?.into();
+            return Ok(Graph::DateTime(date_time_node));
+        }
+        let now = || LinkedBound::Value(ChronoValue::default_of(ChronoValue::now(), self.type_));
+        let (begin, end) = match (
+            self.begin.clone().unwrap_or_else(now),
+            self.end.clone().unwrap_or_else(now),
+        ) {
+            (LinkedBound::Value(begin), LinkedBound::Value(end)) => (begin, end),
+            (begin, end) => {
+                let begin = begin.compile(&mut compiler)?;
+                let end = end.compile(&mut compiler)?;
+                let date_time_node =
+                    RandomDateTime::new_linked(begin, end, self.type_, &self.format).into();
+                return Ok(Graph::DateTime(date_time_node));
+            }
+        };
         if begin > end {
             let fmt = ChronoValueFormatter::new(&self.format);
             return Err(anyhow!(
@@ -297,13 +410,10 @@ impl Compile for DateTimeContent {
 
 fn infer_date_time_type(
     specified_type: Option<ChronoValueType>,
-    begin: &Option<ChronoValue>,
-    end: &Option<ChronoValue>,
+    begin: Option<&ChronoValue>,
+    end: Option<&ChronoValue>,
 ) -> Result<ChronoValueType> {
-    let inferred_type = match (
-        begin.as_ref().map(ChronoValue::type_),
-        end.as_ref().map(ChronoValue::type_),
-    ) {
+    let inferred_type = match (begin.map(ChronoValue::type_), end.map(ChronoValue::type_)) {
         (Some(b), Some(e)) => {
             if b == e {
                 Some(b)
@@ -340,8 +450,9 @@ pub mod tests {
             let unspecified_begin_end = DateTimeContent {
                 format: "yyyy-MM-dd".to_string(),
                 type_: ChronoValueType::NaiveDate,
-                begin: $begin,
-                end: $end,
+                begin: $begin.map(LinkedBound::Value),
+                end: $end.map(LinkedBound::Value),
+                histogram: None,
             };
 
             let content = Content::DateTime(unspecified_begin_end);
@@ -357,8 +468,9 @@ pub mod tests {
             let unspecified_begin_end = DateTimeContent {
                 format: "yyyy-MM-dd".to_string(),
                 type_: ChronoValueType::NaiveDate,
-                begin: $begin,
-                end: $end,
+                begin: $begin.map(LinkedBound::Value),
+                end: $end.map(LinkedBound::Value),
+                histogram: None,
             };
 
             let content = Content::DateTime(unspecified_begin_end);
@@ -393,27 +505,39 @@ pub mod tests {
         ));
 
         assert_eq!(
-            infer_date_time_type(Some(ChronoValueType::DateTime), &None, &None).unwrap(),
+            infer_date_time_type(Some(ChronoValueType::DateTime), None, None).unwrap(),
             ChronoValueType::DateTime
         );
         assert_eq!(
-            infer_date_time_type(Some(ChronoValueType::NaiveTime), &some_time, &some_time).unwrap(),
+            infer_date_time_type(
+                Some(ChronoValueType::NaiveTime),
+                some_time.as_ref(),
+                some_time.as_ref()
+            )
+            .unwrap(),
             ChronoValueType::NaiveTime
         );
         assert_eq!(
-            infer_date_time_type(Some(ChronoValueType::NaiveTime), &some_time, &None).unwrap(),
+            infer_date_time_type(Some(ChronoValueType::NaiveTime), some_time.as_ref(), None)
+                .unwrap(),
             ChronoValueType::NaiveTime
         );
         assert_eq!(
-            infer_date_time_type(None, &some_time, &None).unwrap(),
+            infer_date_time_type(None, some_time.as_ref(), None).unwrap(),
             ChronoValueType::NaiveTime
         );
         assert_eq!(
-            infer_date_time_type(None, &None, &None).unwrap(),
+            infer_date_time_type(None, None, None).unwrap(),
             ChronoValueType::default()
         );
-        assert!(infer_date_time_type(Some(ChronoValueType::NaiveDate), &None, &some_time).is_err());
-        assert!(infer_date_time_type(Some(ChronoValueType::NaiveTime), &None, &some_date).is_err());
-        assert!(infer_date_time_type(None, &some_time, &some_date).is_err());
+        assert!(
+            infer_date_time_type(Some(ChronoValueType::NaiveDate), None, some_time.as_ref())
+                .is_err()
+        );
+        assert!(
+            infer_date_time_type(Some(ChronoValueType::NaiveTime), None, some_date.as_ref())
+                .is_err()
+        );
+        assert!(infer_date_time_type(None, some_time.as_ref(), some_date.as_ref()).is_err());
     }
 }
@@ -0,0 +1,258 @@
+use super::prelude::*;
+use std::hash::{Hash, Hasher};
+
+macro_rules! number_distribution {
+    {
+        $(
+            $(#[$doc:meta])*
+            $name:ident as $key:literal {
+                $($field:ident: $field_ty:ty,)*
+            },
+        )*
+    } => {
+        /// A non-uniform distribution numbers can be sampled from, e.g.
+        /// `{"normal": {"mean": 40, "sd": 12}}`.
+        ///
+        /// Samples are rounded to the nearest integer for integer subtypes, and clamped to `low`
+        /// and `high` when those are set.
+        #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+        #[serde(rename_all = "snake_case")]
+        pub enum NumberDistribution<N> {
+            $($name($name<N>),)*
+        }
+
+        impl<N> NumberDistribution<N> {
+            /// The keys distributions are specified with.
+            pub const KEYS: &'static [&'static str] = &[$($key,)*];
+
+            pub fn low(&self) -> Option<&N> {
+                match self {
+                    $(Self::$name(dist) => dist.low.as_ref(),)*
+                }
+            }
+
+            pub fn high(&self) -> Option<&N> {
+                match self {
+                    $(Self::$name(dist) => dist.high.as_ref(),)*
+                }
+            }
+
+            pub fn bounds_mut(&mut self) -> (&mut Option<N>, &mut Option<N>) {
+                match self {
+                    $(Self::$name(dist) => (&mut dist.low, &mut dist.high),)*
+                }
+            }
+
+            /// The same distribution between `low` and `high`, which may be of another type.
+            pub fn with_bounds<M>(self, low: Option<M>, high: Option<M>) -> NumberDistribution<M> {
+                match self {
+                    $(
+                        Self::$name(dist) => NumberDistribution::$name($name {
+                            $($field: dist.$field,)*
+                            low,
+                            high,
+                        }),
+                    )*
+                }
+            }
+
+            pub fn try_cast<F, M, E>(self, f: F) -> Result<NumberDistribution<M>, E>
+            where
+                F: Fn(N) -> Result<M, E>,
+            {
+                Ok(match self {
+                    $(
+                        Self::$name(dist) => NumberDistribution::$name($name {
+                            $($field: dist.$field,)*
+                            low: dist.low.map(&f).transpose()?,
+                            high: dist.high.map(&f).transpose()?,
+                        }),
+                    )*
+                })
+            }
+        }
+
+        impl<N: HashBound> Hash for NumberDistribution<N> {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                std::mem::discriminant(self).hash(state);
+                match self {
+                    $(
+                        Self::$name(dist) => {
+                            $(dist.$field.hash_bound(state);)*
+                            dist.low.hash_bound(state);
+                            dist.high.hash_bound(state);
+                        }
+                    )*
+                }
+            }
+        }
+
+        $(
+            $(#[$doc])*
+            #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+            #[serde(deny_unknown_fields)]
+            pub struct $name<N> {
+                $(pub $field: $field_ty,)*
+                #[serde(skip_serializing_if = "Option::is_none")]
+                pub low: Option<N>,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                pub high: Option<N>,
+            }
+        )*
+    };
+}
+
+number_distribution! {
+    /// The normal distribution with mean `mean` and standard deviation `sd`.
+    Normal as "normal" {
+        mean: f64,
+        sd: f64,
+    },
+    /// The distribution of `exp(X)` where `X` is normal with mean `mean` and standard deviation
+    /// `sd`.
+    LogNormal as "log_normal" {
+        mean: f64,
+        sd: f64,
+    },
+    /// The exponential distribution with rate `rate`.
+    Exponential as "exponential" {
+        rate: f64,
+    },
+    /// The Poisson distribution with mean `lambda`.
+    Poisson as "poisson" {
+        lambda: f64,
+    },
+    /// The Zipf distribution over `1..=n` with exponent `s`, where `k` is sampled with a
+    /// probability proportional to `1/k^s`.
+    Zipf as "zipf" {
+        n: u64,
+        s: f64,
+    },
+    /// The beta distribution with shapes `alpha` and `beta`, scaled from `[0, 1]` to
+    /// `[low, high]` when those are set.
+    Beta as "beta" {
+        alpha: f64,
+        beta: f64,
+    },
+    /// The distribution of a histogram, which samples one of `buckets` with a probability
+    /// proportional to its `weight` and then a number uniformly within it.
+    Histogram as "histogram" {
+        buckets: Vec<HistogramBucket>,
+    },
+}
+
+/// A bucket of a [`Histogram`], holding the numbers from `low` up to but excluding `high`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct HistogramBucket {
+    pub low: f64,
+    pub high: f64,
+    pub weight: f64,
+}
+
+impl HistogramBucket {
+    /// Whether `number` could have been sampled from this bucket, where `integers` are sampled
+    /// by rounding down. Floats may be `high` itself, as they get as close to it as they can.
+    pub fn contains(&self, number: f64, integers: bool) -> bool {
+        if integers {
+            self.low.floor() <= number && (number < self.high || number == self.low.floor())
+        } else {
+            self.low <= number && number <= self.high
+        }
+    }
+}
+
+/// Hashes floats by their bits, like `RangeStep`.
+pub trait HashBound {
+    fn hash_bound<H: Hasher>(&self, state: &mut H);
+}
+
+impl<N: HashBound> HashBound for Option<N> {
+    fn hash_bound<H: Hasher>(&self, state: &mut H) {
+        if let Some(n) = self {
+            n.hash_bound(state);
+        }
+    }
+}
+
+macro_rules! hash_bound_impl {
+    (float $($t:ty),*) => {
+        $(
+            impl HashBound for $t {
+                fn hash_bound<H: Hasher>(&self, state: &mut H) {
+                    self.to_bits().hash(state);
+                }
+            }
+        )*
+    };
+    ($($t:ty),*) => {
+        $(
+            impl HashBound for $t {
+                fn hash_bound<H: Hasher>(&self, state: &mut H) {
+                    self.hash(state);
+                }
+            }
+        )*
+    };
+}
+
+impl HashBound for Vec<HistogramBucket> {
+    fn hash_bound<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for bucket in self {
+            bucket.low.hash_bound(state);
+            bucket.high.hash_bound(state);
+            bucket.weight.hash_bound(state);
+        }
+    }
+}
+
+hash_bound_impl!(i16, i32, u32, i64, u64);
+hash_bound_impl!(float f32, f64);
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn distribution_keys() {
+        let normal: NumberDistribution<i32> =
+            serde_json::from_value(json!({"normal": {"mean": 40, "sd": 12, "low": 0}})).unwrap();
+        assert_eq!(
+            normal,
+            NumberDistribution::Normal(Normal {
+                mean: 40.0,
+                sd: 12.0,
+                low: Some(0),
+                high: None
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(&normal).unwrap(),
+            json!({"normal": {"mean": 40.0, "sd": 12.0, "low": 0}})
+        );
+
+        let keys: Vec<String> = [
+            json!({"normal": {"mean": 0, "sd": 1}}),
+            json!({"log_normal": {"mean": 0, "sd": 1}}),
+            json!({"exponential": {"rate": 1}}),
+            json!({"poisson": {"lambda": 1}}),
+            json!({"zipf": {"n": 10, "s": 1}}),
+            json!({"beta": {"alpha": 1, "beta": 1}}),
+            json!({"histogram": {"buckets": [{"low": 0, "high": 1, "weight": 1}]}}),
+        ]
+        .into_iter()
+        .map(|value| {
+            let dist: NumberDistribution<i32> = serde_json::from_value(value).unwrap();
+            let value = serde_json::to_value(dist).unwrap();
+            value.as_object().unwrap().keys().next().unwrap().clone()
+        })
+        .collect();
+        assert_eq!(keys, NumberDistribution::<i32>::KEYS);
+
+        assert!(serde_json::from_value::<NumberDistribution<i32>>(
+            json!({"normal": {"mean": 40, "sd": 12, "standard_deviation": 1}})
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,126 @@
+use super::prelude::*;
+use super::NumberContent;
+use crate::Namespace;
+
+use std::collections::BTreeMap;
+
+/// A value computed from other fields with an expression, e.g. `quantity * unit_price`.
+///
+/// The variables of the expression are the `arguments`, which are content nodes like the
+/// arguments of a `format` string: usually references such as `"@orders.content.quantity"`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Hash)]
+#[serde(deny_unknown_fields)]
+pub struct ExpressionContent {
+    pub expression: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub arguments: BTreeMap<String, Content>,
+}
+
+impl Compile for ExpressionContent {
+    fn compile<'a, C: Compiler<'a>>(&'a self, mut compiler: C) -> Result<Graph> {
+        let arguments = self
+            .arguments
+            .iter()
+            .map(|(name, value)| Ok((name.to_string(), compiler.build(name, value)?)))
+            .collect::<Result<_>>()?;
+        let node = ExpressionNode::new(&self.expression, arguments)
+            .with_context(|| format!("invalid expression '{}'", self.expression))?;
+        Ok(Graph::Expression(node))
+    }
+}
+
+impl ExpressionContent {
+    /// The types of the values the expression can evaluate to, as told by the content of its
+    /// `arguments`.
+    pub fn value_types(&self, namespace: &Namespace) -> Result<Vec<ExpressionType>> {
+        let arguments = self
+            .arguments
+            .iter()
+            .map(|(name, content)| {
+                let types = argument_types(namespace, content)
+                    .with_context(|| format!("in the argument '{name}'"))?;
+                Ok((name.clone(), types))
+            })
+            .collect::<Result<_>>()?;
+        expression_types(&self.expression, &arguments)
+            .with_context(|| format!("invalid expression '{}'", self.expression))
+    }
+}
+
+fn argument_types(namespace: &Namespace, content: &Content) -> Result<Vec<ExpressionType>> {
+    let type_ = match content {
+        Content::Null(_) => ExpressionType::Null,
+        Content::Bool(_) => ExpressionType::Bool,
+        Content::Number(NumberContent::F32(_) | NumberContent::F64(_)) => ExpressionType::Float,
+        Content::Number(_) => ExpressionType::Int,
+        Content::String(_) => ExpressionType::String,
+        Content::DateTime(date_time) => ExpressionType::DateTime(Some(date_time.type_)),
+        Content::Unique(unique) => return argument_types(namespace, &unique.content),
+        Content::SameAs(same_as) => {
+            return argument_types(namespace, namespace.get_s_node(&same_as.ref_)?)
+        }
+        Content::Expression(expression) => return expression.value_types(namespace),
+        Content::OneOf(one_of) => return any_types(namespace, one_of.iter()),
+        Content::Switch(switch) => return any_types(namespace, switch.iter()),
+        other => bail!(
+            "cannot tell the type of the values of {} content",
+            other.kind()
+        ),
+    };
+    Ok(vec![type_])
+}
+
+/// The types of the values of any of `contents`.
+fn any_types<'a>(
+    namespace: &Namespace,
+    contents: impl Iterator<Item = &'a Content>,
+) -> Result<Vec<ExpressionType>> {
+    let mut types = Vec::new();
+    for content in contents {
+        for type_ in argument_types(namespace, content)? {
+            if !types.contains(&type_) {
+                types.push(type_);
+            }
+        }
+    }
+    Ok(types)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn expression_serde() {
+        let content: Content = serde_json::from_value(json!({
+            "type": "expression",
+            "expression": "quantity * unit_price",
+            "arguments": {
+                "quantity": "@orders.content.quantity",
+                "unit_price": 2.5
+            }
+        }))
+        .unwrap();
+        let expression = match &content {
+            Content::Expression(expression) => expression,
+            otherwise => panic!("expected an expression, got {otherwise}"),
+        };
+        assert!(matches!(
+            expression.arguments.get("quantity"),
+            Some(Content::SameAs(_))
+        ));
+        assert_eq!(
+            serde_json::from_value::<Content>(serde_json::to_value(&content).unwrap()).unwrap(),
+            content
+        );
+
+        for invalid in [
+            json!({"type": "expression"}),
+            json!({"type": "expression", "expression": "1", "argument": {}}),
+            json!({"type": "string", "pattern": "a", "arguments": {}}),
+        ] {
+            assert!(serde_json::from_value::<Content>(invalid).is_err());
+        }
+    }
+}
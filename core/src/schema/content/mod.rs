@@ -24,7 +24,9 @@ mod r#bool;
 pub use self::r#bool::BoolContent;
 
 mod number;
-pub use number::{number_content, NumberContent, NumberContentKind, NumberKindExt, RangeStep};
+pub use number::{
+    number_content, LinkedRange, NumberContent, NumberContentKind, NumberKindExt, RangeStep,
+};
 
 mod string;
 pub use string::{
@@ -34,14 +36,15 @@ pub use string::{
 
 mod date_time;
 pub use date_time::{
-    ChronoValue, ChronoValueAndFormat, ChronoValueFormatter, ChronoValueType, DateTimeContent,
+    ChronoValue, ChronoValueAndFormat, ChronoValueFormatter, ChronoValueType, DateTimeBucket,
+    DateTimeContent,
 };
 
 mod array;
 pub use array::ArrayContent;
 
 mod object;
-pub use object::ObjectContent;
+pub use object::{ObjectContent, Seen};
 
 mod datasource;
 pub use datasource::DatasourceContent;
@@ -52,6 +55,15 @@ pub use one_of::{OneOfContent, VariantContent};
 mod categorical;
 pub use categorical::{Categorical, CategoricalType};
 
+mod distribution;
+pub use distribution::{
+    Beta, Exponential, Histogram, HistogramBucket, LogNormal, Normal, NumberDistribution, Poisson,
+    Zipf,
+};
+
+mod bound;
+pub use bound::{DateTimeOffset, LinkedBound};
+
 pub use number::Id;
 pub mod prelude;
 
@@ -64,6 +76,12 @@ pub use unique::{UniqueAlgorithm, UniqueContent};
 pub mod hidden;
 pub use hidden::HiddenContent;
 
+mod expression;
+pub use expression::ExpressionContent;
+
+mod switch;
+pub use switch::SwitchContent;
+
 use prelude::*;
 
 use super::{FieldRef, Namespace};
@@ -96,11 +114,15 @@ pub trait Find<C> {
         R: AsRef<str>;
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Hash)]
 #[serde(deny_unknown_fields)]
 pub struct SameAsContent {
     #[serde(rename = "ref")]
     pub ref_: FieldRef,
+    /// The number of elements generated for each value of `ref`, when this is a field of the
+    /// content of an array. See [`ArrayContent::cardinality`](ArrayContent::cardinality).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cardinality: Option<Box<Content>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
@@ -145,7 +167,7 @@ impl ContentLabels {
 lazy_static! {
     static ref UNEXPECTED: BTreeMap<&'static str, Vec<&'static str>> = {
         let mut m = BTreeMap::new();
-        m.insert("arguments", vec!["format"]);
+        m.insert("arguments", vec!["format", "expression"]);
         m.insert("low", vec!["range"]);
         m.insert("high", vec!["range"]);
         m.insert("step", vec!["range"]);
@@ -232,8 +254,9 @@ macro_rules! content {
                             }
                         }
 
+                        let type_ = out.get("type").and_then(|type_| type_.as_str());
                         for key in out.keys() {
-                            if let Some(parent) = UNEXPECTED.get(key as &str) {
+                            if let Some(parent) = UNEXPECTED.get(key as &str).filter(|parent| !parent.iter().any(|p| Some(*p) == type_)) {
                                 let parents = parent.iter().map(|p| format!("`{}`", p)).collect::<Vec<String>>().join(", ");
                                 return Err(A::Error::custom(format!("`{}` is expected to be a field of {}", key, parents)));
                             }
@@ -277,7 +300,7 @@ macro_rules! content {
                     {
                         if let Some(s) = v.strip_prefix("@") {
                             let ref_ = FieldRef::deserialize(s.into_deserializer())?;
-                            Ok(Content::SameAs(SameAsContent { ref_ }))
+                            Ok(Content::SameAs(SameAsContent { ref_, cardinality: None }))
                         } else {
                             Ok(Content::String(StringContent::Constant(ConstantContent::from(v.to_string()))))
                         }
@@ -316,6 +339,8 @@ content! {
         Unique(UniqueContent) => "missing a `content` field",
         Datasource(DatasourceContent) => "missing a `path` field",
         Hidden(HiddenContent) => "missing a `content` field",
+        Expression(ExpressionContent) => "missing an `expression` field",
+        Switch(SwitchContent) => "missing an `on` and `cases` field",
         Empty(EmptyContent) => None,
     }
 }
@@ -328,6 +353,31 @@ impl Content {
         })
     }
 
+    /// Like [`from_value_wrapped_in_array`](Self::from_value_wrapped_in_array), but counts no
+    /// value, for when `value` is merged into the content again with the rest of the values of
+    /// its collection.
+    pub fn from_value_wrapped_in_array_uncounted(value: &Value) -> Self {
+        let mut content = Self::from_value_wrapped_in_array(value);
+        content.uncount();
+        content
+    }
+
+    /// Forgets the number of values seen by the contents inferred from a value.
+    fn uncount(&mut self) {
+        match self {
+            Content::Object(object) => {
+                object.seen = Seen::default();
+                object.fields.values_mut().for_each(Content::uncount);
+            }
+            Content::Array(array) => array.content.uncount(),
+            Content::OneOf(one_of) => one_of.variants.iter_mut().for_each(|variant| {
+                variant.uncount();
+                variant.content.uncount();
+            }),
+            _ => {}
+        }
+    }
+
     pub fn is_null(&self) -> bool {
         matches!(self, Self::Null(_))
     }
@@ -356,6 +406,22 @@ impl Content {
         }
     }
 
+    /// Like [`into_nullable`](Self::into_nullable), with the variants weighted by the number of
+    /// `values` and `nulls` seen, for content inferred from data.
+    #[must_use]
+    pub fn into_nullable_seen(self, values: f64, nulls: f64) -> Self {
+        if !self.is_nullable() {
+            Content::OneOf(OneOfContent {
+                variants: vec![
+                    VariantContent::weighted(self, values),
+                    VariantContent::weighted(Content::null(), nulls),
+                ],
+            })
+        } else {
+            self
+        }
+    }
+
     #[must_use]
     pub fn into_hidden(self) -> Self {
         if !self.is_hidden() {
@@ -388,6 +454,14 @@ impl Content {
                 Ok(true)
             }
             Self::Unique(unique) => unique.content.is_scalar(ns),
+            Self::Switch(switch) => {
+                for content in switch.iter() {
+                    if !content.is_scalar(ns)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
             _ => Ok(true),
         }
     }
@@ -424,6 +498,18 @@ impl Content {
             Self::Unique(unique_content) => unique_content.content.accepts(value),
             Self::Hidden(_) => Ok(()),
             Self::SameAs(_) => Ok(()),
+            Self::Expression(_) => Ok(()),
+            Self::Switch(switch) => {
+                if switch.iter().any(|content| content.accepts(value).is_ok()) {
+                    Ok(())
+                } else {
+                    Err(failed!(
+                        target: Release,
+                        "no case of this will accept: {}",
+                        value
+                    ))
+                }
+            }
             Self::OneOf(one_of_content) => {
                 let res: Vec<_> = one_of_content
                     .iter()
@@ -510,6 +596,8 @@ impl Content {
             Content::Unique(_) => "unique".to_string(),
             Content::Hidden(_) => "hidden".to_string(),
             Content::Datasource(_) => "datasource".to_string(),
+            Content::Expression(_) => "expression".to_string(),
+            Content::Switch(_) => "switch".to_string(),
             Content::Empty(_) => "empty".to_string(),
         }
     }
@@ -549,6 +637,7 @@ impl<'r> From<&'r Value> for Content {
                     .collect();
                 Content::Object(ObjectContent {
                     fields,
+                    seen: Seen(1),
                     ..Default::default()
                 })
             }
@@ -641,6 +730,8 @@ impl Compile for Content {
             Self::Hidden(hidden_content) => hidden_content.compile(compiler),
             Self::Null(_) => Ok(Graph::null()),
             Self::Datasource(datasource) => datasource.compile(compiler),
+            Self::Expression(expression) => expression.compile(compiler),
+            Self::Switch(switch) => switch.compile(compiler),
             Self::Empty(_) => Err(anyhow!("unexpected empty object")),
         }
     }
@@ -896,6 +987,16 @@ pub mod tests {
             "begin": "2020-11-05T09:53:10+0500",
             "end": "2020-11-05T09:53:10+0000"
         });
+
+        assert_idempotent!({
+            "format": "%Y-%m-%d",
+            "histogram": {
+                "buckets": [
+                    {"begin": "2020-11-01", "end": "2020-11-05", "weight": 3},
+                    {"begin": "2020-11-06", "end": "2020-11-30", "weight": 1}
+                ]
+            }
+        });
     }
 
     #[test]
@@ -2,7 +2,8 @@
 use super::prelude::*;
 use std::hash::{Hash, Hasher};
 
-use super::Categorical;
+use super::distribution::HashBound;
+use super::{Categorical, LinkedBound, NumberDistribution};
 
 use crate::graph::number::{RandomF32, RandomI16, RandomI32, RandomU32};
 use serde::{ser::Serializer, Serialize};
@@ -79,7 +80,13 @@ macro_rules! number_content {
                 match self {
                     $($(Self::$as(number_content::$as::$variant(_)) => {
                         concat!(stringify!($as), "::", stringify!($variant)).to_string()
-                    },)*)*
+                    },)*
+                    Self::$as(number_content::$as::Linked(_)) => {
+                        concat!(stringify!($as), "::Linked").to_string()
+                    },
+                    Self::$as(number_content::$as::Distribution(_)) => {
+                        concat!(stringify!($as), "::Distribution").to_string()
+                    },)*
                 }
             }
 
@@ -150,16 +157,58 @@ macro_rules! number_content {
         }
 
         pub mod number_content {
-            use super::{RangeStep, Categorical, NumberContent};
-            use serde::{Serialize, Deserialize};
+            use super::{RangeStep, Categorical, LinkedRange, NumberContent, NumberDistribution};
+            use serde::{Serialize, Deserialize, Deserializer, de::Error};
 
             $(
-                #[derive(Debug, Serialize, Deserialize, Clone)]
+                #[derive(Debug, Serialize, Clone)]
                 #[serde(rename_all = "snake_case")]
-                #[serde(deny_unknown_fields)]
                 $(#[$default])?
                 pub enum $as {
                     $($variant($variant_ty),)*
+                    #[serde(rename = "range")]
+                    Linked(LinkedRange<$ty>),
+                    #[serde(untagged)]
+                    Distribution(NumberDistribution<$ty>),
+                }
+
+                // Distributions are keyed by their name (e.g. `{"normal": {...}}`) rather than
+                // nested under a `distribution` key, and linked ranges share the `range` key, so
+                // they are told apart before deserializing the other variants to keep their error
+                // messages.
+                impl<'de> Deserialize<'de> for $as {
+                    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                    where
+                        D: Deserializer<'de>,
+                    {
+                        #[derive(Deserialize)]
+                        #[serde(rename_all = "snake_case")]
+                        #[serde(deny_unknown_fields)]
+                        enum Shadow {
+                            $($variant($variant_ty),)*
+                        }
+
+                        let v = serde_json::Value::deserialize(deserializer)?;
+                        let is_distribution = v
+                            .as_object()
+                            .and_then(|obj| obj.keys().next())
+                            .map(|key| NumberDistribution::<$ty>::KEYS.contains(&key.as_str()))
+                            .unwrap_or(false);
+
+                        if is_distribution {
+                            NumberDistribution::deserialize(v)
+                                .map(Self::Distribution)
+                                .map_err(D::Error::custom)
+                        } else if LinkedRange::<$ty>::is_linked(&v) {
+                            LinkedRange::deserialize(&v["range"])
+                                .map(Self::Linked)
+                                .map_err(D::Error::custom)
+                        } else {
+                            match Shadow::deserialize(v).map_err(D::Error::custom)? {
+                                $(Shadow::$variant(value) => Ok(Self::$variant(value)),)*
+                            }
+                        }
+                    }
                 }
 
                 $(
@@ -170,6 +219,18 @@ macro_rules! number_content {
                     }
                 )*
 
+                impl From<LinkedRange<$ty>> for $as {
+                    fn from(value: LinkedRange<$ty>) -> Self {
+                        Self::Linked(value)
+                    }
+                }
+
+                impl From<NumberDistribution<$ty>> for $as {
+                    fn from(value: NumberDistribution<$ty>) -> Self {
+                        Self::Distribution(value)
+                    }
+                }
+
                 impl From<$as> for NumberContent {
                     fn from(value: $as) -> Self {
                         Self::$as(value)
@@ -201,6 +262,17 @@ impl NumberContent {
         }
     }
 
+    pub fn is_id(&self) -> bool {
+        matches!(
+            self,
+            Self::U32(number_content::U32::Id(_))
+                | Self::U64(number_content::U64::Id(_))
+                | Self::I16(number_content::I16::Id(_))
+                | Self::I32(number_content::I32::Id(_))
+                | Self::I64(number_content::I64::Id(_))
+        )
+    }
+
     pub fn try_transmute_to_id(self) -> Result<Self> {
         match self {
             NumberContent::U32(_) => Ok(Self::u32_default_id()),
@@ -318,6 +390,99 @@ impl<N> std::ops::RangeBounds<N> for RangeStep<N> {
     }
 }
 
+/// A [`RangeStep`] whose `low` and/or `high` are the values of other fields, e.g.
+/// `{"range": {"low": 0, "high": "@orders.content.price"}}`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+#[serde(bound(deserialize = "N: serde::de::DeserializeOwned"))]
+pub struct LinkedRange<N> {
+    #[serde(default = "Option::default")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub low: Option<LinkedBound<N, N>>,
+    #[serde(default = "Option::default")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub high: Option<LinkedBound<N, N>>,
+    #[serde(default = "Option::default")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<N>,
+    #[serde(default = "LinkedRange::<N>::default_include_low")]
+    #[serde(skip_serializing_if = "std::clone::Clone::clone")]
+    pub include_low: bool,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub include_high: bool,
+}
+
+impl<N> LinkedRange<N> {
+    fn default_include_low() -> bool {
+        true
+    }
+
+    /// Whether the `range` of a number content references other fields, and so should be
+    /// deserialized as a `LinkedRange` rather than a `RangeStep`.
+    fn is_linked(content: &Value) -> bool {
+        content
+            .as_object()
+            .filter(|content| content.len() == 1)
+            .and_then(|content| content.get("range"))
+            .map(|range| {
+                ["low", "high"].iter().any(|bound| {
+                    matches!(range.get(bound), Some(Value::String(_) | Value::Object(_)))
+                })
+            })
+            .unwrap_or(false)
+    }
+
+    fn try_cast<F, M, E>(self, f: F) -> Result<LinkedRange<M>, E>
+    where
+        F: Fn(N) -> Result<M, E>,
+    {
+        let cast_bound = |bound: LinkedBound<N, N>| match bound {
+            LinkedBound::Value(value) => f(value).map(LinkedBound::Value),
+            LinkedBound::Field { ref_, offset } => Ok(LinkedBound::Field {
+                ref_,
+                offset: offset.map(&f).transpose()?,
+            }),
+        };
+        Ok(LinkedRange {
+            low: self.low.map(cast_bound).transpose()?,
+            high: self.high.map(cast_bound).transpose()?,
+            step: self.step.map(&f).transpose()?,
+            include_low: self.include_low,
+            include_high: self.include_high,
+        })
+    }
+
+    pub fn compile<'a, C: Compiler<'a>>(&self, compiler: &mut C) -> Result<LinkedRangeStep<N>>
+    where
+        N: BoundValue<Offset = N> + Clone,
+    {
+        Ok(LinkedRangeStep::new(
+            self.low
+                .as_ref()
+                .map(|low| low.compile(compiler))
+                .transpose()?,
+            self.high
+                .as_ref()
+                .map(|high| high.compile(compiler))
+                .transpose()?,
+            self.step.clone(),
+            self.include_low,
+            self.include_high,
+        ))
+    }
+}
+
+impl<N: HashBound> Hash for LinkedRange<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.low.hash_bound(state);
+        self.high.hash_bound(state);
+        self.step.hash_bound(state);
+        self.include_low.hash(state);
+        self.include_high.hash(state);
+    }
+}
+
 macro_rules! derive_hash {
     (f32) => {
         impl Hash for RangeStep<f32> {
@@ -421,7 +586,8 @@ number_content!(
 );
 
 impl Compile for NumberContent {
-    fn compile<'a, C: Compiler<'a>>(&'a self, _compiler: C) -> Result<Graph> {
+    fn compile<'a, C: Compiler<'a>>(&'a self, mut compiler: C) -> Result<Graph> {
+        let shard = compiler.shard();
         let number_node = match self {
             Self::U64(u64_content) => {
                 let random_u64 = match u64_content {
@@ -430,8 +596,14 @@ impl Compile for NumberContent {
                         RandomU64::categorical(categorical_content.clone())
                     }
                     number_content::U64::Constant(val) => RandomU64::constant(*val),
+                    number_content::U64::Distribution(dist) => {
+                        RandomU64::distribution(dist.clone())?
+                    }
+                    number_content::U64::Linked(range) => {
+                        RandomU64::linked(range.compile(&mut compiler)?)
+                    }
                     number_content::U64::Id(id) => {
-                        let gen = Incrementing::new_at(id.start_at.unwrap_or(1));
+                        let gen = Incrementing::new_at_sharded(id.start_at.unwrap_or(1), shard)?;
                         RandomU64::incrementing(gen)
                     }
                 };
@@ -444,9 +616,15 @@ impl Compile for NumberContent {
                         RandomI64::categorical(categorical_content.clone())
                     }
                     number_content::I64::Constant(val) => RandomI64::constant(*val),
-                    number_content::I64::Id(id) => {
-                        RandomI64::incrementing(Incrementing::new_at(id.start_at.unwrap_or(1)))
+                    number_content::I64::Distribution(dist) => {
+                        RandomI64::distribution(dist.clone())?
+                    }
+                    number_content::I64::Linked(range) => {
+                        RandomI64::linked(range.compile(&mut compiler)?)
                     }
+                    number_content::I64::Id(id) => RandomI64::incrementing(
+                        Incrementing::new_at_sharded(id.start_at.unwrap_or(1), shard)?,
+                    ),
                 };
                 random_i64.into()
             }
@@ -454,6 +632,12 @@ impl Compile for NumberContent {
                 let random_f64 = match f64_content {
                     number_content::F64::Range(range) => RandomF64::range(*range)?,
                     number_content::F64::Constant(val) => RandomF64::constant(*val),
+                    number_content::F64::Distribution(dist) => {
+                        RandomF64::distribution(dist.clone())?
+                    }
+                    number_content::F64::Linked(range) => {
+                        RandomF64::linked(range.compile(&mut compiler)?)
+                    }
                 };
                 random_f64.into()
             }
@@ -464,9 +648,15 @@ impl Compile for NumberContent {
                         RandomU32::categorical(categorical_content.clone())
                     }
                     number_content::U32::Constant(val) => RandomU32::constant(*val),
-                    number_content::U32::Id(id) => {
-                        RandomU32::incrementing(Incrementing::new_at(id.start_at.unwrap_or(1)))
+                    number_content::U32::Distribution(dist) => {
+                        RandomU32::distribution(dist.clone())?
                     }
+                    number_content::U32::Linked(range) => {
+                        RandomU32::linked(range.compile(&mut compiler)?)
+                    }
+                    number_content::U32::Id(id) => RandomU32::incrementing(
+                        Incrementing::new_at_sharded(id.start_at.unwrap_or(1), shard)?,
+                    ),
                 };
                 random_u32.into()
             }
@@ -477,9 +667,15 @@ impl Compile for NumberContent {
                         RandomI32::categorical(categorical_content.clone())
                     }
                     number_content::I32::Constant(val) => RandomI32::constant(*val),
-                    number_content::I32::Id(id) => {
-                        RandomI32::incrementing(Incrementing::new_at(id.start_at.unwrap_or(1)))
+                    number_content::I32::Distribution(dist) => {
+                        RandomI32::distribution(dist.clone())?
+                    }
+                    number_content::I32::Linked(range) => {
+                        RandomI32::linked(range.compile(&mut compiler)?)
                     }
+                    number_content::I32::Id(id) => RandomI32::incrementing(
+                        Incrementing::new_at_sharded(id.start_at.unwrap_or(1), shard)?,
+                    ),
                 };
                 random_i32.into()
             }
@@ -487,6 +683,12 @@ impl Compile for NumberContent {
                 let random_f32 = match f32_content {
                     number_content::F32::Range(range) => RandomF32::range(*range)?,
                     number_content::F32::Constant(val) => RandomF32::constant(*val),
+                    number_content::F32::Distribution(dist) => {
+                        RandomF32::distribution(dist.clone())?
+                    }
+                    number_content::F32::Linked(range) => {
+                        RandomF32::linked(range.compile(&mut compiler)?)
+                    }
                 };
                 random_f32.into()
             }
@@ -497,9 +699,15 @@ impl Compile for NumberContent {
                         RandomI16::categorical(categorical_content.clone())
                     }
                     number_content::I16::Constant(val) => RandomI16::constant(*val),
-                    number_content::I16::Id(id) => {
-                        RandomI16::incrementing(Incrementing::new_at(id.start_at.unwrap_or(1)))
+                    number_content::I16::Distribution(dist) => {
+                        RandomI16::distribution(dist.clone())?
+                    }
+                    number_content::I16::Linked(range) => {
+                        RandomI16::linked(range.compile(&mut compiler)?)
                     }
+                    number_content::I16::Id(id) => RandomI16::incrementing(
+                        Incrementing::new_at_sharded(id.start_at.unwrap_or(1), shard)?,
+                    ),
                 };
                 random_i16.into()
             }
@@ -524,6 +732,98 @@ impl RangeStep<u64> {
     }
 }
 
+impl NumberDistribution<u64> {
+    pub fn upcast(self, to: NumberContentKind) -> Result<NumberContent> {
+        match to {
+            NumberContentKind::U64 => Ok(number_content::U64::Distribution(self).into()),
+            NumberContentKind::I64 => {
+                let cast = self.try_cast(i64::try_from)?;
+                Ok(number_content::I64::Distribution(cast).into())
+            }
+            NumberContentKind::F64 => {
+                let cast =
+                    self.try_cast::<_, _, std::convert::Infallible>(|value| Ok(value as f64))?;
+                Ok(number_content::F64::Distribution(cast).into())
+            }
+        }
+    }
+}
+
+impl NumberDistribution<i64> {
+    pub fn upcast(self, to: NumberContentKind) -> Result<NumberContent> {
+        match to {
+            NumberContentKind::U64 => Err(failed!(
+                target: Release,
+                "cannot downcast numerical subtypes"
+            )),
+            NumberContentKind::I64 => Ok(number_content::I64::Distribution(self).into()),
+            NumberContentKind::F64 => {
+                let cast =
+                    self.try_cast::<_, _, std::convert::Infallible>(|value| Ok(value as f64))?;
+                Ok(number_content::F64::Distribution(cast).into())
+            }
+        }
+    }
+}
+
+impl NumberDistribution<f64> {
+    pub fn upcast(self, to: NumberContentKind) -> Result<NumberContent> {
+        match to {
+            NumberContentKind::U64 | NumberContentKind::I64 => Err(failed!(
+                target: Release,
+                "cannot downcast numerical subtypes"
+            )),
+            NumberContentKind::F64 => Ok(number_content::F64::Distribution(self).into()),
+        }
+    }
+}
+
+impl LinkedRange<u64> {
+    pub fn upcast(self, to: NumberContentKind) -> Result<NumberContent> {
+        match to {
+            NumberContentKind::U64 => Ok(number_content::U64::Linked(self).into()),
+            NumberContentKind::I64 => {
+                let cast = self.try_cast(i64::try_from)?;
+                Ok(number_content::I64::Linked(cast).into())
+            }
+            NumberContentKind::F64 => {
+                let cast =
+                    self.try_cast::<_, _, std::convert::Infallible>(|value| Ok(value as f64))?;
+                Ok(number_content::F64::Linked(cast).into())
+            }
+        }
+    }
+}
+
+impl LinkedRange<i64> {
+    pub fn upcast(self, to: NumberContentKind) -> Result<NumberContent> {
+        match to {
+            NumberContentKind::U64 => Err(failed!(
+                target: Release,
+                "cannot downcast numerical subtypes"
+            )),
+            NumberContentKind::I64 => Ok(number_content::I64::Linked(self).into()),
+            NumberContentKind::F64 => {
+                let cast =
+                    self.try_cast::<_, _, std::convert::Infallible>(|value| Ok(value as f64))?;
+                Ok(number_content::F64::Linked(cast).into())
+            }
+        }
+    }
+}
+
+impl LinkedRange<f64> {
+    pub fn upcast(self, to: NumberContentKind) -> Result<NumberContent> {
+        match to {
+            NumberContentKind::U64 | NumberContentKind::I64 => Err(failed!(
+                target: Release,
+                "cannot downcast numerical subtypes"
+            )),
+            NumberContentKind::F64 => Ok(number_content::F64::Linked(self).into()),
+        }
+    }
+}
+
 impl Categorical<u64> {
     pub fn upcast(self, to: NumberContentKind) -> Result<NumberContent> {
         match to {
@@ -619,6 +919,7 @@ impl number_content::U64 {
     pub fn upcast(self, to: NumberContentKind) -> Result<NumberContent> {
         match self {
             Self::Range(range) => range.upcast(to),
+            Self::Linked(range) => range.upcast(to),
             Self::Categorical(cat) => cat.upcast(to),
             Self::Constant(val) => match to {
                 NumberContentKind::U64 => Ok(self.into()),
@@ -635,6 +936,7 @@ impl number_content::U64 {
                 target: Release,
                 "cannot upcast an id number subtype: only unsigned integers are supported"
             )),
+            Self::Distribution(dist) => dist.upcast(to),
         }
     }
 }
@@ -643,6 +945,7 @@ impl number_content::I64 {
     pub fn upcast(self, to: NumberContentKind) -> Result<NumberContent> {
         match self {
             Self::Range(range) => range.upcast(to),
+            Self::Linked(range) => range.upcast(to),
             Self::Categorical(cat) => cat.upcast(to),
             Self::Constant(val) => match to {
                 NumberContentKind::U64 => Err(failed!(
@@ -656,6 +959,7 @@ impl number_content::I64 {
                 }
             },
             Self::Id(id) => id.upcast(to),
+            Self::Distribution(dist) => dist.upcast(to),
         }
     }
 }
@@ -664,6 +968,7 @@ impl number_content::F64 {
     pub fn upcast(self, to: NumberContentKind) -> Result<NumberContent> {
         match self {
             Self::Range(range) => range.upcast(to),
+            Self::Linked(range) => range.upcast(to),
             Self::Constant(_) => match to {
                 NumberContentKind::U64 => Err(failed!(
                     target: Release,
@@ -675,6 +980,7 @@ impl number_content::F64 {
                 )),
                 NumberContentKind::F64 => Ok(self.into()),
             },
+            Self::Distribution(dist) => dist.upcast(to),
         }
     }
 }
@@ -683,7 +989,9 @@ impl Hash for number_content::F32 {
     fn hash<H: Hasher>(&self, state: &mut H) {
         match self {
             Self::Range(range) => range.hash(state),
+            Self::Linked(range) => range.hash(state),
             Self::Constant(constant) => constant.to_bits().hash(state),
+            Self::Distribution(dist) => dist.hash(state),
         }
     }
 }
@@ -691,6 +999,14 @@ impl Hash for number_content::F32 {
 impl PartialEq for number_content::F32 {
     fn eq(&self, other: &number_content::F32) -> bool {
         match self {
+            Self::Linked(range) => match other {
+                Self::Linked(o_range) => range == o_range,
+                _ => false,
+            },
+            Self::Distribution(dist) => match other {
+                Self::Distribution(o_dist) => dist == o_dist,
+                _ => false,
+            },
             Self::Range(range) => match other {
                 Self::Range(o_range) => range == o_range,
                 _ => false,
@@ -707,7 +1023,9 @@ impl Hash for number_content::F64 {
     fn hash<H: Hasher>(&self, state: &mut H) {
         match self {
             Self::Range(range) => range.hash(state),
+            Self::Linked(range) => range.hash(state),
             Self::Constant(constant) => constant.to_bits().hash(state),
+            Self::Distribution(dist) => dist.hash(state),
         }
     }
 }
@@ -715,6 +1033,14 @@ impl Hash for number_content::F64 {
 impl PartialEq for number_content::F64 {
     fn eq(&self, other: &number_content::F64) -> bool {
         match self {
+            Self::Linked(range) => match other {
+                Self::Linked(o_range) => range == o_range,
+                _ => false,
+            },
+            Self::Distribution(dist) => match other {
+                Self::Distribution(o_dist) => dist == o_dist,
+                _ => false,
+            },
             Self::Range(range) => match other {
                 Self::Range(o_range) => range == o_range,
                 _ => false,
@@ -778,4 +1104,83 @@ pub mod tests {
         test_i32 -> "i32" I32: i32,
         test_i64 -> "i64" I64: i64,
     );
+
+    #[test]
+    fn distribution_for_every_subtype() {
+        for subtype in ["u32", "u64", "i16", "i32", "i64", "f32", "f64"] {
+            let number_content_as_json = json!({
+                "subtype": subtype,
+                "normal": {
+                    "mean": 40,
+                    "sd": 12,
+                    "low": 0,
+                    "high": 100
+                }
+            });
+            let number_content: NumberContent =
+                serde_json::from_value(number_content_as_json).unwrap();
+            assert!(number_content.kind().ends_with("::Distribution"));
+
+            let serialized = serde_json::to_value(&number_content).unwrap();
+            assert_eq!(
+                serde_json::from_value::<NumberContent>(serialized).unwrap(),
+                number_content
+            );
+        }
+    }
+
+    #[test]
+    fn other_variants_keep_their_errors() {
+        let err = serde_json::from_value::<NumberContent>(json!({
+            "subtype": "u32",
+            "range": {
+                "lo": 0
+            }
+        }))
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown field `lo`"), "{}", err);
+    }
+
+    #[test]
+    fn linked_range() {
+        let content: NumberContent = serde_json::from_value(json!({
+            "subtype": "f64",
+            "range": {"low": 0, "high": {"ref": "orders.content.price", "offset": -1}}
+        }))
+        .unwrap();
+        assert_eq!(
+            content,
+            NumberContent::F64(number_content::F64::Linked(LinkedRange {
+                low: Some(LinkedBound::Value(0.)),
+                high: Some(LinkedBound::Field {
+                    ref_: "orders.content.price".parse().unwrap(),
+                    offset: Some(-1.)
+                }),
+                step: None,
+                include_low: true,
+                include_high: false,
+            }))
+        );
+        assert_eq!(content.kind(), "F64::Linked");
+        assert_eq!(
+            serde_json::to_value(&content).unwrap(),
+            json!({
+                "subtype": "f64",
+                "range": {"low": 0.0, "high": {"ref": "orders.content.price", "offset": -1.0}}
+            })
+        );
+
+        let content: NumberContent = serde_json::from_value(json!({
+            "subtype": "u64",
+            "range": {"high": "@orders.content.quantity", "include_high": true}
+        }))
+        .unwrap();
+        assert_eq!(content.kind(), "U64::Linked");
+
+        assert!(serde_json::from_value::<NumberContent>(json!({
+            "subtype": "u64",
+            "range": {"high": "@orders.content.quantity", "highest": 4}
+        }))
+        .is_err());
+    }
 }
@@ -2,18 +2,43 @@ use super::prelude::*;
 use serde::ser::Serializer;
 use std::borrow::Cow;
 use std::collections::BTreeMap;
+use std::hash::Hasher;
 
-const RESERVED_FIELDS: [&str; 2] = ["type", "skip_when_null"];
+const RESERVED_FIELDS: [&str; 3] = ["type", "skip_when_null", "unique_together"];
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Hash)]
 pub struct ObjectContent {
     #[serde(default)]
     #[serde(skip_serializing_if = "std::ops::Not::not")]
     pub skip_when_null: bool,
+    /// Sets of fields, given by their path from the object like `address.city`, whose values are
+    /// never generated together twice.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unique_together: Vec<Vec<String>>,
     #[serde(flatten)]
     #[serde(serialize_with = "normalize_keys")]
     #[serde(deserialize_with = "denormalize_keys")]
     pub fields: BTreeMap<String, Content>,
+    /// The number of objects the content was inferred from, which tells how often its fields were
+    /// missing or null before they became nullable.
+    #[serde(skip)]
+    pub seen: Seen,
+}
+
+/// A count kept while inferring a content, which is not part of its schema: it is neither
+/// serialized nor compared.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Seen(pub(crate) u64);
+
+impl PartialEq for Seen {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Hash for Seen {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
 }
 
 fn normalize_keys<S: Serializer>(
@@ -115,6 +140,43 @@ impl ObjectContent {
         self.fields.len()
     }
 
+    /// The `unique_together` sets of fields, with each path split into field names.
+    pub fn unique_keys(&self) -> Result<Vec<Vec<Vec<String>>>> {
+        self.unique_together
+            .iter()
+            .map(|key| {
+                if key.is_empty() {
+                    return Err(failed!(
+                        target: Release,
+                        "`unique_together` cannot have an empty set of fields"
+                    ));
+                }
+
+                key.iter()
+                    .map(|path| {
+                        let fields = path.split('.').map(str::to_string).collect::<Vec<_>>();
+                        let mut object = self;
+                        for (depth, field) in fields.iter().enumerate() {
+                            match object.get(field)? {
+                                Content::Object(inner) => object = inner,
+                                _ if depth + 1 == fields.len() => {}
+                                _ => {
+                                    return Err(failed!(
+                                        target: Release,
+                                        "'{}' is not an object",
+                                        field
+                                    ))
+                                }
+                            }
+                        }
+                        Ok(fields)
+                    })
+                    .collect::<Result<_>>()
+                    .with_context(|| anyhow!("in `unique_together`"))
+            })
+            .collect()
+    }
+
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
@@ -152,6 +214,10 @@ impl Find<Content> for ObjectContent {
 
 impl Compile for ObjectContent {
     fn compile<'a, C: Compiler<'a>>(&'a self, mut compiler: C) -> Result<Graph> {
+        // Objects with a `unique_together` are made unique by the compiler, as whole objects are
+        // generated again
+        self.unique_keys()?;
+
         let object_node = self
             .iter()
             .map(|(name, field)| {
@@ -160,6 +226,10 @@ impl Compile for ObjectContent {
                     compiler
                         .build(name, nullable)
                         .map(|graph| KeyValueOrNothing::sometimes(name, graph, false))
+                } else if self.skip_when_null && matches!(field, Content::Switch(_)) {
+                    compiler
+                        .build(name, field)
+                        .map(|graph| KeyValueOrNothing::always(name, graph, false).skipping_null())
                 } else {
                     compiler.build(name, field).map(|graph| {
                         KeyValueOrNothing::always(name, graph, matches!(field, Content::Hidden(_)))
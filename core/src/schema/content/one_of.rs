@@ -61,6 +61,17 @@ impl VariantContent {
             content: Box::new(content),
         }
     }
+
+    pub(crate) fn weighted(content: Content, weight: f64) -> Self {
+        VariantContent {
+            weight: Weight(weight),
+            content: Box::new(content),
+        }
+    }
+
+    pub(crate) fn uncount(&mut self) {
+        self.weight = Weight(0.0);
+    }
 }
 
 impl FromIterator<Content> for OneOfContent {
@@ -125,11 +136,15 @@ impl OneOfContent {
         M: MergeStrategy<Self, Value> + MergeStrategy<Content, Value> + Copy,
     {
         let res: Vec<_> = self
+            .variants
             .iter_mut()
-            .map(|variant| strategy.try_merge(variant, what))
+            .map(|variant| strategy.try_merge(variant.content.as_mut(), what))
             .collect();
-        if !res.iter().any(|r| r.is_ok()) {
-            self.variants.push(VariantContent::new(what.into()))
+        // The weight of a variant counts the values it took, so that the variants are generated as
+        // often as they were seen
+        match res.iter().position(|r| r.is_ok()) {
+            Some(index) => self.variants[index].weight.0 += 1.0,
+            None => self.variants.push(VariantContent::new(what.into())),
         }
     }
 
@@ -222,6 +237,9 @@ impl Find<Content> for OneOfContent {
 
 impl Compile for OneOfContent {
     fn compile<'a, C: Compiler<'a>>(&'a self, mut compiler: C) -> Result<Graph> {
+        // Variants which were never seen, like those of a column of an empty table, are as likely
+        // as each other
+        let unweighted = self.variants.iter().all(|variant| variant.weight.0 == 0.0);
         let one_of_node = self
             .variants
             .iter()
@@ -229,7 +247,7 @@ impl Compile for OneOfContent {
             .map(|(idx, variant)| {
                 compiler
                     .build(&idx.to_string(), &variant.content)
-                    .map(|graph| (variant.weight.0, graph))
+                    .map(|graph| (if unweighted { 1.0 } else { variant.weight.0 }, graph))
             })
             .collect::<Result<OneOfNode>>()?;
         Ok(Graph::OneOf(one_of_node))
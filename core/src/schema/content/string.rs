@@ -272,6 +272,12 @@ pub struct SlicedContent {
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 pub struct ConstantContent(String);
 
+impl ConstantContent {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
 impl From<String> for ConstantContent {
     fn from(s: String) -> Self {
         ConstantContent(s)
@@ -0,0 +1,80 @@
+use super::prelude::*;
+
+use std::collections::BTreeMap;
+
+/// Content chosen by the value generated by `on`, usually a reference to another field.
+///
+/// The keys of `cases` are matched against the value as it would be written in JSON, with
+/// strings unquoted: `"card"`, `"42"`, `"true"` or `"null"`. Values without a case generate
+/// `default`, which is `null` unless given.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Hash)]
+#[serde(deny_unknown_fields)]
+pub struct SwitchContent {
+    pub on: Box<Content>,
+    pub cases: BTreeMap<String, Content>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Content::is_null")]
+    pub default: Box<Content>,
+}
+
+impl SwitchContent {
+    /// The content of every case, followed by the default.
+    pub fn iter(&self) -> impl Iterator<Item = &Content> {
+        self.cases
+            .values()
+            .chain(std::iter::once(self.default.as_ref()))
+    }
+}
+
+impl Compile for SwitchContent {
+    fn compile<'a, C: Compiler<'a>>(&'a self, mut compiler: C) -> Result<Graph> {
+        let on = compiler.build("on", &self.on)?;
+        let cases = self
+            .cases
+            .iter()
+            .enumerate()
+            .map(|(index, (key, content))| {
+                Ok((key.clone(), compiler.build(&index.to_string(), content)?))
+            })
+            .collect::<Result<_>>()?;
+        let default = compiler.build("default", &self.default)?;
+        Ok(Graph::Switch(SwitchNode::new(on, cases, default)))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn switch_serde() {
+        let content: Content = serde_json::from_value(json!({
+            "type": "switch",
+            "on": "@payments.content.method",
+            "cases": {
+                "card": {"type": "string", "pattern": "[0-9]{4}"}
+            }
+        }))
+        .unwrap();
+        let switch = match &content {
+            Content::Switch(switch) => switch,
+            otherwise => panic!("expected a switch, got {otherwise}"),
+        };
+        assert!(matches!(switch.on.as_ref(), Content::SameAs(_)));
+        assert!(switch.default.is_null());
+        let serialized = serde_json::to_value(&content).unwrap();
+        assert!(serialized.get("default").is_none());
+        assert_eq!(
+            serde_json::from_value::<Content>(serialized).unwrap(),
+            content
+        );
+
+        for invalid in [
+            json!({"type": "switch"}),
+            json!({"type": "switch", "on": "@a.content.b"}),
+            json!({"type": "switch", "on": "@a.content.b", "cases": {}, "else": null}),
+        ] {
+            assert!(serde_json::from_value::<Content>(invalid).is_err());
+        }
+    }
+}
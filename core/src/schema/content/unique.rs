@@ -1,13 +1,29 @@
 #![allow(clippy::derivable_impls)]
-use crate::compile::Compile;
+use crate::compile::{Compile, Shard};
 use crate::graph::UniqueNode;
+use crate::schema::{number_content, NumberContent, RangeStep};
 use crate::{Compiler, Content, Graph};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use synth_gen::value::Number;
 
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+use std::hash::{Hash, Hasher};
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub enum UniqueAlgorithm {
-    Hash { retries: Option<usize> },
+    Hash {
+        retries: Option<usize>,
+    },
+    /// Remembers the values generated in a bloom filter, which takes a fixed amount of memory
+    /// but wrongly rejects a share of the new values.
+    Bloom {
+        capacity: usize,
+        false_positive_rate: f64,
+        retries: Option<usize>,
+    },
+    /// Goes through the values of the `range` of an integer number in order, so never needs to
+    /// retry nor remember the values.
+    Sequential,
 }
 
 #[allow(clippy::derivable_impls)]
@@ -17,6 +33,25 @@ impl Default for UniqueAlgorithm {
     }
 }
 
+impl Hash for UniqueAlgorithm {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::Hash { retries } => retries.hash(state),
+            Self::Bloom {
+                capacity,
+                false_positive_rate,
+                retries,
+            } => {
+                capacity.hash(state);
+                false_positive_rate.to_bits().hash(state);
+                retries.hash(state);
+            }
+            Self::Sequential => {}
+        }
+    }
+}
+
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Hash)]
 #[serde(deny_unknown_fields)]
@@ -26,11 +61,81 @@ pub struct UniqueContent {
     pub content: Box<Content>,
 }
 
+/// The values of an integer `range` one after the other, from the lowest.
+fn sequence(content: &Content, shard: Shard) -> Result<UniqueNode> {
+    fn bounds<N: Copy + Into<i128>>(range: &RangeStep<N>, min: N, max: N) -> Result<[i128; 3]> {
+        let step = range.step.map(Into::into).unwrap_or(1);
+        if step <= 0 {
+            bail!(
+                "the step of a `Sequential` range must be positive, not {}",
+                step
+            );
+        }
+        let low = range.low.unwrap_or(min).into();
+        let high = range.high.unwrap_or(max).into();
+        let first = if range.include_low { low } else { low + step };
+        let last = if range.include_high { high } else { high - 1 };
+        Ok([first, last, step])
+    }
+
+    let ([first, last, step], number): (_, fn(i128) -> Number) = match content {
+        Content::Number(NumberContent::U32(number_content::U32::Range(range))) => (
+            bounds(range, u32::MIN, u32::MAX)?,
+            |n| Number::U32(n as u32),
+        ),
+        Content::Number(NumberContent::U64(number_content::U64::Range(range))) => (
+            bounds(range, u64::MIN, u64::MAX)?,
+            |n| Number::U64(n as u64),
+        ),
+        Content::Number(NumberContent::I16(number_content::I16::Range(range))) => (
+            bounds(range, i16::MIN, i16::MAX)?,
+            |n| Number::I16(n as i16),
+        ),
+        Content::Number(NumberContent::I32(number_content::I32::Range(range))) => (
+            bounds(range, i32::MIN, i32::MAX)?,
+            |n| Number::I32(n as i32),
+        ),
+        Content::Number(NumberContent::I64(number_content::I64::Range(range))) => (
+            bounds(range, i64::MIN, i64::MAX)?,
+            |n| Number::I64(n as i64),
+        ),
+        _ => bail!(
+            "the `Sequential` algorithm can only make the values of an integer number with a `range` unique"
+        ),
+    };
+    Ok(UniqueNode::sequence(first, last, step, number, shard))
+}
+
 impl Compile for UniqueContent {
     fn compile<'a, C: Compiler<'a>>(&'a self, compiler: C) -> Result<Graph> {
-        let graph = self.content.compile(compiler)?;
+        // The values referenced by a `same_as` were generated by the same shard, so they are
+        // already apart from those of the other shards
+        let shard = match self.content.as_ref() {
+            Content::SameAs(_) => Shard::default(),
+            _ => compiler.shard(),
+        };
         let node = match self.algorithm {
-            UniqueAlgorithm::Hash { retries } => UniqueNode::hash(graph, retries),
+            UniqueAlgorithm::Hash { retries } => {
+                UniqueNode::hash_sharded(self.content.compile(compiler)?, retries, shard)
+            }
+            UniqueAlgorithm::Bloom {
+                capacity,
+                false_positive_rate,
+                retries,
+            } => {
+                if capacity == 0 {
+                    bail!("the `capacity` of a bloom filter must be positive");
+                }
+                if !(false_positive_rate > 0.0 && false_positive_rate < 1.0) {
+                    bail!(
+                        "the `false_positive_rate` of a bloom filter must be between 0 and 1, not {}",
+                        false_positive_rate
+                    );
+                }
+                let graph = self.content.compile(compiler)?;
+                UniqueNode::bloom_sharded(graph, capacity, false_positive_rate, retries, shard)
+            }
+            UniqueAlgorithm::Sequential => sequence(&self.content, shard)?,
         };
         Ok(Graph::Unique(node))
     }
@@ -0,0 +1,310 @@
+//! Finds the pairs of fields of an object with few values whose values depend on each other, like
+//! a city and its country, so that one of them is generated by a switch on the value of the other
+//! rather than independently of it.
+use serde_json::{Map, Value};
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::schema::{
+    number_content, BoolContent, Categorical, CategoricalType, Content, FieldRef, NumberContent,
+    ObjectContent, OneOfContent, SameAsContent, StringContent, SwitchContent, VariantContent,
+};
+
+/// The most distinct values, counting null, of a field which can be correlated.
+const MAX_CATEGORIES: usize = 20;
+
+/// The upper quantile of the standard normal distribution at the significance of the test of
+/// independence, 0.1%.
+const SIGNIFICANCE: f64 = 3.09;
+
+/// The weakest association, as Cramér's V, of a pair of fields generated one from the other.
+const MIN_ASSOCIATION: f64 = 0.5;
+
+/// The value of a scalar as it is keyed in the cases of a switch, `None` for null.
+type Key = Option<String>;
+
+fn key_of(value: &Value) -> Option<Key> {
+    match value {
+        Value::Null => Some(None),
+        Value::Bool(boolean) => Some(Some(boolean.to_string())),
+        Value::Number(number) => Some(Some(number.to_string())),
+        Value::String(string) => Some(Some(string.clone())),
+        Value::Array(_) | Value::Object(_) => None,
+    }
+}
+
+/// Counts the values of a field, while they are few scalars.
+#[derive(Debug, Default)]
+pub(crate) struct Categories {
+    counts: BTreeMap<Key, u64>,
+    too_many: bool,
+}
+
+impl Categories {
+    pub(crate) fn observe(&mut self, value: &Value) {
+        if self.too_many {
+            return;
+        }
+        match key_of(value) {
+            Some(key) => {
+                *self.counts.entry(key).or_default() += 1;
+                if self.counts.len() > MAX_CATEGORIES {
+                    self.too_many = true;
+                    self.counts.clear()
+                }
+            }
+            None => {
+                self.too_many = true;
+                self.counts.clear()
+            }
+        }
+    }
+
+    pub(crate) fn is_categorical(&self) -> bool {
+        !self.too_many
+    }
+}
+
+/// Counts the values taken together by every pair of fields of an object with few values.
+#[derive(Debug, Default)]
+pub(crate) struct Correlations {
+    objects: u64,
+    joint: BTreeMap<(String, String), BTreeMap<(Key, Key), u64>>,
+}
+
+impl Correlations {
+    pub(crate) fn observe<F>(&mut self, object: &Map<String, Value>, is_categorical: F)
+    where
+        F: Fn(&str) -> bool,
+    {
+        self.objects += 1;
+        let keys = object
+            .iter()
+            .filter(|(field, _)| is_categorical(field))
+            .filter_map(|(field, value)| Some((field, key_of(value)?)))
+            .collect::<Vec<_>>();
+        for (index, (left, left_key)) in keys.iter().enumerate() {
+            for (right, right_key) in &keys[index + 1..] {
+                *self
+                    .joint
+                    .entry(((*left).clone(), (*right).clone()))
+                    .or_default()
+                    .entry((left_key.clone(), right_key.clone()))
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    /// Replaces the most associated pairs of fields of `object`, the content at `at`, by the
+    /// categorical of the field with fewer values and a switch on it generating the other. A field
+    /// is generated from at most one other, and never switched on when it is.
+    pub(crate) fn apply(
+        &self,
+        object: &mut ObjectContent,
+        at: &FieldRef,
+        categories: &BTreeMap<&str, &Categories>,
+    ) {
+        let mut pairs = self
+            .joint
+            .iter()
+            .filter(|(_, table)| table.values().sum::<u64>() == self.objects)
+            .filter(|((left, right), _)| {
+                [left, right].iter().all(|field| {
+                    categories
+                        .get(field.as_str())
+                        .filter(|categories| categories.is_categorical())
+                        .zip(object.fields.get(field.as_str()))
+                        .and_then(|(categories, content)| conditional(content, &categories.counts))
+                        .is_some()
+                })
+            })
+            .filter_map(|(fields, table)| Some((fields, table, association(table)?)))
+            .collect::<Vec<_>>();
+        pairs.sort_by(|(_, _, left), (_, _, right)| right.total_cmp(left));
+
+        let mut switched_on = BTreeSet::new();
+        let mut switched = BTreeSet::new();
+        for ((left, right), table, _) in pairs {
+            let fewer = |field: &String| categories[field.as_str()].counts.len();
+            let directions = if fewer(right) < fewer(left) {
+                [(right, left, true), (left, right, false)]
+            } else {
+                [(left, right, false), (right, left, true)]
+            };
+            let direction = directions.into_iter().find(|(on, field, _)| {
+                !switched.contains(*on)
+                    && !switched.contains(*field)
+                    && !switched_on.contains(*field)
+            });
+            if let Some((on, field, flipped)) = direction {
+                let mut cases: BTreeMap<&Key, BTreeMap<Key, u64>> = BTreeMap::new();
+                for ((left_key, right_key), count) in table {
+                    let (on_key, key) = if flipped {
+                        (right_key, left_key)
+                    } else {
+                        (left_key, right_key)
+                    };
+                    *cases
+                        .entry(on_key)
+                        .or_default()
+                        .entry(key.clone())
+                        .or_default() += *count;
+                }
+                // Strings spelling `null` can't be told apart from nulls by the switch
+                if cases.contains_key(&None) && cases.contains_key(&Some("null".to_string())) {
+                    continue;
+                }
+                let like = object.fields[field.as_str()].clone();
+                let cases = cases
+                    .into_iter()
+                    .map(|(on_key, counts)| {
+                        let case = on_key.clone().unwrap_or_else(|| "null".to_string());
+                        Some((case, conditional(&like, &counts)?))
+                    })
+                    .collect::<Option<_>>();
+                let on_content =
+                    conditional(&object.fields[on.as_str()], &categories[on.as_str()].counts);
+                if let (Some(cases), Some(on_content)) = (cases, on_content) {
+                    object.fields.insert(on.clone(), on_content);
+                    object.fields.insert(
+                        field.clone(),
+                        Content::Switch(SwitchContent {
+                            on: Box::new(Content::SameAs(SameAsContent {
+                                ref_: at.child(on.as_str()),
+                                cardinality: None,
+                            })),
+                            cases,
+                            default: Box::new(Content::null()),
+                        }),
+                    );
+                    switched_on.insert(on);
+                    switched.insert(field);
+                }
+            }
+        }
+    }
+}
+
+/// Cramér's V of the values of a pair of fields, if they are not independent.
+fn association(table: &BTreeMap<(Key, Key), u64>) -> Option<f64> {
+    let mut rows: BTreeMap<&Key, f64> = BTreeMap::new();
+    let mut columns: BTreeMap<&Key, f64> = BTreeMap::new();
+    for ((row, column), count) in table {
+        *rows.entry(row).or_default() += *count as f64;
+        *columns.entry(column).or_default() += *count as f64;
+    }
+    if rows.len() < 2 || columns.len() < 2 {
+        return None;
+    }
+    let n = table.values().sum::<u64>() as f64;
+    // The cells never seen add as much as they are expected, so that summing over the ones seen
+    // and subtracting `n` is the statistic over all of them
+    let chi_squared = table
+        .iter()
+        .map(|((row, column), count)| {
+            let expected = rows[row] * columns[column] / n;
+            (*count as f64).powi(2) / expected
+        })
+        .sum::<f64>()
+        - n;
+    let degrees = ((rows.len() - 1) * (columns.len() - 1)) as f64;
+    // Wilson-Hilferty approximation of the quantile of the chi-squared distribution
+    let spread = 2.0 / (9.0 * degrees);
+    let critical = degrees * (1.0 - spread + SIGNIFICANCE * spread.sqrt()).powi(3);
+    if chi_squared <= critical {
+        return None;
+    }
+    let smallest = (rows.len().min(columns.len()) - 1) as f64;
+    let association = (chi_squared / (n * smallest)).sqrt();
+    (association >= MIN_ASSOCIATION).then_some(association)
+}
+
+/// A content of the same kind as `like` generating values as often as they are counted in
+/// `counts`, null included.
+fn conditional(like: &Content, counts: &BTreeMap<Key, u64>) -> Option<Content> {
+    let nulls = counts.get(&None).copied().unwrap_or_default();
+    let seen = counts
+        .iter()
+        .filter_map(|(key, count)| Some((key.as_deref()?, *count)))
+        .collect::<BTreeMap<_, _>>();
+    if seen.is_empty() {
+        return Some(Content::null());
+    }
+    let content = categorical_like(like, &seen)?;
+    if nulls == 0 {
+        return Some(content);
+    }
+    Some(Content::OneOf(OneOfContent {
+        variants: vec![
+            VariantContent::weighted(content, seen.values().sum::<u64>() as f64),
+            VariantContent::weighted(Content::null(), nulls as f64),
+        ],
+    }))
+}
+
+fn categorical_like(like: &Content, seen: &BTreeMap<&str, u64>) -> Option<Content> {
+    let content = match like {
+        Content::String(_) => Content::String(StringContent::Categorical(categorical(seen)?)),
+        Content::Bool(_) => Content::Bool(BoolContent::Categorical(categorical(seen)?)),
+        Content::Number(NumberContent::U32(_)) => {
+            Content::Number(number_content::U32::Categorical(categorical(seen)?).into())
+        }
+        Content::Number(NumberContent::U64(_)) => {
+            Content::Number(number_content::U64::Categorical(categorical(seen)?).into())
+        }
+        Content::Number(NumberContent::I16(_)) => {
+            Content::Number(number_content::I16::Categorical(categorical(seen)?).into())
+        }
+        Content::Number(NumberContent::I32(_)) => {
+            Content::Number(number_content::I32::Categorical(categorical(seen)?).into())
+        }
+        Content::Number(NumberContent::I64(_)) => {
+            Content::Number(number_content::I64::Categorical(categorical(seen)?).into())
+        }
+        Content::OneOf(one_of) => return categorical_like(one_of.as_nullable()?, seen),
+        _ => return None,
+    };
+    Some(content)
+}
+
+fn categorical<T: CategoricalType>(seen: &BTreeMap<&str, u64>) -> Option<Categorical<T>> {
+    let seen = seen
+        .iter()
+        .map(|(key, count)| Some((key.parse().ok()?, *count)))
+        .collect::<Option<BTreeMap<T, u64>>>()?;
+    let total = seen.values().sum();
+    Some(Categorical { seen, total })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn table(cells: &[(&str, &str, u64)]) -> BTreeMap<(Key, Key), u64> {
+        cells
+            .iter()
+            .map(|(row, column, count)| ((Some(row.to_string()), Some(column.to_string())), *count))
+            .collect()
+    }
+
+    #[test]
+    fn associations() {
+        let dependent = table(&[("a", "x", 10), ("b", "y", 10), ("c", "y", 10)]);
+        assert!(association(&dependent).unwrap() > 0.99);
+
+        let independent = table(&[
+            ("a", "x", 10),
+            ("a", "y", 10),
+            ("b", "x", 10),
+            ("b", "y", 10),
+        ]);
+        assert_eq!(association(&independent), None);
+
+        // As dependent as can be, but too few to tell
+        let few = table(&[("a", "x", 1), ("b", "y", 1), ("c", "z", 1)]);
+        assert_eq!(association(&few), None);
+
+        let constant = table(&[("a", "x", 10), ("b", "x", 10)]);
+        assert_eq!(association(&constant), None);
+    }
+}
@@ -0,0 +1,510 @@
+//! Fits a distribution to the values of a number, so that it is generated like the values imported
+//! rather than uniformly over their range.
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::InferenceOptions;
+use crate::schema::{
+    number_content, Exponential, Histogram, HistogramBucket, LogNormal, Normal, NumberContent,
+    NumberDistribution, Poisson, RangeStep,
+};
+
+/// The fewest values, and distinct values, a distribution is fitted to.
+const MIN_VALUES: u64 = 10;
+const MIN_DISTINCT: usize = 3;
+
+/// The most values kept to compare the distributions with, beyond which a uniform sample of them
+/// is kept instead.
+const MAX_KEPT: usize = 10_000;
+
+/// The largest mean of a Poisson distribution whose probabilities are summed in floats.
+const MAX_POISSON_MEAN: f64 = 500.0;
+
+/// The most buckets of a histogram of the values.
+const MAX_BUCKETS: usize = 20;
+
+/// The summary statistics of the values of a number.
+#[derive(Debug)]
+pub(crate) struct NumberStatistics {
+    count: u64,
+    mean: f64,
+    /// The sum of the squared differences to the mean, as in Welford's algorithm
+    m2: f64,
+    min: f64,
+    max: f64,
+    integers: bool,
+    /// The greatest common divisor of the differences of integers to the first one
+    granularity: u64,
+    first: f64,
+    kept: Vec<f64>,
+    rng: StdRng,
+}
+
+impl Default for NumberStatistics {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            integers: true,
+            granularity: 0,
+            first: 0.0,
+            kept: Vec::new(),
+            rng: StdRng::seed_from_u64(0),
+        }
+    }
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// The error function, to within 1.5e-7 (Abramowitz and Stegun, 7.1.26).
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let y = 1.0 - poly * (-x * x).exp();
+    if x < 0.0 {
+        -y
+    } else {
+        y
+    }
+}
+
+fn normal_cdf(x: f64, mean: f64, sd: f64) -> f64 {
+    0.5 * (1.0 + erf((x - mean) / (sd * std::f64::consts::SQRT_2)))
+}
+
+fn poisson_cdf(x: f64, lambda: f64) -> f64 {
+    if x < 0.0 {
+        return 0.0;
+    }
+    let mut pmf = (-lambda).exp();
+    let mut cdf = pmf;
+    for k in 1..=(x.floor() as u64) {
+        pmf *= lambda / k as f64;
+        cdf += pmf;
+    }
+    cdf.min(1.0)
+}
+
+/// A candidate for the distribution of the values.
+enum Candidate {
+    Uniform { low: f64, high: f64 },
+    Fitted(NumberDistribution<f64>),
+}
+
+impl Candidate {
+    fn cdf(&self, x: f64) -> f64 {
+        match self {
+            Self::Uniform { low, high } => ((x - low) / (high - low)).clamp(0.0, 1.0),
+            Self::Fitted(NumberDistribution::Normal(normal)) => {
+                normal_cdf(x, normal.mean, normal.sd)
+            }
+            Self::Fitted(NumberDistribution::LogNormal(log_normal)) if x > 0.0 => {
+                normal_cdf(x.ln(), log_normal.mean, log_normal.sd)
+            }
+            Self::Fitted(NumberDistribution::Exponential(exponential)) if x > 0.0 => {
+                1.0 - (-exponential.rate * x).exp()
+            }
+            Self::Fitted(NumberDistribution::Poisson(poisson)) => poisson_cdf(x, poisson.lambda),
+            _ => 0.0,
+        }
+    }
+}
+
+impl NumberStatistics {
+    pub(crate) fn observe(&mut self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        if self.count == 0 {
+            self.first = value;
+        }
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        if self.integers && value.fract() == 0.0 {
+            self.granularity = gcd(self.granularity, (value - self.first).abs() as u64);
+        } else {
+            self.integers = false;
+        }
+
+        if self.kept.len() < MAX_KEPT {
+            self.kept.push(value);
+        } else {
+            let index = self.rng.gen_range(0..self.count);
+            if let Some(kept) = self.kept.get_mut(index as usize) {
+                *kept = value;
+            }
+        }
+    }
+
+    pub(crate) fn count(&self) -> u64 {
+        self.count
+    }
+
+    fn sorted(&self) -> Vec<f64> {
+        let mut sorted = self.kept.clone();
+        sorted.sort_by(f64::total_cmp);
+        sorted
+    }
+
+    fn sd(&self) -> f64 {
+        (self.m2 / (self.count - 1) as f64).sqrt()
+    }
+
+    /// The distributions the values could follow, starting with the uniform one of their range.
+    fn candidates(&self) -> Vec<Candidate> {
+        // Integers are compared to continuous distributions at the middle between them
+        let half = if self.integers { 0.5 } else { 0.0 };
+        let mut candidates = vec![Candidate::Uniform {
+            low: self.min - half,
+            high: self.max + half,
+        }];
+        candidates.push(Candidate::Fitted(NumberDistribution::Normal(Normal {
+            mean: self.mean,
+            sd: self.sd(),
+            low: None,
+            high: None,
+        })));
+        if self.min > 0.0 {
+            let logs: Vec<f64> = self.kept.iter().map(|value| value.ln()).collect();
+            let mean = logs.iter().sum::<f64>() / logs.len() as f64;
+            let variance =
+                logs.iter().map(|log| (log - mean).powi(2)).sum::<f64>() / (logs.len() - 1) as f64;
+            if variance > 0.0 {
+                candidates.push(Candidate::Fitted(NumberDistribution::LogNormal(
+                    LogNormal {
+                        mean,
+                        sd: variance.sqrt(),
+                        low: None,
+                        high: None,
+                    },
+                )));
+            }
+        }
+        if self.min >= 0.0 && self.mean > 0.0 {
+            candidates.push(Candidate::Fitted(NumberDistribution::Exponential(
+                Exponential {
+                    rate: 1.0 / self.mean,
+                    low: None,
+                    high: None,
+                },
+            )));
+            if self.integers && self.mean <= MAX_POISSON_MEAN {
+                candidates.push(Candidate::Fitted(NumberDistribution::Poisson(Poisson {
+                    lambda: self.mean,
+                    low: None,
+                    high: None,
+                })));
+            }
+        }
+        candidates
+    }
+
+    /// The Kolmogorov-Smirnov statistic of `candidate`: the largest difference between its
+    /// cumulative distribution and that of the values.
+    fn distance(&self, candidate: &Candidate, sorted: &[f64]) -> f64 {
+        let half = if self.integers { 0.5 } else { 0.0 };
+        let n = sorted.len() as f64;
+        let mut distance: f64 = 0.0;
+        let mut index = 0;
+        while index < sorted.len() {
+            let value = sorted[index];
+            let below = index as f64 / n;
+            while index < sorted.len() && sorted[index] == value {
+                index += 1;
+            }
+            let up_to = index as f64 / n;
+            distance = distance
+                .max((candidate.cdf(value - half) - below).abs())
+                .max((candidate.cdf(value + half) - up_to).abs());
+        }
+        distance
+    }
+
+    /// The distribution closest to the values, if it is not the uniform one, with its distance to
+    /// them.
+    fn fit(&self) -> Option<(NumberDistribution<f64>, f64)> {
+        let sorted = self.sorted();
+        let mut distinct = sorted.clone();
+        distinct.dedup();
+        if self.count < MIN_VALUES || distinct.len() < MIN_DISTINCT || self.sd() <= 0.0 {
+            return None;
+        }
+
+        let mut best = None;
+        let mut best_distance = f64::INFINITY;
+        for candidate in self.candidates() {
+            let distance = self.distance(&candidate, &sorted);
+            if distance < best_distance {
+                best_distance = distance;
+                best = Some(candidate);
+            }
+        }
+        match best? {
+            Candidate::Uniform { .. } => None,
+            Candidate::Fitted(dist) => Some((dist, best_distance)),
+        }
+    }
+
+    /// The largest distance of a distribution fitting the values that is likely to be only due to
+    /// chance, at the 5% level of the Kolmogorov-Smirnov test.
+    fn critical_distance(&self) -> f64 {
+        1.36 / (self.kept.len() as f64).sqrt()
+    }
+
+    /// A histogram of the values in up to [`MAX_BUCKETS`] buckets of the same width, leaving out
+    /// the empty ones. Buckets of `integers` have whole bounds.
+    pub(crate) fn histogram(&self, integers: bool) -> Option<Vec<HistogramBucket>> {
+        let sorted = self.sorted();
+        if self.count < MIN_VALUES || self.max <= self.min {
+            return None;
+        }
+
+        let num_buckets = ((sorted.len() as f64).sqrt().ceil() as usize).clamp(1, MAX_BUCKETS);
+        let (low, high) = if integers {
+            (self.min, self.max + 1.0)
+        } else {
+            (self.min, self.max)
+        };
+        let mut width = (high - low) / num_buckets as f64;
+        if integers {
+            width = width.ceil();
+        }
+        let num_buckets = ((high - low) / width).ceil() as usize;
+
+        let mut weights = vec![0u64; num_buckets];
+        for value in sorted {
+            let index = (((value - low) / width) as usize).min(num_buckets - 1);
+            weights[index] += 1;
+        }
+        let buckets = weights
+            .into_iter()
+            .enumerate()
+            .filter(|(_, weight)| *weight > 0)
+            .map(|(index, weight)| HistogramBucket {
+                low: low + index as f64 * width,
+                high: (low + (index + 1) as f64 * width).min(high),
+                weight: weight as f64,
+            })
+            .collect();
+        Some(buckets)
+    }
+
+    /// The distribution to generate the values with: the best fitting one when asked to fit
+    /// distributions, unless it is too far from the values to have a histogram of them instead.
+    fn distribution(&self, options: InferenceOptions) -> Option<NumberDistribution<f64>> {
+        let fitted = if options.fit_distributions {
+            self.fit()
+        } else {
+            None
+        };
+        match fitted {
+            Some((dist, distance))
+                if !options.histograms || distance < self.critical_distance() =>
+            {
+                Some(dist)
+            }
+            _ if options.histograms => self.histogram(self.integers).map(|buckets| {
+                NumberDistribution::Histogram(Histogram {
+                    buckets,
+                    low: None,
+                    high: None,
+                })
+            }),
+            _ => None,
+        }
+    }
+
+    /// Generates `number` like the values seen: integers on a coarser grid than 1 keep their
+    /// uniform `range` with that `step`, others get the distribution fitting them best, or their
+    /// histogram, between the bounds of their `range`. Numbers other than ranges are left as they
+    /// are.
+    pub(crate) fn apply(&self, number: &mut NumberContent, options: InferenceOptions) {
+        macro_rules! fit_range {
+            ($($as:ident => $step:expr,)*) => {
+                match number {
+                    $(
+                        NumberContent::$as(number_content::$as::Range(range)) => {
+                            if let Some(step) = $step {
+                                range.step = Some(step);
+                            } else if let Some(dist) = self.distribution(options) {
+                                let RangeStep { low, high, .. } = range.clone();
+                                *number = number_content::$as::Distribution(
+                                    dist.with_bounds(low, high),
+                                )
+                                .into();
+                            }
+                        }
+                    )*
+                    _ => {}
+                }
+            };
+        }
+        fit_range!(
+            U32 => self.integer_step::<u32>(),
+            U64 => self.integer_step::<u64>(),
+            I16 => self.integer_step::<i16>(),
+            I32 => self.integer_step::<i32>(),
+            I64 => self.integer_step::<i64>(),
+            F32 => None,
+            F64 => None,
+        );
+    }
+
+    /// The step between integers when they all are some multiple of more than 1 apart.
+    fn integer_step<N: TryFrom<u64>>(&self) -> Option<N> {
+        if self.integers && self.count >= MIN_VALUES && self.granularity > 1 {
+            N::try_from(self.granularity).ok()
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn fitted(values: impl IntoIterator<Item = f64>) -> Option<NumberDistribution<f64>> {
+        let mut statistics = NumberStatistics::default();
+        values
+            .into_iter()
+            .for_each(|value| statistics.observe(value));
+        statistics.fit().map(|(dist, _)| dist)
+    }
+
+    #[test]
+    fn fit_distributions() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let normal = rand_distr::Normal::new(50.0, 5.0).unwrap();
+        let values: Vec<f64> = (0..1000).map(|_| rng.sample(normal)).collect();
+        match fitted(values) {
+            Some(NumberDistribution::Normal(normal)) => {
+                assert!((normal.mean - 50.0).abs() < 1.0);
+                assert!((normal.sd - 5.0).abs() < 0.5);
+            }
+            other => panic!("expected a normal distribution, got {other:?}"),
+        }
+
+        let exponential = rand_distr::Exp::new(0.1).unwrap();
+        let values: Vec<f64> = (0..1000).map(|_| rng.sample(exponential)).collect();
+        assert!(matches!(
+            fitted(values),
+            Some(NumberDistribution::Exponential(_))
+        ));
+
+        let poisson = rand_distr::Poisson::new(3.0).unwrap();
+        let values: Vec<f64> = (0..1000).map(|_| rng.sample(poisson)).collect();
+        assert!(matches!(
+            fitted(values),
+            Some(NumberDistribution::Poisson(_))
+        ));
+
+        let values: Vec<f64> = (0..1000).map(|_| rng.gen_range(0.0..100.0)).collect();
+        assert!(fitted(values).is_none());
+
+        // Too few values to tell
+        assert!(fitted([1.0, 2.0, 2.0, 3.0, 10.0]).is_none());
+    }
+
+    #[test]
+    fn fit_ranges() {
+        let fit = InferenceOptions {
+            fit_distributions: true,
+            ..Default::default()
+        };
+        let mut statistics = NumberStatistics::default();
+        (0..20).for_each(|n| statistics.observe((n * 5 + 10) as f64));
+        let mut number: NumberContent =
+            number_content::I64::Range(RangeStep::new(10, 106, 1)).into();
+        statistics.apply(&mut number, fit);
+        match number {
+            NumberContent::I64(number_content::I64::Range(range)) => {
+                assert_eq!(range.step, Some(5))
+            }
+            other => panic!("expected a range, got {other:?}"),
+        }
+
+        let mut statistics = NumberStatistics::default();
+        let mut rng = StdRng::seed_from_u64(2);
+        let normal = rand_distr::Normal::new(0.0, 1.0).unwrap();
+        (0..500).for_each(|_| statistics.observe(rng.sample(normal)));
+        let mut number: NumberContent =
+            number_content::F64::Range(RangeStep::new(-4.0, 4.0, 1.0)).into();
+        statistics.apply(&mut number, fit);
+        match number {
+            NumberContent::F64(number_content::F64::Distribution(NumberDistribution::Normal(
+                normal,
+            ))) => {
+                assert_eq!(normal.low, Some(-4.0));
+                assert_eq!(normal.high, Some(4.0));
+            }
+            other => panic!("expected a normal distribution, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn fit_histograms() {
+        let both = InferenceOptions {
+            fit_distributions: true,
+            histograms: true,
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(3);
+        let low = rand_distr::Normal::new(10.0, 1.0).unwrap();
+        let high = rand_distr::Normal::new(30.0, 1.0).unwrap();
+        let mut statistics = NumberStatistics::default();
+        (0..1000).for_each(|n| {
+            let dist = if n % 4 == 0 { low } else { high };
+            statistics.observe(rng.sample::<f64, _>(dist).round())
+        });
+        let mut number: NumberContent = number_content::I64::Range(RangeStep::new(0, 40, 1)).into();
+        statistics.apply(&mut number, both);
+        let buckets = match number {
+            NumberContent::I64(number_content::I64::Distribution(
+                NumberDistribution::Histogram(histogram),
+            )) => histogram.buckets,
+            other => panic!("expected a histogram, got {other:?}"),
+        };
+        assert!(buckets.len() <= MAX_BUCKETS);
+        assert!(buckets
+            .iter()
+            .all(|bucket| bucket.low.fract() == 0.0 && bucket.high.fract() == 0.0));
+        let weight = |range: std::ops::Range<f64>| -> f64 {
+            buckets
+                .iter()
+                .filter(|bucket| range.contains(&bucket.low))
+                .map(|bucket| bucket.weight)
+                .sum()
+        };
+        assert_eq!(weight(0.0..20.0), 250.0);
+        assert_eq!(weight(20.0..40.0), 750.0);
+
+        // A good enough fit is kept over the histogram
+        let mut statistics = NumberStatistics::default();
+        let normal = rand_distr::Normal::new(0.0, 1.0).unwrap();
+        (0..500).for_each(|_| statistics.observe(rng.sample(normal)));
+        let mut number: NumberContent =
+            number_content::F64::Range(RangeStep::new(-4.0, 4.0, 1.0)).into();
+        statistics.apply(&mut number, both);
+        assert!(matches!(
+            number,
+            NumberContent::F64(number_content::F64::Distribution(
+                NumberDistribution::Normal(_)
+            ))
+        ));
+    }
+}
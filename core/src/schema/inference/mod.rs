@@ -10,10 +10,18 @@ use std::fmt::Display;
 pub mod value;
 pub use value::ValueMergeStrategy;
 
+mod correlation;
+mod fit;
+mod pattern;
+mod semantic;
+
+pub mod profile;
+pub use profile::{InferenceOptions, Profiler};
+
 use super::{
     number_content, ArrayContent, BoolContent, Categorical, CategoricalType, ChronoValueFormatter,
-    Content, DateTimeContent, Id, NumberContent, NumberKindExt, ObjectContent, OneOfContent,
-    RangeStep, StringContent, ValueKindExt,
+    Content, DateTimeContent, Id, LinkedBound, LinkedRange, NumberContent, NumberDistribution,
+    NumberKindExt, ObjectContent, OneOfContent, RangeStep, StringContent, ValueKindExt,
 };
 use crate::graph::prelude::content::number_content::{I16, I32, I64};
 use crate::schema::UniqueContent;
@@ -36,7 +44,7 @@ impl MergeStrategy<Content, Value> for OptionalMergeStrategy {
     fn try_merge(self, master: &mut Content, candidate: &Value) -> Result<()> {
         match (master, candidate) {
             // Logical nodes go first
-            (Content::SameAs(_), _) => {
+            (Content::SameAs(_), _) | (Content::Expression(_), _) | (Content::Switch(_), _) => {
                 // Nothing can happen here because this is not a visitor pattern
                 Ok(())
             }
@@ -51,7 +59,10 @@ impl MergeStrategy<Content, Value> for OptionalMergeStrategy {
                 Self.try_merge(master_obj, candidate_obj)
             }
             (Content::Array(ArrayContent { content, length }), Value::Array(values)) => {
-                Self.try_merge(length.as_mut(), &Value::from(values.len()))?;
+                // Arrays whose length is given by a cardinality have no `length`
+                if !length.is_null() {
+                    Self.try_merge(length.as_mut(), &Value::from(values.len()))?;
+                }
                 values
                     .iter()
                     .try_for_each(|value| Self.try_merge(content.as_mut(), value))
@@ -164,13 +175,27 @@ impl MergeStrategy<ObjectContent, Map<String, Value>> for OptionalMergeStrategy
             .collect();
 
         for key in master_keys.symmetric_difference(&candidate_keys) {
-            if let Some(field) = master.fields.remove(key) {
-                master.fields.insert(key.clone(), field.into_nullable());
-            } else {
-                // SAFETY: if `key` is not in master then it is in candidate
-                let candidate_field = candidate_obj.get(key).unwrap();
-                let field = Content::from(candidate_field).into_nullable();
-                master.fields.insert(key.clone(), field);
+            match master.fields.remove(key) {
+                // A null seen by a field which already is nullable counts towards its null variant
+                Some(mut field) if field.is_nullable() => {
+                    Self.try_merge(&mut field, &Value::Null)?;
+                    master.fields.insert(key.clone(), field);
+                }
+                // A field which is not nullable took a value in every object before this one
+                Some(field) if master_keys.contains(key) => {
+                    let seen = master.seen.0 as f64;
+                    master
+                        .fields
+                        .insert(key.clone(), field.into_nullable_seen(seen, 1.0));
+                }
+                // A field missing or null until now was so in every object before this one
+                _ => {
+                    // SAFETY: if `key` is not in master then it is in candidate
+                    let candidate_field = candidate_obj.get(key).unwrap();
+                    let seen = master.seen.0 as f64;
+                    let field = Content::from(candidate_field).into_nullable_seen(1.0, seen);
+                    master.fields.insert(key.clone(), field);
+                }
             }
         }
 
@@ -181,6 +206,7 @@ impl MergeStrategy<ObjectContent, Map<String, Value>> for OptionalMergeStrategy
             Self.try_merge(master_value, candidate_value)?;
         }
 
+        master.seen.0 += 1;
         Ok(())
     }
 }
@@ -189,20 +215,25 @@ impl MergeStrategy<DateTimeContent, String> for OptionalMergeStrategy {
     fn try_merge(self, master: &mut DateTimeContent, candidate: &String) -> Result<()> {
         let fmt = ChronoValueFormatter::new(&master.format);
         let candidate = fmt.parse(candidate.as_str())?;
-        if let Some(begin) = master.begin.as_mut() {
-            if *begin > candidate {
-                *begin = candidate.clone();
+        // Bounds referencing other fields are left as they are
+        match master.begin.as_mut() {
+            Some(LinkedBound::Value(begin)) => {
+                if *begin > candidate {
+                    *begin = candidate.clone();
+                }
             }
-        } else {
-            master.begin = Some(candidate.clone());
+            Some(LinkedBound::Field { .. }) => {}
+            None => master.begin = Some(LinkedBound::Value(candidate.clone())),
         }
 
-        if let Some(end) = master.end.as_mut() {
-            if *end < candidate {
-                *end = candidate;
+        match master.end.as_mut() {
+            Some(LinkedBound::Value(end)) => {
+                if *end < candidate {
+                    *end = candidate;
+                }
             }
-        } else {
-            master.end = Some(candidate);
+            Some(LinkedBound::Field { .. }) => {}
+            None => master.end = Some(LinkedBound::Value(candidate)),
         }
 
         Ok(())
@@ -222,6 +253,40 @@ where
     }
 }
 
+impl<N> MergeStrategy<NumberDistribution<N>, N> for OptionalMergeStrategy
+where
+    N: PartialOrd + Copy,
+{
+    /// Widens the clamping bounds of the distribution, if any, to include `value`.
+    fn try_merge(self, master: &mut NumberDistribution<N>, value: &N) -> Result<()> {
+        let (low, high) = master.bounds_mut();
+        if let Some(low) = low.as_mut().filter(|low| *value < **low) {
+            *low = *value;
+        }
+        if let Some(high) = high.as_mut().filter(|high| *value > **high) {
+            *high = *value;
+        }
+        Ok(())
+    }
+}
+
+impl<N> MergeStrategy<LinkedRange<N>, N> for OptionalMergeStrategy
+where
+    N: PartialOrd + Copy,
+{
+    /// Widens the fixed bounds of the range to include `value`. Bounds referencing other fields
+    /// are left as they are.
+    fn try_merge(self, master: &mut LinkedRange<N>, value: &N) -> Result<()> {
+        if let LinkedBound::Value(low) = master.low.get_or_insert(LinkedBound::Value(*value)) {
+            *low = if *value < *low { *value } else { *low };
+        }
+        if let LinkedBound::Value(high) = master.high.get_or_insert(LinkedBound::Value(*value)) {
+            *high = if *value > *high { *value } else { *high };
+        }
+        Ok(())
+    }
+}
+
 impl<N> MergeStrategy<Categorical<N>, N> for OptionalMergeStrategy
 where
     N: Copy + CategoricalType,
@@ -268,6 +333,8 @@ impl MergeStrategy<number_content::U64, u64> for OptionalMergeStrategy {
             number_content::U64::Range(range) => self.try_merge(range, candidate),
             number_content::U64::Categorical(cat) => self.try_merge(cat, candidate),
             number_content::U64::Constant(cst) => self.try_merge(cst, candidate),
+            number_content::U64::Distribution(dist) => self.try_merge(dist, candidate),
+            number_content::U64::Linked(range) => self.try_merge(range, candidate),
             number_content::U64::Id(id) => self.try_merge(id, candidate),
         }
     }
@@ -279,6 +346,8 @@ impl MergeStrategy<number_content::I64, i64> for OptionalMergeStrategy {
             number_content::I64::Range(range) => self.try_merge(range, candidate),
             number_content::I64::Categorical(cat) => self.try_merge(cat, candidate),
             number_content::I64::Constant(cst) => self.try_merge(cst, candidate),
+            number_content::I64::Distribution(dist) => self.try_merge(dist, candidate),
+            number_content::I64::Linked(range) => self.try_merge(range, candidate),
             I64::Id(id) => self.try_merge(id, candidate),
         }
     }
@@ -289,6 +358,8 @@ impl MergeStrategy<number_content::F64, f64> for OptionalMergeStrategy {
         match master {
             number_content::F64::Range(range) => self.try_merge(range, candidate),
             number_content::F64::Constant(cst) => self.try_merge(cst, candidate),
+            number_content::F64::Distribution(dist) => self.try_merge(dist, candidate),
+            number_content::F64::Linked(range) => self.try_merge(range, candidate),
         }
     }
 }
@@ -299,6 +370,8 @@ impl MergeStrategy<number_content::U32, u32> for OptionalMergeStrategy {
             number_content::U32::Range(range) => self.try_merge(range, candidate),
             number_content::U32::Categorical(cat) => self.try_merge(cat, candidate),
             number_content::U32::Constant(cst) => self.try_merge(cst, candidate),
+            number_content::U32::Distribution(dist) => self.try_merge(dist, candidate),
+            number_content::U32::Linked(range) => self.try_merge(range, candidate),
             number_content::U32::Id(id) => self.try_merge(id, candidate),
         }
     }
@@ -310,6 +383,8 @@ impl MergeStrategy<number_content::I32, i32> for OptionalMergeStrategy {
             number_content::I32::Range(range) => self.try_merge(range, candidate),
             number_content::I32::Categorical(cat) => self.try_merge(cat, candidate),
             number_content::I32::Constant(cst) => self.try_merge(cst, candidate),
+            number_content::I32::Distribution(dist) => self.try_merge(dist, candidate),
+            number_content::I32::Linked(range) => self.try_merge(range, candidate),
             I32::Id(id) => self.try_merge(id, candidate),
         }
     }
@@ -320,6 +395,8 @@ impl MergeStrategy<number_content::F32, f32> for OptionalMergeStrategy {
         match master {
             number_content::F32::Range(range) => self.try_merge(range, candidate),
             number_content::F32::Constant(cst) => self.try_merge(cst, candidate),
+            number_content::F32::Distribution(dist) => self.try_merge(dist, candidate),
+            number_content::F32::Linked(range) => self.try_merge(range, candidate),
         }
     }
 }
@@ -330,6 +407,8 @@ impl MergeStrategy<number_content::I16, i16> for OptionalMergeStrategy {
             number_content::I16::Range(range) => self.try_merge(range, candidate),
             number_content::I16::Categorical(cat) => self.try_merge(cat, candidate),
             number_content::I16::Constant(cst) => self.try_merge(cst, candidate),
+            number_content::I16::Distribution(dist) => self.try_merge(dist, candidate),
+            number_content::I16::Linked(range) => self.try_merge(range, candidate),
             I16::Id(id) => self.try_merge(id, candidate),
         }
     }
@@ -543,6 +622,47 @@ pub mod tests {
             .is_err());
     }
 
+    #[test]
+    fn merge_null_frequency() {
+        fn weights(content: &Content) -> Vec<Value> {
+            serde_json::to_value(content).unwrap()["variants"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|variant| variant["weight"].clone())
+                .collect()
+        }
+
+        let rows = json!([
+            { "a": 1, "b": null },
+            { "a": 2, "b": null },
+            { "a": null, "b": null },
+            { "a": 3, "b": "x" },
+            { "a": 4 }
+        ]);
+        let mut master = Content::from(&json!({ "a": 0, "b": null }));
+        for row in rows.as_array().unwrap() {
+            OptionalMergeStrategy.try_merge(&mut master, row).unwrap();
+        }
+        let fields = match &master {
+            Content::Object(object) => &object.fields,
+            _ => unreachable!(),
+        };
+        // `a` took 5 values and 1 null, counting the 3 values before its first null
+        assert_eq!(weights(&fields["a"]), vec![json!(5.0), json!(1.0)]);
+        // `b` was null or missing 5 times, 4 of them before its only value
+        assert_eq!(weights(&fields["b"]), vec![json!(1.0), json!(5.0)]);
+
+        // A nullable column counts its first value and every value merged after it
+        let mut column = Content::from(&json!(0)).into_nullable_seen(1.0, 0.0);
+        for value in [json!(1), json!(2), json!(null), json!(3)] {
+            OptionalMergeStrategy
+                .try_merge(&mut column, &value)
+                .unwrap();
+        }
+        assert_eq!(weights(&column), vec![json!(4.0), json!(1.0)]);
+    }
+
     #[test]
     fn merge_numbers() {
         let mut master: NumberContent = serde_json::from_value(json!({
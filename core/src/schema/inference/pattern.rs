@@ -0,0 +1,286 @@
+//! Learns a regex generalising the strings seen, like `INV-[0-9]{4}-[0-9]{6}` from invoice numbers,
+//! so that imported identifiers are generated with the same structure.
+use std::collections::BTreeMap;
+
+use crate::schema::{Categorical, RegexContent, StringContent};
+
+/// The most values of a string which are generated as categories rather than as a pattern.
+const MAX_CATEGORIES: usize = 20;
+
+/// The most runs of characters in a pattern, beyond which strings are more likely free text than
+/// identifiers.
+const MAX_RUNS: usize = 16;
+
+const DIGIT: u8 = 1;
+const UPPER: u8 = 2;
+const LOWER: u8 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    /// Any of the ASCII digits, upper and lower case letters in the mask
+    Word(u8),
+    /// Any other character, like separators, which is kept as it is
+    Literal(char),
+}
+
+impl Class {
+    fn of(c: char) -> Self {
+        if c.is_ascii_digit() {
+            Self::Word(DIGIT)
+        } else if c.is_ascii_uppercase() {
+            Self::Word(UPPER)
+        } else if c.is_ascii_lowercase() {
+            Self::Word(LOWER)
+        } else {
+            Self::Literal(c)
+        }
+    }
+
+    fn to_regex(self) -> String {
+        match self {
+            Self::Word(mask) => {
+                let mut class = "[".to_string();
+                if mask & UPPER != 0 {
+                    class.push_str("A-Z");
+                }
+                if mask & LOWER != 0 {
+                    class.push_str("a-z");
+                }
+                if mask & DIGIT != 0 {
+                    class.push_str("0-9");
+                }
+                class.push(']');
+                class
+            }
+            Self::Literal(c) => regex::escape(&c.to_string()),
+        }
+    }
+}
+
+/// Consecutive characters of the same class.
+#[derive(Debug, Clone)]
+struct Run {
+    class: Class,
+    min: usize,
+    max: usize,
+    /// The character at each position of the run where all the values had the same one, while
+    /// the run always had the same length
+    chars: Vec<Option<char>>,
+}
+
+impl Run {
+    fn to_regex(&self) -> String {
+        let class = self.class.to_regex();
+        if let Class::Literal(_) = self.class {
+            return class;
+        }
+        if self.min != self.max {
+            return format!("{}{{{},{}}}", class, self.min, self.max);
+        }
+
+        let mut regex = String::new();
+        let mut varying = 0;
+        let flush = |regex: &mut String, varying: &mut usize| {
+            match *varying {
+                0 => {}
+                1 => regex.push_str(&class),
+                n => regex.push_str(&format!("{class}{{{n}}}")),
+            }
+            *varying = 0;
+        };
+        for c in &self.chars {
+            match c {
+                Some(c) => {
+                    flush(&mut regex, &mut varying);
+                    regex.push_str(&regex::escape(&c.to_string()));
+                }
+                None => varying += 1,
+            }
+        }
+        flush(&mut regex, &mut varying);
+        regex
+    }
+}
+
+/// Splits `value` into runs of the same class. Coarse runs put all ASCII letters and digits
+/// together, so that words of varying case still line up.
+fn runs(value: &str, coarse: bool) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+    for c in value.chars() {
+        let class = Class::of(c);
+        if let (Some(last), Class::Word(mask)) = (runs.last_mut(), class) {
+            match last.class {
+                Class::Word(last_mask) if coarse || last_mask == mask => {
+                    last.class = Class::Word(last_mask | mask);
+                    last.min += 1;
+                    last.max += 1;
+                    last.chars.push(Some(c));
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        runs.push(Run {
+            class,
+            min: 1,
+            max: 1,
+            chars: vec![Some(c)],
+        });
+    }
+    runs
+}
+
+/// Widens `template` to the runs of another value, or fails when they do not line up.
+fn widen(template: &mut [Run], runs: Vec<Run>, coarse: bool) -> bool {
+    let lines_up = template.len() == runs.len()
+        && template
+            .iter()
+            .zip(&runs)
+            .all(|(left, right)| match (left.class, right.class) {
+                (Class::Word(left), Class::Word(right)) => coarse || left == right,
+                (left, right) => left == right,
+            });
+    if !lines_up {
+        return false;
+    }
+    for (run, other) in template.iter_mut().zip(runs) {
+        if let (Class::Word(mask), Class::Word(other_mask)) = (run.class, other.class) {
+            run.class = Class::Word(mask | other_mask);
+        }
+        if run.min == run.max && other.min == run.min {
+            run.chars
+                .iter_mut()
+                .zip(other.chars)
+                .for_each(|(c, other)| {
+                    if *c != other {
+                        *c = None
+                    }
+                });
+        } else {
+            run.chars.clear();
+        }
+        run.min = run.min.min(other.min);
+        run.max = run.max.max(other.max);
+    }
+    true
+}
+
+/// Follows the strings of a field to learn how they are generated best.
+#[derive(Debug, Default)]
+pub(crate) struct PatternLearner {
+    count: u64,
+    /// How often each value was seen, until there are too many for a categorical
+    values: BTreeMap<String, u64>,
+    too_many_values: bool,
+    /// The runs of all the values so far, or `None` once they do not line up
+    fine: Option<Vec<Run>>,
+    coarse: Option<Vec<Run>>,
+}
+
+impl PatternLearner {
+    pub(crate) fn observe(&mut self, value: &str) {
+        if !self.too_many_values {
+            *self.values.entry(value.to_string()).or_default() += 1;
+            if self.values.len() > MAX_CATEGORIES {
+                self.too_many_values = true;
+                self.values.clear();
+            }
+        }
+
+        if self.count == 0 {
+            self.fine = Some(runs(value, false));
+            self.coarse = Some(runs(value, true));
+        } else {
+            if let Some(fine) = self.fine.as_mut() {
+                if !widen(fine, runs(value, false), false) {
+                    self.fine = None;
+                }
+            }
+            if let Some(coarse) = self.coarse.as_mut() {
+                if !widen(coarse, runs(value, true), true) {
+                    self.coarse = None;
+                }
+            }
+        }
+        self.count += 1;
+    }
+
+    /// A categorical of the values when they are few and repeated, or else the pattern they all
+    /// follow, if any.
+    pub(crate) fn to_content(&self) -> Option<StringContent> {
+        let distinct = self.values.len() as u64;
+        if !self.too_many_values && distinct > 0 && distinct * 2 <= self.count {
+            return Some(StringContent::Categorical(Categorical {
+                seen: self.values.clone(),
+                total: self.count,
+            }));
+        }
+        // A single value would be learnt as a constant
+        if !self.too_many_values && distinct < 2 {
+            return None;
+        }
+        let runs = self
+            .fine
+            .as_ref()
+            .or(self.coarse.as_ref())
+            .filter(|runs| !runs.is_empty() && runs.len() <= MAX_RUNS)?;
+        let pattern = runs.iter().map(Run::to_regex).collect();
+        RegexContent::pattern(pattern)
+            .ok()
+            .map(StringContent::Pattern)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn learn(values: &[&str]) -> Option<StringContent> {
+        let mut learner = PatternLearner::default();
+        values.iter().for_each(|value| learner.observe(value));
+        learner.to_content()
+    }
+
+    fn pattern(values: &[&str]) -> String {
+        match learn(values) {
+            Some(StringContent::Pattern(pattern)) => pattern.to_string(),
+            other => panic!("expected a pattern, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn learn_patterns() {
+        assert_eq!(
+            pattern(&["INV-2023-000123", "INV-2023-004567", "INV-2022-000890"]),
+            "INV\\-202[0-9]\\-00[0-9]{4}"
+        );
+        assert_eq!(
+            pattern(&["AB12 CDE", "XY9 ZZA", "QR34 ABC"]),
+            "[A-Z]{2}[0-9]{1,2} [A-Z]{3}"
+        );
+        assert_eq!(
+            pattern(&["Jane Doe", "McDonald Smith"]),
+            "[A-Za-z]{4,8} [A-Za-z]{3,5}"
+        );
+        assert!(learn(&["free text", "with a varying number of words"]).is_none());
+        assert!(learn(&["only"]).is_none());
+    }
+
+    #[test]
+    fn learn_categories() {
+        match learn(&["active", "inactive", "active", "active"]) {
+            Some(StringContent::Categorical(categorical)) => {
+                assert_eq!(categorical.seen.get("active"), Some(&3));
+                assert_eq!(categorical.seen.get("inactive"), Some(&1));
+            }
+            other => panic!("expected a categorical, got {other:?}"),
+        }
+        let distinct: Vec<_> = (0..30).map(|n| format!("code-{n}")).collect();
+        let distinct: Vec<_> = distinct
+            .iter()
+            .chain(&distinct)
+            .map(String::as_str)
+            .collect();
+        assert_eq!(pattern(&distinct), "code\\-[0-9]{1,2}");
+    }
+}
@@ -0,0 +1,334 @@
+//! Refines the content inferred from values with what only shows across many of them, like the
+//! shapes and patterns of strings, the distributions of numbers or the fields depending on others.
+use serde_json::Value;
+
+use chrono::{Duration, Timelike};
+
+use std::collections::BTreeMap;
+
+use super::correlation::{Categories, Correlations};
+use super::fit::NumberStatistics;
+use super::pattern::PatternLearner;
+use super::semantic::Shape;
+use crate::schema::{
+    ChronoValue, ChronoValueFormatter, ChronoValueType, Content, DateTimeBucket, FieldRef,
+    StringContent,
+};
+
+/// What is inferred from values beyond the kinds and ranges of the content merged from them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InferenceOptions {
+    /// Fit a distribution to the values of numbers, instead of generating them uniformly over
+    /// their range
+    pub fit_distributions: bool,
+    /// Generate numbers and date times from a histogram of their values, when no distribution
+    /// fits them
+    pub histograms: bool,
+    /// Generate the fields of objects with few values which depend on another such field from
+    /// the value of that field
+    pub correlations: bool,
+}
+
+/// Follows the values of a content field by field, keeping what they all have in common so far.
+#[derive(Debug, Default)]
+pub struct Profiler {
+    options: InferenceOptions,
+    /// `None` until a string is seen
+    shapes: Option<Vec<Shape>>,
+    learner: PatternLearner,
+    numbers: NumberStatistics,
+    /// The seconds since the epoch of the strings which are date times
+    date_times: NumberStatistics,
+    strings: u64,
+    categories: Categories,
+    correlations: Correlations,
+    fields: BTreeMap<String, Profiler>,
+    elements: Option<Box<Profiler>>,
+}
+
+impl Profiler {
+    pub fn new(options: InferenceOptions) -> Self {
+        Self {
+            options,
+            ..Default::default()
+        }
+    }
+
+    pub fn observe(&mut self, value: &Value) {
+        if self.options.correlations {
+            self.categories.observe(value)
+        }
+        match value {
+            Value::String(string) => {
+                match self.shapes.as_mut() {
+                    Some(shapes) => shapes.retain_mut(|shape| shape.accepts(string)),
+                    None => self.shapes = Some(Shape::all_of(string)),
+                }
+                self.learner.observe(string);
+                self.strings += 1;
+                if self.options.histograms {
+                    if let Some(seconds) = self.date_time_seconds(string) {
+                        self.date_times.observe(seconds)
+                    }
+                }
+            }
+            Value::Number(number) if self.options.fit_distributions || self.options.histograms => {
+                if let Some(number) = number.as_f64() {
+                    self.numbers.observe(number)
+                }
+            }
+            Value::Object(object) => {
+                object.iter().for_each(|(key, value)| {
+                    let options = self.options;
+                    self.fields
+                        .entry(key.clone())
+                        .or_insert_with(|| Self::new(options))
+                        .observe(value)
+                });
+                if self.options.correlations {
+                    self.correlations
+                        .observe(object, |key| self.fields[key].categories.is_categorical())
+                }
+            }
+            Value::Array(values) => {
+                let options = self.options;
+                let elements = self
+                    .elements
+                    .get_or_insert_with(|| Box::new(Self::new(options)));
+                values.iter().for_each(|value| elements.observe(value))
+            }
+            _ => {}
+        }
+    }
+
+    /// The seconds since the epoch of `string`, parsed as the first date time shape all the
+    /// strings so far have.
+    fn date_time_seconds(&self, string: &str) -> Option<f64> {
+        let format = self.shapes.iter().flatten().find_map(|shape| match shape {
+            Shape::DateTime(date_time) => Some(date_time.format.as_str()),
+            _ => None,
+        })?;
+        let value = ChronoValueFormatter::new(format).parse(string).ok()?;
+        Some(to_seconds(&value))
+    }
+
+    /// Replaces the string patterns in `content` whose values all had a shape by the content
+    /// generating that shape, and the others by the pattern learnt from their values. Other kinds
+    /// of strings, like categoricals, are left as they are. Ranges of numbers get the
+    /// distribution fitting their values, and date times their histogram, when asked to.
+    pub fn apply(&self, content: &mut Content) {
+        self.refine(content, None)
+    }
+
+    /// Like [`apply`](Self::apply), for the `content` at the path `at`, also making the fields
+    /// which depend on another a switch on it, when asked to.
+    pub fn apply_at(&self, content: &mut Content, at: &FieldRef) {
+        self.refine(content, Some(at))
+    }
+
+    /// Like [`apply_at`](Self::apply_at), for the `content` of the collection named `collection`.
+    pub fn apply_to_collection(&self, collection: &str, content: &mut Content) {
+        let at = FieldRef::from_collection_name(collection.to_string()).ok();
+        self.refine(content, at.as_ref())
+    }
+
+    fn refine(&self, content: &mut Content, at: Option<&FieldRef>) {
+        match content {
+            Content::Object(object) => {
+                self.fields.iter().for_each(|(key, profiler)| {
+                    if let Some(field) = object.fields.get_mut(key) {
+                        profiler.refine(field, at.map(|at| at.child(key.as_str())).as_ref())
+                    }
+                });
+                if let Some(at) = at.filter(|_| self.options.correlations) {
+                    let categories = self
+                        .fields
+                        .iter()
+                        .map(|(key, profiler)| (key.as_str(), &profiler.categories))
+                        .collect();
+                    self.correlations.apply(object, at, &categories)
+                }
+            }
+            Content::Array(array) => {
+                if let Some(elements) = self.elements.as_ref() {
+                    elements.refine(
+                        &mut array.content,
+                        at.map(|at| at.child("content")).as_ref(),
+                    )
+                }
+            }
+            // Fields in variants or unique content can't be referred to by their path alone
+            Content::OneOf(one_of) => one_of
+                .iter_mut()
+                .for_each(|variant| self.refine(variant, None)),
+            Content::Unique(unique) => self.refine(&mut unique.content, None),
+            Content::String(StringContent::Pattern(_)) => {
+                if let Some(shape) = self.shapes.as_ref().and_then(|shapes| shapes.first()) {
+                    *content = shape.clone().into_content();
+                    if let Content::DateTime(_) = content {
+                        self.refine(content, at)
+                    }
+                } else if let Some(string) = self.learner.to_content() {
+                    *content = Content::String(string)
+                }
+            }
+            Content::Number(number)
+                if self.options.fit_distributions || self.options.histograms =>
+            {
+                self.numbers.apply(number, self.options)
+            }
+            // Date times not all parsed as a shape, e.g. with a format of a database, are left
+            Content::DateTime(date_time)
+                if self.options.histograms && self.date_times.count() == self.strings =>
+            {
+                if let Some(buckets) = self.date_times.histogram(false) {
+                    let type_ = date_time.type_;
+                    let buckets = buckets
+                        .into_iter()
+                        .map(|bucket| DateTimeBucket {
+                            begin: from_seconds(bucket.low, type_),
+                            end: from_seconds(bucket.high, type_),
+                            weight: bucket.weight,
+                        })
+                        .collect();
+                    date_time.histogram = Some(buckets);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn to_seconds(value: &ChronoValue) -> f64 {
+    let (seconds, nanos) = match value {
+        ChronoValue::NaiveDate(date) => (date.and_hms_opt(0, 0, 0).unwrap().timestamp(), 0),
+        ChronoValue::NaiveTime(time) => {
+            (time.num_seconds_from_midnight() as i64, time.nanosecond())
+        }
+        ChronoValue::NaiveDateTime(date_time) => {
+            (date_time.timestamp(), date_time.timestamp_subsec_nanos())
+        }
+        ChronoValue::DateTime(date_time) => {
+            (date_time.timestamp(), date_time.timestamp_subsec_nanos())
+        }
+    };
+    seconds as f64 + nanos as f64 / 1e9
+}
+
+/// The date time of `type_` some `seconds` after the epoch, in UTC.
+fn from_seconds(seconds: f64, type_: ChronoValueType) -> ChronoValue {
+    let millis = Duration::milliseconds((seconds * 1000.0).round() as i64);
+    ChronoValue::default_of(ChronoValue::origin() + millis, type_)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::schema::{MergeStrategy, OptionalMergeStrategy};
+
+    #[test]
+    fn date_time_histograms() {
+        let values = Value::Array(
+            (1..=28)
+                .map(|day| format!("2021-02-{day:02}"))
+                .chain((1..=10).map(|day| format!("2021-03-{day:02}")))
+                .chain(std::iter::repeat("2021-03-01".to_string()).take(100))
+                .map(Value::String)
+                .collect(),
+        );
+        let mut content = Content::from_value_wrapped_in_array(&values[0]);
+        OptionalMergeStrategy
+            .try_merge(&mut content, &values)
+            .unwrap();
+        let mut profiler = Profiler::new(InferenceOptions {
+            histograms: true,
+            ..Default::default()
+        });
+        profiler.observe(&values);
+        profiler.apply(&mut content);
+
+        let date_time = match content {
+            Content::Array(array) => match *array.content {
+                Content::DateTime(date_time) => date_time,
+                other => panic!("expected a date time, got {other:?}"),
+            },
+            _ => unreachable!(),
+        };
+        let buckets = date_time.histogram.unwrap();
+        let date =
+            |day| ChronoValue::NaiveDate(chrono::NaiveDate::from_ymd_opt(2021, 2, day).unwrap());
+        assert_eq!(buckets.first().unwrap().begin, date(1));
+        assert_eq!(
+            buckets.last().unwrap().end,
+            ChronoValue::NaiveDate(chrono::NaiveDate::from_ymd_opt(2021, 3, 10).unwrap())
+        );
+        let heaviest = buckets
+            .iter()
+            .max_by(|left, right| left.weight.total_cmp(&right.weight))
+            .unwrap();
+        assert!(heaviest.begin <= date(28) && heaviest.end >= date(28));
+        assert_eq!(
+            buckets.iter().map(|bucket| bucket.weight).sum::<f64>(),
+            138.0
+        );
+    }
+
+    #[test]
+    fn correlations() {
+        let cities = [
+            ("Paris", "FR"),
+            ("Lyon", "FR"),
+            ("Berlin", "DE"),
+            ("Bonn", "DE"),
+        ];
+        let values = Value::Array(
+            (0..100)
+                .map(|index| {
+                    let (city, country) = cities[index % cities.len()];
+                    let age = if index % 3 == 0 {
+                        Value::Null
+                    } else {
+                        (index % 5).into()
+                    };
+                    serde_json::json!({"id": index, "city": city, "country": country, "age": age})
+                })
+                .collect(),
+        );
+        let mut content = Content::from_value_wrapped_in_array(&values[0]);
+        OptionalMergeStrategy
+            .try_merge(&mut content, &values)
+            .unwrap();
+        let mut profiler = Profiler::new(InferenceOptions {
+            correlations: true,
+            ..Default::default()
+        });
+        profiler.observe(&values);
+        profiler.apply_to_collection("users", &mut content);
+
+        let fields = match content {
+            Content::Array(array) => match *array.content {
+                Content::Object(object) => object.fields,
+                other => panic!("expected an object, got {other:?}"),
+            },
+            _ => unreachable!(),
+        };
+        assert!(matches!(fields["id"], Content::Number(_)));
+        // Unrelated to the others
+        assert!(matches!(fields["age"], Content::OneOf(_)));
+        assert_eq!(
+            serde_json::to_value(&fields["country"]).unwrap(),
+            serde_json::json!({"type": "string", "categorical": {"DE": 50, "FR": 50}})
+        );
+        assert_eq!(
+            serde_json::to_value(&fields["city"]).unwrap(),
+            serde_json::json!({
+                "type": "switch",
+                "on": {"type": "same_as", "ref": "users.content.country"},
+                "cases": {
+                    "DE": {"type": "string", "categorical": {"Berlin": 25, "Bonn": 25}},
+                    "FR": {"type": "string", "categorical": {"Lyon": 25, "Paris": 25}}
+                }
+            })
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! Recognises strings of a well known shape, like emails, UUIDs or timestamps, in the values a
+//! content is inferred from, so that they are generated as such instead of as a pattern.
+use regex::Regex;
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+use super::{MergeStrategy, OptionalMergeStrategy};
+use crate::graph::string::FakerArgs;
+use crate::schema::{
+    ChronoValueFormatter, ChronoValueType, Content, DateTimeContent, FakerContent, RegexContent,
+    StringContent, Uuid,
+};
+
+lazy_static! {
+    static ref EMAIL: Regex =
+        Regex::new(r"^[A-Za-z0-9._%+\-]+@[A-Za-z0-9\-]+(\.[A-Za-z0-9\-]+)*\.[A-Za-z]{2,}$")
+            .unwrap();
+    static ref URL: Regex = Regex::new(r"^https?://[A-Za-z0-9\-.]+(:[0-9]+)?(/\S*)?$").unwrap();
+    static ref PHONE_NUMBER: Regex = Regex::new(r"^\+?[0-9 ().\-]+$").unwrap();
+}
+
+/// The formats of date times that are recognised, in the order of preference. Those without
+/// fractions of a second go first so whole seconds are not generated with fractions.
+const DATE_TIME_FORMATS: &[(&str, ChronoValueType)] = &[
+    ("%Y-%m-%dT%H:%M:%S%:z", ChronoValueType::DateTime),
+    ("%Y-%m-%dT%H:%M:%S%.f%:z", ChronoValueType::DateTime),
+    ("%Y-%m-%dT%H:%M:%SZ", ChronoValueType::NaiveDateTime),
+    ("%Y-%m-%dT%H:%M:%S%.fZ", ChronoValueType::NaiveDateTime),
+    ("%Y-%m-%dT%H:%M:%S", ChronoValueType::NaiveDateTime),
+    ("%Y-%m-%dT%H:%M:%S%.f", ChronoValueType::NaiveDateTime),
+    ("%Y-%m-%d %H:%M:%S", ChronoValueType::NaiveDateTime),
+    ("%Y-%m-%d %H:%M:%S%.f", ChronoValueType::NaiveDateTime),
+    ("%Y-%m-%d", ChronoValueType::NaiveDate),
+    ("%H:%M:%S", ChronoValueType::NaiveTime),
+];
+
+/// The generator for URLs, as there is no faker for them.
+const URL_PATTERN: &str = "https://(www\\.)?[a-z]{3,12}\\.(com|org|net|io)(/[a-z0-9]{1,10}){0,3}";
+
+/// A shape of string, in the order of preference when values have several.
+#[derive(Clone, Debug)]
+pub(crate) enum Shape {
+    DateTime(DateTimeContent),
+    Uuid,
+    Email,
+    Url,
+    Ipv4,
+    Ipv6,
+    PhoneNumber,
+}
+
+impl Shape {
+    /// All the shapes of `value`.
+    pub(crate) fn all_of(value: &str) -> Vec<Self> {
+        let mut shapes: Vec<_> = DATE_TIME_FORMATS
+            .iter()
+            .map(|(format, type_)| {
+                Self::DateTime(DateTimeContent {
+                    format: format.to_string(),
+                    type_: *type_,
+                    begin: None,
+                    end: None,
+                    histogram: None,
+                })
+            })
+            .collect();
+        shapes.extend([
+            Self::Uuid,
+            Self::Email,
+            Self::Url,
+            Self::Ipv4,
+            Self::Ipv6,
+            Self::PhoneNumber,
+        ]);
+        shapes.retain_mut(|shape| shape.accepts(value));
+        shapes
+    }
+
+    /// Whether `value` has this shape, which widens the range of a date time to it.
+    pub(crate) fn accepts(&mut self, value: &str) -> bool {
+        match self {
+            Self::DateTime(date_time) => {
+                let type_ = date_time.type_;
+                let format = date_time.format.clone();
+                let parsed = ChronoValueFormatter::new(&format).parse(value);
+                matches!(parsed, Ok(parsed) if parsed.type_() == type_)
+                    && OptionalMergeStrategy
+                        .try_merge(date_time, &value.to_string())
+                        .is_ok()
+            }
+            Self::Uuid => uuid::Uuid::parse_str(value).is_ok(),
+            Self::Email => EMAIL.is_match(value),
+            Self::Url => URL.is_match(value),
+            Self::Ipv4 => Ipv4Addr::from_str(value).is_ok(),
+            Self::Ipv6 => Ipv6Addr::from_str(value).is_ok(),
+            // Some separator tells phone numbers from other numbers kept as strings
+            Self::PhoneNumber => {
+                let digits = value.chars().filter(char::is_ascii_digit).count();
+                PHONE_NUMBER.is_match(value) && (7..=15).contains(&digits) && digits < value.len()
+            }
+        }
+    }
+
+    pub(crate) fn into_content(self) -> Content {
+        let faker = |generator: &str| {
+            Content::String(StringContent::Faker(FakerContent {
+                generator: generator.to_string(),
+                locales: Vec::new(),
+                args: FakerArgs::default(),
+            }))
+        };
+        match self {
+            Self::DateTime(date_time) => Content::DateTime(date_time),
+            Self::Uuid => Content::String(StringContent::Uuid(Uuid)),
+            Self::Email => faker("safe_email"),
+            // SAFETY: the pattern is a valid regex
+            Self::Url => Content::String(StringContent::Pattern(
+                RegexContent::pattern(URL_PATTERN.to_string()).unwrap(),
+            )),
+            Self::Ipv4 => faker("ipv4"),
+            Self::Ipv6 => faker("ipv6"),
+            Self::PhoneNumber => faker("phone_number"),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::schema::inference::Profiler;
+    use serde_json::Value;
+
+    fn infer(values: Value) -> Content {
+        let mut content = Content::from_value_wrapped_in_array(&values[0]);
+        OptionalMergeStrategy
+            .try_merge(&mut content, &values)
+            .unwrap();
+        let mut profiler = Profiler::default();
+        profiler.observe(&values);
+        profiler.apply(&mut content);
+        match content {
+            Content::Array(array) => *array.content,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn detect_shapes() {
+        let content = infer(json!([
+            {
+                "email": "jane@example.com",
+                "id": "a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8",
+                "site": "https://example.com/about",
+                "ip": "10.0.0.1",
+                "ip6": "::1",
+                "phone": "+1 (555) 010-9999",
+                "at": "2021-03-04T05:06:07+01:00",
+                "on": "2021-03-04",
+                "zulu": "2021-03-04T05:06:07Z",
+                "name": "Jane",
+                "mixed": "jane@example.com"
+            },
+            {
+                "email": "j.doe+spam@mail.example.org",
+                "id": "A1A2A3A4-B1B2-C1C2-D1D2-D3D4D5D6D7D8",
+                "site": "http://example.org",
+                "ip": "192.168.100.200",
+                "ip6": "fe80::1ff:fe23:4567:890a",
+                "phone": "555-010-1234",
+                "at": "2021-01-02T03:04:05.678+00:00",
+                "on": "2020-12-31",
+                "zulu": "2020-12-31T23:59:59Z",
+                "name": "John",
+                "mixed": "john"
+            }
+        ]));
+        let generated = |field: &str| -> String {
+            match &content {
+                Content::Object(object) => match &object.fields[field] {
+                    Content::String(StringContent::Faker(faker)) => faker.generator.clone(),
+                    Content::String(string) => string.kind(),
+                    other => other.kind(),
+                },
+                _ => unreachable!(),
+            }
+        };
+        assert_eq!(generated("email"), "safe_email");
+        assert_eq!(generated("id"), "uuid");
+        assert_eq!(generated("site"), "pattern");
+        assert_eq!(generated("ip"), "ipv4");
+        assert_eq!(generated("ip6"), "ipv6");
+        assert_eq!(generated("phone"), "phone_number");
+        assert_eq!(generated("name"), "pattern");
+        assert_eq!(generated("mixed"), "pattern");
+
+        let date_time = |field: &str| match &content {
+            Content::Object(object) => match &object.fields[field] {
+                Content::DateTime(date_time) => date_time.clone(),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        let at = date_time("at");
+        assert_eq!(at.format, "%Y-%m-%dT%H:%M:%S%.f%:z");
+        assert_eq!(at.type_, ChronoValueType::DateTime);
+        assert_eq!(date_time("zulu").format, "%Y-%m-%dT%H:%M:%SZ");
+        let on = date_time("on");
+        assert_eq!(on.format, "%Y-%m-%d");
+        assert_eq!(
+            serde_json::to_value(Content::DateTime(on)).unwrap(),
+            json!({
+                "type": "date_time",
+                "format": "%Y-%m-%d",
+                "subtype": "naive_date",
+                "begin": "2020-12-31",
+                "end": "2021-03-04"
+            })
+        );
+    }
+
+    #[test]
+    fn detect_through_nullable_and_arrays() {
+        let content = infer(json!([
+            { "emails": ["a@example.com", "b@example.com"], "uuid": null },
+            { "emails": [], "uuid": "a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8" }
+        ]));
+        let object = match content {
+            Content::Object(object) => object,
+            _ => unreachable!(),
+        };
+        match &object.fields["emails"] {
+            Content::Array(array) => match array.content.as_ref() {
+                Content::OneOf(one_of) => assert!(one_of.iter().all(|variant| matches!(
+                    variant,
+                    Content::String(StringContent::Faker(faker)) if faker.generator == "safe_email"
+                ))),
+                other => panic!("unexpected {other:?}"),
+            },
+            other => panic!("unexpected {other:?}"),
+        }
+        let uuid = object.fields["uuid"].as_nullable().unwrap();
+        assert!(matches!(uuid, Content::String(StringContent::Uuid(_))));
+    }
+}
@@ -12,10 +12,15 @@ use serde::{
 use serde_json::Value as JsonValue;
 
 pub mod inference;
-pub use inference::{MergeStrategy, OptionalMergeStrategy, ValueMergeStrategy};
+pub use inference::{
+    InferenceOptions, MergeStrategy, OptionalMergeStrategy, Profiler, ValueMergeStrategy,
+};
 
 pub mod optionalise;
 
+pub mod validate;
+pub use validate::Violation;
+
 pub mod namespace;
 pub use namespace::Namespace;
 
@@ -142,6 +147,12 @@ impl FieldRef {
         std::iter::once(self.collection.as_ref()).chain(self.iter_fields())
     }
 
+    pub(crate) fn child<R: Into<String>>(&self, field: R) -> FieldRef {
+        let mut child = self.clone();
+        child.fields.push(field.into());
+        child
+    }
+
     pub(crate) fn parent(&self) -> Option<FieldRef> {
         if self.is_top_level() {
             return None;
@@ -0,0 +1,503 @@
+//! Checks existing data against the content that is meant to generate it. Unlike
+//! [`Content::accepts`], which stops at the first problem and only looks at types, this collects
+//! every value that could not have been generated: type mismatches, numbers out of range, values
+//! that are not one of a categorical's, strings not matching a pattern and missing fields.
+use anyhow::Result;
+use num::ToPrimitive;
+use regex::Regex;
+use serde_json::{Map, Number, Value};
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::ops::RangeBounds;
+
+use super::{
+    number_content, ArrayContent, BoolContent, Categorical, CategoricalType, ChronoValueFormatter,
+    Content, DateTimeBucket, DateTimeContent, Id, LinkedBound, LinkedRange, NumberContent,
+    NumberDistribution, ObjectContent, RangeStep, StringContent,
+};
+use crate::Namespace;
+
+/// A value that does not conform to the content it is checked against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Where the value is, starting from the collection, e.g. `users[3].address.city`.
+    pub path: String,
+    pub reason: String,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.reason)
+    }
+}
+
+impl Namespace {
+    /// Every violation found in `values`, which are the elements of the collection `name`.
+    pub fn violations(&self, name: &str, values: &[Value]) -> Result<Vec<Violation>> {
+        let content = match self.get_collection(name)? {
+            Content::Array(ArrayContent { content, .. }) => content.as_ref(),
+            content => content,
+        };
+
+        let mut validator = Validator::default();
+        for (index, value) in values.iter().enumerate() {
+            validator.validate(content, value, &mut format!("{name}[{index}]"));
+        }
+
+        Ok(validator.violations)
+    }
+}
+
+#[derive(Default)]
+struct Validator {
+    violations: Vec<Violation>,
+    /// Patterns are compiled once, anchored so they must match whole strings. Patterns that do
+    /// not compile are `None` and never reported.
+    patterns: HashMap<String, Option<Regex>>,
+}
+
+impl Validator {
+    fn report(&mut self, path: &str, reason: String) {
+        self.violations.push(Violation {
+            path: path.to_string(),
+            reason,
+        });
+    }
+
+    /// Checks `value` against every content in `contents`, which it must match at least one of.
+    fn validate_any<'c>(
+        &mut self,
+        contents: impl Iterator<Item = &'c Content>,
+        value: &Value,
+        path: &mut String,
+        what: &str,
+    ) {
+        let mut attempts = Vec::new();
+        for content in contents {
+            let mut validator = Validator {
+                violations: Vec::new(),
+                patterns: std::mem::take(&mut self.patterns),
+            };
+            validator.validate(content, value, path);
+            self.patterns = validator.patterns;
+
+            if validator.violations.is_empty() {
+                return;
+            }
+            attempts.push((content, validator.violations));
+        }
+
+        // A field that is only nullable is reported as if it was not
+        let mut not_null = attempts
+            .into_iter()
+            .filter(|(content, _)| !content.is_null());
+        match (not_null.next(), not_null.next()) {
+            (Some((_, violations)), None) => self.violations.extend(violations),
+            _ => self.report(path, format!("{value} does not match any {what}")),
+        }
+    }
+
+    fn validate(&mut self, content: &Content, value: &Value, path: &mut String) {
+        match (content, value) {
+            // These either cannot be checked from a single value or generate anything
+            (Content::SameAs(_), _)
+            | (Content::Hidden(_), _)
+            | (Content::Series(_), _)
+            | (Content::Datasource(_), _)
+            | (Content::Expression(_), _)
+            | (Content::Empty(_), _) => {}
+            (Content::Unique(unique), value) => self.validate(&unique.content, value, path),
+            (Content::OneOf(one_of), value) => {
+                self.validate_any(one_of.iter(), value, path, "variant of the one_of")
+            }
+            (Content::Switch(switch), value) => {
+                self.validate_any(switch.iter(), value, path, "case of the switch")
+            }
+            (Content::Null(_), Value::Null) => {}
+            (Content::Bool(bool_content), Value::Bool(b)) => match bool_content {
+                BoolContent::Frequency(_) => {}
+                BoolContent::Constant(constant) if constant == b => {}
+                BoolContent::Constant(constant) => {
+                    self.report(path, format!("{b} is not the constant {constant}"))
+                }
+                BoolContent::Categorical(categorical) => {
+                    if let Some(reason) = not_in_categorical(categorical, b) {
+                        self.report(path, reason)
+                    }
+                }
+            },
+            (Content::Number(number_content), Value::Number(number)) => {
+                if let Some(reason) = number_violation(number_content, number) {
+                    self.report(path, reason)
+                }
+            }
+            (Content::String(string_content), Value::String(s)) => {
+                if let Some(reason) = self.string_violation(string_content, s) {
+                    self.report(path, reason)
+                }
+            }
+            (Content::DateTime(date_time_content), Value::String(s)) => {
+                if let Some(reason) = date_time_violation(date_time_content, s) {
+                    self.report(path, reason)
+                }
+            }
+            (Content::Array(ArrayContent { length, content }), Value::Array(values)) => {
+                // Arrays whose length is given by a cardinality have no `length`
+                if !length.is_null() {
+                    let mut validator = Validator::default();
+                    validator.validate(length, &Value::from(values.len()), path);
+                    for violation in validator.violations {
+                        self.report(path, format!("length {}", violation.reason));
+                    }
+                }
+
+                for (index, value) in values.iter().enumerate() {
+                    let len = path.len();
+                    path.push_str(&format!("[{index}]"));
+                    self.validate(content, value, path);
+                    path.truncate(len);
+                }
+            }
+            (Content::Object(object_content), Value::Object(object)) => {
+                self.validate_object(object_content, object, path)
+            }
+            (content, value) => self.report(
+                path,
+                format!("expected {}, found {}", content.kind(), json_kind(value)),
+            ),
+        }
+    }
+
+    fn validate_object(
+        &mut self,
+        object_content: &ObjectContent,
+        object: &Map<String, Value>,
+        path: &mut String,
+    ) {
+        for (name, content) in object_content.iter() {
+            let len = path.len();
+            path.push('.');
+            path.push_str(name);
+
+            match object.get(name) {
+                Some(value) => self.validate(content, value, path),
+                None if content.is_nullable() || matches!(content, Content::Hidden(_)) => {}
+                None => self.report(path, "missing required field".to_string()),
+            }
+
+            path.truncate(len);
+        }
+
+        for name in object.keys() {
+            if !object_content.fields.contains_key(name) {
+                self.report(
+                    &format!("{path}.{name}"),
+                    "not a field of the schema".to_string(),
+                );
+            }
+        }
+    }
+
+    fn string_violation(&mut self, string_content: &StringContent, s: &str) -> Option<String> {
+        match string_content {
+            StringContent::Pattern(regex_content) => {
+                let pattern = regex_content.to_string();
+                let regex = self
+                    .patterns
+                    .entry(pattern.clone())
+                    .or_insert_with(|| Regex::new(&format!("^(?:{pattern})$")).ok());
+
+                match regex {
+                    Some(regex) if !regex.is_match(s) => {
+                        Some(format!("\"{s}\" does not match the pattern \"{pattern}\""))
+                    }
+                    _ => None,
+                }
+            }
+            StringContent::Categorical(categorical) => {
+                not_in_categorical(categorical, &s.to_string())
+            }
+            StringContent::Constant(constant) if constant.as_str() != s => Some(format!(
+                "\"{s}\" is not the constant \"{}\"",
+                constant.as_str()
+            )),
+            StringContent::Uuid(_) if uuid::Uuid::parse_str(s).is_err() => {
+                Some(format!("\"{s}\" is not a UUID"))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn json_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn not_in_categorical<T: CategoricalType + Display>(
+    categorical: &Categorical<T>,
+    value: &T,
+) -> Option<String> {
+    (!categorical.seen.contains_key(value)).then(|| format!("{value} is not one of the categories"))
+}
+
+fn out_of_range<N: PartialOrd + Display>(range: &RangeStep<N>, value: N) -> Option<String> {
+    if range.contains(&value) {
+        return None;
+    }
+
+    let low = match &range.low {
+        Some(low) if range.include_low => format!("[{low}"),
+        Some(low) => format!("({low}"),
+        None => "(..".to_string(),
+    };
+    let high = match &range.high {
+        Some(high) if range.include_high => format!("{high}]"),
+        Some(high) => format!("{high})"),
+        None => "..)".to_string(),
+    };
+
+    Some(format!("{value} is out of range {low}, {high}"))
+}
+
+fn outside_distribution<N: PartialOrd + Display + Copy + ToPrimitive>(
+    dist: &NumberDistribution<N>,
+    value: N,
+    integers: bool,
+) -> Option<String> {
+    if let NumberDistribution::Histogram(histogram) = dist {
+        let number = value.to_f64()?;
+        if !histogram
+            .buckets
+            .iter()
+            .any(|bucket| bucket.contains(number, integers))
+        {
+            return Some(format!("{value} is in no bucket of the histogram"));
+        }
+    }
+    let range = RangeStep {
+        low: dist.low().copied(),
+        high: dist.high().copied(),
+        include_high: true,
+        ..Default::default()
+    };
+    out_of_range(&range, value)
+}
+
+fn outside_linked_range<N: PartialOrd + Display + Copy>(
+    range: &LinkedRange<N>,
+    value: N,
+) -> Option<String> {
+    // Bounds referencing other fields are not checked
+    let range = RangeStep {
+        low: range.low.as_ref().and_then(LinkedBound::value).copied(),
+        high: range.high.as_ref().and_then(LinkedBound::value).copied(),
+        step: None,
+        include_low: range.include_low,
+        include_high: range.include_high,
+    };
+    out_of_range(&range, value)
+}
+
+fn below_id<N: PartialOrd + Display>(id: &Id<N>, value: N, start_at: N) -> Option<String> {
+    let start_at = id.start_at.as_ref().unwrap_or(&start_at);
+    (value < *start_at).then(|| format!("{value} is below the first id {start_at}"))
+}
+
+macro_rules! integer_violation {
+    ($content:ident, $number:ident, $as:ident, $subtype:ty) => {
+        match $number
+            .as_i64()
+            .and_then(|n| <$subtype>::try_from(n).ok())
+            .or_else(|| $number.as_u64().and_then(|n| <$subtype>::try_from(n).ok()))
+        {
+            None => Some(format!(
+                "{} is not a valid {}",
+                $number,
+                stringify!($subtype)
+            )),
+            Some(n) => match $content {
+                number_content::$as::Range(range) => out_of_range(range, n),
+                number_content::$as::Categorical(categorical) => {
+                    not_in_categorical(categorical, &n)
+                }
+                number_content::$as::Constant(constant) => {
+                    (*constant != n).then(|| format!("{n} is not the constant {constant}"))
+                }
+                number_content::$as::Id(id) => below_id(id, n, 1),
+                number_content::$as::Distribution(dist) => outside_distribution(dist, n, true),
+                number_content::$as::Linked(range) => outside_linked_range(range, n),
+            },
+        }
+    };
+}
+
+fn number_violation(number_content: &NumberContent, number: &Number) -> Option<String> {
+    match number_content {
+        NumberContent::U32(content) => integer_violation!(content, number, U32, u32),
+        NumberContent::U64(content) => integer_violation!(content, number, U64, u64),
+        NumberContent::I16(content) => integer_violation!(content, number, I16, i16),
+        NumberContent::I32(content) => integer_violation!(content, number, I32, i32),
+        NumberContent::I64(content) => integer_violation!(content, number, I64, i64),
+        NumberContent::F64(content) => {
+            let n = number.as_f64()?;
+            match content {
+                number_content::F64::Range(range) => out_of_range(range, n),
+                number_content::F64::Constant(constant) => {
+                    (*constant != n).then(|| format!("{n} is not the constant {constant}"))
+                }
+                number_content::F64::Distribution(dist) => outside_distribution(dist, n, false),
+                number_content::F64::Linked(range) => outside_linked_range(range, n),
+            }
+        }
+        NumberContent::F32(content) => {
+            let n = number.as_f64()? as f32;
+            match content {
+                number_content::F32::Range(range) => out_of_range(range, n),
+                number_content::F32::Constant(constant) => {
+                    (*constant != n).then(|| format!("{n} is not the constant {constant}"))
+                }
+                number_content::F32::Distribution(dist) => outside_distribution(dist, n, false),
+                number_content::F32::Linked(range) => outside_linked_range(range, n),
+            }
+        }
+    }
+}
+
+fn date_time_violation(date_time_content: &DateTimeContent, s: &str) -> Option<String> {
+    let value = match ChronoValueFormatter::new(&date_time_content.format).parse(s) {
+        Ok(value) if value.type_() == date_time_content.type_ => value,
+        _ => {
+            return Some(format!(
+                "\"{s}\" is not a {} formatted as \"{}\"",
+                date_time_content.type_, date_time_content.format
+            ))
+        }
+    };
+
+    if let Some(buckets) = date_time_content.histogram.as_ref() {
+        let in_bucket = |bucket: &DateTimeBucket| bucket.begin <= value && value <= bucket.end;
+        if !buckets.iter().any(in_bucket) {
+            return Some(format!("\"{s}\" is in no bucket of the histogram"));
+        }
+    }
+
+    // Bounds referencing other fields are not checked
+    let before_begin =
+        matches!(&date_time_content.begin, Some(LinkedBound::Value(begin)) if value < *begin);
+    let after_end = matches!(&date_time_content.end, Some(LinkedBound::Value(end)) if value > *end);
+    (before_begin || after_end).then(|| format!("\"{s}\" is outside of the date time range"))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn violations(collection: Value, values: Value) -> Vec<String> {
+        let namespace = serde_json::from_value::<Content>(json!({
+            "type": "object",
+            "users": collection
+        }))
+        .unwrap()
+        .into_namespace()
+        .unwrap();
+        namespace
+            .violations("users", values.as_array().unwrap())
+            .unwrap()
+            .into_iter()
+            .map(|violation| violation.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn conforming_values_have_no_violations() {
+        let found = violations(
+            json!({
+                "type": "array",
+                "length": 2,
+                "content": {
+                    "type": "object",
+                    "id": { "type": "number", "id": {} },
+                    "email": { "type": "string", "pattern": "[a-z]+@example\\.com" },
+                    "nickname": {
+                        "type": "one_of",
+                        "variants": [{ "type": "null" }, { "type": "string", "pattern": "[a-z]+" }]
+                    },
+                    "joined": {
+                        "type": "date_time",
+                        "format": "%Y-%m-%d",
+                        "begin": "2020-01-01",
+                        "end": "2020-12-31"
+                    }
+                }
+            }),
+            json!([
+                { "id": 1, "email": "jo@example.com", "nickname": null, "joined": "2020-02-03" },
+                { "id": 2, "email": "al@example.com", "joined": "2020-12-31" }
+            ]),
+        );
+
+        assert!(found.is_empty(), "{found:?}");
+    }
+
+    #[test]
+    fn every_violation_is_reported() {
+        let found = violations(
+            json!({
+                "type": "array",
+                "length": 1,
+                "content": {
+                    "type": "object",
+                    "age": { "type": "number", "subtype": "u32", "range": { "low": 18, "high": 100 } },
+                    "plan": { "type": "string", "categorical": { "free": 1, "pro": 1 } },
+                    "email": { "type": "string", "pattern": "[a-z]+@example\\.com" },
+                    "active": { "type": "bool", "frequency": 0.5 },
+                    "tags": {
+                        "type": "array",
+                        "length": { "type": "number", "subtype": "u64", "range": { "low": 0, "high": 2 } },
+                        "content": { "type": "string", "pattern": "[a-z]+" }
+                    },
+                    "nickname": {
+                        "type": "one_of",
+                        "variants": [{ "type": "null" }, { "type": "string", "pattern": "[a-z]+" }]
+                    }
+                }
+            }),
+            json!([
+                {
+                    "age": 12,
+                    "plan": "enterprise",
+                    "email": "JO@example.com",
+                    "tags": ["a", "B"],
+                    "nickname": "Jo",
+                    "extra": true
+                },
+                { "age": -1, "plan": "free", "email": "a@example.com", "active": "yes", "tags": [] }
+            ]),
+        );
+
+        assert_eq!(
+            found,
+            vec![
+                "users[0].active: missing required field",
+                "users[0].age: 12 is out of range [18, 100)",
+                "users[0].email: \"JO@example.com\" does not match the pattern \"[a-z]+@example\\.com\"",
+                "users[0].nickname: \"Jo\" does not match the pattern \"[a-z]+\"",
+                "users[0].plan: enterprise is not one of the categories",
+                "users[0].tags: length 2 is out of range [0, 2)",
+                "users[0].tags[1]: \"B\" does not match the pattern \"[a-z]+\"",
+                "users[0].extra: not a field of the schema",
+                "users[1].active: expected bool::frequency, found string",
+                "users[1].age: -1 is not a valid u32",
+            ]
+        );
+    }
+}
@@ -4,6 +4,7 @@
 use std::io;
 
 use synth::cli::{Args, Cli, GenerateCommand};
+use synth::sampler::SampleSize;
 
 fn bench_generate_1_to_stdout() {
     bench_generate_n_to_stdout(1);
@@ -24,10 +25,11 @@ fn bench_generate_n_to_stdout(size: usize) {
             namespace,
             collection: None,
             scenario: None,
-            size,
+            size: SampleSize::Total(size),
             to: "json:".to_string(),
             seed: Some(0),
             random: false,
+            threads: 1,
             schema: None,
         });
         let output = io::stdout();
@@ -378,6 +378,7 @@ mod tests {
                             VariantContent::new(Content::Null(NullContent)),
                             VariantContent::new(Content::SameAs(SameAsContent {
                                 ref_: FieldRef::new("my_collection.z").unwrap(),
+                                cardinality: None,
                             })),
                         ],
                     }),
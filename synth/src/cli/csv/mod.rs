@@ -3,15 +3,17 @@
 mod headers;
 
 use crate::cli::export::ExportStrategy;
-use crate::sampler::SamplerOutput;
+use crate::sampler::{SampleStream, SamplerOutput};
 
 use synth_core::schema::content::{number_content, ArrayContent, NumberContent};
-use synth_core::schema::{MergeStrategy, OptionalMergeStrategy};
+use synth_core::schema::{InferenceOptions, MergeStrategy, OptionalMergeStrategy, Profiler};
 use synth_core::{Content, Namespace, Value};
 use synth_gen::value::Number;
 
 use anyhow::Result;
 
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
 use std::path::PathBuf;
 
 use super::import::ImportStrategy;
@@ -22,22 +24,38 @@ pub struct CsvFileExportStrategy {
 }
 
 impl ExportStrategy for CsvFileExportStrategy {
-    fn export(&self, namespace: Namespace, sample: SamplerOutput) -> Result<()> {
+    fn export(&self, namespace: Namespace, sample: SampleStream) -> Result<()> {
         if self.to_dir.exists() {
             return Err(anyhow::anyhow!("Output directory already exists"));
         } else {
             std::fs::create_dir_all(&self.to_dir)?;
         }
 
-        match csv_output_from_sampler_ouput(sample, &namespace)? {
-            CsvOutput::Namespace(ns) => {
-                for (name, csv) in ns {
-                    std::fs::write(self.to_dir.join(name + ".csv"), csv)?;
+        let single_collection = sample.collection().is_some();
+        let mut writers: HashMap<String, csv::Writer<std::fs::File>> = HashMap::new();
+
+        for batch in sample {
+            let (collection_name, value) = batch?;
+
+            let writer = match writers.get_mut(&collection_name) {
+                Some(writer) => writer,
+                None => {
+                    let path = if single_collection {
+                        self.to_dir.join("collection.csv")
+                    } else {
+                        self.to_dir.join(format!("{collection_name}.csv"))
+                    };
+                    let mut writer = csv::Writer::from_path(path)?;
+                    write_csv_header(&mut writer, &collection_name, &namespace)?;
+                    writers.entry(collection_name.clone()).or_insert(writer)
                 }
-            }
-            CsvOutput::Collection(csv) => {
-                std::fs::write(self.to_dir.join("collection.csv"), csv)?;
-            }
+            };
+
+            write_csv_records(writer, &collection_name, value, &namespace)?;
+        }
+
+        for (_, mut writer) in writers {
+            writer.flush()?;
         }
 
         Ok(())
@@ -48,8 +66,8 @@ impl ExportStrategy for CsvFileExportStrategy {
 pub struct CsvStdoutExportStrategy;
 
 impl ExportStrategy for CsvStdoutExportStrategy {
-    fn export(&self, namespace: Namespace, sample: SamplerOutput) -> Result<()> {
-        match csv_output_from_sampler_ouput(sample, &namespace)? {
+    fn export(&self, namespace: Namespace, sample: SampleStream) -> Result<()> {
+        match csv_output_from_sampler_ouput(sample.collect_output()?, &namespace)? {
             CsvOutput::Namespace(ns) => {
                 for (name, csv) in ns {
                     println!("\n{}\n{}\n\n{}\n", name, "-".repeat(name.len()), csv)
@@ -66,6 +84,7 @@ impl ExportStrategy for CsvStdoutExportStrategy {
 pub struct CsvFileImportStrategy {
     pub from_dir: PathBuf,
     pub expect_header_row: bool,
+    pub inference: InferenceOptions,
 }
 
 impl ImportStrategy for CsvFileImportStrategy {
@@ -81,26 +100,53 @@ impl ImportStrategy for CsvFileImportStrategy {
                     .has_headers(self.expect_header_row)
                     .from_path(entry.path())?;
 
-                let collection = import_csv_collection(reader, self.expect_header_row)?;
-
-                let mut name_string = entry.file_name().into_string().map_err(|_| {
-                    anyhow!("Failed to interpret collection name when importing a CSV namespace")
-                })?;
-                if name_string.ends_with(".csv") {
-                    name_string.truncate(name_string.len() - 4);
-                }
+                let name = csv_collection_name(&entry)?;
+                let collection =
+                    import_csv_collection(&name, reader, self.expect_header_row, self.inference)?;
 
-                namespace.put_collection(name_string, collection)?;
+                namespace.put_collection(name, collection)?;
             }
         }
 
         Ok(namespace)
     }
+
+    fn import_data(&self) -> Result<BTreeMap<String, Vec<serde_json::Value>>> {
+        let mut data = BTreeMap::new();
+
+        for entry in std::fs::read_dir(&self.from_dir)? {
+            let entry = entry?;
+
+            if entry.file_type()?.is_file() {
+                let reader = csv::ReaderBuilder::new()
+                    .has_headers(self.expect_header_row)
+                    .from_path(entry.path())?;
+
+                let (_, values) = import_csv_values(reader, self.expect_header_row)?;
+
+                data.insert(csv_collection_name(&entry)?, values);
+            }
+        }
+
+        Ok(data)
+    }
+}
+
+fn csv_collection_name(entry: &std::fs::DirEntry) -> Result<String> {
+    let mut name_string = entry.file_name().into_string().map_err(|_| {
+        anyhow!("Failed to interpret collection name when importing a CSV namespace")
+    })?;
+    if name_string.ends_with(".csv") {
+        name_string.truncate(name_string.len() - 4);
+    }
+
+    Ok(name_string)
 }
 
 #[derive(Clone, Debug)]
 pub struct CsvStdinImportStrategy {
     pub expect_header_row: bool,
+    pub inference: InferenceOptions,
 }
 
 impl ImportStrategy for CsvStdinImportStrategy {
@@ -111,18 +157,54 @@ impl ImportStrategy for CsvStdinImportStrategy {
             .from_reader(stdin.lock());
 
         let name = "collection".to_string();
-        import_csv_collection(reader, self.expect_header_row).map(|collection| {
-            let mut namespace = Namespace::new();
-            namespace.put_collection(name, collection).unwrap();
-            namespace
-        })
+        import_csv_collection(&name, reader, self.expect_header_row, self.inference).map(
+            |collection| {
+                let mut namespace = Namespace::new();
+                namespace.put_collection(name, collection).unwrap();
+                namespace
+            },
+        )
+    }
+
+    fn import_data(&self) -> Result<BTreeMap<String, Vec<serde_json::Value>>> {
+        let stdin = std::io::stdin();
+        let reader = csv::ReaderBuilder::new()
+            .has_headers(self.expect_header_row)
+            .from_reader(stdin.lock());
+
+        let (_, values) = import_csv_values(reader, self.expect_header_row)?;
+
+        Ok(BTreeMap::from([("collection".to_string(), values)]))
     }
 }
 
 pub fn import_csv_collection(
-    mut reader: csv::Reader<impl std::io::Read>,
+    name: &str,
+    reader: csv::Reader<impl std::io::Read>,
     expect_header_row: bool,
+    inference: InferenceOptions,
 ) -> Result<Content> {
+    let (headers, mut values) = import_csv_values(reader, expect_header_row)?;
+
+    if values.is_empty() {
+        values.push(csv_record_to_value(&csv::StringRecord::new(), &headers)?);
+    }
+
+    let mut content = Content::from_value_wrapped_in_array_uncounted(&values[0]);
+    let values = serde_json::Value::Array(values);
+    OptionalMergeStrategy.try_merge(&mut content, &values)?;
+    let mut profiler = Profiler::new(inference);
+    profiler.observe(&values);
+    profiler.apply_to_collection(name, &mut content);
+
+    Ok(content)
+}
+
+/// Reads the headers, if expected, and every record of the CSV data as a value.
+fn import_csv_values(
+    mut reader: csv::Reader<impl std::io::Read>,
+    expect_header_row: bool,
+) -> Result<(Option<headers::CsvHeaders>, Vec<serde_json::Value>)> {
     let headers = if expect_header_row {
         Some(headers::CsvHeaders::from_csv_header_record(
             &reader.headers()?.clone(),
@@ -131,26 +213,12 @@ pub fn import_csv_collection(
         None
     };
 
-    let mut records = reader.records();
-
-    let head = csv_record_to_value(
-        &records
-            .next()
-            .unwrap_or_else(|| Ok(csv::StringRecord::new()))?,
-        &headers,
-    )?;
-    let tail = records
+    let values = reader
+        .records()
         .map(|res| res.map(|record| csv_record_to_value(&record, &headers)))
         .collect::<csv::Result<Result<Vec<serde_json::Value>>>>()??;
 
-    let mut content = Content::from_value_wrapped_in_array(&head);
-
-    let mut values = vec![head];
-    values.extend(tail);
-
-    OptionalMergeStrategy.try_merge(&mut content, &serde_json::Value::Array(values))?;
-
-    Ok(content)
+    Ok((headers, values))
 }
 
 fn csv_record_to_value(
@@ -296,30 +364,56 @@ fn csv_output_from_sampler_ouput(
 fn to_csv_string(collection_name: String, value: Value, namespace: &Namespace) -> Result<String> {
     let mut writer = csv::Writer::from_writer(vec![]);
 
-    let collection = namespace.get_collection(&collection_name)?;
+    write_csv_header(&mut writer, &collection_name, namespace)?;
+    write_csv_records(&mut writer, &collection_name, value, namespace)?;
 
-    match (collection, value) {
-        (Content::Array(array_content), Value::Array(elements)) => {
-            let inner_content: &Content = &array_content.content;
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
 
-            writer.write_record(
-                &headers::CsvHeaders::from_content(inner_content, namespace)?.to_csv_record(),
-            )?;
+/// The content describing a single CSV record of a collection.
+fn csv_record_content(collection: &Content) -> &Content {
+    match collection {
+        Content::Array(array_content) => &array_content.content,
+        collection => collection,
+    }
+}
+
+fn write_csv_header<W: Write>(
+    writer: &mut csv::Writer<W>,
+    collection_name: &str,
+    namespace: &Namespace,
+) -> Result<()> {
+    let collection = namespace.get_collection(collection_name)?;
 
+    writer.write_record(
+        &headers::CsvHeaders::from_content(csv_record_content(collection), namespace)?
+            .to_csv_record(),
+    )?;
+
+    Ok(())
+}
+
+fn write_csv_records<W: Write>(
+    writer: &mut csv::Writer<W>,
+    collection_name: &str,
+    value: Value,
+    namespace: &Namespace,
+) -> Result<()> {
+    let collection = namespace.get_collection(collection_name)?;
+
+    match (collection, value) {
+        (Content::Array(array_content), Value::Array(elements)) => {
             for val in elements {
-                let record = synth_val_to_csv_record(val, inner_content, namespace);
+                let record = synth_val_to_csv_record(val, &array_content.content, namespace);
                 writer.write_record(record)?;
             }
         }
         (_, value) => {
-            writer.write_record(
-                &headers::CsvHeaders::from_content(collection, namespace)?.to_csv_record(),
-            )?;
             writer.write_record(synth_val_to_csv_record(value, collection, namespace))?;
         }
     }
 
-    Ok(String::from_utf8(writer.into_inner()?)?)
+    Ok(())
 }
 
 fn synth_val_to_csv_record(val: Value, content: &Content, namespace: &Namespace) -> Vec<String> {
@@ -425,7 +519,7 @@ fn count_scalars_in_content(content: &Content, ns: &Namespace) -> usize {
 
 #[cfg(test)]
 mod tests {
-    use crate::sampler::Sampler;
+    use crate::sampler::{SampleSize, Sampler};
 
     use super::*;
     #[test]
@@ -532,9 +626,10 @@ mod tests {
         let mut ns = Namespace::new();
         ns.put_collection(collection_name.clone(), content).unwrap();
 
-        let generator = Sampler::try_from(&ns).unwrap();
+        let generator = Sampler::new(&ns, &SampleSize::Total(1)).unwrap();
         let output = generator
-            .sample_seeded(Some(collection_name), 1, 0)
+            .sample_seeded(Some(collection_name), 0)
+            .collect_output()
             .unwrap();
 
         assert_eq!(
@@ -3,23 +3,28 @@ use crate::cli::json::{JsonFileExportStrategy, JsonStdoutExportStrategy};
 use crate::cli::jsonl::{JsonLinesFileExportStrategy, JsonLinesStdoutExportStrategy};
 use crate::cli::mongo::MongoExportStrategy;
 use crate::cli::mysql::MySqlExportStrategy;
+use crate::cli::parquet::ParquetFileExportStrategy;
 use crate::cli::postgres::PostgresExportStrategy;
+use crate::cli::sql::{SqlFileExportStrategy, SqlStdoutExportStrategy};
+use crate::cli::sqlite::SqliteExportStrategy;
 
 use anyhow::{Context, Result};
 
 use std::cell::RefCell;
 use std::io::Write;
 use std::path::PathBuf;
+use uriparse::URI;
 
+use crate::datasource::ddl::SqlDialect;
 use crate::datasource::DataSource;
-use crate::sampler::SamplerOutput;
+use crate::sampler::SampleStream;
 use async_std::task;
 use synth_core::{DataSourceParams, Namespace, Value};
 
 use super::map_from_uri_query;
 
 pub(crate) trait ExportStrategy {
-    fn export(&self, namespace: Namespace, sample: SamplerOutput) -> Result<()>;
+    fn export(&self, namespace: Namespace, sample: SampleStream) -> Result<()>;
 }
 
 pub(crate) struct ExportStrategyBuilder<'a, W> {
@@ -58,16 +63,25 @@ where
         // 'mysql' suprisingly) it seems simpler to just match based on the scheme string instead of on enum variants.
         let scheme = params.uri.scheme().as_str().to_lowercase();
         let query = map_from_uri_query(params.uri.query());
+        let create_tables = query
+            .get("create_tables")
+            .map(|create_tables| *create_tables == "true")
+            .unwrap_or(false);
 
         let export_strategy: Box<dyn ExportStrategy> = match scheme.as_str() {
             "postgres" | "postgresql" => Box::new(PostgresExportStrategy {
-                uri_string: params.uri.to_string(),
+                uri_string: uri_without_query_param(&params.uri, "create_tables")?,
                 schema: params.schema,
+                create_tables,
             }),
             "mongodb" => Box::new(MongoExportStrategy {
                 uri_string: params.uri.to_string(),
             }),
             "mysql" | "mariadb" => Box::new(MySqlExportStrategy {
+                uri_string: uri_without_query_param(&params.uri, "create_tables")?,
+                create_tables,
+            }),
+            "sqlite" => Box::new(SqliteExportStrategy {
                 uri_string: params.uri.to_string(),
             }),
             "json" => {
@@ -108,9 +122,40 @@ where
                     })
                 }
             }
+            "sql" => {
+                let dialect = query
+                    .get("dialect")
+                    .map(|dialect| dialect.parse())
+                    .transpose()?
+                    .unwrap_or(SqlDialect::Postgres);
+
+                if params.uri.path() == "" {
+                    Box::new(SqlStdoutExportStrategy {
+                        writer: RefCell::new(writer),
+                        dialect,
+                        create_tables,
+                    })
+                } else {
+                    Box::new(SqlFileExportStrategy {
+                        to_file: PathBuf::from(params.uri.path().to_string()),
+                        dialect,
+                        create_tables,
+                    })
+                }
+            }
+            "parquet" => {
+                if params.uri.path() == "" {
+                    return Err(anyhow!(
+                        "Parquet can only be exported to a directory. Specify one like `parquet:path/to/dir`."
+                    ));
+                }
+                Box::new(ParquetFileExportStrategy {
+                    to_dir: PathBuf::from(params.uri.path().to_string()),
+                })
+            }
             _ => {
                 return Err(anyhow!(
-                    "Export URI scheme not recognised. Was expecting one of 'mongodb', 'postgres', 'mysql', 'mariadb', 'json', 'jsonl' or 'csv'."
+                    "Export URI scheme not recognised. Was expecting one of 'mongodb', 'postgres', 'mysql', 'mariadb', 'sqlite', 'json', 'jsonl', 'csv', 'sql' or 'parquet'."
                 ));
             }
         };
@@ -118,20 +163,30 @@ where
     }
 }
 
+/// Removes a parameter synth consumes from a URI, so it is not passed on to the database driver.
+fn uri_without_query_param(uri: &URI, key: &str) -> Result<String> {
+    let query_str = uri.query().map(uriparse::Query::as_str).unwrap_or_default();
+    let query = querystring::querify(query_str)
+        .into_iter()
+        .filter(|(name, _)| *name != key)
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let mut uri = uri.clone();
+    uri.set_query((!query.is_empty()).then_some(query.as_str()))?;
+
+    Ok(uri.to_string())
+}
+
 pub(crate) fn create_and_insert_values<T: DataSource>(
-    sample: SamplerOutput,
+    sample: SampleStream,
     datasource: &T,
 ) -> Result<()> {
-    match sample {
-        SamplerOutput::Collection(name, value) => {
-            insert_data(datasource, name.as_ref(), value)?;
-        }
-        SamplerOutput::Namespace(namespace) => {
-            for (name, value) in namespace.into_iter() {
-                insert_data(datasource, name.as_ref(), value)?;
-            }
-        }
-    };
+    for batch in sample {
+        let (name, value) = batch?;
+        insert_data(datasource, name.as_ref(), value)?;
+    }
 
     Ok(())
 }
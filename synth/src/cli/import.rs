@@ -1,8 +1,9 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use anyhow::Result;
 
-use synth_core::schema::Namespace;
+use synth_core::schema::{InferenceOptions, Namespace};
 use synth_core::{Content, DataSourceParams};
 
 use crate::cli::csv::{CsvFileImportStrategy, CsvStdinImportStrategy};
@@ -10,7 +11,10 @@ use crate::cli::json::{JsonFileImportStrategy, JsonStdinImportStrategy};
 use crate::cli::jsonl::{JsonLinesFileImportStrategy, JsonLinesStdinImportStrategy};
 use crate::cli::mongo::MongoImportStrategy;
 use crate::cli::mysql::MySqlImportStrategy;
+use crate::cli::parquet::ParquetFileImportStrategy;
 use crate::cli::postgres::PostgresImportStrategy;
+use crate::cli::sqlite::SqliteImportStrategy;
+use crate::datasource::sampling::Sampling;
 
 use super::map_from_uri_query;
 
@@ -18,6 +22,9 @@ pub trait ImportStrategy {
     /// Import an entire namespace.
     fn import(&self) -> Result<Namespace>;
 
+    /// Read the values of every collection as they are, without deriving any content from them.
+    fn import_data(&self) -> Result<BTreeMap<String, Vec<serde_json::Value>>>;
+
     /// Import a single collection. Default implementation works by calling `import` and then extracting from the
     /// returned namespace the correct collection based on the `name` parameter.
     fn import_collection(&self, name: &str) -> Result<Content> {
@@ -27,10 +34,14 @@ pub trait ImportStrategy {
     }
 }
 
-impl TryFrom<DataSourceParams<'_>> for Box<dyn ImportStrategy> {
+impl TryFrom<(DataSourceParams<'_>, Sampling, InferenceOptions)> for Box<dyn ImportStrategy> {
     type Error = anyhow::Error;
 
-    fn try_from(params: DataSourceParams) -> Result<Self, Self::Error> {
+    /// The strategy importing from `params`, reading the rows of databases picked by `sampling`
+    /// and inferring their content as asked by `inference`.
+    fn try_from(
+        (params, sampling, inference): (DataSourceParams, Sampling, InferenceOptions),
+    ) -> Result<Self, Self::Error> {
         let scheme = params.uri.scheme().as_str().to_lowercase();
         let query = map_from_uri_query(params.uri.query());
 
@@ -38,19 +49,31 @@ impl TryFrom<DataSourceParams<'_>> for Box<dyn ImportStrategy> {
             "postgres" | "postgresql" => Box::new(PostgresImportStrategy {
                 uri_string: params.uri.to_string(),
                 schema: params.schema,
+                sampling,
+                inference,
             }),
             "mongodb" => Box::new(MongoImportStrategy {
                 uri_string: params.uri.to_string(),
+                sampling,
+                inference,
             }),
             "mysql" | "mariadb" => Box::new(MySqlImportStrategy {
                 uri_string: params.uri.to_string(),
+                sampling,
+                inference,
+            }),
+            "sqlite" => Box::new(SqliteImportStrategy {
+                uri_string: params.uri.to_string(),
+                sampling,
+                inference,
             }),
             "json" => {
                 if params.uri.path() == "" {
-                    Box::new(JsonStdinImportStrategy)
+                    Box::new(JsonStdinImportStrategy { inference })
                 } else {
                     Box::new(JsonFileImportStrategy {
                         from_file: PathBuf::from(params.uri.path().to_string()),
+                        inference,
                     })
                 }
             }
@@ -63,11 +86,13 @@ impl TryFrom<DataSourceParams<'_>> for Box<dyn ImportStrategy> {
                 if params.uri.path() == "" {
                     Box::new(JsonLinesStdinImportStrategy {
                         collection_field_name,
+                        inference,
                     })
                 } else {
                     Box::new(JsonLinesFileImportStrategy {
                         from_file: PathBuf::from(params.uri.path().to_string()),
                         collection_field_name,
+                        inference,
                     })
                 }
             }
@@ -80,17 +105,33 @@ impl TryFrom<DataSourceParams<'_>> for Box<dyn ImportStrategy> {
                     .unwrap_or(true);
 
                 if params.uri.path() == "" {
-                    Box::new(CsvStdinImportStrategy { expect_header_row })
+                    Box::new(CsvStdinImportStrategy {
+                        expect_header_row,
+                        inference,
+                    })
                 } else {
                     Box::new(CsvFileImportStrategy {
                         from_dir: PathBuf::from(params.uri.path().to_string()),
                         expect_header_row,
+                        inference,
                     })
                 }
             }
+            "parquet" => {
+                if params.uri.path() == "" {
+                    return Err(anyhow!(
+                        "Parquet can only be imported from a file or directory. Specify one like `parquet:path/to/dir`."
+                    ));
+                }
+
+                Box::new(ParquetFileImportStrategy {
+                    from_path: PathBuf::from(params.uri.path().to_string()),
+                    inference,
+                })
+            }
             _ => {
                 return Err(anyhow!(
-                    "Import URI scheme not recognised. Was expecting one of 'mongodb', 'postgres', 'mysql', 'mariadb', 'json', 'jsonl', or 'csv'."
+                    "Import URI scheme not recognised. Was expecting one of 'mongodb', 'postgres', 'mysql', 'mariadb', 'sqlite', 'json', 'jsonl', 'csv' or 'parquet'."
                 ));
             }
         };
@@ -103,6 +144,8 @@ mod tests {
     use crate::cli::csv::import_csv_collection;
     use crate::cli::json::import_json;
     use crate::cli::jsonl::import_json_lines;
+    use synth_core::schema::InferenceOptions;
+    use synth_core::Content;
 
     #[test]
     fn test_json_and_json_lines_import_equivalence() {
@@ -125,15 +168,20 @@ mod tests {
         });
 
         assert_eq!(
-            import_json_lines(json_lines, "type").unwrap(),
-            import_json(json).unwrap()
+            import_json_lines(json_lines, "type", InferenceOptions::default()).unwrap(),
+            import_json(json, InferenceOptions::default()).unwrap()
         );
     }
 
     fn json_csv_equiv_assert(csv: &str, json: serde_json::Value) {
-        let from_csv =
-            import_csv_collection(csv::Reader::from_reader(csv.as_bytes()), true).unwrap();
-        let from_json = import_json(json)
+        let from_csv = import_csv_collection(
+            "collection",
+            csv::Reader::from_reader(csv.as_bytes()),
+            true,
+            InferenceOptions::default(),
+        )
+        .unwrap();
+        let from_json = import_json(json, InferenceOptions::default())
             .unwrap()
             .get_collection("collection")
             .unwrap()
@@ -192,4 +240,66 @@ mod tests {
             }),
         );
     }
+
+    #[test]
+    fn import_null_frequency() {
+        fn weights(collection: &Content) -> Vec<serde_json::Value> {
+            serde_json::to_value(collection).unwrap()["content"]["note"]["variants"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|variant| variant["weight"].clone())
+                .collect()
+        }
+
+        // 288 notes and 12 nulls, the first row having a note
+        let rows = (0..300)
+            .map(|index| match index % 25 {
+                24 => serde_json::json!({ "id": index, "note": null }),
+                _ => serde_json::json!({ "id": index, "note": "x" }),
+            })
+            .collect::<Vec<_>>();
+        let expected = vec![serde_json::json!(288.0), serde_json::json!(12.0)];
+
+        let from_json = import_json(
+            serde_json::json!({ "collection": rows.clone() }),
+            InferenceOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            weights(from_json.get_collection("collection").unwrap()),
+            expected
+        );
+
+        let json_lines = rows
+            .iter()
+            .map(|row| {
+                let mut line = row.clone();
+                line["type"] = serde_json::json!("collection");
+                line
+            })
+            .collect();
+        let from_json_lines =
+            import_json_lines(json_lines, "type", InferenceOptions::default()).unwrap();
+        assert_eq!(
+            weights(from_json_lines.get_collection("collection").unwrap()),
+            expected
+        );
+
+        let csv = std::iter::once("id,note".to_string())
+            .chain(rows.iter().map(|row| match row["note"].as_str() {
+                Some(note) => format!("{},{}", row["id"], note),
+                None => format!("{},", row["id"]),
+            }))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let from_csv = import_csv_collection(
+            "collection",
+            csv::Reader::from_reader(csv.as_bytes()),
+            true,
+            InferenceOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(weights(&from_csv), expected);
+    }
 }
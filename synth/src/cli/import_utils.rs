@@ -1,17 +1,21 @@
 use crate::datasource::relational_datasource::{
-    get_columns_info, ColumnInfo, ForeignKey, PrimaryKey, SqlxDataSource, ValueWrapper,
+    get_columns_info, ColumnInfo, ForeignKey, PrimaryKey, SqlxDataSource, UniqueKeyColumn,
+    ValueWrapper,
 };
+use crate::datasource::sampling::Sampling;
 use crate::datasource::DataSource;
 use anyhow::{Context, Result};
 use async_std::task;
+use futures::TryStreamExt;
 use log::debug;
 use serde_json::Value;
 use sqlx::{Executor, Row};
+use std::collections::BTreeMap;
 use synth_core::graph::json::synth_val_to_json;
 use synth_core::schema::content::number_content::U64;
 use synth_core::schema::{
-    ArrayContent, FieldRef, NumberContent, ObjectContent, OptionalMergeStrategy, RangeStep,
-    SameAsContent, UniqueContent,
+    ArrayContent, FieldRef, InferenceOptions, MergeStrategy, NumberContent, ObjectContent,
+    OptionalMergeStrategy, Profiler, RangeStep, SameAsContent, UniqueContent,
 };
 use synth_core::{Content, Namespace};
 
@@ -25,6 +29,8 @@ struct FieldContentWrapper(Content);
 
 pub(crate) fn build_namespace_import<T: DataSource + SqlxDataSource>(
     datasource: &T,
+    sampling: Sampling,
+    inference: InferenceOptions,
 ) -> Result<Namespace>
 where
     T: Sync,
@@ -34,6 +40,7 @@ where
     usize: sqlx::ColumnIndex<<T::DB as sqlx::Database>::Row>,
     PrimaryKey: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
     ForeignKey: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
+    UniqueKeyColumn: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
     ValueWrapper: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
     ColumnInfo: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
 {
@@ -51,12 +58,55 @@ where
     info!("Building namespace foreign keys...");
     populate_namespace_foreign_keys(&mut namespace, datasource)?;
 
+    info!("Building namespace unique keys...");
+    populate_namespace_unique_keys(&mut namespace, &table_names, datasource)?;
+
     info!("Building namespace values...");
-    populate_namespace_values(&mut namespace, &table_names, datasource)?;
+    populate_namespace_values(
+        &mut namespace,
+        &table_names,
+        datasource,
+        sampling,
+        inference,
+    )?;
 
     Ok(namespace)
 }
 
+/// Reads every row of every table.
+pub(crate) fn build_namespace_data<T: DataSource + SqlxDataSource>(
+    datasource: &T,
+) -> Result<BTreeMap<String, Vec<Value>>>
+where
+    for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
+    String: sqlx::Type<T::DB>,
+    for<'d> String: sqlx::Decode<'d, T::DB>,
+    usize: sqlx::ColumnIndex<<T::DB as sqlx::Database>::Row>,
+    ValueWrapper: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
+{
+    let table_names = task::block_on(get_table_names(datasource))
+        .with_context(|| "Failed to get table names".to_string())?;
+
+    let mut data = BTreeMap::new();
+
+    for table_name in table_names {
+        info!("Reading {} rows...", table_name);
+
+        let query = format!(
+            "SELECT * FROM {}",
+            datasource.get_table_name_for_insert(&table_name)
+        );
+        let rows = task::block_on(get_values(datasource, &query))?;
+
+        data.insert(
+            table_name,
+            rows.into_iter().map(synth_val_to_json).collect(),
+        );
+    }
+
+    Ok(data)
+}
+
 async fn get_table_names<T: SqlxDataSource>(datasource: &T) -> Result<Vec<String>>
 where
     for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
@@ -117,15 +167,14 @@ where
         let primary_keys = task::block_on(get_primary_keys(datasource, table_name.to_string()))?;
 
         if primary_keys.len() > 1 {
-            bail!(
-                "{} primary keys found at collection {}. Synth does not currently support \
-            composite primary keys.",
-                primary_keys.len(),
-                table_name
-            )
-        }
-
-        if let Some(primary_key) = primary_keys.first() {
+            // Each column of a composite primary key is generated independently, but together
+            // they are unique
+            let columns = primary_keys
+                .into_iter()
+                .map(|primary_key| primary_key.column_name)
+                .collect();
+            add_unique_together(namespace, table_name, columns)?;
+        } else if let Some(primary_key) = primary_keys.first() {
             let field = FieldRef::new(&format!(
                 "{}.content.{}",
                 table_name, primary_key.column_name
@@ -188,7 +237,10 @@ where
         let from_field = FieldRef::new(&format!("{}.content.{}", fk.from_table, fk.from_column))?;
         let to_field = FieldRef::new(&format!("{}.content.{}", fk.to_table, fk.to_column))?;
         let node = namespace.get_s_node_mut(&from_field)?;
-        *node = Content::SameAs(SameAsContent { ref_: to_field });
+        *node = Content::SameAs(SameAsContent {
+            ref_: to_field,
+            cardinality: None,
+        });
     }
 
     Ok(())
@@ -211,10 +263,119 @@ where
         .collect()
 }
 
+fn populate_namespace_unique_keys<T: SqlxDataSource>(
+    namespace: &mut Namespace,
+    table_names: &[String],
+    datasource: &T,
+) -> Result<()>
+where
+    for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
+    String: sqlx::Type<T::DB>,
+    for<'d> String: sqlx::Encode<'d, T::DB>,
+    UniqueKeyColumn: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
+{
+    for table_name in table_names.iter() {
+        let mut unique_keys: Vec<(String, Vec<String>)> = Vec::new();
+        for column in task::block_on(get_unique_keys(datasource, table_name.to_string()))? {
+            match unique_keys.last_mut() {
+                Some((key_name, columns)) if *key_name == column.key_name => {
+                    columns.push(column.column_name)
+                }
+                _ => unique_keys.push((column.key_name, vec![column.column_name])),
+            }
+        }
+
+        for (key_name, mut columns) in unique_keys {
+            debug!("unique key {} on {}", key_name, table_name);
+            if columns.len() > 1 {
+                add_unique_together(namespace, table_name, columns)?;
+            } else {
+                add_unique(namespace, table_name, &columns.remove(0))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn get_unique_keys<T: SqlxDataSource>(
+    datasource: &T,
+    table_name: String,
+) -> Result<Vec<UniqueKeyColumn>>
+where
+    for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
+    String: sqlx::Type<T::DB>,
+    for<'d> String: sqlx::Encode<'d, T::DB>,
+    UniqueKeyColumn: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
+{
+    let query = datasource.get_unique_keys_query();
+    let pool = datasource.get_pool();
+
+    datasource
+        .query(query)
+        .bind(table_name)
+        .fetch_all(&pool)
+        .await?
+        .into_iter()
+        .map(UniqueKeyColumn::try_from)
+        .collect()
+}
+
+/// Makes the values of the column `column_name` of the collection `table_name` unique, except for
+/// its `null`s.
+fn add_unique(namespace: &mut Namespace, table_name: &str, column_name: &str) -> Result<()> {
+    let field = FieldRef::new(format!("{}.content.{}", table_name, column_name))?;
+    let node = namespace.get_s_node_mut(&field)?;
+    let nullable = node.is_nullable();
+    let node = match node {
+        Content::OneOf(one_of) if nullable => one_of
+            .iter_mut()
+            .find(|content| !content.is_null())
+            .unwrap(),
+        node => node,
+    };
+
+    match node {
+        // Already unique. Foreign keys go through the values they reference in order, so are
+        // unique as long as there are no more of them than of the values referenced
+        Content::Unique(_) | Content::SameAs(_) => {}
+        Content::Number(number) if number.is_id() => {}
+        _ => {
+            *node = Content::Unique(UniqueContent {
+                algorithm: Default::default(),
+                content: Box::new(node.clone()),
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Makes the values of `columns` unique together in the collection `table_name`.
+fn add_unique_together(
+    namespace: &mut Namespace,
+    table_name: &str,
+    columns: Vec<String>,
+) -> Result<()> {
+    match namespace.get_collection_mut(table_name)? {
+        Content::Array(ArrayContent {
+            content: box Content::Object(object),
+            ..
+        }) => {
+            if !object.unique_together.contains(&columns) {
+                object.unique_together.push(columns);
+            }
+            Ok(())
+        }
+        _ => bail!("the collection {} is not an array of objects", table_name),
+    }
+}
+
 fn populate_namespace_values<T: SqlxDataSource>(
     namespace: &mut Namespace,
     table_names: &[String],
     datasource: &T,
+    sampling: Sampling,
+    inference: InferenceOptions,
 ) -> Result<()>
 where
     T: Sync,
@@ -226,30 +387,104 @@ where
     task::block_on(datasource.set_seed())?;
 
     for table_name in table_names {
-        let values = task::block_on(get_deterministic_samples(
-            datasource,
-            table_name.to_string(),
+        let query = datasource.get_deterministic_samples_query(table_name.to_string(), sampling)?;
+        task::block_on(merge_values(
+            namespace, table_name, datasource, &query, inference,
         ))?;
-        let json_values: Vec<Value> = values.into_iter().map(synth_val_to_json).collect();
-        namespace.try_update(OptionalMergeStrategy, table_name, &Value::from(json_values))?;
     }
 
     Ok(())
 }
 
-async fn get_deterministic_samples<T: SqlxDataSource>(
+/// Merges the rows returned by `query` into the collection `table_name` one at a time, so that
+/// not even a full scan of a table has to fit in memory.
+async fn merge_values<T: SqlxDataSource>(
+    namespace: &mut Namespace,
+    table_name: &str,
+    datasource: &T,
+    query: &str,
+    inference: InferenceOptions,
+) -> Result<()>
+where
+    for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
+    ValueWrapper: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
+{
+    let mut merger = CollectionMerger::new(namespace, table_name, inference)?;
+
+    let pool = datasource.get_pool();
+    let mut rows = datasource.query(query).fetch(&pool);
+    while let Some(row) = rows.try_next().await? {
+        let value = ValueWrapper::try_from(row).map_err(|e| {
+            anyhow!(
+                "Failed to convert to value wrapper from query results: {:?}",
+                e
+            )
+        })?;
+        merger.merge(&synth_val_to_json(value.0))?;
+    }
+
+    merger.finish()
+}
+
+/// Merges the values of a collection into its content one at a time, which is the same as
+/// merging them all as an array but does not need them all at once.
+pub(crate) struct CollectionMerger<'a> {
+    content: &'a mut Content,
+    length: &'a mut Content,
+    num_values: usize,
+    profiler: Profiler,
+    /// The path of the content of the collection
+    at: FieldRef,
+}
+
+impl<'a> CollectionMerger<'a> {
+    pub(crate) fn new(
+        namespace: &'a mut Namespace,
+        name: &str,
+        inference: InferenceOptions,
+    ) -> Result<Self> {
+        match namespace.get_collection_mut(name)? {
+            Content::Array(ArrayContent { content, length }) => Ok(Self {
+                content,
+                length,
+                num_values: 0,
+                profiler: Profiler::new(inference),
+                at: FieldRef::new(format!("{name}.content"))?,
+            }),
+            _ => bail!("the collection {} is not an array", name),
+        }
+    }
+
+    pub(crate) fn merge(&mut self, value: &Value) -> Result<()> {
+        self.num_values += 1;
+        self.profiler.observe(value);
+        OptionalMergeStrategy.try_merge(self.content, value)
+    }
+
+    /// Merges the number of values into the length of the collection, and refines the content
+    /// with what was learnt from all the values, like the shapes of strings.
+    pub(crate) fn finish(self) -> Result<()> {
+        self.profiler.apply_at(self.content, &self.at);
+        // Arrays whose length is given by a cardinality have no `length`
+        if self.length.is_null() {
+            return Ok(());
+        }
+        OptionalMergeStrategy.try_merge(self.length, &Value::from(self.num_values))
+    }
+}
+
+async fn get_values<T: SqlxDataSource>(
     datasource: &T,
-    table: String,
+    query: &str,
 ) -> Result<Vec<synth_core::Value>>
 where
     for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
     ValueWrapper: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
 {
-    let query = datasource.get_deterministic_samples_query(table);
     let pool = datasource.get_pool();
 
     datasource
-        .query(&query)
+        .query(query)
         .fetch_all(&pool)
         .await?
         .into_iter()
@@ -295,8 +530,9 @@ impl<T: SqlxDataSource> TryFrom<(&T, &ColumnInfo)> for FieldContentWrapper {
     fn try_from(column_meta: (&T, &ColumnInfo)) -> Result<Self> {
         let mut content = column_meta.0.decode_to_content(column_meta.1)?;
 
+        // The weights count the values and nulls of the rows merged into the content
         if column_meta.1.is_nullable {
-            content = content.into_nullable();
+            content = content.into_nullable_seen(0.0, 0.0);
         }
 
         Ok(FieldContentWrapper(content))
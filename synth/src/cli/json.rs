@@ -1,13 +1,14 @@
 use crate::cli::export::ExportStrategy;
 use crate::cli::import::ImportStrategy;
-use crate::sampler::SamplerOutput;
+use crate::sampler::SampleStream;
 
-use synth_core::schema::{MergeStrategy, OptionalMergeStrategy};
+use synth_core::schema::{InferenceOptions, MergeStrategy, OptionalMergeStrategy, Profiler};
 use synth_core::{Content, Namespace};
 
 use anyhow::{Context, Result};
 
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::io::Write;
 use std::path::PathBuf;
 
@@ -17,8 +18,12 @@ pub struct JsonFileExportStrategy {
 }
 
 impl ExportStrategy for JsonFileExportStrategy {
-    fn export(&self, _namespace: Namespace, sample: SamplerOutput) -> Result<()> {
-        std::fs::write(&self.from_file, sample.into_json().to_string())?;
+    fn export(&self, _namespace: Namespace, sample: SampleStream) -> Result<()> {
+        // A JSON document can only be written once every collection is complete.
+        std::fs::write(
+            &self.from_file,
+            sample.collect_output()?.into_json().to_string(),
+        )?;
 
         Ok(())
     }
@@ -30,8 +35,9 @@ pub struct JsonStdoutExportStrategy<W> {
 }
 
 impl<W: Write> ExportStrategy for JsonStdoutExportStrategy<W> {
-    fn export(&self, _namespace: Namespace, sample: SamplerOutput) -> Result<()> {
-        writeln!(self.writer.borrow_mut(), "{}", sample.into_json())
+    fn export(&self, _namespace: Namespace, sample: SampleStream) -> Result<()> {
+        let output = sample.collect_output()?;
+        writeln!(self.writer.borrow_mut(), "{}", output.into_json())
             .expect("failed to write json output");
 
         Ok(())
@@ -41,31 +47,45 @@ impl<W: Write> ExportStrategy for JsonStdoutExportStrategy<W> {
 #[derive(Clone, Debug)]
 pub struct JsonFileImportStrategy {
     pub from_file: PathBuf,
+    pub inference: InferenceOptions,
 }
 
 impl ImportStrategy for JsonFileImportStrategy {
     fn import(&self) -> Result<Namespace> {
-        import_json(serde_json::from_reader(std::fs::File::open(
+        import_json(
+            serde_json::from_reader(std::fs::File::open(&self.from_file)?)?,
+            self.inference,
+        )
+    }
+
+    fn import_data(&self) -> Result<BTreeMap<String, Vec<serde_json::Value>>> {
+        import_json_data(serde_json::from_reader(std::fs::File::open(
             &self.from_file,
         )?)?)
     }
 }
 
 #[derive(Clone, Debug)]
-pub struct JsonStdinImportStrategy;
+pub struct JsonStdinImportStrategy {
+    pub inference: InferenceOptions,
+}
 
 impl ImportStrategy for JsonStdinImportStrategy {
     fn import(&self) -> Result<Namespace> {
-        import_json(serde_json::from_reader(std::io::stdin())?)
+        import_json(serde_json::from_reader(std::io::stdin())?, self.inference)
+    }
+
+    fn import_data(&self) -> Result<BTreeMap<String, Vec<serde_json::Value>>> {
+        import_json_data(serde_json::from_reader(std::io::stdin())?)
     }
 }
 
-pub fn import_json(val: serde_json::Value) -> Result<Namespace> {
+pub fn import_json(val: serde_json::Value, inference: InferenceOptions) -> Result<Namespace> {
     match val {
         serde_json::Value::Object(object) => object
             .into_iter()
             .map(|(name, value)| {
-                collection_from_value(&value)
+                collection_from_value(&name, &value, inference)
                     .map(|content| (name.clone(), content))
                     .with_context(|| anyhow!("While importing the collection `{}`", name))
             })
@@ -77,12 +97,39 @@ pub fn import_json(val: serde_json::Value) -> Result<Namespace> {
     }
 }
 
-fn collection_from_value(value: &serde_json::Value) -> Result<Content> {
+fn import_json_data(val: serde_json::Value) -> Result<BTreeMap<String, Vec<serde_json::Value>>> {
+    match val {
+        serde_json::Value::Object(object) => object
+            .into_iter()
+            .map(|(name, value)| match value {
+                serde_json::Value::Array(values) => Ok((name, values)),
+                unacceptable => Err(anyhow!(
+                    "Was expecting a collection, instead got `{}`",
+                    unacceptable
+                ))
+                .with_context(|| anyhow!("While reading the collection `{}`", name)),
+            })
+            .collect(),
+        unacceptable => Err(anyhow!(
+            "Was expecting an object, instead got `{}`",
+            unacceptable
+        )),
+    }
+}
+
+fn collection_from_value(
+    name: &str,
+    value: &serde_json::Value,
+    inference: InferenceOptions,
+) -> Result<Content> {
     match value {
         serde_json::Value::Array(values) => {
             let fst = values.first().unwrap_or(&serde_json::Value::Null);
-            let mut as_content = Content::from_value_wrapped_in_array(fst);
+            let mut as_content = Content::from_value_wrapped_in_array_uncounted(fst);
             OptionalMergeStrategy.try_merge(&mut as_content, value)?;
+            let mut profiler = Profiler::new(inference);
+            profiler.observe(value);
+            profiler.apply_to_collection(name, &mut as_content);
             Ok(as_content)
         }
         unacceptable => Err(anyhow!(
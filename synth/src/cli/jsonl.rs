@@ -1,15 +1,15 @@
 use crate::cli::export::ExportStrategy;
 use crate::cli::import::ImportStrategy;
-use crate::sampler::SamplerOutput;
+use crate::sampler::{SampleStream, SamplerOutput};
 
 use synth_core::graph::{json::synth_val_to_json, Value};
-use synth_core::schema::{MergeStrategy, OptionalMergeStrategy};
+use synth_core::schema::{InferenceOptions, MergeStrategy, OptionalMergeStrategy, Profiler};
 use synth_core::{Content, Namespace};
 
 use anyhow::{Context, Result};
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::io::{BufRead, Write};
 use std::path::PathBuf;
 
@@ -20,14 +20,13 @@ pub struct JsonLinesFileExportStrategy {
 }
 
 impl ExportStrategy for JsonLinesFileExportStrategy {
-    fn export(&self, _namespace: Namespace, sample: SamplerOutput) -> Result<()> {
+    fn export(&self, _namespace: Namespace, sample: SampleStream) -> Result<()> {
         let mut f = std::io::BufWriter::new(std::fs::File::create(&self.from_file)?);
 
-        for val in json_lines_from_sampler_output(sample, &self.collection_field_name) {
+        write_json_lines(sample, &self.collection_field_name, |val| {
             f.write_all((val.to_string() + "\n").as_bytes())?;
-        }
-
-        Ok(())
+            Ok(())
+        })
     }
 }
 
@@ -38,19 +37,23 @@ pub struct JsonLinesStdoutExportStrategy<W> {
 }
 
 impl<W: Write> ExportStrategy for JsonLinesStdoutExportStrategy<W> {
-    fn export(&self, _namespace: Namespace, sample: SamplerOutput) -> Result<()> {
+    fn export(&self, _namespace: Namespace, sample: SampleStream) -> Result<()> {
         // TODO: Warn user if the collection field name would overwrite an existing field in a collection.
-        for line in json_lines_from_sampler_output(sample, &self.collection_field_name) {
+        write_json_lines(sample, &self.collection_field_name, |line| {
             writeln!(self.writer.borrow_mut(), "{line}").expect("failed to write jsonl line");
-        }
-
-        Ok(())
+            Ok(())
+        })
     }
 }
 
+/// The collection of the JSON lines without a collection field, like the ones generated with
+/// `--collection`.
+pub(crate) const UNLABELLED_COLLECTION: &str = "collection";
+
 pub struct JsonLinesFileImportStrategy {
     pub from_file: PathBuf,
     pub collection_field_name: String,
+    pub inference: InferenceOptions,
 }
 
 impl ImportStrategy for JsonLinesFileImportStrategy {
@@ -61,12 +64,24 @@ impl ImportStrategy for JsonLinesFileImportStrategy {
                 .map(|line| serde_json::from_str(&line.unwrap()))
                 .collect::<serde_json::Result<Vec<serde_json::Value>>>()?,
             &self.collection_field_name,
+            self.inference,
         )
     }
+
+    fn import_data(&self) -> Result<BTreeMap<String, Vec<serde_json::Value>>> {
+        Ok(group_json_lines(
+            std::io::BufReader::new(std::fs::File::open(&self.from_file)?)
+                .lines()
+                .map(|line| serde_json::from_str(&line.unwrap()))
+                .collect::<serde_json::Result<Vec<serde_json::Value>>>()?,
+            &self.collection_field_name,
+        ))
+    }
 }
 
 pub struct JsonLinesStdinImportStrategy {
     pub collection_field_name: String,
+    pub inference: InferenceOptions,
 }
 
 impl ImportStrategy for JsonLinesStdinImportStrategy {
@@ -78,102 +93,137 @@ impl ImportStrategy for JsonLinesStdinImportStrategy {
                 .map(|line| serde_json::from_str(&line.unwrap()))
                 .collect::<serde_json::Result<Vec<serde_json::Value>>>()?,
             &self.collection_field_name,
+            self.inference,
         )
     }
+
+    fn import_data(&self) -> Result<BTreeMap<String, Vec<serde_json::Value>>> {
+        Ok(group_json_lines(
+            std::io::stdin()
+                .lock()
+                .lines()
+                .map(|line| serde_json::from_str(&line.unwrap()))
+                .collect::<serde_json::Result<Vec<serde_json::Value>>>()?,
+            &self.collection_field_name,
+        ))
+    }
 }
 
 pub fn import_json_lines(
     json_lines: Vec<serde_json::Value>,
     collection_field_name: &str,
+    inference: InferenceOptions,
 ) -> Result<Namespace> {
-    let mut collection_names_to_values: HashMap<Option<String>, Vec<serde_json::Value>> =
-        HashMap::new();
+    group_json_lines(json_lines, collection_field_name)
+        .into_iter()
+        .map(|(name, values)| {
+            collection_from_values_jsonl(&name, values, inference)
+                .and_then(|content| Ok((name.parse()?, content)))
+                .with_context(|| anyhow!("While importing the collection '{}'", name))
+        })
+        .collect()
+}
+
+/// Groups JSON lines by the collection named in their `collection_field_name` field, which is
+/// removed from them. Lines without one belong to [`UNLABELLED_COLLECTION`].
+fn group_json_lines(
+    json_lines: Vec<serde_json::Value>,
+    collection_field_name: &str,
+) -> BTreeMap<String, Vec<serde_json::Value>> {
+    let mut collection_names_to_values: BTreeMap<String, Vec<serde_json::Value>> = BTreeMap::new();
 
     for mut value in json_lines {
-        match value {
+        let name = match value {
             serde_json::Value::Object(ref mut obj_content) => {
-                let entry = {
-                    if let Some(serde_json::Value::String(collection_name)) =
-                        obj_content.remove(collection_field_name)
-                    {
-                        collection_names_to_values.entry(Some(collection_name))
-                    } else {
-                        collection_names_to_values.entry(None)
-                    }
+                match obj_content.remove(collection_field_name) {
+                    Some(serde_json::Value::String(collection_name)) => Some(collection_name),
+                    _ => None,
                 }
-                .or_default();
+            }
+            _ => None,
+        };
+
+        collection_names_to_values
+            .entry(name.unwrap_or_else(|| UNLABELLED_COLLECTION.to_string()))
+            .or_default()
+            .push(value);
+    }
 
-                entry.push(value);
+    collection_names_to_values
+}
+
+/// Passes the lines of `sample` to `write`. The lines of a single collection are written as they
+/// are generated, but those of a namespace are grouped by collection, so they are only written once
+/// the whole namespace is sampled.
+fn write_json_lines<F>(
+    sample: SampleStream,
+    collection_field_name: &str,
+    mut write: F,
+) -> Result<()>
+where
+    F: FnMut(serde_json::Value) -> Result<()>,
+{
+    if sample.collection().is_some() {
+        for batch in sample {
+            let (collection, value) = batch?;
+            for line in json_lines_from_batch(collection, value, false, collection_field_name) {
+                write(line)?;
             }
-            _ => {
-                collection_names_to_values
-                    .entry(None)
-                    .or_default()
-                    .push(value);
+        }
+    } else if let SamplerOutput::Namespace(collections) = sample.collect_output()? {
+        for (collection, value) in collections {
+            for line in json_lines_from_batch(collection, value, true, collection_field_name) {
+                write(line)?;
             }
         }
     }
 
-    collection_names_to_values
-        .into_iter()
-        .map(|(name, values)| {
-            let name_or_default = name.unwrap_or_else(|| "collection".to_string());
-
-            collection_from_values_jsonl(values)
-                .and_then(|content| Ok((name_or_default.parse()?, content)))
-                .with_context(|| anyhow!("While importing the collection '{}'", name_or_default))
-        })
-        .collect()
+    Ok(())
 }
 
-fn json_lines_from_sampler_output(
-    output: SamplerOutput,
+fn json_lines_from_batch(
+    collection: String,
+    value: Value,
+    labelled: bool,
     collection_field_name: &str,
 ) -> Vec<serde_json::Value> {
-    match output {
-        SamplerOutput::Namespace(key_values) => {
-            let mut jsonl = Vec::new();
-
-            for (collection, value) in key_values {
-                let json_values = synth_val_to_jsonl(value);
-
-                let lines = json_values.into_iter().map(|val| {
-                    // When no specific collection to generate data with is specified with --collection,
-                    // each output line is labelled to indicate which collection in the namespace it was
-                    // generated from.
-
-                    match val {
-                        serde_json::Value::Object(mut obj_values) => {
-                            // If the collection generates an object, then the collection name is saved directly as
-                            // a field of the object.
-
-                            obj_values.insert(
-                                collection_field_name.to_string(),
-                                serde_json::Value::String(collection.clone()),
-                            );
-
-                            serde_json::Value::Object(obj_values)
-                        }
-                        non_obj_val => {
-                            // If the collection does not generate a object, then the output value is an object with
-                            // the collection specified as a field, and the generated non-object data as another.
-
-                            serde_json::json!({
-                                collection_field_name: collection,
-                                "data": non_obj_val
-                            })
-                        }
-                    }
-                });
-
-                jsonl.extend(lines);
-            }
-
-            jsonl
-        }
+    let json_values = synth_val_to_jsonl(value);
 
-        SamplerOutput::Collection(_, value) => synth_val_to_jsonl(value),
+    if !labelled {
+        return json_values;
     }
+
+    json_values
+        .into_iter()
+        .map(|val| {
+            // When no specific collection to generate data with is specified with --collection,
+            // each output line is labelled to indicate which collection in the namespace it was
+            // generated from.
+
+            match val {
+                serde_json::Value::Object(mut obj_values) => {
+                    // If the collection generates an object, then the collection name is saved directly as
+                    // a field of the object.
+
+                    obj_values.insert(
+                        collection_field_name.to_string(),
+                        serde_json::Value::String(collection.clone()),
+                    );
+
+                    serde_json::Value::Object(obj_values)
+                }
+                non_obj_val => {
+                    // If the collection does not generate a object, then the output value is an object with
+                    // the collection specified as a field, and the generated non-object data as another.
+
+                    serde_json::json!({
+                        collection_field_name: collection,
+                        "data": non_obj_val
+                    })
+                }
+            }
+        })
+        .collect()
 }
 
 fn synth_val_to_jsonl(val: Value) -> Vec<serde_json::Value> {
@@ -185,9 +235,72 @@ fn synth_val_to_jsonl(val: Value) -> Vec<serde_json::Value> {
 
 /// Create a collection (`Content`) from a set of Serde JSON values that were all generated originally from the same
 /// collection.
-fn collection_from_values_jsonl(values: Vec<serde_json::Value>) -> Result<Content> {
+fn collection_from_values_jsonl(
+    name: &str,
+    values: Vec<serde_json::Value>,
+    inference: InferenceOptions,
+) -> Result<Content> {
     let fst = values.first().unwrap_or(&serde_json::Value::Null);
-    let mut as_content = Content::from_value_wrapped_in_array(fst);
-    OptionalMergeStrategy.try_merge(&mut as_content, &serde_json::Value::Array(values))?;
+    let mut as_content = Content::from_value_wrapped_in_array_uncounted(fst);
+    let values = serde_json::Value::Array(values);
+    OptionalMergeStrategy.try_merge(&mut as_content, &values)?;
+    let mut profiler = Profiler::new(inference);
+    profiler.observe(&values);
+    profiler.apply_to_collection(name, &mut as_content);
     Ok(as_content)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::{SampleSize, Sampler};
+
+    #[test]
+    fn namespace_lines_are_grouped_by_collection() {
+        let namespace = serde_json::from_value::<Content>(serde_json::json!({
+            "type": "object",
+            "users": {
+                "type": "array",
+                "length": 2,
+                "content": {
+                    "type": "object",
+                    "id": { "type": "number", "subtype": "u64", "id": {} }
+                }
+            },
+            "orders": {
+                "type": "array",
+                "length": 1,
+                "content": {
+                    "type": "object",
+                    "user_id": { "type": "same_as", "ref": "users.content.id" }
+                }
+            }
+        }))
+        .unwrap()
+        .into_namespace()
+        .unwrap();
+
+        let sample = Sampler::new(&namespace, &SampleSize::Total(6))
+            .unwrap()
+            .sample_seeded(None, 0);
+        let export = JsonLinesStdoutExportStrategy {
+            collection_field_name: "type".to_string(),
+            writer: RefCell::new(Vec::new()),
+        };
+        export.export(namespace, sample).unwrap();
+
+        // Collections referenced by others come first
+        let collections: Vec<_> = String::from_utf8(export.writer.into_inner())
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let value: serde_json::Value = serde_json::from_str(line).unwrap();
+                value["type"].as_str().unwrap().to_string()
+            })
+            .collect();
+        assert_eq!(
+            collections,
+            ["users", "users", "users", "users", "orders", "orders"]
+        );
+    }
+}
@@ -7,12 +7,16 @@ mod json;
 mod jsonl;
 mod mongo;
 mod mysql;
+mod parquet;
 mod postgres;
+mod sql;
+mod sqlite;
 mod store;
 
 use crate::cli::import::ImportStrategy;
 use crate::cli::store::Store;
-use crate::sampler::Sampler;
+use crate::datasource::sampling::{Sampling, SamplingStrategy};
+use crate::sampler::{ParallelSampler, SampleSize, Sampler};
 use crate::version::print_version_message;
 
 use anyhow::{Context, Result};
@@ -23,6 +27,7 @@ use std::io::Write;
 use std::path::PathBuf;
 use structopt::clap::AppSettings;
 use structopt::StructOpt;
+use synth_core::schema::InferenceOptions;
 use synth_core::DataSourceParams;
 use uriparse::URI;
 
@@ -82,6 +87,7 @@ impl<'w> Cli {
         match args {
             Args::Generate(cmd) => self.generate(cmd, writer),
             Args::Import(cmd) => self.import(cmd),
+            Args::Validate(cmd) => self.validate(cmd, writer),
             #[cfg(feature = "telemetry")]
             Args::Telemetry(cmd) => self.telemetry(cmd, writer),
             Args::Version => {
@@ -119,12 +125,21 @@ impl<'w> Cli {
         // TODO: If ns exists and no collection: break
         // If collection and ns exists and collection exists: break
 
-        let import_strategy: Box<dyn ImportStrategy> = DataSourceParams {
+        let params = DataSourceParams {
             uri: URI::try_from(cmd.from.as_str())
                 .with_context(|| format!("Parsing import URI '{}'", cmd.from))?,
             schema: cmd.schema,
-        }
-        .try_into()?;
+        };
+        let sampling = Sampling {
+            size: cmd.sample_size,
+            strategy: cmd.sample_strategy,
+        };
+        let inference = InferenceOptions {
+            fit_distributions: cmd.fit_distributions,
+            histograms: cmd.histograms,
+            correlations: cmd.correlations,
+        };
+        let import_strategy: Box<dyn ImportStrategy> = (params, sampling, inference).try_into()?;
 
         if let Some(collection) = cmd.collection {
             if self.store.collection_exists(&cmd.namespace, &collection) {
@@ -164,6 +179,64 @@ impl<'w> Cli {
         }
     }
 
+    fn validate<W: Write>(&self, cmd: ValidateCommand, mut writer: W) -> Result<()> {
+        let namespace = self.store.get_ns(cmd.namespace.clone()).context(format!(
+            "Unable to open the namespace \"{}\"",
+            cmd.namespace
+                .to_str()
+                .expect("The provided namespace is not a valid UTF-8 string")
+        ))?;
+
+        let params = DataSourceParams {
+            uri: URI::try_from(cmd.from.as_str())
+                .with_context(|| format!("Parsing validation URI '{}'", cmd.from))?,
+            schema: cmd.schema,
+        };
+        let is_json_lines = params.uri.scheme().as_str().eq_ignore_ascii_case("jsonl");
+        // Validation reads every row, whatever the sampling
+        let import_strategy: Box<dyn ImportStrategy> =
+            (params, Sampling::default(), InferenceOptions::default()).try_into()?;
+
+        let mut data = import_strategy.import_data()?;
+        if let Some(collection) = &cmd.collection {
+            // Lines without a collection field are the ones `generate --collection` writes
+            if is_json_lines {
+                if let Some(unlabelled) = data.remove(jsonl::UNLABELLED_COLLECTION) {
+                    data.entry(collection.clone())
+                        .or_default()
+                        .extend(unlabelled);
+                }
+            }
+            data.retain(|name, _| name == collection);
+            if data.is_empty() {
+                return Err(anyhow!("Could not find collection '{}'.", collection));
+            }
+        }
+
+        let mut num_violations = 0;
+        for (name, values) in data {
+            if !namespace.collection_exists(&name) {
+                writeln!(writer, "{name}: not a collection of the namespace")?;
+                num_violations += 1;
+                continue;
+            }
+
+            for violation in namespace.violations(&name, &values)? {
+                writeln!(writer, "{violation}")?;
+                num_violations += 1;
+            }
+        }
+
+        if num_violations > 0 {
+            Err(anyhow!(
+                "Found {} values that do not conform to the namespace",
+                num_violations
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
     fn generate<W: Write + 'w>(&self, cmd: GenerateCommand, writer: W) -> Result<()> {
         let mut namespace = self.store.get_ns(cmd.namespace.clone()).context(format!(
             "Unable to open the namespace \"{}\"",
@@ -205,8 +278,12 @@ impl<'w> Cli {
         }
 
         let seed = Self::derive_seed(cmd.random, cmd.seed)?;
-        let sample =
-            Sampler::try_from(&namespace)?.sample_seeded(cmd.collection.clone(), cmd.size, seed)?;
+        let sample = if cmd.threads == 1 {
+            Sampler::new(&namespace, &cmd.size)?.sample_seeded(cmd.collection.clone(), seed)
+        } else {
+            ParallelSampler::new(&namespace, &cmd.size, cmd.threads)?
+                .sample_seeded(cmd.collection.clone(), seed)?
+        };
 
         export_strategy
             .export(namespace, sample)
@@ -230,6 +307,8 @@ pub enum Args {
     Generate(GenerateCommand),
     #[structopt(about = "Import data from an external source")]
     Import(ImportCommand),
+    #[structopt(about = "Check that existing data conforms to a namespace")]
+    Validate(ValidateCommand),
     #[cfg(feature = "telemetry")]
     #[structopt(about = "Toggle anonymous usage data collection")]
     Telemetry(TelemetryCommand),
@@ -264,11 +343,15 @@ pub struct GenerateCommand {
     )]
     #[serde(skip)]
     pub scenario: Option<String>,
-    #[structopt(long, help = "the number of samples", default_value = "1")]
-    pub size: usize,
     #[structopt(
         long,
-        help = "The URI into which data will be generated. Can be a file-based URI scheme to output data to the filesystem or stdout ('json:', 'jsonl:' and 'csv:' allow outputting JSON, JSON Lines and CSV data respectively) or can be a database URI to write data directly to some database (supports Postgres, MongoDB, and MySQL). Defaults to writing JSON data to stdout. [example: jsonl:/tmp/generation_output]",
+        help = "the number of samples, either in total (e.g. 100) or for each collection (e.g. users=1000,orders=50000)",
+        default_value = "1"
+    )]
+    pub size: SampleSize,
+    #[structopt(
+        long,
+        help = "The URI into which data will be generated. Can be a file-based URI scheme to output data to the filesystem or stdout ('json:', 'jsonl:', 'csv:' and 'sql:' allow outputting JSON, JSON Lines, CSV and SQL INSERT statements respectively, and 'parquet:' outputs Parquet files to a directory) or can be a database URI to write data directly to some database (supports Postgres, MySQL, MariaDB, MongoDB and SQLite with 'sqlite://'). Defaults to writing JSON data to stdout. [example: jsonl:/tmp/generation_output]",
         default_value = "json:"
     )]
     #[serde(skip)]
@@ -283,6 +366,12 @@ pub struct GenerateCommand {
         help = "generation will use a random seed - this cannot be used with --seed"
     )]
     pub random: bool,
+    #[structopt(
+        long,
+        help = "the number of threads to generate data on. The output for a given seed depends on the number of threads",
+        default_value = "1"
+    )]
+    pub threads: usize,
     #[structopt(
         long,
         help = "(Postgres only) Specify the schema into which to generate. Defaults to 'public'."
@@ -307,7 +396,7 @@ pub struct ImportCommand {
     pub collection: Option<String>,
     #[structopt(
         long,
-        help = "The source URI from which to import data. Can be a file-based URI scheme to read data from a file or stdin ('json:', 'jsonl:' and 'csv:' allow reading JSON, JSON Lines and CSV data respectively) or can be a database URI to read data directly from some database (supports Postgres, MongoDB, and MySQL). Defaults to reading JSON data from stdin. [example: jsonl:/tmp/test_data_input]",
+        help = "The source URI from which to import data. Can be a file-based URI scheme to read data from a file or stdin ('json:', 'jsonl:' and 'csv:' allow reading JSON, JSON Lines and CSV data respectively, and 'parquet:' reads Parquet files from a file or directory) or can be a database URI to read data directly from some database (supports Postgres, MySQL, MariaDB, MongoDB and SQLite with 'sqlite://'). Defaults to reading JSON data from stdin. [example: jsonl:/tmp/test_data_input]",
         default_value = "json:"
     )]
     #[serde(skip)]
@@ -318,6 +407,59 @@ pub struct ImportCommand {
     )]
     #[serde(skip)]
     pub schema: Option<String>,
+    #[structopt(
+        long,
+        help = "(Databases only) The number of rows of each table or collection from which to infer content. Ignored by the 'full' sampling strategy.",
+        default_value = "10"
+    )]
+    pub sample_size: usize,
+    #[structopt(
+        long,
+        help = "(Databases only) How the rows from which to infer content are picked: 'random', 'first', 'tablesample' (Postgres and MongoDB only) or 'full' to read every row.",
+        default_value = "random"
+    )]
+    pub sample_strategy: SamplingStrategy,
+    #[structopt(
+        long,
+        help = "Fit a distribution (normal, log-normal, exponential or Poisson) to the values of each number, instead of generating them uniformly between the smallest and largest value seen."
+    )]
+    pub fit_distributions: bool,
+    #[structopt(
+        long,
+        help = "Generate numbers and date times from a histogram of the values imported. With --fit-distributions, only numbers no distribution fits get a histogram."
+    )]
+    pub histograms: bool,
+    #[structopt(
+        long,
+        help = "Generate each field with few values which depends on another such field with a switch on the value of that field, so that the pairs of values imported are kept."
+    )]
+    pub correlations: bool,
+}
+
+#[derive(StructOpt, Serialize)]
+pub struct ValidateCommand {
+    #[structopt(
+        help = "The namespace directory from which to read schema files",
+        parse(from_os_str)
+    )]
+    #[serde(skip)]
+    pub namespace: PathBuf,
+    #[structopt(long, help = "The specific collection to validate")]
+    #[serde(skip)]
+    pub collection: Option<String>,
+    #[structopt(
+        long,
+        help = "The source URI from which to read the data to validate. Accepts the same URIs as `synth import --from`. Defaults to reading JSON data from stdin. [example: jsonl:/tmp/test_data_input]",
+        default_value = "json:"
+    )]
+    #[serde(skip)]
+    pub from: String,
+    #[structopt(
+        long,
+        help = "(Postgres only) Specify the schema from which to read data. Defaults to 'public'."
+    )]
+    #[serde(skip)]
+    pub schema: Option<String>,
 }
 
 #[cfg(feature = "telemetry")]
@@ -342,4 +484,42 @@ pub mod tests {
         assert!(Cli::derive_seed(true, Some(5)).is_err());
         assert!(Cli::derive_seed(true, None).is_ok());
     }
+
+    #[test]
+    fn validate_unlabelled_json_lines_of_collection() {
+        let dir = tempfile::tempdir().unwrap();
+        let namespace = dir.path().join("ns");
+        std::fs::create_dir(&namespace).unwrap();
+        std::fs::write(
+            namespace.join("users.json"),
+            serde_json::json!({
+                "type": "array",
+                "length": 2,
+                "content": {
+                    "type": "object",
+                    "id": { "type": "number", "subtype": "u64", "range": { "low": 1, "high": 10 } }
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let validate = |lines: &str| {
+            let from = dir.path().join("users.jsonl");
+            std::fs::write(&from, lines).unwrap();
+            Cli::new().unwrap().validate(
+                ValidateCommand {
+                    namespace: namespace.clone(),
+                    collection: Some("users".to_string()),
+                    from: format!("jsonl:{}", from.display()),
+                    schema: None,
+                },
+                Vec::new(),
+            )
+        };
+
+        assert!(validate("{\"id\":1}\n{\"id\":2}\n").is_ok());
+        assert!(validate("{\"id\":1}\n{\"id\":20}\n").is_err());
+        assert!(validate("{\"id\":1,\"type\":\"users\"}\n{\"id\":2}\n").is_ok());
+    }
 }
@@ -1,9 +1,11 @@
 use crate::cli::export::ExportStrategy;
 use crate::cli::import::ImportStrategy;
-use crate::sampler::SamplerOutput;
+use crate::cli::import_utils::CollectionMerger;
+use crate::datasource::sampling::{Sampling, SamplingStrategy};
+use crate::sampler::SampleStream;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
-use mongodb::bson::Bson;
+use mongodb::bson::{doc, Bson};
 use mongodb::options::FindOptions;
 use mongodb::{bson::Document, options::ClientOptions, sync::Client};
 use std::collections::BTreeMap;
@@ -12,8 +14,8 @@ use synth_core::graph::prelude::number_content::I64;
 use synth_core::graph::prelude::{ChronoValue, Number, NumberContent, ObjectContent, RangeStep};
 use synth_core::schema::number_content::F64;
 use synth_core::schema::{
-    ArrayContent, BoolContent, Categorical, ChronoValueType, DateTimeContent, RegexContent,
-    StringContent,
+    ArrayContent, BoolContent, Categorical, ChronoValueType, DateTimeContent, InferenceOptions,
+    RegexContent, StringContent,
 };
 use synth_core::{Content, Namespace, Value};
 
@@ -25,6 +27,8 @@ pub struct MongoExportStrategy {
 #[derive(Clone, Debug)]
 pub struct MongoImportStrategy {
     pub uri_string: String,
+    pub sampling: Sampling,
+    pub inference: InferenceOptions,
 }
 
 impl ImportStrategy for MongoImportStrategy {
@@ -59,29 +63,62 @@ impl ImportStrategy for MongoImportStrategy {
             }
         }
 
-        // 2: Run an ingest step with 10 documents
+        // 2: Run an ingest step with the sampled documents
         for collection_name in database.list_collection_names(None)? {
-            let collection = database.collection(&collection_name);
+            if !namespace.collection_exists(&collection_name) {
+                continue;
+            }
 
-            // This may be useful later
-            // let count = collection.estimated_document_count(None)?;
+            let collection = database.collection::<Document>(&collection_name);
+            let size = self.sampling.size as i64;
+
+            let documents = match self.sampling.strategy {
+                SamplingStrategy::First => {
+                    let mut find_options = FindOptions::default();
+                    find_options.limit = Some(size);
+                    collection.find(None, find_options)?
+                }
+                // `$sample` cannot be seeded, so its documents differ from one import to the next
+                SamplingStrategy::Random | SamplingStrategy::TableSample => {
+                    collection.aggregate([doc! {"$sample": {"size": size}}], None)?
+                }
+                SamplingStrategy::Full => collection.find(None, None)?,
+            };
+
+            let mut merger =
+                CollectionMerger::new(&mut namespace, &collection_name, self.inference)?;
+            for document in documents {
+                let mut document = document?;
+                document.remove("_id");
+                merger.merge(&serde_json::to_value(document)?)?;
+            }
+            merger.finish()?;
+        }
 
-            let mut find_options = FindOptions::default();
-            find_options.limit = Some(10);
+        Ok(namespace)
+    }
 
-            let mut random_sample: Vec<Document> = collection
-                .find(None, find_options)?
-                .collect::<Result<Vec<Document>, _>>()?;
+    fn import_data(&self) -> Result<BTreeMap<String, Vec<serde_json::Value>>> {
+        let client = Client::with_options(ClientOptions::parse(&self.uri_string)?)?;
+        let database = client.database(parse_db_name(&self.uri_string)?);
 
-            random_sample.iter_mut().for_each(|doc| {
-                doc.remove("_id");
-            });
+        let mut data = BTreeMap::new();
 
-            namespace
-                .default_try_update(&collection_name, &serde_json::to_value(random_sample)?)?;
+        for collection_name in database.list_collection_names(None)? {
+            let documents = database
+                .collection::<Document>(&collection_name)
+                .find(None, None)?
+                .map(|doc| {
+                    let mut doc = doc?;
+                    doc.remove("_id");
+                    Ok(serde_json::to_value(doc)?)
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            data.insert(collection_name, documents);
         }
 
-        Ok(namespace)
+        Ok(data)
     }
 }
 
@@ -143,6 +180,7 @@ fn bson_to_content(bson: &Bson) -> Content {
             type_: ChronoValueType::DateTime,
             begin: None,
             end: None,
+            histogram: None,
         }),
         // There should be a more explicit enumeration here, but we don't support
         // all the required types here.
@@ -151,20 +189,13 @@ fn bson_to_content(bson: &Bson) -> Content {
 }
 
 impl ExportStrategy for MongoExportStrategy {
-    fn export(&self, _namespace: Namespace, sample: SamplerOutput) -> Result<()> {
+    fn export(&self, _namespace: Namespace, sample: SampleStream) -> Result<()> {
         let mut client = Client::with_uri_str(&self.uri_string)?;
 
-        match sample {
-            SamplerOutput::Collection(name, value) => {
-                self.insert_data(name.as_ref(), value, &mut client)
-            }
-            SamplerOutput::Namespace(namespace) => {
-                for (name, value) in namespace {
-                    self.insert_data(name.as_ref(), value.clone(), &mut client)?;
-                }
-                Ok(())
-            }
-        }?;
+        for batch in sample {
+            let (name, value) = batch?;
+            self.insert_data(name.as_ref(), value, &mut client)?;
+        }
 
         Ok(())
     }
@@ -187,13 +218,16 @@ impl MongoExportStrategy {
         };
 
         for value in values {
-            docs.push(match value_to_bson(value.clone()) {
+            docs.push(match value_to_bson(value) {
                 Bson::Document(doc) => doc,
                 _ => bail!("invalid bson document"),
             });
         }
 
         let n_values = docs.len();
+        if n_values == 0 {
+            return Ok(());
+        }
 
         client
             .database(db_name)
@@ -1,21 +1,30 @@
 use crate::cli::export::{create_and_insert_values, ExportStrategy};
 use crate::cli::import::ImportStrategy;
-use crate::cli::import_utils::build_namespace_import;
+use crate::cli::import_utils::{build_namespace_data, build_namespace_import};
+use crate::datasource::ddl::{create_tables, SqlDialect};
 use crate::datasource::mysql_datasource::MySqlDataSource;
+use crate::datasource::sampling::Sampling;
 use crate::datasource::DataSource;
-use crate::sampler::SamplerOutput;
+use crate::sampler::SampleStream;
 use anyhow::Result;
-use synth_core::schema::Namespace;
+use async_std::task;
+use std::collections::BTreeMap;
+use synth_core::schema::{InferenceOptions, Namespace};
 
 #[derive(Clone, Debug)]
 pub struct MySqlExportStrategy {
     pub uri_string: String,
+    pub create_tables: bool,
 }
 
 impl ExportStrategy for MySqlExportStrategy {
-    fn export(&self, _namespace: Namespace, sample: SamplerOutput) -> Result<()> {
+    fn export(&self, namespace: Namespace, sample: SampleStream) -> Result<()> {
         let datasource = MySqlDataSource::new(&self.uri_string)?;
 
+        if self.create_tables {
+            task::block_on(create_tables(&datasource, &namespace, SqlDialect::MySql))?;
+        }
+
         create_and_insert_values(sample, &datasource)
     }
 }
@@ -23,12 +32,20 @@ impl ExportStrategy for MySqlExportStrategy {
 #[derive(Clone, Debug)]
 pub struct MySqlImportStrategy {
     pub uri_string: String,
+    pub sampling: Sampling,
+    pub inference: InferenceOptions,
 }
 
 impl ImportStrategy for MySqlImportStrategy {
     fn import(&self) -> Result<Namespace> {
         let datasource = MySqlDataSource::new(&self.uri_string)?;
 
-        build_namespace_import(&datasource)
+        build_namespace_import(&datasource, self.sampling, self.inference)
+    }
+
+    fn import_data(&self) -> Result<BTreeMap<String, Vec<serde_json::Value>>> {
+        let datasource = MySqlDataSource::new(&self.uri_string)?;
+
+        build_namespace_data(&datasource)
     }
 }
@@ -0,0 +1,536 @@
+use crate::cli::import_utils::CollectionMerger;
+
+use anyhow::{Context, Result};
+use arrow_array::cast::AsArray;
+use arrow_array::types::ArrowTemporalType;
+use arrow_array::types::{
+    Date32Type, Date64Type, Decimal128Type, Float32Type, Float64Type, Int16Type, Int32Type,
+    Int64Type, Int8Type, Time32MillisecondType, Time32SecondType, Time64MicrosecondType,
+    Time64NanosecondType, TimestampMicrosecondType, TimestampMillisecondType,
+    TimestampNanosecondType, TimestampSecondType, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+use arrow_array::{Array, ArrowPrimitiveType, PrimitiveArray};
+use arrow_schema::{DataType, Field, TimeUnit};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::basic::{Encoding, PageType};
+use parquet::file::metadata::ParquetMetaData;
+use serde_json::{Map, Value};
+use synth_core::schema::number_content::{F32, F64, I16, I32, I64, U32, U64};
+use synth_core::schema::{
+    ArrayContent, BoolContent, Categorical, ChronoValueType, DateTimeContent, InferenceOptions,
+    NumberContent, ObjectContent, RangeStep, StringContent,
+};
+use synth_core::{Content, Namespace};
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::path::Path;
+
+/// How many rows, from the start of each file, are sampled to refine the imported collections.
+const SAMPLE_SIZE: usize = 1000;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const TIME_FORMAT: &str = "%H:%M:%S";
+const NAIVE_DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+const DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%z";
+
+/// Imports the Parquet file at `path` as the collection `name` of `namespace`. The collection's
+/// content is derived from the schema of the file, then refined with the first rows of the file,
+/// from which it is inferred as asked by `inference`.
+pub(super) fn import_file(
+    namespace: &mut Namespace,
+    name: &str,
+    path: &Path,
+    inference: InferenceOptions,
+) -> Result<()> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)?;
+
+    let dictionary_encoded = dictionary_encoded_columns(builder.metadata());
+    let fields = builder
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| {
+            let content = content_from_field(field, field.name(), &dictionary_encoded)
+                .with_context(|| format!("in column '{}'", field.name()))?;
+            Ok((field.name().clone(), content))
+        })
+        .collect::<Result<BTreeMap<_, _>>>()?;
+
+    namespace.put_collection(
+        name.to_string(),
+        Content::Array(ArrayContent {
+            length: Box::new(Content::Number(NumberContent::U64(U64::Range(
+                RangeStep::new(1, 2, 1),
+            )))),
+            content: Box::new(Content::Object(ObjectContent {
+                fields,
+                ..Default::default()
+            })),
+        }),
+    )?;
+
+    let mut merger = CollectionMerger::new(namespace, name, inference)?;
+    for row in read_rows(path, Some(SAMPLE_SIZE))? {
+        merger.merge(&row)?;
+    }
+    merger.finish()
+}
+
+/// Reads the rows of the Parquet file at `path` as JSON objects, only the first `limit` of them if
+/// one is given.
+pub(super) fn read_rows(path: &Path, limit: Option<usize>) -> Result<Vec<Value>> {
+    let mut builder = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)?;
+    if let Some(limit) = limit {
+        builder = builder.with_limit(limit);
+    }
+
+    let mut rows = Vec::new();
+    for batch in builder.build()? {
+        let batch = batch?;
+        let mut batch_rows = vec![Map::new(); batch.num_rows()];
+        for (field, column) in batch.schema().fields().iter().zip(batch.columns()) {
+            let values = json_values(column.as_ref())
+                .with_context(|| format!("in column '{}'", field.name()))?;
+            for (row, value) in batch_rows.iter_mut().zip(values) {
+                row.insert(field.name().clone(), value);
+            }
+        }
+        rows.extend(batch_rows.into_iter().map(Value::Object));
+    }
+
+    Ok(rows)
+}
+
+/// The dotted paths of the columns whose data pages are all dictionary encoded in every row
+/// group. Writers only record this when they write page encoding statistics.
+fn dictionary_encoded_columns(metadata: &ParquetMetaData) -> HashSet<String> {
+    let mut columns = HashSet::new();
+    let mut plain = HashSet::new();
+
+    for row_group in metadata.row_groups() {
+        for column in row_group.columns() {
+            let path = column.column_path().string();
+            let data_pages: Vec<_> = column
+                .page_encoding_stats()
+                .into_iter()
+                .flatten()
+                .filter(|stats| {
+                    matches!(
+                        stats.page_type,
+                        PageType::DATA_PAGE | PageType::DATA_PAGE_V2
+                    )
+                })
+                .collect();
+
+            let is_dictionary_encoded = !data_pages.is_empty()
+                && data_pages.iter().all(|stats| {
+                    matches!(
+                        stats.encoding,
+                        Encoding::PLAIN_DICTIONARY | Encoding::RLE_DICTIONARY
+                    )
+                });
+
+            if is_dictionary_encoded {
+                columns.insert(path);
+            } else {
+                plain.insert(path);
+            }
+        }
+    }
+
+    &columns - &plain
+}
+
+fn content_from_field(
+    field: &Field,
+    path: &str,
+    dictionary_encoded: &HashSet<String>,
+) -> Result<Content> {
+    let content = match field.data_type() {
+        DataType::Null => Content::null(),
+        DataType::Boolean => Content::Bool(BoolContent::default()),
+        DataType::Int8 | DataType::Int16 => number(NumberContent::I16(I16::Range(range()))),
+        DataType::Int32 => number(NumberContent::I32(I32::Range(range()))),
+        DataType::Int64 => number(NumberContent::I64(I64::Range(range()))),
+        DataType::UInt8 | DataType::UInt16 | DataType::UInt32 => {
+            number(NumberContent::U32(U32::Range(range())))
+        }
+        DataType::UInt64 => number(NumberContent::U64(U64::Range(range()))),
+        DataType::Float32 => number(NumberContent::F32(F32::Range(range()))),
+        DataType::Float64 | DataType::Decimal128(_, _) => {
+            number(NumberContent::F64(F64::Range(range())))
+        }
+        DataType::Utf8 | DataType::LargeUtf8 if dictionary_encoded.contains(path) => {
+            Content::String(StringContent::Categorical(Categorical::default()))
+        }
+        DataType::Utf8 | DataType::LargeUtf8 => Content::String(StringContent::default()),
+        DataType::Dictionary(_, values)
+            if matches!(**values, DataType::Utf8 | DataType::LargeUtf8) =>
+        {
+            Content::String(StringContent::Categorical(Categorical::default()))
+        }
+        DataType::Dictionary(_, values) => {
+            let values = Field::new(field.name(), (**values).clone(), field.is_nullable());
+            return content_from_field(&values, path, dictionary_encoded);
+        }
+        DataType::Date32 | DataType::Date64 => date_time(DATE_FORMAT, ChronoValueType::NaiveDate),
+        DataType::Time32(_) | DataType::Time64(_) => {
+            date_time(TIME_FORMAT, ChronoValueType::NaiveTime)
+        }
+        DataType::Timestamp(_, None) => {
+            date_time(NAIVE_DATE_TIME_FORMAT, ChronoValueType::NaiveDateTime)
+        }
+        DataType::Timestamp(_, Some(_)) => date_time(DATE_TIME_FORMAT, ChronoValueType::DateTime),
+        DataType::List(item) | DataType::LargeList(item) | DataType::FixedSizeList(item, _) => {
+            // Items of lists are stored in the `list` group of the column
+            let path = format!("{}.list.{}", path, item.name());
+            Content::Array(ArrayContent {
+                length: Box::new(number(NumberContent::U64(U64::Range(range())))),
+                content: Box::new(content_from_field(item, &path, dictionary_encoded)?),
+            })
+        }
+        DataType::Struct(fields) => Content::Object(ObjectContent {
+            fields: fields
+                .iter()
+                .map(|child| {
+                    let path = format!("{}.{}", path, child.name());
+                    let content = content_from_field(child, &path, dictionary_encoded)
+                        .with_context(|| format!("in field '{}'", child.name()))?;
+                    Ok((child.name().clone(), content))
+                })
+                .collect::<Result<_>>()?,
+            ..Default::default()
+        }),
+        other => bail!("We haven't implemented a converter for {}", other),
+    };
+
+    // The weights count the values and nulls of the rows merged into the content
+    if field.is_nullable() {
+        Ok(content.into_nullable_seen(0.0, 0.0))
+    } else {
+        Ok(content)
+    }
+}
+
+/// Sampled bounds are values that were actually seen, so both ends are included.
+fn range<N>() -> RangeStep<N> {
+    RangeStep {
+        include_high: true,
+        ..Default::default()
+    }
+}
+
+fn number(number_content: NumberContent) -> Content {
+    Content::Number(number_content)
+}
+
+fn date_time(format: &str, type_: ChronoValueType) -> Content {
+    Content::DateTime(DateTimeContent {
+        format: format.to_string(),
+        type_,
+        begin: None,
+        end: None,
+        histogram: None,
+    })
+}
+
+/// Converts `array` to JSON values that merge into the content [`content_from_field`] derives for
+/// its type.
+fn json_values(array: &dyn Array) -> Result<Vec<Value>> {
+    let values = match array.data_type() {
+        DataType::Null => vec![Value::Null; array.len()],
+        DataType::Boolean => {
+            let array = array.as_boolean();
+            collect(array, |i| Value::Bool(array.value(i)))
+        }
+        DataType::Int8 => primitive::<Int8Type>(array, Value::from),
+        DataType::Int16 => primitive::<Int16Type>(array, Value::from),
+        DataType::Int32 => primitive::<Int32Type>(array, Value::from),
+        DataType::Int64 => primitive::<Int64Type>(array, Value::from),
+        DataType::UInt8 => primitive::<UInt8Type>(array, Value::from),
+        DataType::UInt16 => primitive::<UInt16Type>(array, Value::from),
+        DataType::UInt32 => primitive::<UInt32Type>(array, Value::from),
+        DataType::UInt64 => primitive::<UInt64Type>(array, Value::from),
+        DataType::Float32 => primitive::<Float32Type>(array, |f| Value::from(f as f64)),
+        DataType::Float64 => primitive::<Float64Type>(array, Value::from),
+        DataType::Decimal128(_, scale) => {
+            let divisor = 10f64.powi(i32::from(*scale));
+            primitive::<Decimal128Type>(array, |d| Value::from(d as f64 / divisor))
+        }
+        DataType::Utf8 => {
+            let array = array.as_string::<i32>();
+            collect(array, |i| Value::from(array.value(i)))
+        }
+        DataType::LargeUtf8 => {
+            let array = array.as_string::<i64>();
+            collect(array, |i| Value::from(array.value(i)))
+        }
+        DataType::Dictionary(_, _) => {
+            let array = array.as_any_dictionary();
+            let values = json_values(array.values().as_ref())?;
+            let keys = array.normalized_keys();
+            collect(array.keys(), |i| values[keys[i]].clone())
+        }
+        DataType::Date32 => dates(array.as_primitive::<Date32Type>()),
+        DataType::Date64 => dates(array.as_primitive::<Date64Type>()),
+        DataType::Time32(TimeUnit::Second) => times(array.as_primitive::<Time32SecondType>()),
+        DataType::Time32(_) => times(array.as_primitive::<Time32MillisecondType>()),
+        DataType::Time64(TimeUnit::Microsecond) => {
+            times(array.as_primitive::<Time64MicrosecondType>())
+        }
+        DataType::Time64(_) => times(array.as_primitive::<Time64NanosecondType>()),
+        DataType::Timestamp(unit, timezone) => {
+            let format = match timezone {
+                None => NAIVE_DATE_TIME_FORMAT,
+                Some(_) => DATE_TIME_FORMAT,
+            };
+            match unit {
+                TimeUnit::Second => timestamps(array.as_primitive::<TimestampSecondType>(), format),
+                TimeUnit::Millisecond => {
+                    timestamps(array.as_primitive::<TimestampMillisecondType>(), format)
+                }
+                TimeUnit::Microsecond => {
+                    timestamps(array.as_primitive::<TimestampMicrosecondType>(), format)
+                }
+                TimeUnit::Nanosecond => {
+                    timestamps(array.as_primitive::<TimestampNanosecondType>(), format)
+                }
+            }
+        }
+        DataType::List(_) => {
+            let array = array.as_list::<i32>();
+            (0..array.len())
+                .map(|i| match array.is_null(i) {
+                    true => Ok(Value::Null),
+                    false => json_values(array.value(i).as_ref()).map(Value::Array),
+                })
+                .collect::<Result<_>>()?
+        }
+        DataType::LargeList(_) => {
+            let array = array.as_list::<i64>();
+            (0..array.len())
+                .map(|i| match array.is_null(i) {
+                    true => Ok(Value::Null),
+                    false => json_values(array.value(i).as_ref()).map(Value::Array),
+                })
+                .collect::<Result<_>>()?
+        }
+        DataType::FixedSizeList(_, _) => {
+            let array = array.as_fixed_size_list();
+            (0..array.len())
+                .map(|i| match array.is_null(i) {
+                    true => Ok(Value::Null),
+                    false => json_values(array.value(i).as_ref()).map(Value::Array),
+                })
+                .collect::<Result<_>>()?
+        }
+        DataType::Struct(fields) => {
+            let array = array.as_struct();
+            let mut objects = vec![Map::new(); array.len()];
+            for (field, column) in fields.iter().zip(array.columns()) {
+                let values = json_values(column.as_ref())
+                    .with_context(|| format!("in field '{}'", field.name()))?;
+                for (object, value) in objects.iter_mut().zip(values) {
+                    object.insert(field.name().clone(), value);
+                }
+            }
+            objects
+                .into_iter()
+                .enumerate()
+                .map(|(i, object)| match array.is_null(i) {
+                    true => Value::Null,
+                    false => Value::Object(object),
+                })
+                .collect()
+        }
+        other => bail!("We haven't implemented a converter for {}", other),
+    };
+
+    Ok(values)
+}
+
+fn collect(array: &dyn Array, value: impl Fn(usize) -> Value) -> Vec<Value> {
+    (0..array.len())
+        .map(|i| match array.is_null(i) {
+            true => Value::Null,
+            false => value(i),
+        })
+        .collect()
+}
+
+fn primitive<T: ArrowPrimitiveType>(
+    array: &dyn Array,
+    value: impl Fn(T::Native) -> Value,
+) -> Vec<Value> {
+    let array = array.as_primitive::<T>();
+    collect(array, |i| value(array.value(i)))
+}
+
+fn dates<T: ArrowTemporalType>(array: &PrimitiveArray<T>) -> Vec<Value>
+where
+    i64: From<T::Native>,
+{
+    collect(array, |i| {
+        formatted(array.value_as_date(i), |date: NaiveDate| {
+            date.format(DATE_FORMAT)
+        })
+    })
+}
+
+fn times<T: ArrowTemporalType>(array: &PrimitiveArray<T>) -> Vec<Value>
+where
+    i64: From<T::Native>,
+{
+    collect(array, |i| {
+        formatted(array.value_as_time(i), |time: NaiveTime| {
+            time.format(TIME_FORMAT)
+        })
+    })
+}
+
+fn timestamps<T: ArrowTemporalType>(array: &PrimitiveArray<T>, format: &str) -> Vec<Value>
+where
+    i64: From<T::Native>,
+{
+    collect(array, |i| {
+        formatted(array.value_as_datetime(i), |date_time: NaiveDateTime| {
+            date_time.and_utc().format(format)
+        })
+    })
+}
+
+/// Values that do not fit chrono's types are treated as missing.
+fn formatted<V, D: std::fmt::Display>(value: Option<V>, format: impl Fn(V) -> D) -> Value {
+    value
+        .map(|value| Value::String(format(value).to_string()))
+        .unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{DictionaryArray, Float64Array, Int32Array, RecordBatch, StringArray};
+    use parquet::arrow::ArrowWriter;
+    use parquet::file::properties::WriterProperties;
+    use std::sync::Arc;
+    use synth_core::schema::number_content::F64;
+
+    /// Imports `batch`, written as a Parquet file with `properties`, as the collection `name`, and
+    /// returns the fields of its objects.
+    fn import_batch(
+        batch: RecordBatch,
+        properties: Option<WriterProperties>,
+        name: &str,
+        inference: InferenceOptions,
+    ) -> BTreeMap<String, Content> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(format!("{name}.parquet"));
+        let mut writer =
+            ArrowWriter::try_new(File::create(&path).unwrap(), batch.schema(), properties).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let mut namespace = Namespace::new();
+        import_file(&mut namespace, name, &path, inference).unwrap();
+
+        match namespace.get_collection(name).unwrap() {
+            Content::Array(ArrayContent { content, .. }) => match content.as_ref() {
+                Content::Object(ObjectContent { fields, .. }) => fields.clone(),
+                other => panic!("expected an object, found {other:?}"),
+            },
+            other => panic!("expected an array, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn import_types() {
+        let ids = Int32Array::from(vec![3, 1, 7]);
+        let status: DictionaryArray<Int32Type> =
+            vec!["open", "closed", "open"].into_iter().collect();
+        let created = PrimitiveArray::<TimestampMicrosecondType>::from(vec![
+            1_600_000_000_000_000,
+            1_700_000_000_000_000,
+            1_650_000_000_000_000,
+        ]);
+        let note = StringArray::from(vec![Some("a"), None, Some("c")]);
+
+        let batch = RecordBatch::try_from_iter_with_nullable(vec![
+            ("id", Arc::new(ids) as _, false),
+            ("status", Arc::new(status) as _, false),
+            ("created", Arc::new(created) as _, false),
+            ("note", Arc::new(note) as _, true),
+        ])
+        .unwrap();
+        let fields = import_batch(batch, None, "tickets", InferenceOptions::default());
+
+        match &fields["id"] {
+            Content::Number(NumberContent::I32(I32::Range(range))) => {
+                assert_eq!((range.low, range.high), (Some(1), Some(7)));
+            }
+            other => panic!("expected an i32 range, found {other:?}"),
+        }
+        match &fields["status"] {
+            Content::String(StringContent::Categorical(categorical)) => {
+                assert_eq!(
+                    serde_json::to_value(categorical).unwrap(),
+                    serde_json::json!({ "open": 2, "closed": 1 })
+                );
+            }
+            other => panic!("expected a categorical, found {other:?}"),
+        }
+        match &fields["created"] {
+            Content::DateTime(date_time) => {
+                assert_eq!(date_time.type_, ChronoValueType::NaiveDateTime);
+                assert!(date_time.begin.is_some() && date_time.end.is_some());
+            }
+            other => panic!("expected a date time, found {other:?}"),
+        }
+        assert!(fields["note"].is_nullable());
+        let weights: Vec<_> = serde_json::to_value(&fields["note"]).unwrap()["variants"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|variant| variant["weight"].clone())
+            .collect();
+        assert_eq!(
+            weights,
+            vec![serde_json::json!(2.0), serde_json::json!(1.0)]
+        );
+    }
+
+    #[test]
+    fn import_inference() {
+        let priority = StringArray::from_iter_values((0..40).map(|i| ["low", "high"][i % 2]));
+        let score = Float64Array::from_iter_values((0..40).map(|i| f64::from(i * i) / 7.0));
+        let batch = RecordBatch::try_from_iter_with_nullable(vec![
+            ("priority", Arc::new(priority) as _, false),
+            ("score", Arc::new(score) as _, false),
+        ])
+        .unwrap();
+        let properties = WriterProperties::builder()
+            .set_dictionary_enabled(false)
+            .build();
+        let inference = InferenceOptions {
+            histograms: true,
+            ..Default::default()
+        };
+        let fields = import_batch(batch, Some(properties), "tasks", inference);
+
+        // Plain strings are only found to be few from the sampled rows
+        match &fields["priority"] {
+            Content::String(StringContent::Categorical(categorical)) => {
+                assert_eq!(
+                    serde_json::to_value(categorical).unwrap(),
+                    serde_json::json!({ "low": 20, "high": 20 })
+                );
+            }
+            other => panic!("expected a categorical, found {other:?}"),
+        }
+        assert!(matches!(
+            &fields["score"],
+            Content::Number(NumberContent::F64(F64::Distribution(_)))
+        ));
+    }
+}
@@ -0,0 +1,737 @@
+mod import;
+
+use crate::cli::export::ExportStrategy;
+use crate::cli::import::ImportStrategy;
+use crate::sampler::SampleStream;
+
+use synth_core::schema::{
+    ChronoValue, ChronoValueAndFormat, ChronoValueType, InferenceOptions, NumberContent,
+};
+use synth_core::{Content, Namespace, Value};
+use synth_gen::value::Number;
+
+use anyhow::{Context, Result};
+
+use arrow_array::types::{
+    Date32Type, Decimal128Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type,
+    Int8Type, Time64MicrosecondType, TimestampMicrosecondType, UInt16Type, UInt32Type, UInt64Type,
+    UInt8Type,
+};
+use arrow_array::{
+    Array, ArrayRef, ArrowPrimitiveType, BooleanArray, ListArray, NullArray, PrimitiveArray,
+    RecordBatch, StringArray, StructArray,
+};
+use arrow_buffer::{NullBuffer, OffsetBuffer};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::Timelike;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The values of a collection are held back until the type of each of their fields the schema
+/// does not give is known, but only up to this many. Fields that were null in all of them are
+/// written as null columns.
+const MAX_SCHEMA_INFERENCE_ROWS: usize = 10_000;
+
+/// The name of the column collections of anything but objects are written to.
+const VALUE_COLUMN: &str = "value";
+
+static NULL: Value = Value::Null(());
+
+#[derive(Clone, Debug)]
+pub struct ParquetFileExportStrategy {
+    pub to_dir: PathBuf,
+}
+
+impl ExportStrategy for ParquetFileExportStrategy {
+    fn export(&self, namespace: Namespace, sample: SampleStream) -> Result<()> {
+        if self.to_dir.exists() {
+            return Err(anyhow!("Output directory already exists"));
+        } else {
+            std::fs::create_dir_all(&self.to_dir)?;
+        }
+
+        let single_collection = sample.collection().is_some();
+        let mut writers: BTreeMap<String, CollectionWriter> = BTreeMap::new();
+
+        for batch in sample {
+            let (collection_name, value) = batch?;
+            let rows = match value {
+                Value::Array(rows) => rows,
+                non_array => vec![non_array],
+            };
+
+            let writer = writers.entry(collection_name.clone()).or_insert_with(|| {
+                let path = if single_collection {
+                    self.to_dir.join("collection.parquet")
+                } else {
+                    self.to_dir.join(format!("{collection_name}.parquet"))
+                };
+                let content = namespace.get_collection(&collection_name).ok();
+                CollectionWriter::new(path, &namespace, content)
+            });
+
+            writer
+                .write(rows)
+                .with_context(|| format!("Failed to write collection '{collection_name}'"))?;
+        }
+
+        for (collection_name, writer) in writers {
+            writer
+                .finish()
+                .with_context(|| format!("Failed to write collection '{collection_name}'"))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ParquetFileImportStrategy {
+    pub from_path: PathBuf,
+    pub inference: InferenceOptions,
+}
+
+impl ImportStrategy for ParquetFileImportStrategy {
+    fn import(&self) -> Result<Namespace> {
+        let mut namespace = Namespace::new();
+
+        for path in self.paths()? {
+            let name = collection_name(&path)?;
+            import::import_file(&mut namespace, &name, &path, self.inference)
+                .with_context(|| format!("Failed to import {}", path.display()))?;
+        }
+
+        Ok(namespace)
+    }
+
+    fn import_data(&self) -> Result<BTreeMap<String, Vec<serde_json::Value>>> {
+        let mut data = BTreeMap::new();
+
+        for path in self.paths()? {
+            let rows = import::read_rows(&path, None)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            data.insert(collection_name(&path)?, rows);
+        }
+
+        Ok(data)
+    }
+}
+
+impl ParquetFileImportStrategy {
+    /// Either the `.parquet` files of a directory, one per collection, or a single file.
+    fn paths(&self) -> Result<Vec<PathBuf>> {
+        if !self.from_path.is_dir() {
+            return Ok(vec![self.from_path.clone()]);
+        }
+
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(&self.from_path)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().map_or(false, |ext| ext == "parquet") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        Ok(paths)
+    }
+}
+
+fn collection_name(path: &Path) -> Result<String> {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .map(str::to_string)
+        .ok_or_else(|| {
+            anyhow!("Failed to interpret collection name when importing a Parquet namespace")
+        })
+}
+
+/// Writes the values of a single collection to a Parquet file, whose schema is given by the
+/// content of the collection, and inferred from the values themselves where it does not tell.
+struct CollectionWriter {
+    path: PathBuf,
+    data_type: DataType,
+    /// Whether the values can be null, when they are not objects
+    nullable: bool,
+    pending: Vec<Value>,
+    writer: Option<(ArrowWriter<File>, SchemaRef)>,
+}
+
+impl CollectionWriter {
+    fn new(path: PathBuf, namespace: &Namespace, content: Option<&Content>) -> Self {
+        let (data_type, nullable) = match content {
+            Some(Content::Array(array)) => content_type(namespace, &array.content),
+            Some(content) => content_type(namespace, content),
+            None => (DataType::Null, true),
+        };
+        Self {
+            path,
+            data_type,
+            nullable,
+            pending: Vec::new(),
+            writer: None,
+        }
+    }
+
+    fn write(&mut self, rows: Vec<Value>) -> Result<()> {
+        if let Some((writer, schema)) = &mut self.writer {
+            let batch = record_batch(schema, &self.data_type, &rows)?;
+            return writer.write(&batch).map_err(Into::into);
+        }
+
+        for row in &rows {
+            self.data_type = infer_type(self.data_type.clone(), row)?;
+        }
+        self.pending.extend(rows);
+
+        if is_inferred(&self.data_type) || self.pending.len() >= MAX_SCHEMA_INFERENCE_ROWS {
+            self.open()?;
+        }
+
+        Ok(())
+    }
+
+    fn open(&mut self) -> Result<()> {
+        let schema = Arc::new(match &self.data_type {
+            DataType::Struct(fields) => Schema::new(fields.clone()),
+            other => Schema::new(vec![Field::new(VALUE_COLUMN, other.clone(), self.nullable)]),
+        });
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let mut writer = ArrowWriter::try_new(
+            File::create(&self.path)?,
+            Arc::clone(&schema),
+            Some(properties),
+        )?;
+
+        let pending = std::mem::take(&mut self.pending);
+        if !pending.is_empty() {
+            writer.write(&record_batch(&schema, &self.data_type, &pending)?)?;
+        }
+
+        self.writer = Some((writer, schema));
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        if self.writer.is_none() {
+            self.open()?;
+        }
+        if let Some((writer, _)) = self.writer {
+            writer.close()?;
+        }
+        Ok(())
+    }
+}
+
+/// The type of the values generated by `content`, and whether they can be null. The parts of it
+/// which are not known from the content alone, like the result of an expression, are
+/// [`DataType::Null`] for their type to be inferred from the values.
+fn content_type(namespace: &Namespace, content: &Content) -> (DataType, bool) {
+    let data_type = match content {
+        Content::Null(_) => return (DataType::Null, true),
+        Content::Bool(_) => DataType::Boolean,
+        Content::Number(number) => match number {
+            NumberContent::U32(_) => DataType::UInt32,
+            NumberContent::U64(_) => DataType::UInt64,
+            NumberContent::I16(_) => DataType::Int16,
+            NumberContent::I32(_) => DataType::Int32,
+            NumberContent::I64(_) => DataType::Int64,
+            NumberContent::F32(_) => DataType::Float32,
+            NumberContent::F64(_) => DataType::Float64,
+        },
+        Content::String(_) => DataType::Utf8,
+        Content::DateTime(date_time) => match date_time.type_ {
+            ChronoValueType::NaiveDate => DataType::Date32,
+            ChronoValueType::NaiveTime => DataType::Time64(TimeUnit::Microsecond),
+            ChronoValueType::NaiveDateTime => DataType::Timestamp(TimeUnit::Microsecond, None),
+            ChronoValueType::DateTime => {
+                DataType::Timestamp(TimeUnit::Microsecond, Some("+00:00".into()))
+            }
+        },
+        Content::Array(array) => {
+            let (item, nullable) = content_type(namespace, &array.content);
+            DataType::List(Arc::new(Field::new("item", item, nullable)))
+        }
+        Content::Object(object) => DataType::Struct(
+            object
+                .fields
+                .iter()
+                .filter(|(_, content)| !matches!(content, Content::Hidden(_)))
+                .map(|(name, content)| {
+                    let (data_type, nullable) = content_type(namespace, content);
+                    Field::new(name, data_type, nullable)
+                })
+                .collect(),
+        ),
+        Content::OneOf(one_of) => return common_type(namespace, one_of.iter()),
+        Content::Switch(switch) => return common_type(namespace, switch.iter()),
+        Content::Unique(unique) => return content_type(namespace, &unique.content),
+        Content::SameAs(same_as) => match namespace.get_s_node(&same_as.ref_) {
+            Ok(content) => return content_type(namespace, content),
+            Err(_) => return (DataType::Null, true),
+        },
+        _ => return (DataType::Null, true),
+    };
+    (data_type, false)
+}
+
+/// The type of the values generated by any of `contents`, unknown unless they all agree.
+fn common_type<'a>(
+    namespace: &Namespace,
+    contents: impl Iterator<Item = &'a Content>,
+) -> (DataType, bool) {
+    let mut common = None;
+    let mut nullable = false;
+    for content in contents {
+        let (data_type, can_be_null) = content_type(namespace, content);
+        nullable |= can_be_null;
+        if content.is_null() {
+            continue;
+        }
+        match &common {
+            None => common = Some(data_type),
+            Some(common) if *common == data_type => {}
+            Some(_) => return (DataType::Null, true),
+        }
+    }
+    (common.unwrap_or(DataType::Null), nullable)
+}
+
+/// Refines `data_type`, the type inferred from the values seen so far, with `value`. Nulls leave
+/// the type unchanged, so a [`DataType::Null`] anywhere within it means that part is not known yet.
+fn infer_type(data_type: DataType, value: &Value) -> Result<DataType> {
+    let inferred = match value {
+        Value::Null(_) => return Ok(data_type),
+        Value::Bool(_) => DataType::Boolean,
+        Value::Number(number) => match number {
+            Number::I8(_) => DataType::Int8,
+            Number::I16(_) => DataType::Int16,
+            Number::I32(_) => DataType::Int32,
+            Number::I64(_) => DataType::Int64,
+            Number::U8(_) => DataType::UInt8,
+            Number::U16(_) => DataType::UInt16,
+            Number::U32(_) => DataType::UInt32,
+            Number::U64(_) => DataType::UInt64,
+            Number::I128(_) | Number::U128(_) => DataType::Decimal128(38, 0),
+            Number::F32(_) => DataType::Float32,
+            Number::F64(_) => DataType::Float64,
+        },
+        Value::String(_) => DataType::Utf8,
+        // Microseconds are used over nanoseconds as they cover a much wider range of dates and
+        // are what most analytics engines use anyway
+        Value::DateTime(ChronoValueAndFormat { value, .. }) => match value {
+            ChronoValue::NaiveDate(_) => DataType::Date32,
+            ChronoValue::NaiveTime(_) => DataType::Time64(TimeUnit::Microsecond),
+            ChronoValue::NaiveDateTime(_) => DataType::Timestamp(TimeUnit::Microsecond, None),
+            ChronoValue::DateTime(_) => {
+                DataType::Timestamp(TimeUnit::Microsecond, Some("+00:00".into()))
+            }
+        },
+        Value::Array(elements) => {
+            let field = match &data_type {
+                DataType::List(field) => field.as_ref().clone(),
+                _ => Field::new("item", DataType::Null, true),
+            };
+            let item = elements
+                .iter()
+                .try_fold(field.data_type().clone(), infer_type)?;
+            DataType::List(Arc::new(field.with_data_type(item)))
+        }
+        Value::Object(object) => {
+            let mut fields: Vec<Field> = match &data_type {
+                DataType::Struct(fields) => fields.iter().map(|f| f.as_ref().clone()).collect(),
+                _ => Vec::new(),
+            };
+            for (name, value) in object {
+                match fields.iter_mut().find(|field| field.name() == name) {
+                    Some(field) => {
+                        let inferred = infer_type(field.data_type().clone(), value)
+                            .with_context(|| format!("in field '{name}'"))?;
+                        *field = field.clone().with_data_type(inferred);
+                    }
+                    None => {
+                        let inferred = infer_type(DataType::Null, value)
+                            .with_context(|| format!("in field '{name}'"))?;
+                        fields.push(Field::new(name, inferred, true));
+                    }
+                }
+            }
+            DataType::Struct(fields.into())
+        }
+    };
+
+    match (&data_type, &inferred) {
+        (DataType::Null, _)
+        | (DataType::List(_), DataType::List(_))
+        | (DataType::Struct(_), DataType::Struct(_)) => Ok(inferred),
+        (current, inferred) if current == inferred => Ok(data_type),
+        (current, inferred) => Err(anyhow!(
+            "found values of both type {} and {}, which cannot be written to the same Parquet column",
+            current,
+            inferred
+        )),
+    }
+}
+
+/// Whether the type of every part of `data_type` is known.
+fn is_inferred(data_type: &DataType) -> bool {
+    match data_type {
+        DataType::Null => false,
+        DataType::List(field) => is_inferred(field.data_type()),
+        DataType::Struct(fields) => fields.iter().all(|field| is_inferred(field.data_type())),
+        _ => true,
+    }
+}
+
+/// Builds a record batch of `rows`, whose columns are the fields of `data_type` if they are objects.
+fn record_batch(schema: &SchemaRef, data_type: &DataType, rows: &[Value]) -> Result<RecordBatch> {
+    let rows: Vec<&Value> = rows.iter().collect();
+    let array = build_array(data_type, &rows)?;
+    let columns = match array.as_any().downcast_ref::<StructArray>() {
+        Some(objects) => objects.columns().to_vec(),
+        None => vec![array],
+    };
+
+    RecordBatch::try_new(Arc::clone(schema), columns).map_err(Into::into)
+}
+
+fn build_array(data_type: &DataType, values: &[&Value]) -> Result<ArrayRef> {
+    let array: ArrayRef = match data_type {
+        DataType::Null => {
+            if let Some(value) = values.iter().find(|value| !value.is_null()) {
+                return Err(anyhow!(
+                    "the type of {} could not be inferred as it was null in the first {} values",
+                    value,
+                    MAX_SCHEMA_INFERENCE_ROWS
+                ));
+            }
+            Arc::new(NullArray::new(values.len()))
+        }
+        DataType::Boolean => Arc::new(
+            values
+                .iter()
+                .map(|value| match value {
+                    Value::Null(_) => Ok(None),
+                    Value::Bool(b) => Ok(Some(*b)),
+                    other => Err(unexpected(data_type, other)),
+                })
+                .collect::<Result<BooleanArray>>()?,
+        ),
+        DataType::Int8 => Arc::new(primitive::<Int8Type>(data_type, values, |v| match v {
+            Value::Number(Number::I8(i)) => Some(*i),
+            _ => None,
+        })?),
+        DataType::Int16 => Arc::new(primitive::<Int16Type>(data_type, values, |v| match v {
+            Value::Number(Number::I16(i)) => Some(*i),
+            _ => None,
+        })?),
+        DataType::Int32 => Arc::new(primitive::<Int32Type>(data_type, values, |v| match v {
+            Value::Number(Number::I32(i)) => Some(*i),
+            _ => None,
+        })?),
+        DataType::Int64 => Arc::new(primitive::<Int64Type>(data_type, values, |v| match v {
+            Value::Number(Number::I64(i)) => Some(*i),
+            _ => None,
+        })?),
+        DataType::UInt8 => Arc::new(primitive::<UInt8Type>(data_type, values, |v| match v {
+            Value::Number(Number::U8(u)) => Some(*u),
+            _ => None,
+        })?),
+        DataType::UInt16 => Arc::new(primitive::<UInt16Type>(data_type, values, |v| match v {
+            Value::Number(Number::U16(u)) => Some(*u),
+            _ => None,
+        })?),
+        DataType::UInt32 => Arc::new(primitive::<UInt32Type>(data_type, values, |v| match v {
+            Value::Number(Number::U32(u)) => Some(*u),
+            _ => None,
+        })?),
+        DataType::UInt64 => Arc::new(primitive::<UInt64Type>(data_type, values, |v| match v {
+            Value::Number(Number::U64(u)) => Some(*u),
+            _ => None,
+        })?),
+        DataType::Decimal128(precision, scale) => Arc::new(
+            primitive::<Decimal128Type>(data_type, values, |v| match v {
+                Value::Number(Number::I128(i)) => Some(*i),
+                Value::Number(Number::U128(u)) => i128::try_from(*u).ok(),
+                _ => None,
+            })?
+            .with_precision_and_scale(*precision, *scale)?,
+        ),
+        DataType::Float32 => Arc::new(primitive::<Float32Type>(data_type, values, |v| match v {
+            Value::Number(Number::F32(f)) => Some(f.into_inner()),
+            _ => None,
+        })?),
+        DataType::Float64 => Arc::new(primitive::<Float64Type>(data_type, values, |v| match v {
+            Value::Number(Number::F64(f)) => Some(f.into_inner()),
+            _ => None,
+        })?),
+        DataType::Utf8 => Arc::new(
+            values
+                .iter()
+                .map(|value| match value {
+                    Value::Null(_) => Ok(None),
+                    Value::String(s) => Ok(Some(s.as_str())),
+                    other => Err(unexpected(data_type, other)),
+                })
+                .collect::<Result<StringArray>>()?,
+        ),
+        DataType::Date32 => {
+            Arc::new(primitive::<Date32Type>(
+                data_type,
+                values,
+                |v| match chrono_value(v)? {
+                    ChronoValue::NaiveDate(date) => Some(Date32Type::from_naive_date(*date)),
+                    _ => None,
+                },
+            )?)
+        }
+        DataType::Time64(TimeUnit::Microsecond) => Arc::new(primitive::<Time64MicrosecondType>(
+            data_type,
+            values,
+            |v| match chrono_value(v)? {
+                ChronoValue::NaiveTime(time) => Some(
+                    i64::from(time.num_seconds_from_midnight()) * 1_000_000
+                        + i64::from(time.nanosecond() / 1_000),
+                ),
+                _ => None,
+            },
+        )?),
+        DataType::Timestamp(TimeUnit::Microsecond, timezone) => Arc::new(
+            primitive::<TimestampMicrosecondType>(data_type, values, |v| {
+                match (chrono_value(v)?, timezone) {
+                    (ChronoValue::NaiveDateTime(date_time), None) => {
+                        Some(date_time.and_utc().timestamp_micros())
+                    }
+                    (ChronoValue::DateTime(date_time), Some(_)) => {
+                        Some(date_time.timestamp_micros())
+                    }
+                    _ => None,
+                }
+            })?
+            .with_timezone_opt(timezone.clone()),
+        ),
+        DataType::List(field) => {
+            let mut items = Vec::new();
+            let mut lengths = Vec::with_capacity(values.len());
+            let mut validity = Vec::with_capacity(values.len());
+            for value in values {
+                match value {
+                    Value::Null(_) => {
+                        lengths.push(0);
+                        validity.push(false);
+                    }
+                    Value::Array(elements) => {
+                        items.extend(elements);
+                        lengths.push(elements.len());
+                        validity.push(true);
+                    }
+                    other => return Err(unexpected(data_type, other)),
+                }
+            }
+            Arc::new(ListArray::try_new(
+                Arc::clone(field),
+                OffsetBuffer::from_lengths(lengths),
+                build_array(field.data_type(), &items)?,
+                null_buffer(validity),
+            )?)
+        }
+        DataType::Struct(fields) => {
+            let mut validity = Vec::with_capacity(values.len());
+            for value in values {
+                match value {
+                    Value::Null(_) => validity.push(false),
+                    Value::Object(_) => validity.push(true),
+                    other => return Err(unexpected(data_type, other)),
+                }
+            }
+            let columns = fields
+                .iter()
+                .map(|field| {
+                    let children: Vec<&Value> = values
+                        .iter()
+                        .map(|value| match value {
+                            Value::Object(object) => object.get(field.name()).unwrap_or(&NULL),
+                            _ => &NULL,
+                        })
+                        .collect();
+                    build_array(field.data_type(), &children)
+                        .with_context(|| format!("in field '{}'", field.name()))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Arc::new(StructArray::try_new(
+                fields.clone(),
+                columns,
+                null_buffer(validity),
+            )?)
+        }
+        other => unreachable!("{} is never inferred", other),
+    };
+
+    Ok(array)
+}
+
+fn primitive<T: ArrowPrimitiveType>(
+    data_type: &DataType,
+    values: &[&Value],
+    native: impl Fn(&Value) -> Option<T::Native>,
+) -> Result<PrimitiveArray<T>> {
+    values
+        .iter()
+        .map(|value| match value {
+            Value::Null(_) => Ok(None),
+            value => native(value)
+                .map(Some)
+                .ok_or_else(|| unexpected(data_type, value)),
+        })
+        .collect()
+}
+
+fn chrono_value(value: &Value) -> Option<&ChronoValue> {
+    match value {
+        Value::DateTime(ChronoValueAndFormat { value, .. }) => Some(value),
+        _ => None,
+    }
+}
+
+fn null_buffer(validity: Vec<bool>) -> Option<NullBuffer> {
+    if validity.iter().all(|valid| *valid) {
+        None
+    } else {
+        Some(NullBuffer::from(validity))
+    }
+}
+
+fn unexpected(data_type: &DataType, value: &Value) -> anyhow::Error {
+    anyhow!(
+        "found {} in a Parquet column of type {}. All values of a field need to have the same type",
+        value,
+        data_type
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::{SampleSize, Sampler};
+    use arrow_array::RecordBatchReader;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn read_back(path: PathBuf) -> (SchemaRef, usize) {
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let schema = reader.schema();
+        let rows = reader.map(|batch| batch.unwrap().num_rows()).sum();
+        (schema, rows)
+    }
+
+    #[test]
+    fn export_types() {
+        let namespace: Namespace = serde_json::from_value::<synth_core::Content>(serde_json::json!({
+            "type": "object",
+            "users": {
+                "type": "array",
+                "length": 10,
+                "content": {
+                    "type": "object",
+                    "id": { "type": "number", "subtype": "u32", "id": {} },
+                    "age": { "type": "number", "subtype": "i16", "range": { "low": 0, "high": 100 } },
+                    "score": { "type": "number", "subtype": "f32", "range": { "low": 0, "high": 1 } },
+                    "joined": { "type": "date_time", "format": "%Y-%m-%d", "subtype": "naive_date" },
+                    "seen": {
+                        "type": "date_time",
+                        "format": "%Y-%m-%dT%H:%M:%S%z",
+                        "subtype": "date_time"
+                    },
+                    "nickname": {
+                        "type": "one_of",
+                        "variants": [{ "type": "null" }, { "type": "string", "pattern": "[a-z]{3}" }]
+                    },
+                    "address": {
+                        "type": "object",
+                        "city": { "type": "string", "pattern": "[A-Z][a-z]{5}" },
+                        "tags": {
+                            "type": "array",
+                            "length": { "type": "number", "subtype": "u64", "range": { "low": 0, "high": 3 } },
+                            "content": { "type": "bool", "frequency": 0.5 }
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap()
+        .into_namespace()
+        .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let to_dir = dir.path().join("out");
+        let sample = Sampler::new(&namespace, &SampleSize::Total(25))
+            .unwrap()
+            .sample_seeded(None, 0);
+        ParquetFileExportStrategy {
+            to_dir: to_dir.clone(),
+        }
+        .export(namespace, sample)
+        .unwrap();
+
+        let (schema, rows) = read_back(to_dir.join("users.parquet"));
+        assert_eq!(rows, 30);
+
+        let types: BTreeMap<&str, &DataType> = schema
+            .fields()
+            .iter()
+            .map(|field| (field.name().as_str(), field.data_type()))
+            .collect();
+        assert_eq!(types["id"], &DataType::UInt32);
+        assert_eq!(types["age"], &DataType::Int16);
+        assert_eq!(types["score"], &DataType::Float32);
+        assert_eq!(types["joined"], &DataType::Date32);
+        assert_eq!(
+            types["seen"],
+            &DataType::Timestamp(TimeUnit::Microsecond, Some("+00:00".into()))
+        );
+        assert_eq!(types["nickname"], &DataType::Utf8);
+        match types["address"] {
+            DataType::Struct(fields) => {
+                assert_eq!(fields[0].data_type(), &DataType::Utf8);
+                assert!(matches!(
+                    fields[1].data_type(),
+                    DataType::List(item) if item.data_type() == &DataType::Boolean
+                ));
+            }
+            other => panic!("expected a struct, found {other}"),
+        }
+
+        let nullable: Vec<&str> = schema
+            .fields()
+            .iter()
+            .filter(|field| field.is_nullable())
+            .map(|field| field.name().as_str())
+            .collect();
+        assert_eq!(nullable, vec!["nickname"]);
+        match types["address"] {
+            DataType::Struct(fields) => assert!(fields.iter().all(|field| !field.is_nullable())),
+            other => panic!("expected a struct, found {other}"),
+        }
+    }
+
+    #[test]
+    fn infer_type_rejects_mixed_types() {
+        let number = Value::Number(Number::I32(1));
+        let string = Value::String("one".to_string());
+
+        let data_type = infer_type(DataType::Null, &Value::Null(())).unwrap();
+        let data_type = infer_type(data_type, &number).unwrap();
+        assert_eq!(data_type, DataType::Int32);
+        assert!(infer_type(data_type, &string).is_err());
+    }
+}
@@ -1,20 +1,25 @@
 use crate::cli::export::{create_and_insert_values, ExportStrategy};
 use crate::cli::import::ImportStrategy;
-use crate::cli::import_utils::build_namespace_import;
+use crate::cli::import_utils::{build_namespace_data, build_namespace_import};
+use crate::datasource::ddl::{create_tables, SqlDialect};
 use crate::datasource::postgres_datasource::{PostgresConnectParams, PostgresDataSource};
+use crate::datasource::sampling::Sampling;
 use crate::datasource::DataSource;
-use crate::sampler::SamplerOutput;
+use crate::sampler::SampleStream;
 use anyhow::Result;
-use synth_core::schema::Namespace;
+use async_std::task;
+use std::collections::BTreeMap;
+use synth_core::schema::{InferenceOptions, Namespace};
 
 #[derive(Clone, Debug)]
 pub struct PostgresExportStrategy {
     pub uri_string: String,
     pub schema: Option<String>,
+    pub create_tables: bool,
 }
 
 impl ExportStrategy for PostgresExportStrategy {
-    fn export(&self, _namespace: Namespace, sample: SamplerOutput) -> Result<()> {
+    fn export(&self, namespace: Namespace, sample: SampleStream) -> Result<()> {
         let connect_params = PostgresConnectParams {
             uri: self.uri_string.clone(),
             schema: self.schema.clone(),
@@ -22,6 +27,10 @@ impl ExportStrategy for PostgresExportStrategy {
 
         let datasource = PostgresDataSource::new(&connect_params)?;
 
+        if self.create_tables {
+            task::block_on(create_tables(&datasource, &namespace, SqlDialect::Postgres))?;
+        }
+
         create_and_insert_values(sample, &datasource)
     }
 }
@@ -30,6 +39,8 @@ impl ExportStrategy for PostgresExportStrategy {
 pub struct PostgresImportStrategy {
     pub uri_string: String,
     pub schema: Option<String>,
+    pub sampling: Sampling,
+    pub inference: InferenceOptions,
 }
 
 impl ImportStrategy for PostgresImportStrategy {
@@ -41,6 +52,17 @@ impl ImportStrategy for PostgresImportStrategy {
 
         let datasource = PostgresDataSource::new(&connect_params)?;
 
-        build_namespace_import(&datasource)
+        build_namespace_import(&datasource, self.sampling, self.inference)
+    }
+
+    fn import_data(&self) -> Result<BTreeMap<String, Vec<serde_json::Value>>> {
+        let connect_params = PostgresConnectParams {
+            uri: self.uri_string.clone(),
+            schema: self.schema.clone(),
+        };
+
+        let datasource = PostgresDataSource::new(&connect_params)?;
+
+        build_namespace_data(&datasource)
     }
 }
@@ -0,0 +1,312 @@
+use crate::cli::export::ExportStrategy;
+use crate::datasource::ddl::{create_table_statements, SqlDialect};
+use crate::datasource::relational_datasource::DEFAULT_INSERT_BATCH_SIZE;
+use crate::sampler::SampleStream;
+
+use synth_core::schema::{ChronoValue, ChronoValueAndFormat};
+use synth_core::{Namespace, Value};
+use synth_gen::value::Number;
+
+use anyhow::{Context, Result};
+
+use chrono::Utc;
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+#[derive(Clone, Debug)]
+pub struct SqlFileExportStrategy {
+    pub to_file: PathBuf,
+    pub dialect: SqlDialect,
+    pub create_tables: bool,
+}
+
+impl ExportStrategy for SqlFileExportStrategy {
+    fn export(&self, namespace: Namespace, sample: SampleStream) -> Result<()> {
+        let mut writer =
+            BufWriter::new(File::create(&self.to_file).with_context(|| {
+                format!("Failed to create the file {}", self.to_file.display())
+            })?);
+
+        write_dump(
+            &mut writer,
+            &namespace,
+            sample,
+            self.dialect,
+            self.create_tables,
+        )?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SqlStdoutExportStrategy<W> {
+    pub writer: RefCell<W>,
+    pub dialect: SqlDialect,
+    pub create_tables: bool,
+}
+
+impl<W: Write> ExportStrategy for SqlStdoutExportStrategy<W> {
+    fn export(&self, namespace: Namespace, sample: SampleStream) -> Result<()> {
+        write_dump(
+            &mut *self.writer.borrow_mut(),
+            &namespace,
+            sample,
+            self.dialect,
+            self.create_tables,
+        )
+    }
+}
+
+fn write_dump<W: Write>(
+    writer: &mut W,
+    namespace: &Namespace,
+    sample: SampleStream,
+    dialect: SqlDialect,
+    create_tables: bool,
+) -> Result<()> {
+    if create_tables {
+        for statement in create_table_statements(namespace, dialect)? {
+            writeln!(writer, "{statement}\n")?;
+        }
+    }
+
+    // Collections are sampled in dependency order, so the inserts can be replayed as they are
+    for batch in sample {
+        let (collection_name, value) = batch?;
+        let rows = match value {
+            Value::Array(rows) => rows,
+            non_array => vec![non_array],
+        };
+
+        write_inserts(writer, &collection_name, &rows, dialect)
+            .with_context(|| format!("Failed to write the values of {collection_name}"))?;
+    }
+
+    Ok(())
+}
+
+/// Writes `rows` as `INSERT` statements of at most `DEFAULT_INSERT_BATCH_SIZE` rows each, the same
+/// batches a database export would send.
+fn write_inserts<W: Write>(
+    writer: &mut W,
+    collection_name: &str,
+    rows: &[Value],
+    dialect: SqlDialect,
+) -> Result<()> {
+    let first_row = match rows.first() {
+        Some(row) => as_row(row, collection_name)?,
+        None => return Ok(()),
+    };
+
+    let column_names = first_row
+        .keys()
+        .map(|column| dialect.quote(column))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    for batch in rows.chunks(DEFAULT_INSERT_BATCH_SIZE) {
+        let values = batch
+            .iter()
+            .map(|row| {
+                as_row(row, collection_name)?
+                    .values()
+                    .map(|value| sql_literal(value, dialect))
+                    .collect::<Result<Vec<_>>>()
+                    .map(|literals| format!("({})", literals.join(",")))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        writeln!(
+            writer,
+            "INSERT INTO {} ({}) VALUES\n{};\n",
+            dialect.quote(collection_name),
+            column_names,
+            values.join(",\n")
+        )?;
+    }
+
+    Ok(())
+}
+
+/// The columns of `row`, a value of the collection `collection_name`.
+fn as_row<'a>(row: &'a Value, collection_name: &str) -> Result<&'a BTreeMap<String, Value>> {
+    match row {
+        Value::Object(row) => Ok(row),
+        _ => bail!(
+            "the collection {} is not an array of objects, so it cannot be inserted as rows",
+            collection_name
+        ),
+    }
+}
+
+/// The literal a value is written as, mirroring how `Value`s are encoded when inserted directly
+/// into the database.
+fn sql_literal(value: &Value, dialect: SqlDialect) -> Result<String> {
+    let literal = match value {
+        Value::Null(_) => "NULL".to_string(),
+        Value::Bool(b) => b.to_string().to_uppercase(),
+        Value::Number(Number::F32(f)) if f.is_finite() => f.to_string(),
+        Value::Number(Number::F64(f)) if f.is_finite() => f.to_string(),
+        Value::Number(Number::F32(f)) => non_finite_literal(f.into_inner() as f64, dialect)?,
+        Value::Number(Number::F64(f)) => non_finite_literal(f.into_inner(), dialect)?,
+        Value::Number(num) => num.to_string(),
+        Value::String(s) => quote_string(s, dialect),
+        Value::DateTime(ChronoValueAndFormat { value, .. }) => {
+            quote_string(&date_time_string(value, dialect), dialect)
+        }
+        Value::Object(_) => quote_string(&value.to_postgres_string(), dialect),
+        Value::Array(_) if dialect == SqlDialect::MySql => {
+            bail!("Arrays cannot be exported to MySQL")
+        }
+        Value::Array(_) => {
+            let (typ, depth) = value.get_postgres_type();
+            let literal = quote_string(&value.to_postgres_string(), dialect);
+            match typ {
+                "unknown" => literal,
+                // Cannot have an array of jsonb - ie jsonb[]
+                "jsonb" => format!("{literal}::jsonb"),
+                _ => format!("{literal}::{typ}{}", "[]".repeat(depth)),
+            }
+        }
+    };
+
+    Ok(literal)
+}
+
+fn non_finite_literal(f: f64, dialect: SqlDialect) -> Result<String> {
+    match dialect {
+        SqlDialect::Postgres if f.is_nan() => Ok("'NaN'".to_string()),
+        SqlDialect::Postgres if f.is_sign_positive() => Ok("'Infinity'".to_string()),
+        SqlDialect::Postgres => Ok("'-Infinity'".to_string()),
+        SqlDialect::MySql => bail!("MySQL cannot store the float {}", f),
+    }
+}
+
+fn date_time_string(value: &ChronoValue, dialect: SqlDialect) -> String {
+    match (value, dialect) {
+        (ChronoValue::NaiveDate(d), _) => d.format("%Y-%m-%d").to_string(),
+        (ChronoValue::NaiveTime(t), _) => t.format("%H:%M:%S%.f").to_string(),
+        (ChronoValue::NaiveDateTime(dt), _) => dt.format("%Y-%m-%d %H:%M:%S%.f").to_string(),
+        (ChronoValue::DateTime(dt), SqlDialect::Postgres) => {
+            dt.format("%Y-%m-%d %H:%M:%S%.f%:z").to_string()
+        }
+        // MySQL has no time zones, so timestamps are stored in UTC
+        (ChronoValue::DateTime(dt), SqlDialect::MySql) => dt
+            .with_timezone(&Utc)
+            .format("%Y-%m-%d %H:%M:%S%.f")
+            .to_string(),
+    }
+}
+
+fn quote_string(s: &str, dialect: SqlDialect) -> String {
+    let escaped = match dialect {
+        SqlDialect::Postgres => s.replace('\'', "''"),
+        // Backslashes are escape characters in MySQL strings
+        SqlDialect::MySql => s.replace('\\', "\\\\").replace('\'', "''"),
+    };
+
+    format!("'{escaped}'")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, TimeZone};
+
+    fn row(values: Vec<(&str, Value)>) -> Value {
+        Value::Object(
+            values
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect::<BTreeMap<_, _>>(),
+        )
+    }
+
+    #[test]
+    fn inserts() {
+        let timestamp = FixedOffset::east_opt(3600)
+            .unwrap()
+            .with_ymd_and_hms(2022, 3, 4, 5, 6, 7)
+            .unwrap();
+        let rows = vec![
+            row(vec![
+                ("id", Value::Number(Number::U64(1))),
+                ("score", Value::Number(Number::F32(0.1.into()))),
+                ("name", Value::String("O'Brien \\o/".to_string())),
+                (
+                    "created_at",
+                    Value::DateTime(ChronoValueAndFormat {
+                        value: ChronoValue::DateTime(timestamp),
+                        format: "%Y".into(),
+                    }),
+                ),
+            ]),
+            row(vec![
+                ("id", Value::Number(Number::U64(2))),
+                ("score", Value::Number(Number::F32(f32::NAN.into()))),
+                ("name", Value::Null(())),
+                (
+                    "created_at",
+                    Value::DateTime(ChronoValueAndFormat {
+                        value: ChronoValue::DateTime(timestamp),
+                        format: "%Y".into(),
+                    }),
+                ),
+            ]),
+        ];
+
+        let mut postgres = Vec::new();
+        write_inserts(&mut postgres, "users", &rows, SqlDialect::Postgres).unwrap();
+        assert_eq!(
+            String::from_utf8(postgres).unwrap(),
+            r#"INSERT INTO "users" ("created_at","id","name","score") VALUES
+('2022-03-04 05:06:07+01:00',1,'O''Brien \o/',0.1),
+('2022-03-04 05:06:07+01:00',2,NULL,'NaN');
+
+"#
+        );
+
+        let mut mysql = Vec::new();
+        write_inserts(&mut mysql, "users", &rows[..1], SqlDialect::MySql).unwrap();
+        assert!(write_inserts(&mut Vec::new(), "users", &rows, SqlDialect::MySql).is_err());
+        assert_eq!(
+            String::from_utf8(mysql).unwrap(),
+            r#"INSERT INTO `users` (`created_at`,`id`,`name`,`score`) VALUES
+('2022-03-04 04:06:07',1,'O''Brien \\o/',0.1);
+
+"#
+        );
+    }
+
+    #[test]
+    fn inserts_of_scalars() {
+        let rows = vec![Value::Number(Number::U64(1)), Value::Number(Number::U64(2))];
+        let err = write_inserts(&mut Vec::new(), "ids", &rows, SqlDialect::Postgres).unwrap_err();
+        assert!(err.to_string().contains("ids"));
+    }
+
+    #[test]
+    fn postgres_arrays() {
+        let array = Value::Array(vec![
+            Value::String("a \"quoted\" word".to_string()),
+            Value::String("b".to_string()),
+        ]);
+        assert_eq!(
+            sql_literal(&array, SqlDialect::Postgres).unwrap(),
+            r#"'{"a \"quoted\" word", "b"}'::text[]"#
+        );
+        assert!(sql_literal(&array, SqlDialect::MySql).is_err());
+
+        let objects = Value::Array(vec![row(vec![("a", Value::Bool(true))])]);
+        assert_eq!(
+            sql_literal(&objects, SqlDialect::Postgres).unwrap(),
+            r#"'[{"a":true}]'::jsonb"#
+        );
+    }
+}
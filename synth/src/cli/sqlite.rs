@@ -0,0 +1,221 @@
+use crate::cli::export::{create_and_insert_values, ExportStrategy};
+use crate::cli::import::ImportStrategy;
+use crate::cli::import_utils::{build_namespace_data, build_namespace_import};
+use crate::datasource::sampling::Sampling;
+use crate::datasource::sqlite_datasource::SqliteDataSource;
+use crate::datasource::DataSource;
+use crate::sampler::SampleStream;
+use anyhow::Result;
+use std::collections::BTreeMap;
+use synth_core::schema::{InferenceOptions, Namespace};
+
+#[derive(Clone, Debug)]
+pub struct SqliteExportStrategy {
+    pub uri_string: String,
+}
+
+impl ExportStrategy for SqliteExportStrategy {
+    fn export(&self, _namespace: Namespace, sample: SampleStream) -> Result<()> {
+        let datasource = SqliteDataSource::new(&self.uri_string)?;
+
+        create_and_insert_values(sample, &datasource)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SqliteImportStrategy {
+    pub uri_string: String,
+    pub sampling: Sampling,
+    pub inference: InferenceOptions,
+}
+
+impl ImportStrategy for SqliteImportStrategy {
+    fn import(&self) -> Result<Namespace> {
+        let datasource = SqliteDataSource::new(&self.uri_string)?;
+
+        build_namespace_import(&datasource, self.sampling, self.inference)
+    }
+
+    fn import_data(&self) -> Result<BTreeMap<String, Vec<serde_json::Value>>> {
+        let datasource = SqliteDataSource::new(&self.uri_string)?;
+
+        build_namespace_data(&datasource)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SqliteImportStrategy;
+    use crate::cli::import::ImportStrategy;
+    use crate::datasource::relational_datasource::SqlxDataSource;
+    use crate::datasource::sampling::{Sampling, SamplingStrategy};
+    use crate::datasource::sqlite_datasource::SqliteDataSource;
+    use crate::datasource::DataSource;
+    use async_std::task;
+    use sqlx::Executor;
+    use synth_core::graph::Value;
+    use synth_core::schema::{FieldRef, InferenceOptions, NumberContent};
+    use synth_core::Content;
+    use synth_gen::value::Number;
+
+    #[test]
+    fn import_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let uri_string = format!(
+            "sqlite://{}?mode=rwc",
+            dir.path().join("db.sqlite").display()
+        );
+
+        let datasource = SqliteDataSource::new(&uri_string).unwrap();
+        task::block_on(datasource.get_pool().execute(
+            r"CREATE TABLE users (id INTEGER PRIMARY KEY, name VARCHAR(20) NOT NULL);
+            CREATE TABLE orders (id INTEGER PRIMARY KEY, user_id INTEGER REFERENCES users);
+            INSERT INTO users VALUES (1, 'alice'), (2, 'bob');
+            INSERT INTO orders VALUES (1, 1), (2, 2);",
+        ))
+        .unwrap();
+
+        let namespace = SqliteImportStrategy {
+            uri_string,
+            sampling: Sampling::default(),
+            inference: InferenceOptions::default(),
+        }
+        .import()
+        .unwrap();
+
+        let id = FieldRef::new("users.content.id").unwrap();
+        assert!(matches!(
+            namespace.get_s_node(&id).unwrap(),
+            Content::Number(NumberContent::I64(_))
+        ));
+
+        let user_id = FieldRef::new("orders.content.user_id").unwrap();
+        match namespace.get_s_node(&user_id).unwrap() {
+            Content::SameAs(same_as) => assert_eq!(same_as.ref_, id),
+            other => panic!("expected a same_as, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn import_unique_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let uri_string = format!(
+            "sqlite://{}?mode=rwc",
+            dir.path().join("db.sqlite").display()
+        );
+
+        let datasource = SqliteDataSource::new(&uri_string).unwrap();
+        task::block_on(datasource.get_pool().execute(
+            r"CREATE TABLE users (
+                id INTEGER PRIMARY KEY,
+                email TEXT NOT NULL UNIQUE,
+                nickname TEXT UNIQUE,
+                name TEXT NOT NULL
+            );
+            CREATE UNIQUE INDEX users_lower_name ON users (lower(name));
+            INSERT INTO users VALUES
+                (1, 'alice@example.com', 'al', 'Alice'),
+                (2, 'bob@example.com', NULL, 'Bob');",
+        ))
+        .unwrap();
+
+        let namespace = SqliteImportStrategy {
+            uri_string,
+            sampling: Sampling::default(),
+            inference: InferenceOptions::default(),
+        }
+        .import()
+        .unwrap();
+
+        let field = |name: &str| {
+            namespace
+                .get_s_node(&FieldRef::new(format!("users.content.{name}")).unwrap())
+                .unwrap()
+        };
+        assert!(matches!(field("email"), Content::Unique(_)));
+        match field("nickname") {
+            Content::OneOf(one_of) => {
+                assert!(one_of
+                    .iter()
+                    .any(|variant| matches!(variant, Content::Unique(_))))
+            }
+            other => panic!("expected a one_of, found {other:?}"),
+        }
+        // Only unique once lower cased
+        assert!(matches!(field("name"), Content::String(_)));
+    }
+
+    #[test]
+    fn import_sampling() {
+        let dir = tempfile::tempdir().unwrap();
+        let uri_string = format!(
+            "sqlite://{}?mode=rwc",
+            dir.path().join("db.sqlite").display()
+        );
+
+        let datasource = SqliteDataSource::new(&uri_string).unwrap();
+        task::block_on(datasource.get_pool().execute(
+            r"CREATE TABLE numbers (n INTEGER NOT NULL);
+            WITH RECURSIVE g(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM g WHERE n < 25)
+            INSERT INTO numbers SELECT n FROM g;",
+        ))
+        .unwrap();
+
+        let import = |size, strategy| {
+            let namespace = SqliteImportStrategy {
+                uri_string: uri_string.clone(),
+                sampling: Sampling { size, strategy },
+                inference: InferenceOptions::default(),
+            }
+            .import()
+            .unwrap();
+            let collection = namespace.get_collection("numbers").unwrap();
+            let n = FieldRef::new("numbers.content.n").unwrap();
+            (
+                serde_json::to_value(collection).unwrap()["length"]["range"]["high"].clone(),
+                serde_json::to_value(namespace.get_s_node(&n).unwrap()).unwrap()["range"].clone(),
+            )
+        };
+
+        let (length, range) = import(10, SamplingStrategy::First);
+        assert_eq!(length, 10);
+        assert_eq!(range, serde_json::json!({"low": 1, "high": 10}));
+
+        let (length, range) = import(10, SamplingStrategy::Full);
+        assert_eq!(length, 25);
+        assert_eq!(range, serde_json::json!({"low": 1, "high": 25}));
+    }
+
+    #[test]
+    fn large_unsigned_integers_do_not_wrap() {
+        let dir = tempfile::tempdir().unwrap();
+        let uri_string = format!(
+            "sqlite://{}?mode=rwc",
+            dir.path().join("db.sqlite").display()
+        );
+        let datasource = SqliteDataSource::new(&uri_string).unwrap();
+        let pool = datasource.get_pool();
+        task::block_on(pool.execute("CREATE TABLE numbers (n)")).unwrap();
+
+        for n in [42, u64::MAX] {
+            task::block_on(
+                sqlx::query("INSERT INTO numbers VALUES (?)")
+                    .bind(Value::Number(Number::U64(n)))
+                    .execute(&pool),
+            )
+            .unwrap();
+        }
+
+        let rows: Vec<(String, String)> = task::block_on(
+            sqlx::query_as("SELECT typeof(n), CAST(n AS TEXT) FROM numbers").fetch_all(&pool),
+        )
+        .unwrap();
+        assert_eq!(
+            rows,
+            [
+                ("integer".to_string(), "42".to_string()),
+                ("text".to_string(), u64::MAX.to_string())
+            ]
+        );
+    }
+}
@@ -5,6 +5,7 @@ use owo_colors::OwoColorize;
 
 use std::cell::RefCell;
 use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
 use std::future::Future;
 use std::hash::{Hash, Hasher};
 use std::io::{self, BufRead, Read, Write};
@@ -14,13 +15,14 @@ use std::rc::Rc;
 use uuid::Uuid;
 
 use crate::cli::export::ExportStrategy;
-use crate::cli::{config, GenerateCommand, ImportCommand};
-use crate::sampler::SamplerOutput;
+use crate::cli::{config, GenerateCommand, ImportCommand, ValidateCommand};
+use crate::sampler::{SampleBatch, SampleStream};
 use crate::version::version;
 
 use synth_core::{
     compile::{Address, CompilerState, FromLink, Source},
-    Compile, Compiler, Content, Graph, Namespace,
+    graph::json::synth_val_to_json,
+    Compile, Compiler, Content, Graph, Namespace, Value,
 };
 
 use super::{Args, TelemetryCommand};
@@ -268,19 +270,10 @@ impl<'w> TelemetryExportStrategy<'w> {
 
         Ok(())
     }
-
-    fn fill_telemetry_bytes(&self, output: SamplerOutput) -> Result<()> {
-        let j = output.into_json();
-        let s = serde_json::to_string(&j)?;
-
-        self.telemetry_context.borrow_mut().bytes = Some(s.len());
-
-        Ok(())
-    }
 }
 
 impl<'w> ExportStrategy for TelemetryExportStrategy<'w> {
-    fn export(&self, namespace: Namespace, sample: SamplerOutput) -> Result<()> {
+    fn export(&self, namespace: Namespace, sample: SampleStream) -> Result<()> {
         Self::fill_telemetry(
             Rc::clone(&self.telemetry_context),
             &namespace,
@@ -288,9 +281,82 @@ impl<'w> ExportStrategy for TelemetryExportStrategy<'w> {
             self.ns_path.clone(),
         )?;
 
-        self.fill_telemetry_bytes(sample.clone())?;
+        let output_size = Rc::new(RefCell::new(JsonOutputSize::default()));
+        let single_collection = sample.collection().is_some();
+        let sample = {
+            let output_size = Rc::clone(&output_size);
+            sample.inspect(move |batch| output_size.borrow_mut().add(batch))
+        };
+
+        let exported = self.exporter.export(namespace, sample);
+
+        self.telemetry_context.borrow_mut().bytes =
+            Some(output_size.borrow().total(single_collection));
+
+        exported
+    }
+}
+
+/// Tracks the size the generated data would have as a single JSON document, without having to
+/// hold on to the data itself.
+#[derive(Default)]
+struct JsonOutputSize {
+    collections: BTreeMap<String, CollectionSize>,
+}
+
+enum CollectionSize {
+    Array { len: usize, bytes: usize },
+    Single(usize),
+}
+
+impl CollectionSize {
+    fn total(&self) -> usize {
+        match self {
+            // Brackets and the commas between elements
+            Self::Array { len, bytes } => 2 + bytes + len.saturating_sub(1),
+            Self::Single(bytes) => *bytes,
+        }
+    }
+}
+
+impl JsonOutputSize {
+    fn add(&mut self, (collection, value): &SampleBatch) {
+        let json_len = |value: &Value| synth_val_to_json(value.clone()).to_string().len();
+
+        let entry = self
+            .collections
+            .entry(collection.clone())
+            .or_insert(CollectionSize::Array { len: 0, bytes: 0 });
+
+        match (entry, value) {
+            (CollectionSize::Array { len, bytes }, Value::Array(elements)) => {
+                *len += elements.len();
+                *bytes += elements.iter().map(json_len).sum::<usize>();
+            }
+            (entry, non_array) => *entry = CollectionSize::Single(json_len(non_array)),
+        }
+    }
+
+    fn total(&self, single_collection: bool) -> usize {
+        if single_collection {
+            return self
+                .collections
+                .values()
+                .next()
+                .map(CollectionSize::total)
+                .unwrap_or(2);
+        }
+
+        let fields: usize = self
+            .collections
+            .iter()
+            .map(|(name, size)| {
+                serde_json::Value::from(name.as_str()).to_string().len() + 1 + size.total()
+            })
+            .sum();
 
-        self.exporter.export(namespace, sample)
+        // Braces and the commas between fields
+        2 + fields + self.collections.len().saturating_sub(1)
     }
 }
 
@@ -320,6 +386,7 @@ where
     let command_name = match &args {
         Args::Generate { .. } => "generate",
         Args::Import { .. } => "import",
+        Args::Validate { .. } => "validate",
         Args::Telemetry(TelemetryCommand::Enable) => "telemetry::enable",
         Args::Telemetry(TelemetryCommand::Disable) => "telemetry::disable",
         Args::Telemetry(TelemetryCommand::Status) => "telemetry::status",
@@ -330,6 +397,9 @@ where
         Args::Generate(GenerateCommand { to: uri_string, .. })
         | Args::Import(ImportCommand {
             from: uri_string, ..
+        })
+        | Args::Validate(ValidateCommand {
+            from: uri_string, ..
         }) => uriparse::URI::try_from(uri_string.as_str())
             .map(|uri| uri.scheme().to_string())
             .ok(),
@@ -527,13 +597,12 @@ impl TelemetryClient {
 #[cfg(test)]
 pub mod tests {
     use super::{
-        ExportStrategy, Namespace, SamplerOutput, TelemetryClient, TelemetryContext,
+        ExportStrategy, Namespace, SampleStream, TelemetryClient, TelemetryContext,
         TelemetryExportStrategy,
     };
-    use crate::sampler::Sampler;
+    use crate::sampler::{SampleSize, Sampler};
     use anyhow::Result;
     use std::cell::RefCell;
-    use std::convert::TryFrom;
     use std::path::PathBuf;
     use std::rc::Rc;
 
@@ -549,7 +618,10 @@ pub mod tests {
     pub struct DummyExportStrategy {}
 
     impl ExportStrategy for DummyExportStrategy {
-        fn export(&self, _namespace: Namespace, _sample: SamplerOutput) -> Result<()> {
+        fn export(&self, _namespace: Namespace, sample: SampleStream) -> Result<()> {
+            for batch in sample {
+                batch?;
+            }
             Ok(())
         }
     }
@@ -606,10 +678,9 @@ pub mod tests {
             PathBuf::from("/dummy/path"),
         );
 
-        let sample = Sampler::try_from(&schema)
+        let sample = Sampler::new(&schema, &SampleSize::Total(1))
             .unwrap()
-            .sample_seeded(None, 1, 500)
-            .unwrap();
+            .sample_seeded(None, 500);
 
         export_strategy.export(schema, sample).unwrap();
 
@@ -692,10 +763,9 @@ pub mod tests {
         .into_namespace()
         .unwrap();
 
-        let sample = Sampler::try_from(&schema)
+        let sample = Sampler::new(&schema, &SampleSize::Total(1))
             .unwrap()
-            .sample_seeded(None, 1, 500)
-            .unwrap();
+            .sample_seeded(None, 500);
 
         export_strategy.export(schema, sample).unwrap();
 
@@ -766,10 +836,9 @@ pub mod tests {
         .into_namespace()
         .unwrap();
 
-        let sample = Sampler::try_from(&schema)
+        let sample = Sampler::new(&schema, &SampleSize::Total(1))
             .unwrap()
-            .sample_seeded(None, 1, 500)
-            .unwrap();
+            .sample_seeded(None, 500);
 
         export_strategy.export(schema, sample).unwrap();
 
@@ -840,10 +909,9 @@ pub mod tests {
         .into_namespace()
         .unwrap();
 
-        let sample = Sampler::try_from(&schema)
+        let sample = Sampler::new(&schema, &SampleSize::Total(1))
             .unwrap()
-            .sample_seeded(None, 1, 500)
-            .unwrap();
+            .sample_seeded(None, 500);
 
         export_strategy.export(schema, sample).unwrap();
 
@@ -0,0 +1,583 @@
+//! Reads the CHECK constraints over a single column, as printed by Postgres'
+//! `pg_get_constraintdef`, into bounds on the content generating the column.
+//!
+//! Only conjunctions of comparisons to constants and `IN` lists are understood, like
+//! `CHECK (((qty >= 1) AND (qty <= 10)))` or `CHECK ((status = ANY (ARRAY['a'::text, 'b'::text])))`.
+//! Anything else is skipped.
+
+use std::str::FromStr;
+use synth_core::schema::number_content::{F32, F64, I16, I32, I64};
+use synth_core::schema::{Categorical, CategoricalType, NumberContent, RangeStep, StringContent};
+use synth_core::Content;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Comparison {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+}
+
+impl Comparison {
+    fn from_operator(operator: &str) -> Option<Self> {
+        match operator {
+            "<" => Some(Self::Lt),
+            "<=" => Some(Self::Le),
+            ">" => Some(Self::Gt),
+            ">=" => Some(Self::Ge),
+            "=" => Some(Self::Eq),
+            _ => None,
+        }
+    }
+
+    /// The comparison with its sides swapped, so that `0 < x` becomes `x > 0`.
+    fn flip(self) -> Self {
+        match self {
+            Self::Lt => Self::Gt,
+            Self::Le => Self::Ge,
+            Self::Gt => Self::Lt,
+            Self::Ge => Self::Le,
+            Self::Eq => Self::Eq,
+        }
+    }
+}
+
+/// A condition on the values of a column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Check {
+    /// The column compared to a constant, like `price > 0`
+    Compare(Comparison, String),
+    /// The column is one of the constants, like `status IN ('active', 'inactive')`
+    In(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    OpenBracket,
+    CloseBracket,
+    Comma,
+    Operator(String),
+    /// A keyword, function name or unquoted identifier
+    Word(String),
+    /// A quoted identifier
+    Identifier(String),
+    Literal(String),
+}
+
+const KEYWORDS: [&str; 8] = ["AND", "OR", "NOT", "IN", "IS", "ANY", "ALL", "BETWEEN"];
+
+fn is_keyword(word: &str) -> bool {
+    KEYWORDS
+        .iter()
+        .any(|keyword| word.eq_ignore_ascii_case(keyword))
+}
+
+/// Splits `definition` into tokens, leaving out type casts like `::numeric` or `::text[]`.
+fn tokenize(definition: &str) -> Option<Vec<Token>> {
+    let chars: Vec<char> = definition.chars().collect();
+    let mut tokens = Vec::new();
+    let mut at = 0;
+
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let read_word = |at: &mut usize| {
+        let start = *at;
+        while *at < chars.len() && is_word_char(chars[*at]) {
+            *at += 1;
+        }
+        chars[start..*at].iter().collect::<String>()
+    };
+    let read_quoted = |at: &mut usize, quote: char| {
+        let mut quoted = String::new();
+        *at += 1;
+        loop {
+            match chars.get(*at) {
+                Some(c) if *c == quote && chars.get(*at + 1) == Some(&quote) => {
+                    quoted.push(quote);
+                    *at += 2;
+                }
+                Some(c) if *c == quote => {
+                    *at += 1;
+                    return Some(quoted);
+                }
+                Some(c) => {
+                    quoted.push(*c);
+                    *at += 1;
+                }
+                None => return None,
+            }
+        }
+    };
+
+    while at < chars.len() {
+        let c = chars[at];
+        match c {
+            _ if c.is_whitespace() => at += 1,
+            ':' if chars.get(at + 1) == Some(&':') => {
+                // A cast to a type, which can be several words long like `double precision`
+                at += 2;
+                loop {
+                    while chars.get(at) == Some(&' ') {
+                        at += 1;
+                    }
+                    let start = at;
+                    match chars.get(at) {
+                        Some('"') => {
+                            read_quoted(&mut at, '"')?;
+                        }
+                        Some(c) if is_word_char(*c) => {
+                            if is_keyword(&read_word(&mut at)) {
+                                at = start;
+                            }
+                        }
+                        // The modifiers of the type, like `(10)` in `character varying(10)`
+                        Some('(') => match chars[at..].iter().position(|c| *c == ')') {
+                            Some(close) => at += close + 1,
+                            None => return None,
+                        },
+                        Some('[') if chars.get(at + 1) == Some(&']') => at += 2,
+                        _ => {}
+                    }
+                    if at == start {
+                        break;
+                    }
+                }
+            }
+            '(' => {
+                tokens.push(Token::Open);
+                at += 1;
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                at += 1;
+            }
+            '[' => {
+                tokens.push(Token::OpenBracket);
+                at += 1;
+            }
+            ']' => {
+                tokens.push(Token::CloseBracket);
+                at += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                at += 1;
+            }
+            '\'' => tokens.push(Token::Literal(read_quoted(&mut at, '\'')?)),
+            '"' => tokens.push(Token::Identifier(read_quoted(&mut at, '"')?)),
+            _ if c.is_ascii_digit()
+                || c == '-' && chars.get(at + 1).map_or(false, char::is_ascii_digit) =>
+            {
+                let start = at;
+                at += 1;
+                while chars
+                    .get(at)
+                    .map_or(false, |c| c.is_ascii_digit() || *c == '.')
+                {
+                    at += 1;
+                }
+                tokens.push(Token::Literal(chars[start..at].iter().collect()));
+            }
+            '<' | '>' | '=' | '!' | '~' | '-' | '+' | '*' | '/' | '%' | '|' => {
+                let start = at;
+                while chars.get(at).map_or(false, |c| "<>=!~-+*/%|".contains(*c)) {
+                    at += 1;
+                }
+                tokens.push(Token::Operator(chars[start..at].iter().collect()));
+            }
+            _ if is_word_char(c) => tokens.push(Token::Word(read_word(&mut at))),
+            _ => return None,
+        }
+    }
+
+    Some(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Operand {
+    Column,
+    Constant(String),
+    Array(Vec<String>),
+    /// Anything else, like a function call
+    Other,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    at: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.at)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.at).cloned();
+        self.at += 1;
+        token
+    }
+
+    fn expect(&mut self, token: Token) -> Option<()> {
+        (self.next()? == token).then_some(())
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    /// Tries `parse`, going back to where it started if it fails.
+    fn attempt<T>(&mut self, parse: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        let at = self.at;
+        let parsed = parse(self);
+        if parsed.is_none() {
+            self.at = at;
+        }
+        parsed
+    }
+
+    /// The checks that all hold when the expression holds.
+    fn expression(&mut self) -> Option<Vec<Check>> {
+        let mut disjuncts = vec![self.conjunction()?];
+        while self.is_keyword("OR") {
+            self.next();
+            disjuncts.push(self.conjunction()?);
+        }
+        if disjuncts.len() == 1 {
+            disjuncts.pop()
+        } else {
+            Some(Vec::new())
+        }
+    }
+
+    fn conjunction(&mut self) -> Option<Vec<Check>> {
+        let mut checks = self.condition()?;
+        while self.is_keyword("AND") {
+            self.next();
+            checks.extend(self.condition()?);
+        }
+        Some(checks)
+    }
+
+    fn condition(&mut self) -> Option<Vec<Check>> {
+        if self.is_keyword("NOT") {
+            self.next();
+            self.condition()?;
+            return Some(Vec::new());
+        }
+
+        if let Some(checks) = self.attempt(Self::comparison) {
+            return Some(checks);
+        }
+
+        self.expect(Token::Open)?;
+        let checks = self.expression()?;
+        self.expect(Token::Close)?;
+        Some(checks)
+    }
+
+    fn comparison(&mut self) -> Option<Vec<Check>> {
+        let left = self.operand()?;
+        match self.next()? {
+            Token::Operator(operator) => {
+                if self.is_keyword("ANY") || self.is_keyword("ALL") {
+                    let any = self.is_keyword("ANY");
+                    self.next();
+                    self.expect(Token::Open)?;
+                    let right = self.operand()?;
+                    self.expect(Token::Close)?;
+                    return Some(match (left, operator.as_str(), right) {
+                        (Operand::Column, "=", Operand::Array(values)) if any => {
+                            vec![Check::In(values)]
+                        }
+                        _ => Vec::new(),
+                    });
+                }
+
+                let right = self.operand()?;
+                let comparison = Comparison::from_operator(&operator);
+                Some(match (left, comparison, right) {
+                    (Operand::Column, Some(comparison), Operand::Constant(value)) => {
+                        vec![Check::Compare(comparison, value)]
+                    }
+                    (Operand::Constant(value), Some(comparison), Operand::Column) => {
+                        vec![Check::Compare(comparison.flip(), value)]
+                    }
+                    _ => Vec::new(),
+                })
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("BETWEEN") => {
+                let low = self.operand()?;
+                if !self.is_keyword("AND") {
+                    return None;
+                }
+                self.next();
+                let high = self.operand()?;
+                Some(match (left, low, high) {
+                    (Operand::Column, Operand::Constant(low), Operand::Constant(high)) => vec![
+                        Check::Compare(Comparison::Ge, low),
+                        Check::Compare(Comparison::Le, high),
+                    ],
+                    _ => Vec::new(),
+                })
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("IN") => {
+                self.expect(Token::Open)?;
+                let values = self.constants(Token::Close)?;
+                Some(match left {
+                    Operand::Column => vec![Check::In(values)],
+                    _ => Vec::new(),
+                })
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("IS") => {
+                // Like `IS NOT NULL` or `IS TRUE`
+                if self.is_keyword("NOT") {
+                    self.next();
+                }
+                match self.next()? {
+                    Token::Word(word) if !is_keyword(&word) => Some(Vec::new()),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn operand(&mut self) -> Option<Operand> {
+        match self.next()? {
+            Token::Open => {
+                let operand = self.operand()?;
+                self.expect(Token::Close)?;
+                Some(operand)
+            }
+            Token::Literal(value) => Some(Operand::Constant(value)),
+            Token::Identifier(_) => Some(Operand::Column),
+            Token::Word(word) if word.eq_ignore_ascii_case("ARRAY") => {
+                self.expect(Token::OpenBracket)?;
+                Some(Operand::Array(self.constants(Token::CloseBracket)?))
+            }
+            Token::Word(word)
+                if word.eq_ignore_ascii_case("TRUE") || word.eq_ignore_ascii_case("FALSE") =>
+            {
+                Some(Operand::Constant(word.to_lowercase()))
+            }
+            Token::Word(word) if is_keyword(&word) => None,
+            Token::Word(_) if self.peek() == Some(&Token::Open) => {
+                // A function call, whose arguments are skipped
+                let mut depth = 0;
+                loop {
+                    match self.next()? {
+                        Token::Open => depth += 1,
+                        Token::Close if depth == 1 => return Some(Operand::Other),
+                        Token::Close => depth -= 1,
+                        _ => {}
+                    }
+                }
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("NULL") => Some(Operand::Other),
+            // Either the column or `VALUE` in the constraints of a domain
+            Token::Word(_) => Some(Operand::Column),
+            _ => None,
+        }
+    }
+
+    /// Constants separated by commas, up to `close`.
+    fn constants(&mut self, close: Token) -> Option<Vec<String>> {
+        let mut values = Vec::new();
+        loop {
+            match self.operand()? {
+                Operand::Constant(value) => values.push(value),
+                _ => return None,
+            }
+            match self.next()? {
+                Token::Comma => continue,
+                token if token == close => return Some(values),
+                _ => return None,
+            }
+        }
+    }
+}
+
+/// The conditions that all hold for the values of a column with the CHECK constraint given by
+/// `definition`, as far as they are understood.
+pub(crate) fn parse(definition: &str) -> Vec<Check> {
+    let definition = definition.trim();
+    let expression = match definition.get(..5) {
+        Some(check) if check.eq_ignore_ascii_case("CHECK") => &definition[5..],
+        _ => definition,
+    };
+    let expression = expression.trim_end_matches(" NOT VALID");
+
+    tokenize(expression)
+        .and_then(|tokens| {
+            let mut parser = Parser { tokens, at: 0 };
+            let checks = parser.expression()?;
+            (parser.at == parser.tokens.len()).then_some(checks)
+        })
+        .unwrap_or_else(|| {
+            debug!("ignoring the constraint {}", definition);
+            Vec::new()
+        })
+}
+
+fn bound<N: FromStr + PartialOrd + Copy>(
+    range: &mut RangeStep<N>,
+    comparison: Comparison,
+    value: &str,
+    exclusive_low: bool,
+) {
+    let value = match value.parse::<N>() {
+        Ok(value) => value,
+        Err(_) => return,
+    };
+    let raise_low = |range: &mut RangeStep<N>, include_low: bool| {
+        if range.low.map_or(true, |low| low <= value) {
+            range.low = Some(value);
+            range.include_low = include_low;
+        }
+    };
+    let lower_high = |range: &mut RangeStep<N>, include_high: bool| {
+        if range.high.map_or(true, |high| high >= value) {
+            range.high = Some(value);
+            range.include_high = include_high;
+        }
+    };
+    match comparison {
+        Comparison::Gt => raise_low(range, !exclusive_low),
+        Comparison::Ge => raise_low(range, true),
+        Comparison::Lt => lower_high(range, false),
+        Comparison::Le => lower_high(range, true),
+        Comparison::Eq => {
+            raise_low(range, true);
+            lower_high(range, true);
+        }
+    }
+}
+
+fn categorical<N: CategoricalType + Default>(values: &[String]) -> Option<Categorical<N>> {
+    let mut categorical = Categorical::default();
+    for value in values {
+        categorical.push(value.parse().ok()?);
+    }
+    Some(categorical)
+}
+
+/// Narrows `content` down to the values satisfying `check`, where it knows how to.
+pub(crate) fn constrain(content: &mut Content, check: &Check) {
+    match (content, check) {
+        (Content::Number(number), Check::Compare(comparison, value)) => match number {
+            NumberContent::I16(I16::Range(range)) => bound(range, *comparison, value, true),
+            NumberContent::I32(I32::Range(range)) => bound(range, *comparison, value, true),
+            NumberContent::I64(I64::Range(range)) => bound(range, *comparison, value, true),
+            // Float ranges can only exclude their low with a step, and are unlikely to generate
+            // exactly their low anyway
+            NumberContent::F32(F32::Range(range)) => {
+                bound(range, *comparison, value, range.step.is_some())
+            }
+            NumberContent::F64(F64::Range(range)) => {
+                bound(range, *comparison, value, range.step.is_some())
+            }
+            _ => {}
+        },
+        (Content::Number(number), Check::In(values)) => {
+            let categorical = match number {
+                NumberContent::I16(_) => categorical(values).map(I16::Categorical).map(Into::into),
+                NumberContent::I32(_) => categorical(values).map(I32::Categorical).map(Into::into),
+                NumberContent::I64(_) => categorical(values).map(I64::Categorical).map(Into::into),
+                _ => None,
+            };
+            if let Some(categorical) = categorical {
+                *number = categorical;
+            }
+        }
+        (Content::String(string @ StringContent::Pattern(_)), Check::In(values)) => {
+            if let Some(categorical) = categorical(values) {
+                *string = StringContent::Categorical(categorical);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_postgres_checks() {
+        assert_eq!(
+            parse("CHECK ((price > (0)::numeric))"),
+            vec![Check::Compare(Comparison::Gt, "0".to_string())]
+        );
+        assert_eq!(
+            parse("CHECK (((qty >= 1) AND (qty <= 10)))"),
+            vec![
+                Check::Compare(Comparison::Ge, "1".to_string()),
+                Check::Compare(Comparison::Le, "10".to_string())
+            ]
+        );
+        assert_eq!(
+            parse("CHECK (((pct >= (0)::double precision) AND (pct <= (1)::double precision)))"),
+            vec![
+                Check::Compare(Comparison::Ge, "0".to_string()),
+                Check::Compare(Comparison::Le, "1".to_string())
+            ]
+        );
+        assert_eq!(
+            parse("CHECK ((status = ANY (ARRAY['active'::text, 'in''active'::text])))"),
+            vec![Check::In(vec![
+                "active".to_string(),
+                "in'active".to_string()
+            ])]
+        );
+        assert_eq!(
+            parse("CHECK (((VALUE)::text = ANY ((ARRAY['YES'::character varying, 'NO'::character varying])::text[])))"),
+            vec![Check::In(vec!["YES".to_string(), "NO".to_string()])]
+        );
+        assert_eq!(
+            parse("CHECK ((VALUE > '-5'::integer))"),
+            vec![Check::Compare(Comparison::Gt, "-5".to_string())]
+        );
+        assert_eq!(
+            parse("CHECK ((0 < \"Quantity\"))"),
+            vec![Check::Compare(Comparison::Gt, "0".to_string())]
+        );
+        assert_eq!(
+            parse("CHECK (((qty > 0) AND (length(name) > 3)))"),
+            vec![Check::Compare(Comparison::Gt, "0".to_string())]
+        );
+        assert_eq!(parse("CHECK (((qty < 100) OR (qty > 200)))"), vec![]);
+        assert_eq!(parse("CHECK ((name ~~ 'a%'::text))"), vec![]);
+        assert_eq!(parse("CHECK ((qty IS NOT NULL))"), vec![]);
+    }
+
+    #[test]
+    fn constrain_content() {
+        let mut content = Content::Number(NumberContent::I32(I32::Range(RangeStep::default())));
+        for check in parse("CHECK (((qty > 0) AND (qty <= 10)))") {
+            constrain(&mut content, &check);
+        }
+        assert_eq!(
+            content,
+            Content::Number(NumberContent::I32(I32::Range(RangeStep {
+                low: Some(0),
+                high: Some(10),
+                step: None,
+                include_low: false,
+                include_high: true,
+            })))
+        );
+
+        let mut content = Content::Number(NumberContent::I64(I64::Range(RangeStep::default())));
+        constrain(
+            &mut content,
+            &Check::In(vec!["1".to_string(), "2".to_string()]),
+        );
+        assert!(matches!(
+            content,
+            Content::Number(NumberContent::I64(I64::Categorical(_)))
+        ));
+    }
+}
@@ -0,0 +1,645 @@
+//! Derives `CREATE TABLE` statements from a namespace, so that it can be exported to a database
+//! whose tables do not exist yet.
+use crate::datasource::relational_datasource::SqlxDataSource;
+use anyhow::{Context, Result};
+use sqlx::{Database, Encode, Executor, Type};
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+use synth_core::graph::ExpressionType;
+use synth_core::schema::{ArrayContent, ChronoValueType, FieldRef, NumberContent, SameAsContent};
+use synth_core::{Content, Namespace, Value};
+
+/// The SQL dialects tables can be created in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SqlDialect {
+    Postgres,
+    MySql,
+}
+
+impl FromStr for SqlDialect {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "postgres" | "postgresql" => Ok(SqlDialect::Postgres),
+            "mysql" | "mariadb" => Ok(SqlDialect::MySql),
+            _ => Err(anyhow!(
+                "Unknown SQL dialect '{}'. Was expecting one of 'postgres' or 'mysql'.",
+                s
+            )),
+        }
+    }
+}
+
+impl SqlDialect {
+    pub(crate) fn quote(self, identifier: &str) -> String {
+        let quote = match self {
+            SqlDialect::Postgres => '"',
+            SqlDialect::MySql => '`',
+        };
+        format!("{quote}{identifier}{quote}")
+    }
+
+    /// The type of the column `content` is inserted into. Types match how `Value`s are encoded
+    /// when inserted, e.g. a `u32` is sent to Postgres as an `int4`.
+    fn column_type(self, namespace: &Namespace, content: &Content) -> Result<String> {
+        let column_type = match (self, content) {
+            (_, Content::Unique(unique)) => return self.column_type(namespace, &unique.content),
+            (_, Content::SameAs(SameAsContent { ref_, .. })) => {
+                return self.column_type(namespace, namespace.get_s_node(ref_)?)
+            }
+            (_, Content::OneOf(one_of)) => {
+                return self.common_column_type(namespace, one_of.iter(), "variants of a one_of")
+            }
+            (_, Content::Switch(switch)) => {
+                return self.common_column_type(namespace, switch.iter(), "cases of a switch")
+            }
+            (SqlDialect::Postgres, Content::Bool(_)) => "boolean",
+            (SqlDialect::MySql, Content::Bool(_)) => "BOOLEAN",
+            (SqlDialect::Postgres, Content::Number(number)) => match number {
+                NumberContent::I16(_) => "smallint",
+                NumberContent::U32(_) | NumberContent::I32(_) => "integer",
+                NumberContent::U64(_) | NumberContent::I64(_) => "bigint",
+                NumberContent::F32(_) => "real",
+                NumberContent::F64(_) => "double precision",
+            },
+            (SqlDialect::MySql, Content::Number(number)) => match number {
+                NumberContent::I16(_) => "SMALLINT",
+                NumberContent::U32(_) => "INT UNSIGNED",
+                NumberContent::I32(_) => "INT",
+                NumberContent::U64(_) | NumberContent::I64(_) => "BIGINT",
+                NumberContent::F32(_) => "FLOAT",
+                NumberContent::F64(_) => "DOUBLE",
+            },
+            (SqlDialect::Postgres, Content::String(_)) => "text",
+            (SqlDialect::MySql, Content::String(_)) => "TEXT",
+            (_, Content::DateTime(date_time)) => self.date_time_type(date_time.type_),
+            (_, Content::Expression(expression)) => {
+                let types = expression.value_types(namespace)?;
+                return match types
+                    .iter()
+                    .filter(|type_| **type_ != ExpressionType::Null)
+                    .collect::<Vec<_>>()[..]
+                {
+                    [type_] => self.expression_column_type(*type_),
+                    _ => Err(anyhow!(
+                        "Cannot derive a column type from the expression '{}', which can evaluate to any of {:?}",
+                        expression.expression,
+                        types
+                    )),
+                }
+                .map(str::to_string);
+            }
+            (SqlDialect::Postgres, Content::Object(_)) => "jsonb",
+            (SqlDialect::MySql, Content::Object(_)) => "JSON",
+            (SqlDialect::Postgres, Content::Array(ArrayContent { content, .. })) => {
+                let item = self.column_type(namespace, content)?;
+                // Arrays of objects are inserted as a single JSON array
+                return Ok(if item == "jsonb" {
+                    item
+                } else {
+                    format!("{item}[]")
+                });
+            }
+            (SqlDialect::MySql, Content::Array(_)) => {
+                bail!("Arrays cannot be exported to MySQL")
+            }
+            (_, other) => bail!("Cannot derive a column type from {} content", other.kind()),
+        };
+
+        Ok(column_type.to_string())
+    }
+
+    fn date_time_type(self, type_: ChronoValueType) -> &'static str {
+        match (self, type_) {
+            (SqlDialect::Postgres, ChronoValueType::NaiveDate) => "date",
+            (SqlDialect::Postgres, ChronoValueType::NaiveTime) => "time",
+            (SqlDialect::Postgres, ChronoValueType::NaiveDateTime) => "timestamp",
+            (SqlDialect::Postgres, ChronoValueType::DateTime) => "timestamptz",
+            (SqlDialect::MySql, ChronoValueType::NaiveDate) => "DATE",
+            (SqlDialect::MySql, ChronoValueType::NaiveTime) => "TIME",
+            (SqlDialect::MySql, ChronoValueType::NaiveDateTime | ChronoValueType::DateTime) => {
+                "DATETIME"
+            }
+        }
+    }
+
+    /// The type of the column the values of an expression are inserted into, as they are
+    /// evaluated to an `i64`, an `f64`, a string, a bool or a date/time.
+    fn expression_column_type(self, type_: ExpressionType) -> Result<&'static str> {
+        let column_type = match (self, type_) {
+            (SqlDialect::Postgres, ExpressionType::Bool) => "boolean",
+            (SqlDialect::MySql, ExpressionType::Bool) => "BOOLEAN",
+            (SqlDialect::Postgres, ExpressionType::Int) => "bigint",
+            (SqlDialect::MySql, ExpressionType::Int) => "BIGINT",
+            (SqlDialect::Postgres, ExpressionType::Float) => "double precision",
+            (SqlDialect::MySql, ExpressionType::Float) => "DOUBLE",
+            (SqlDialect::Postgres, ExpressionType::String) => "text",
+            (SqlDialect::MySql, ExpressionType::String) => "TEXT",
+            (_, ExpressionType::DateTime(Some(type_))) => self.date_time_type(type_),
+            (_, ExpressionType::DateTime(None)) => bail!(
+                "Cannot derive a column type from an expression parsing a date/time with a format"
+            ),
+            (_, ExpressionType::Null | ExpressionType::Duration) => {
+                bail!(
+                    "Cannot derive a column type from an expression of {:?}",
+                    type_
+                )
+            }
+        };
+        Ok(column_type)
+    }
+
+    /// The column type shared by all the non-null `contents`, one of which is inserted in each row.
+    fn common_column_type<'c>(
+        self,
+        namespace: &Namespace,
+        contents: impl Iterator<Item = &'c Content>,
+        what: &str,
+    ) -> Result<String> {
+        let types = contents
+            .filter(|content| !content.is_null())
+            .map(|content| self.column_type(namespace, content))
+            .collect::<Result<BTreeSet<_>>>()?;
+
+        match types.len() {
+            1 => Ok(types.into_iter().next().unwrap()),
+            _ => Err(anyhow!(
+                "The {} must all have the same column type, found {}",
+                what,
+                types.into_iter().collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+
+    /// Text columns cannot be keys in MySQL without a maximum length.
+    fn key_column_type(self, column_type: String) -> String {
+        match (self, column_type.as_str()) {
+            (SqlDialect::MySql, "TEXT") => "VARCHAR(255)".to_string(),
+            _ => column_type,
+        }
+    }
+}
+
+struct Column {
+    name: String,
+    column_type: String,
+    nullable: bool,
+    unique: bool,
+}
+
+struct ForeignKey {
+    column: String,
+    to_table: String,
+    to_column: String,
+}
+
+struct Table {
+    name: String,
+    columns: Vec<Column>,
+    primary_key: Vec<String>,
+    foreign_keys: Vec<ForeignKey>,
+    /// Sets of columns whose values are unique together
+    unique: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn from_collection(namespace: &Namespace, name: &str, dialect: SqlDialect) -> Result<Self> {
+        let object = match namespace.get_collection(name)? {
+            Content::Array(ArrayContent {
+                content: box Content::Object(object),
+                ..
+            }) => object,
+            _ => bail!("Only collections of objects can be exported to tables"),
+        };
+
+        let mut table = Table {
+            name: name.to_string(),
+            columns: Vec::new(),
+            primary_key: Vec::new(),
+            foreign_keys: Vec::new(),
+            unique: Vec::new(),
+        };
+
+        for key in &object.unique_together {
+            if key.iter().any(|path| path.contains('.')) {
+                warn!(
+                    "Not creating a unique constraint on {}({}), as it is not only made of columns",
+                    name,
+                    key.join(", ")
+                );
+            } else {
+                table.unique.push(key.clone());
+            }
+        }
+        let unique_columns: BTreeSet<&String> = table.unique.iter().flatten().collect();
+
+        for (column, content) in &object.fields {
+            let (content, nullable) = match content {
+                Content::Switch(switch) => (content, switch.iter().any(Content::is_null)),
+                Content::Expression(expression) => {
+                    let types = expression
+                        .value_types(namespace)
+                        .with_context(|| format!("In the column {name}.{column}"))?;
+                    (content, types.contains(&ExpressionType::Null))
+                }
+                _ => match content.as_nullable() {
+                    Some(content) => (content, true),
+                    None => (content, false),
+                },
+            };
+
+            let is_key = match content {
+                // Hidden fields are never inserted
+                Content::Hidden(_) => continue,
+                Content::Number(number) if number.is_id() => {
+                    table.primary_key.push(column.clone());
+                    true
+                }
+                Content::SameAs(SameAsContent { ref_, .. }) => {
+                    match foreign_key(namespace, column, ref_) {
+                        Some(foreign_key) => table.foreign_keys.push(foreign_key),
+                        None => warn!(
+                            "Not creating a foreign key from {}.{} to {}, as it does not reference a primary key or unique column of a table",
+                            name, column, ref_
+                        ),
+                    }
+                    true
+                }
+                Content::Unique(_) => true,
+                _ => unique_columns.contains(column),
+            };
+
+            let column_type = dialect
+                .column_type(namespace, content)
+                .with_context(|| format!("In the column {name}.{column}"))?;
+
+            table.columns.push(Column {
+                name: column.clone(),
+                column_type: if is_key {
+                    dialect.key_column_type(column_type)
+                } else {
+                    column_type
+                },
+                nullable,
+                unique: matches!(content, Content::Unique(_)),
+            });
+        }
+
+        Ok(table)
+    }
+
+    /// The other tables this one references, which must be created first.
+    fn dependencies(&self) -> BTreeSet<&str> {
+        self.foreign_keys
+            .iter()
+            .map(|foreign_key| foreign_key.to_table.as_str())
+            .filter(|to_table| *to_table != self.name)
+            .collect()
+    }
+
+    fn to_sql(&self, dialect: SqlDialect) -> String {
+        let mut definitions: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| {
+                let mut definition =
+                    format!("{} {}", dialect.quote(&column.name), column.column_type);
+                if !column.nullable {
+                    definition.push_str(" NOT NULL");
+                }
+                if column.unique {
+                    definition.push_str(" UNIQUE");
+                }
+                definition
+            })
+            .collect();
+
+        if !self.primary_key.is_empty() {
+            definitions.push(format!(
+                "PRIMARY KEY ({})",
+                quote_all(dialect, &self.primary_key)
+            ));
+        }
+
+        for key in &self.unique {
+            definitions.push(format!("UNIQUE ({})", quote_all(dialect, key)));
+        }
+
+        for foreign_key in &self.foreign_keys {
+            definitions.push(format!(
+                "FOREIGN KEY ({}) REFERENCES {} ({})",
+                dialect.quote(&foreign_key.column),
+                dialect.quote(&foreign_key.to_table),
+                dialect.quote(&foreign_key.to_column)
+            ));
+        }
+
+        format!(
+            "CREATE TABLE IF NOT EXISTS {} (\n    {}\n);",
+            dialect.quote(&self.name),
+            definitions.join(",\n    ")
+        )
+    }
+}
+
+/// A foreign key to what `ref_` references, if it is a primary key or a unique column of a table.
+fn foreign_key(namespace: &Namespace, column: &str, ref_: &FieldRef) -> Option<ForeignKey> {
+    let to_column = match ref_.iter_fields().collect::<Vec<_>>()[..] {
+        ["content", to_column] => to_column,
+        _ => return None,
+    };
+
+    match namespace.get_s_node(ref_).ok()? {
+        Content::Number(number) if number.is_id() => {}
+        Content::Unique(_) => {}
+        _ => return None,
+    }
+
+    Some(ForeignKey {
+        column: column.to_string(),
+        to_table: ref_.collection().to_string(),
+        to_column: to_column.to_string(),
+    })
+}
+
+fn quote_all(dialect: SqlDialect, identifiers: &[String]) -> String {
+    identifiers
+        .iter()
+        .map(|identifier| dialect.quote(identifier))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The `CREATE TABLE` statements of every collection of `namespace`, ordered so that tables are
+/// created before the tables whose foreign keys reference them.
+pub(crate) fn create_table_statements(
+    namespace: &Namespace,
+    dialect: SqlDialect,
+) -> Result<Vec<String>> {
+    let mut remaining = namespace
+        .keys()
+        .map(|name| {
+            Table::from_collection(namespace, name, dialect)
+                .with_context(|| format!("While deriving the table of the collection {name}"))
+                .map(|table| (name.to_string(), table))
+        })
+        .collect::<Result<BTreeMap<_, _>>>()?;
+
+    let mut statements = Vec::with_capacity(remaining.len());
+    while !remaining.is_empty() {
+        let ready: Vec<String> = remaining
+            .values()
+            .filter(|table| {
+                table
+                    .dependencies()
+                    .iter()
+                    .all(|dependency| !remaining.contains_key(*dependency))
+            })
+            .map(|table| table.name.clone())
+            .collect();
+
+        if ready.is_empty() {
+            bail!(
+                "The foreign keys between the collections {} form a cycle, so their tables cannot be created",
+                remaining.keys().cloned().collect::<Vec<_>>().join(", ")
+            );
+        }
+
+        for name in ready {
+            statements.push(remaining.remove(&name).unwrap().to_sql(dialect));
+        }
+    }
+
+    Ok(statements)
+}
+
+/// Creates the tables of every collection of `namespace` that do not exist yet.
+pub(crate) async fn create_tables<T: SqlxDataSource + Sync>(
+    datasource: &T,
+    namespace: &Namespace,
+    dialect: SqlDialect,
+) -> Result<()>
+where
+    for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
+    Value: Type<T::DB>,
+    for<'d> Value: Encode<'d, T::DB>,
+    <T::DB as Database>::QueryResult: Send,
+{
+    for statement in create_table_statements(namespace, dialect)? {
+        debug!("Creating table: {}", statement);
+        datasource
+            .execute_query(statement, Vec::new())
+            .await
+            .context("Failed to create a table")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use synth_core::schema::ExpressionContent;
+
+    fn namespace(value: serde_json::Value) -> Namespace {
+        serde_json::from_value::<Content>(value)
+            .unwrap()
+            .into_namespace()
+            .unwrap()
+    }
+
+    fn bank() -> Namespace {
+        namespace(serde_json::json!({
+            "type": "object",
+            "transactions": {
+                "type": "array",
+                "length": 1,
+                "content": {
+                    "type": "object",
+                    "unique_together": [["user_id", "timestamp"]],
+                    "id": { "type": "number", "subtype": "u64", "id": {} },
+                    "user_id": { "type": "same_as", "ref": "users.content.id" },
+                    "amount": { "type": "number", "subtype": "f64", "range": { "low": 0, "high": 10 } },
+                    "note": {
+                        "type": "one_of",
+                        "variants": [{ "type": "null" }, { "type": "string", "pattern": "[a-z]+" }]
+                    },
+                    "timestamp": { "type": "date_time", "format": "%Y-%m-%dT%H:%M:%S%z", "subtype": "date_time" },
+                    "card_last4": {
+                        "type": "switch",
+                        "on": "@transactions.content.note",
+                        "cases": { "card": { "type": "string", "pattern": "[0-9]{4}" } }
+                    }
+                }
+            },
+            "users": {
+                "type": "array",
+                "length": 1,
+                "content": {
+                    "type": "object",
+                    "id": { "type": "number", "subtype": "i32", "id": {} },
+                    "email": { "type": "unique", "content": { "type": "string", "faker": { "generator": "safe_email" } } },
+                    "tags": {
+                        "type": "array",
+                        "length": 1,
+                        "content": { "type": "string", "pattern": "[a-z]+" }
+                    },
+                    "secret": { "type": "hidden", "content": { "type": "bool", "frequency": 0.5 } }
+                }
+            }
+        }))
+    }
+
+    #[test]
+    fn postgres_tables() {
+        assert_eq!(
+            create_table_statements(&bank(), SqlDialect::Postgres).unwrap(),
+            vec![
+                "CREATE TABLE IF NOT EXISTS \"users\" (
+    \"email\" text NOT NULL UNIQUE,
+    \"id\" integer NOT NULL,
+    \"tags\" text[] NOT NULL,
+    PRIMARY KEY (\"id\")
+);",
+                "CREATE TABLE IF NOT EXISTS \"transactions\" (
+    \"amount\" double precision NOT NULL,
+    \"card_last4\" text,
+    \"id\" bigint NOT NULL,
+    \"note\" text,
+    \"timestamp\" timestamptz NOT NULL,
+    \"user_id\" integer NOT NULL,
+    PRIMARY KEY (\"id\"),
+    UNIQUE (\"user_id\", \"timestamp\"),
+    FOREIGN KEY (\"user_id\") REFERENCES \"users\" (\"id\")
+);",
+            ]
+        );
+    }
+
+    #[test]
+    fn mysql_tables() {
+        let mut namespace = bank();
+        namespace
+            .get_collection_mut("users")
+            .map(|users| match users {
+                Content::Array(ArrayContent {
+                    content: box Content::Object(object),
+                    ..
+                }) => object.fields.remove("tags"),
+                _ => unreachable!(),
+            })
+            .unwrap();
+
+        assert_eq!(
+            create_table_statements(&namespace, SqlDialect::MySql).unwrap()[0],
+            "CREATE TABLE IF NOT EXISTS `users` (
+    `email` VARCHAR(255) NOT NULL UNIQUE,
+    `id` INT NOT NULL,
+    PRIMARY KEY (`id`)
+);"
+        );
+        assert!(create_table_statements(&bank(), SqlDialect::MySql).is_err());
+    }
+
+    #[test]
+    fn expression_columns() {
+        let mut orders = namespace(serde_json::json!({
+            "type": "object",
+            "orders": {
+                "type": "array",
+                "length": 1,
+                "content": {
+                    "type": "object",
+                    "quantity": { "type": "number", "subtype": "u32", "range": { "low": 1, "high": 10 } },
+                    "unit_price": { "type": "number", "subtype": "f64", "range": { "low": 1, "high": 50 } },
+                    "placed": { "type": "date_time", "format": "%Y-%m-%d", "subtype": "naive_date" },
+                    "total": {
+                        "type": "expression",
+                        "expression": "round(quantity * unit_price, 2)",
+                        "arguments": {
+                            "quantity": "@orders.content.quantity",
+                            "unit_price": "@orders.content.unit_price"
+                        }
+                    },
+                    "items": {
+                        "type": "expression",
+                        "expression": "quantity * 2",
+                        "arguments": { "quantity": "@orders.content.quantity" }
+                    },
+                    "size": {
+                        "type": "expression",
+                        "expression": "quantity > 5 ? 'bulk' : null",
+                        "arguments": { "quantity": "@orders.content.quantity" }
+                    },
+                    "due": {
+                        "type": "expression",
+                        "expression": "placed + duration('30d')",
+                        "arguments": { "placed": "@orders.content.placed" }
+                    }
+                }
+            }
+        }));
+
+        assert_eq!(
+            create_table_statements(&orders, SqlDialect::Postgres).unwrap(),
+            vec![
+                "CREATE TABLE IF NOT EXISTS \"orders\" (
+    \"due\" date NOT NULL,
+    \"items\" bigint NOT NULL,
+    \"placed\" date NOT NULL,
+    \"quantity\" integer NOT NULL,
+    \"size\" text,
+    \"total\" double precision NOT NULL,
+    \"unit_price\" double precision NOT NULL
+);"
+            ]
+        );
+
+        let mixed: ExpressionContent = serde_json::from_value(serde_json::json!({
+            "expression": "quantity > 5 ? 'bulk' : quantity",
+            "arguments": { "quantity": "@orders.content.quantity" }
+        }))
+        .unwrap();
+        orders
+            .get_collection_mut("orders")
+            .map(|orders| match orders {
+                Content::Array(ArrayContent {
+                    content: box Content::Object(object),
+                    ..
+                }) => object
+                    .fields
+                    .insert("size".to_string(), Content::Expression(mixed)),
+                _ => unreachable!(),
+            })
+            .unwrap();
+        assert!(create_table_statements(&orders, SqlDialect::Postgres).is_err());
+    }
+
+    #[test]
+    fn cycles_are_rejected() {
+        let namespace = namespace(serde_json::json!({
+            "type": "object",
+            "a": {
+                "type": "array",
+                "length": 1,
+                "content": {
+                    "type": "object",
+                    "id": { "type": "number", "subtype": "u64", "id": {} },
+                    "b_id": { "type": "same_as", "ref": "b.content.id" }
+                }
+            },
+            "b": {
+                "type": "array",
+                "length": 1,
+                "content": {
+                    "type": "object",
+                    "id": { "type": "number", "subtype": "u64", "id": {} },
+                    "a_id": { "type": "same_as", "ref": "a.content.id" }
+                }
+            }
+        }));
+
+        assert!(create_table_statements(&namespace, SqlDialect::Postgres).is_err());
+    }
+}
@@ -2,9 +2,13 @@ use anyhow::Result;
 use async_trait::async_trait;
 use synth_core::Value;
 
+pub(crate) mod check;
+pub(crate) mod ddl;
 pub(crate) mod mysql_datasource;
 pub(crate) mod postgres_datasource;
 pub(crate) mod relational_datasource;
+pub(crate) mod sampling;
+pub(crate) mod sqlite_datasource;
 
 /// This trait encompasses all data source types, whether it's SQL or No-SQL. APIs should be defined
 /// async when possible, delegating to the caller on how to handle it. Data source specific
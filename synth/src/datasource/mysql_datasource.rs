@@ -1,6 +1,8 @@
 use crate::datasource::relational_datasource::{
-    insert_relational_data, ColumnInfo, ForeignKey, PrimaryKey, SqlxDataSource, ValueWrapper,
+    insert_relational_data, ColumnInfo, ForeignKey, PrimaryKey, SqlxDataSource, UniqueKeyColumn,
+    ValueWrapper,
 };
+use crate::datasource::sampling::{Sampling, SamplingStrategy};
 use crate::datasource::DataSource;
 use anyhow::{Context, Result};
 use async_std::task;
@@ -48,7 +50,7 @@ impl DataSource for MySqlDataSource {
 
 impl SqlxDataSource for MySqlDataSource {
     type DB = MySql;
-    type Arguments = sqlx::mysql::MySqlArguments;
+    type Arguments<'q> = sqlx::mysql::MySqlArguments;
     type Connection = sqlx::mysql::MySqlConnection;
 
     const IDENTIFIER_QUOTE: char = '`';
@@ -78,8 +80,36 @@ impl SqlxDataSource for MySqlDataSource {
             WHERE referenced_table_schema = DATABASE()"
     }
 
-    fn get_deterministic_samples_query(&self, table_name: String) -> String {
-        format!("SELECT * FROM {table_name} ORDER BY rand(0.5) LIMIT 10")
+    fn get_unique_keys_query(&self) -> &str {
+        // Indexes on expressions are not unique over the columns alone
+        r"SELECT s.index_name, s.column_name
+            FROM information_schema.statistics s
+            WHERE s.table_schema = DATABASE() AND s.table_name = ? AND s.non_unique = 0
+                AND s.index_name != 'PRIMARY'
+                AND NOT EXISTS (
+                    SELECT 1 FROM information_schema.statistics e
+                    WHERE e.table_schema = s.table_schema AND e.table_name = s.table_name
+                        AND e.index_name = s.index_name AND e.column_name IS NULL
+                )
+            ORDER BY s.index_name, s.seq_in_index"
+    }
+
+    fn get_deterministic_samples_query(
+        &self,
+        table_name: String,
+        sampling: Sampling,
+    ) -> Result<String> {
+        let size = sampling.size;
+        Ok(match sampling.strategy {
+            SamplingStrategy::Random => {
+                format!("SELECT * FROM {table_name} ORDER BY rand(0.5) LIMIT {size}")
+            }
+            SamplingStrategy::First => format!("SELECT * FROM {table_name} LIMIT {size}"),
+            SamplingStrategy::TableSample => {
+                bail!("MySQL has no TABLESAMPLE, use the 'random' sampling strategy instead")
+            }
+            SamplingStrategy::Full => format!("SELECT * FROM {table_name}"),
+        })
     }
 
     fn get_table_name_for_insert(&self, table_name: &str) -> String {
@@ -109,24 +139,28 @@ impl SqlxDataSource for MySqlDataSource {
                 type_: ChronoValueType::NaiveDateTime,
                 begin: None,
                 end: None,
+                histogram: None,
             }),
             "date" => Content::DateTime(DateTimeContent {
                 format: "%Y-%m-%d".to_string(),
                 type_: ChronoValueType::NaiveDate,
                 begin: None,
                 end: None,
+                histogram: None,
             }),
             "datetime" => Content::DateTime(DateTimeContent {
                 format: "%Y-%m-%d %H:%M:%S".to_string(),
                 type_: ChronoValueType::NaiveDateTime,
                 begin: None,
                 end: None,
+                histogram: None,
             }),
             "time" => Content::DateTime(DateTimeContent {
                 format: "%H:%M:%S".to_string(),
                 type_: ChronoValueType::NaiveTime,
                 begin: None,
                 end: None,
+                histogram: None,
             }),
             _ => bail!(
                 "We haven't implemented a converter for {}",
@@ -156,6 +190,10 @@ impl TryFrom<MySqlRow> for ColumnInfo {
             data_type: row.try_get::<String, usize>(3)?,
             character_maximum_length: extract_column_char_max_len(4, row)?,
             is_custom_type: false,
+            numeric_scale: None,
+            column_default: None,
+            enum_values: Vec::new(),
+            checks: Vec::new(),
         })
     }
 }
@@ -184,6 +222,17 @@ impl TryFrom<MySqlRow> for PrimaryKey {
     }
 }
 
+impl TryFrom<MySqlRow> for UniqueKeyColumn {
+    type Error = anyhow::Error;
+
+    fn try_from(row: MySqlRow) -> Result<Self, Self::Error> {
+        Ok(UniqueKeyColumn {
+            key_name: row.try_get(0)?,
+            column_name: row.try_get(1)?,
+        })
+    }
+}
+
 impl TryFrom<MySqlRow> for ForeignKey {
     type Error = anyhow::Error;
 
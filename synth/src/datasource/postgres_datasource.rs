@@ -1,6 +1,9 @@
+use crate::datasource::check::{self, Check};
 use crate::datasource::relational_datasource::{
-    insert_relational_data, ColumnInfo, ForeignKey, PrimaryKey, SqlxDataSource, ValueWrapper,
+    insert_relational_data, ColumnInfo, ForeignKey, PrimaryKey, SqlxDataSource, UniqueKeyColumn,
+    ValueWrapper,
 };
+use crate::datasource::sampling::{Sampling, SamplingStrategy};
 use crate::datasource::DataSource;
 use anyhow::{Context, Result};
 use async_std::sync::Arc;
@@ -8,13 +11,17 @@ use async_std::task;
 use async_trait::async_trait;
 use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
+use sqlx::postgres::types::PgInterval;
 use sqlx::postgres::{PgColumn, PgPoolOptions, PgRow, PgTypeInfo, PgTypeKind};
 use sqlx::{Column, Executor, Pool, Postgres, Row, TypeInfo};
 use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use synth_core::graph::string::FakerArgs;
 use synth_core::schema::number_content::{F32, F64, I16, I32, I64};
 use synth_core::schema::{
     ArrayContent, BoolContent, Categorical, ChronoValue, ChronoValueAndFormat, ChronoValueType,
-    DateTimeContent, NumberContent, ObjectContent, RangeStep, RegexContent, StringContent, Uuid,
+    DateTimeContent, FakerContent, NumberContent, ObjectContent, RangeStep, RegexContent,
+    StringContent, Uuid,
 };
 use synth_core::{Content, Value};
 
@@ -114,7 +121,7 @@ impl PostgresDataSource {
 #[async_trait]
 impl SqlxDataSource for PostgresDataSource {
     type DB = Postgres;
-    type Arguments = sqlx::postgres::PgArguments;
+    type Arguments<'q> = sqlx::postgres::PgArguments;
     type Connection = sqlx::postgres::PgConnection;
 
     const IDENTIFIER_QUOTE: char = '\"';
@@ -127,7 +134,7 @@ impl SqlxDataSource for PostgresDataSource {
         Pool::clone(&self.pool)
     }
 
-    fn query<'q>(&self, query: &'q str) -> sqlx::query::Query<'q, Self::DB, Self::Arguments> {
+    fn query<'q>(&self, query: &'q str) -> sqlx::query::Query<'q, Self::DB, Self::Arguments<'q>> {
         sqlx::query(query).bind(self.schema.clone())
     }
 
@@ -159,6 +166,19 @@ impl SqlxDataSource for PostgresDataSource {
             and tc.table_catalog = current_catalog"
     }
 
+    fn get_unique_keys_query(&self) -> &str {
+        // Partial indexes are not unique over the whole table, and indexes on expressions are not
+        // unique over the columns alone
+        r#"SELECT c.relname, a.attname
+        FROM pg_index i
+        JOIN pg_class c ON c.oid = i.indexrelid
+        CROSS JOIN LATERAL unnest(i.indkey) WITH ORDINALITY AS k(attnum, position)
+        JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = k.attnum
+        WHERE i.indrelid = cast(concat('"', $2, '"') as regclass)
+            AND i.indisunique AND NOT i.indisprimary AND i.indpred IS NULL AND i.indexprs IS NULL
+        ORDER BY c.relname, k.position"#
+    }
+
     /// Must use the singled threaded pool when setting this in conjunction with random, called by
     /// [get_deterministic_samples]. Otherwise, expect endless facepalms (-_Q)
     async fn set_seed(&self) -> Result<()> {
@@ -168,8 +188,26 @@ impl SqlxDataSource for PostgresDataSource {
         Ok(())
     }
 
-    fn get_deterministic_samples_query(&self, table_name: String) -> String {
-        format!("SELECT * FROM \"{table_name}\" ORDER BY random() LIMIT 10",)
+    fn get_deterministic_samples_query(
+        &self,
+        table_name: String,
+        sampling: Sampling,
+    ) -> Result<String> {
+        let size = sampling.size;
+        Ok(match sampling.strategy {
+            SamplingStrategy::Random => {
+                format!("SELECT * FROM \"{table_name}\" ORDER BY random() LIMIT {size}")
+            }
+            SamplingStrategy::First => format!("SELECT * FROM \"{table_name}\" LIMIT {size}"),
+            // The share of rows to sample is estimated from the statistics of the table, so there
+            // can be a few less rows than asked for
+            SamplingStrategy::TableSample => format!(
+                r#"SELECT * FROM "{table_name}" TABLESAMPLE BERNOULLI (least(100, 100.0 * {size} / greatest(
+                    (SELECT reltuples FROM pg_class WHERE oid = '"{table_name}"'::regclass), 1
+                ))) REPEATABLE (0) LIMIT {size}"#
+            ),
+            SamplingStrategy::Full => format!("SELECT * FROM \"{table_name}\""),
+        })
     }
 
     fn get_table_name_for_insert(&self, table_name: &str) -> String {
@@ -177,13 +215,28 @@ impl SqlxDataSource for PostgresDataSource {
     }
 
     fn decode_to_content(&self, column_info: &ColumnInfo) -> Result<Content> {
+        let checks: Vec<_> = column_info
+            .checks
+            .iter()
+            .flat_map(|definition| check::parse(definition))
+            .collect();
+
         if column_info.is_custom_type {
-            return Ok(Content::String(StringContent::Categorical(
-                Categorical::default(),
-            )));
+            // The labels of an enum are all generated, unless a check leaves some out
+            let mut categorical = Categorical::default();
+            for label in &column_info.enum_values {
+                let allowed = checks.iter().all(|check| match check {
+                    Check::In(values) => values.contains(label),
+                    Check::Compare(..) => true,
+                });
+                if allowed {
+                    categorical.push(label.clone());
+                }
+            }
+            return Ok(Content::String(StringContent::Categorical(categorical)));
         }
 
-        let content = match column_info.data_type.to_lowercase().as_str() {
+        let mut content = match column_info.data_type.to_lowercase().as_str() {
             "bool" => Content::Bool(BoolContent::default()),
             "oid" => {
                 bail!("OID data type not supported")
@@ -202,40 +255,61 @@ impl SqlxDataSource for PostgresDataSource {
             "int8" => Content::Number(NumberContent::I64(I64::Range(RangeStep::default()))),
             "float4" => Content::Number(NumberContent::F32(F32::Range(RangeStep::default()))),
             "float8" => Content::Number(NumberContent::F64(F64::Range(RangeStep::default()))),
-            "numeric" => Content::Number(NumberContent::F64(F64::Range(RangeStep::default()))),
+            "numeric" => Content::Number(NumberContent::F64(F64::Range(RangeStep {
+                // Values with more digits than the scale would be rounded when inserted
+                step: column_info.numeric_scale.map(|scale| 10f64.powi(-scale)),
+                ..RangeStep::default()
+            }))),
             "timestamptz" => Content::DateTime(DateTimeContent {
                 format: "%Y-%m-%dT%H:%M:%S%z".to_string(),
                 type_: ChronoValueType::DateTime,
                 begin: None,
                 end: None,
+                histogram: None,
             }),
             "timestamp" => Content::DateTime(DateTimeContent {
                 format: "%Y-%m-%dT%H:%M:%S".to_string(),
                 type_: ChronoValueType::NaiveDateTime,
                 begin: None,
                 end: None,
+                histogram: None,
             }),
             "date" => Content::DateTime(DateTimeContent {
                 format: "%Y-%m-%d".to_string(),
                 type_: ChronoValueType::NaiveDate,
                 begin: None,
                 end: None,
+                histogram: None,
             }),
             "time" => Content::DateTime(DateTimeContent {
                 format: "%H:%M:%S".to_string(),
                 type_: ChronoValueType::NaiveTime,
                 begin: None,
                 end: None,
+                histogram: None,
             }),
-            "json" | "jsonb" => Content::Object(ObjectContent {
-                skip_when_null: false,
-                fields: BTreeMap::new(),
-            }),
+            "json" | "jsonb" => Content::Object(ObjectContent::default()),
             "uuid" => Content::String(StringContent::Uuid(Uuid)),
+            "interval" => Content::String(StringContent::Pattern(
+                RegexContent::pattern("[1-9][0-9]{0,2} (minutes|hours|days)".to_string())
+                    .context("pattern will always compile")?,
+            )),
+            "inet" => Content::String(StringContent::Faker(FakerContent {
+                generator: "ipv4".to_string(),
+                locales: Vec::new(),
+                args: FakerArgs::default(),
+            })),
+            "bytea" => Content::String(StringContent::Pattern(
+                RegexContent::pattern("\\\\x([0-9a-f]{2}){0,16}".to_string())
+                    .context("pattern will always compile")?,
+            )),
             _ => {
                 if let Some(data_type) = column_info.data_type.strip_prefix('_') {
+                    // The checks and default are those of the whole array
                     let mut column_info = column_info.clone();
                     column_info.data_type = data_type.to_string();
+                    column_info.column_default = None;
+                    column_info.checks.clear();
 
                     Content::Array(ArrayContent::from_content_default_length(
                         self.decode_to_content(&column_info)?,
@@ -249,10 +323,29 @@ impl SqlxDataSource for PostgresDataSource {
             }
         };
 
+        let is_serial = column_info
+            .column_default
+            .as_ref()
+            .map_or(false, |default| default.starts_with("nextval("));
+        if let Content::Number(number) = &mut content {
+            if is_serial {
+                *number = number.clone().try_transmute_to_id()?;
+            }
+        }
+
+        for check in &checks {
+            check::constrain(&mut content, check);
+        }
+
         Ok(content)
     }
 
-    fn get_function_argument_placeholder(current: usize, index: usize, value: &Value) -> String {
+    fn get_function_argument_placeholder(
+        current: usize,
+        index: usize,
+        value: &Value,
+        column: Option<&ColumnInfo>,
+    ) -> String {
         let extra = if let Value::Array(_) = value {
             let (typ, depth) = value.get_postgres_type();
             if typ == "unknown" {
@@ -262,6 +355,26 @@ impl SqlxDataSource for PostgresDataSource {
             } else {
                 format!("::{}{}", typ, "[]".repeat(depth))
             }
+        } else if let (Value::String(_), Some(column)) = (value, column) {
+            // Strings are sent in the binary format of text, which only text-like types and enums
+            // read, so others like `inet` or `bytea` parse them from text instead
+            let text_like = matches!(
+                column.data_type.as_str(),
+                "char" | "varchar" | "text" | "citext" | "bpchar" | "name" | "unknown"
+            );
+            if text_like || column.is_custom_type {
+                "".to_string()
+            } else {
+                format!("::text::{}", column.data_type)
+            }
+        } else if let (Value::Number(_), Some(column)) = (value, column) {
+            // Likewise `numeric` does not read the binary format of the other numbers, but it can
+            // be assigned from them
+            if column.data_type == "numeric" {
+                format!("::{}", value.get_postgres_type().0)
+            } else {
+                "".to_string()
+            }
         } else {
             "".to_string()
         };
@@ -270,12 +383,28 @@ impl SqlxDataSource for PostgresDataSource {
     }
 
     fn get_columns_info_query(&self) -> &str {
-        r"SELECT column_name, ordinal_position, is_nullable, udt_name,
-        character_maximum_length, data_type
-        FROM information_schema.columns
-        WHERE table_name = $2
-        AND table_schema = $1
-        AND table_catalog = current_catalog"
+        // The checks are those over the column alone, as well as those of its domain if any
+        r#"SELECT c.column_name, c.ordinal_position, c.is_nullable, c.udt_name,
+        c.character_maximum_length, c.data_type, c.numeric_scale, c.column_default,
+        ARRAY(
+            SELECT e.enumlabel::text
+            FROM pg_enum e
+            WHERE e.enumtypid = to_regtype(quote_ident(c.udt_schema) || '.' || quote_ident(c.udt_name))
+            ORDER BY e.enumsortorder
+        ) AS enum_values,
+        ARRAY(
+            SELECT pg_get_constraintdef(k.oid)
+            FROM pg_constraint k
+            WHERE k.contype = 'c' AND (
+                (k.conrelid = cast(concat('"', $2, '"') as regclass)
+                    AND k.conkey = ARRAY[c.ordinal_position::int2])
+                OR k.contypid = to_regtype(quote_ident(c.domain_schema) || '.' || quote_ident(c.domain_name))
+            )
+        ) AS checks
+        FROM information_schema.columns c
+        WHERE c.table_name = $2
+        AND c.table_schema = $1
+        AND c.table_catalog = current_catalog"#
     }
 }
 
@@ -290,6 +419,10 @@ impl TryFrom<PgRow> for ColumnInfo {
             data_type: row.try_get(3)?,
             character_maximum_length: row.try_get(4)?,
             is_custom_type: row.try_get::<String, usize>(5)? == "USER-DEFINED",
+            numeric_scale: row.try_get(6)?,
+            column_default: row.try_get(7)?,
+            enum_values: row.try_get(8)?,
+            checks: row.try_get(9)?,
         })
     }
 }
@@ -305,6 +438,17 @@ impl TryFrom<PgRow> for PrimaryKey {
     }
 }
 
+impl TryFrom<PgRow> for UniqueKeyColumn {
+    type Error = anyhow::Error;
+
+    fn try_from(row: PgRow) -> Result<Self, Self::Error> {
+        Ok(UniqueKeyColumn {
+            key_name: row.try_get(0)?,
+            column_name: row.try_get(1)?,
+        })
+    }
+}
+
 impl TryFrom<PgRow> for ForeignKey {
     type Error = anyhow::Error;
 
@@ -378,6 +522,22 @@ fn try_match_value(row: &PgRow, column: &PgColumn) -> Result<Value> {
             let serde_value = row.try_get::<serde_json::Value, &str>(column.name())?;
             serde_json::from_value(serde_value)?
         }
+        "interval" => {
+            let interval = row.try_get::<PgInterval, &str>(column.name())?;
+            Value::String(format!(
+                "{} months {} days {} microseconds",
+                interval.months, interval.days, interval.microseconds
+            ))
+        }
+        "inet" => Value::String(row.try_get::<InetType, &str>(column.name())?.into()),
+        "bytea" => {
+            let bytes = row.try_get::<Vec<u8>, &str>(column.name())?;
+            let mut hex = "\\x".to_string();
+            for byte in bytes {
+                hex.push_str(&format!("{byte:02x}"));
+            }
+            Value::String(hex)
+        }
         "char[]" | "varchar[]" | "text[]" | "citext[]" | "bpchar[]" | "name[]" | "unknown[]" => {
             Value::Array(
                 row.try_get::<Vec<String>, &str>(column.name())
@@ -512,3 +672,39 @@ impl From<EnumType> for String {
         enum_type.0
     }
 }
+
+/// Special type for importing inet addresses, which sqlx only decodes with the `ipnetwork` feature
+struct InetType(String);
+
+impl sqlx::types::Type<Postgres> for InetType {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("inet")
+    }
+}
+
+impl<'r> sqlx::Decode<'r, Postgres> for InetType {
+    /// Decodes the binary format, which is the family, the bits of the netmask, whether it is a
+    /// cidr, the length of the address and then the address itself.
+    fn decode(
+        value: sqlx::postgres::PgValueRef<'r>,
+    ) -> std::result::Result<Self, Box<dyn std::error::Error + 'static + Send + Sync>> {
+        let bytes = <&[u8] as sqlx::Decode<Postgres>>::decode(value)?;
+        let (header, address) = bytes.split_at(bytes.len().min(4));
+        let address = match (header, address.len()) {
+            ([2, ..], 4) => IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(address)?)),
+            ([3, ..], 16) => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(address)?)),
+            _ => return Err("invalid inet address".into()),
+        };
+        let full = if address.is_ipv4() { 32 } else { 128 };
+        Ok(Self(match header[1] {
+            bits if bits == full => address.to_string(),
+            bits => format!("{address}/{bits}"),
+        }))
+    }
+}
+
+impl From<InetType> for String {
+    fn from(inet: InetType) -> Self {
+        inet.0
+    }
+}
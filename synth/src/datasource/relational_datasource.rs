@@ -1,15 +1,17 @@
+use crate::datasource::sampling::Sampling;
 use crate::datasource::DataSource;
 use anyhow::Result;
 use async_trait::async_trait;
 use beau_collector::BeauCollector;
 use futures::future::join_all;
+use sqlx::database::HasArguments;
 use sqlx::{
     query::Query, Arguments, Connection, Database, Encode, Executor, IntoArguments, Pool, Type,
 };
 use synth_core::{Content, Value};
 use synth_gen::value::Number;
 
-const DEFAULT_INSERT_BATCH_SIZE: usize = 1000;
+pub(crate) const DEFAULT_INSERT_BATCH_SIZE: usize = 1000;
 
 //TODO: Remove this once https://github.com/rust-lang/rust/issues/88900 gets fixed
 #[allow(dead_code)]
@@ -21,6 +23,21 @@ pub struct ColumnInfo {
     pub(crate) is_custom_type: bool,
     pub(crate) data_type: String,
     pub(crate) character_maximum_length: Option<i32>,
+    /// The digits after the decimal point of a fixed point number, like 2 for `numeric(10, 2)`
+    pub(crate) numeric_scale: Option<i32>,
+    pub(crate) column_default: Option<String>,
+    /// The labels of the enumerated type of the column, in their order
+    pub(crate) enum_values: Vec<String>,
+    /// The definitions of the CHECK constraints over the column alone, including those of its domain
+    pub(crate) checks: Vec<String>,
+}
+
+impl ColumnInfo {
+    /// Whether the column holds text, like `varchar(255)` or `text`, in any of the databases.
+    pub(crate) fn is_text(&self) -> bool {
+        let data_type = self.data_type.to_lowercase();
+        data_type.contains("char") || data_type.contains("text")
+    }
 }
 
 #[allow(dead_code)]
@@ -38,6 +55,13 @@ pub struct ForeignKey {
     pub(crate) to_column: String,
 }
 
+/// A column of the unique constraint or index `key_name`.
+#[derive(Debug)]
+pub struct UniqueKeyColumn {
+    pub(crate) key_name: String,
+    pub(crate) column_name: String,
+}
+
 /// Wrapper around `Value` since we can't impl `TryFrom` on a struct in a non-owned crate
 #[derive(Debug)]
 pub struct ValueWrapper(pub(crate) Value);
@@ -46,8 +70,9 @@ pub struct ValueWrapper(pub(crate) Value);
 /// their own implementations.
 #[async_trait]
 pub trait SqlxDataSource: DataSource {
-    type DB: Database<Arguments = Self::Arguments, Connection = Self::Connection>;
-    type Arguments: for<'q> Arguments<'q, Database = Self::DB> + for<'q> IntoArguments<'q, Self::DB>;
+    type DB: Database<Connection = Self::Connection>
+        + for<'q> HasArguments<'q, Arguments = Self::Arguments<'q>>;
+    type Arguments<'q>: Arguments<'q, Database = Self::DB> + IntoArguments<'q, Self::DB>;
     type Connection: Connection<Database = Self::DB>;
 
     const IDENTIFIER_QUOTE: char;
@@ -59,7 +84,7 @@ pub trait SqlxDataSource: DataSource {
     fn get_multithread_pool(&self) -> Pool<Self::DB>;
 
     /// Prepare a single query with data source specifics
-    fn query<'q>(&self, query: &'q str) -> Query<'q, Self::DB, Self::Arguments> {
+    fn query<'q>(&self, query: &'q str) -> Query<'q, Self::DB, Self::Arguments<'q>> {
         sqlx::query(query)
     }
 
@@ -72,6 +97,10 @@ pub trait SqlxDataSource: DataSource {
     /// Get query for foreign keys
     fn get_foreign_keys_query(&self) -> &str;
 
+    /// Get query for the columns of the unique keys of a table other than its primary key, in
+    /// order within each key
+    fn get_unique_keys_query(&self) -> &str;
+
     /// Get query for columns info
     fn get_columns_info_query(&self) -> &str;
 
@@ -83,25 +112,43 @@ pub trait SqlxDataSource: DataSource {
         Ok(())
     }
 
-    /// Get query for deterministic values
-    fn get_deterministic_samples_query(&self, table_name: String) -> String;
+    /// Get query for the values of `table_name` picked by `sampling`, which are the same on every
+    /// import where the database allows it
+    fn get_deterministic_samples_query(
+        &self,
+        table_name: String,
+        sampling: Sampling,
+    ) -> Result<String>;
 
     /// Decodes column to our Content
     fn decode_to_content(&self, column_info: &ColumnInfo) -> Result<Content>;
 
     /// Get the function arguments for datasource
-    fn get_function_argument_placeholder(_current: usize, _index: usize, _value: &Value) -> String {
+    fn get_function_argument_placeholder(
+        _current: usize,
+        _index: usize,
+        _value: &Value,
+        _column: Option<&ColumnInfo>,
+    ) -> String {
         "?".to_string()
     }
 
     // Returns extended query string + current index
-    fn extend_parameterised_query(query: &mut String, curr_index: usize, query_params: Vec<Value>) {
+    fn extend_parameterised_query(
+        query: &mut String,
+        curr_index: usize,
+        query_params: Vec<Value>,
+        columns: &[Option<&ColumnInfo>],
+    ) {
         let extend = query_params.len();
 
         query.push('(');
         for (i, param) in query_params.iter().enumerate() {
             query.push_str(&Self::get_function_argument_placeholder(
-                curr_index, i, param,
+                curr_index,
+                i,
+                param,
+                columns.get(i).copied().flatten(),
             ));
             if i != extend - 1 {
                 query.push(',');
@@ -157,7 +204,7 @@ where
         .as_object()
         .expect("This is always an object (sampler contract)");
 
-    for column_info in column_infos {
+    for column_info in &column_infos {
         if let Some(value) = first_valueset.get(&column_info.column_name) {
             match (value, &*column_info.data_type) {
                 (
@@ -207,6 +254,14 @@ where
         .map(|k| format!("{}{}{}", T::IDENTIFIER_QUOTE, k, T::IDENTIFIER_QUOTE))
         .collect::<Vec<String>>()
         .join(",");
+    let columns: Vec<_> = first_valueset
+        .keys()
+        .map(|key| {
+            column_infos
+                .iter()
+                .find(|column_info| &column_info.column_name == key)
+        })
+        .collect();
 
     let mut futures = Vec::with_capacity(collection.len());
 
@@ -222,8 +277,23 @@ where
                 .as_object()
                 .expect("This is always an object (sampler contract)");
 
-            let mut curr_query_params: Vec<Value> = row_obj.values().cloned().collect();
-            T::extend_parameterised_query(&mut query, curr_index, curr_query_params.clone());
+            // Date times inferred from the strings of a text column go back into it as strings
+            let mut curr_query_params: Vec<Value> = row_obj
+                .values()
+                .zip(&columns)
+                .map(|(value, column)| match (value, column) {
+                    (Value::DateTime(date_time), Some(column)) if column.is_text() => {
+                        Value::String(date_time.format_to_string())
+                    }
+                    _ => value.clone(),
+                })
+                .collect();
+            T::extend_parameterised_query(
+                &mut query,
+                curr_index,
+                curr_query_params.clone(),
+                &columns,
+            );
             curr_index += curr_query_params.len();
             query_params.append(&mut curr_query_params);
 
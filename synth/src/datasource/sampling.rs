@@ -0,0 +1,57 @@
+//! Which rows of a table are read on import to infer its content from.
+use anyhow::Result;
+use serde::Serialize;
+use std::str::FromStr;
+
+/// The number of rows read from each table unless given.
+pub const DEFAULT_SAMPLE_SIZE: usize = 10;
+
+/// How the rows read from each table are chosen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SamplingStrategy {
+    /// Rows in a random, but repeatable, order where the database can seed its random numbers
+    #[default]
+    Random,
+    /// The first rows in the order the database stores them
+    First,
+    /// Rows picked by the sampling of the database itself, like Postgres' `TABLESAMPLE`, which
+    /// does not sort the whole table
+    TableSample,
+    /// Every row, read as a stream so the table never has to fit in memory
+    Full,
+}
+
+impl FromStr for SamplingStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "random" => Ok(Self::Random),
+            "first" => Ok(Self::First),
+            "tablesample" => Ok(Self::TableSample),
+            "full" => Ok(Self::Full),
+            _ => Err(anyhow!(
+                "Unknown sampling strategy '{}'. Was expecting one of 'random', 'first', 'tablesample' or 'full'.",
+                s
+            )),
+        }
+    }
+}
+
+/// The rows read from each table on import.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sampling {
+    /// The number of rows read, which is ignored by a `Full` scan
+    pub size: usize,
+    pub strategy: SamplingStrategy,
+}
+
+impl Default for Sampling {
+    fn default() -> Self {
+        Self {
+            size: DEFAULT_SAMPLE_SIZE,
+            strategy: SamplingStrategy::default(),
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use crate::datasource::relational_datasource::{
+    insert_relational_data, ColumnInfo, ForeignKey, PrimaryKey, SqlxDataSource, UniqueKeyColumn,
+    ValueWrapper,
+};
+use crate::datasource::sampling::{Sampling, SamplingStrategy};
+use crate::datasource::DataSource;
+use anyhow::{Context, Result};
+use async_std::task;
+use async_trait::async_trait;
+use sqlx::sqlite::{SqliteColumn, SqlitePoolOptions, SqliteRow};
+use sqlx::{Column, Pool, Row, Sqlite, TypeInfo, ValueRef};
+use std::collections::BTreeMap;
+use synth_core::schema::number_content::{F64, I64};
+use synth_core::schema::{
+    BoolContent, ChronoValueType, DateTimeContent, NumberContent, RangeStep, RegexContent,
+    StringContent,
+};
+use synth_core::{Content, Value};
+use synth_gen::prelude::*;
+
+/// SQLite columns can hold values of any type, whatever their declared type is. Columns are
+/// mapped to content from their declared type following SQLite's type affinity rules
+/// (https://www.sqlite.org/datatype3.html), and values are read according to how they are
+/// actually stored.
+pub struct SqliteDataSource {
+    pool: Pool<Sqlite>,
+}
+
+#[async_trait]
+impl DataSource for SqliteDataSource {
+    type ConnectParams = String;
+
+    fn new(connect_params: &Self::ConnectParams) -> Result<Self> {
+        task::block_on(async {
+            // SQLite only allows a single writer at a time, so more connections would only end up
+            // waiting on each other's locks
+            let pool = SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect(connect_params.as_str())
+                .await?;
+
+            Ok::<Self, anyhow::Error>(SqliteDataSource { pool })
+        })
+    }
+
+    async fn insert_data(&self, collection_name: &str, collection: &[Value]) -> Result<()> {
+        insert_relational_data(self, collection_name, collection).await
+    }
+}
+
+impl SqlxDataSource for SqliteDataSource {
+    type DB = Sqlite;
+    type Arguments<'q> = sqlx::sqlite::SqliteArguments<'q>;
+    type Connection = sqlx::sqlite::SqliteConnection;
+
+    const IDENTIFIER_QUOTE: char = '\"';
+
+    fn get_pool(&self) -> Pool<Self::DB> {
+        Pool::clone(&self.pool)
+    }
+
+    fn get_multithread_pool(&self) -> Pool<Self::DB> {
+        Pool::clone(&self.pool)
+    }
+
+    fn get_table_names_query(&self) -> &str {
+        r"SELECT name FROM sqlite_master
+            WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+            ORDER BY name"
+    }
+
+    fn get_primary_keys_query(&self) -> &str {
+        r"SELECT name, type FROM pragma_table_info(?) WHERE pk > 0"
+    }
+
+    fn get_foreign_keys_query(&self) -> &str {
+        // A foreign key without a column references the primary key of the parent table
+        r#"SELECT m.name, f."from", f."table",
+            COALESCE(f."to", (SELECT p.name FROM pragma_table_info(f."table") p WHERE p.pk = 1))
+            FROM sqlite_master m JOIN pragma_foreign_key_list(m.name) f
+            WHERE m.type = 'table'"#
+    }
+
+    fn get_unique_keys_query(&self) -> &str {
+        // Partial indexes are not unique over the whole table, and indexes on expressions are not
+        // unique over the columns alone
+        r#"SELECT l.name, i.name
+            FROM pragma_index_list(?) l JOIN pragma_index_info(l.name) i
+            WHERE l."unique" = 1 AND l.origin != 'pk' AND l.partial = 0
+                AND NOT EXISTS (SELECT 1 FROM pragma_index_info(l.name) e WHERE e.name IS NULL)
+            ORDER BY l.name, i.seqno"#
+    }
+
+    fn get_deterministic_samples_query(
+        &self,
+        table_name: String,
+        sampling: Sampling,
+    ) -> Result<String> {
+        let size = sampling.size;
+        Ok(match sampling.strategy {
+            // SQLite's random() cannot be seeded, so take the first rows in storage order instead
+            SamplingStrategy::Random | SamplingStrategy::First => {
+                format!("SELECT * FROM \"{table_name}\" LIMIT {size}")
+            }
+            SamplingStrategy::TableSample => {
+                bail!("SQLite has no TABLESAMPLE, use the 'first' sampling strategy instead")
+            }
+            SamplingStrategy::Full => format!("SELECT * FROM \"{table_name}\""),
+        })
+    }
+
+    fn get_table_name_for_insert(&self, table_name: &str) -> String {
+        format!("\"{table_name}\"")
+    }
+
+    fn decode_to_content(&self, column_info: &ColumnInfo) -> Result<Content> {
+        let data_type = column_info.data_type.as_str();
+
+        let content = match data_type {
+            "boolean" | "bool" => Content::Bool(BoolContent::default()),
+            "date" => Content::DateTime(DateTimeContent {
+                format: "%Y-%m-%d".to_string(),
+                type_: ChronoValueType::NaiveDate,
+                begin: None,
+                end: None,
+                histogram: None,
+            }),
+            "datetime" | "timestamp" => Content::DateTime(DateTimeContent {
+                format: "%Y-%m-%d %H:%M:%S".to_string(),
+                type_: ChronoValueType::NaiveDateTime,
+                begin: None,
+                end: None,
+                histogram: None,
+            }),
+            "time" => Content::DateTime(DateTimeContent {
+                format: "%H:%M:%S".to_string(),
+                type_: ChronoValueType::NaiveTime,
+                begin: None,
+                end: None,
+                histogram: None,
+            }),
+            _ if data_type.contains("int") => {
+                Content::Number(NumberContent::I64(I64::Range(RangeStep::default())))
+            }
+            _ if data_type.contains("char")
+                || data_type.contains("clob")
+                || data_type.contains("text") =>
+            {
+                let pattern = "[a-zA-Z0-9]{0, {}}".replace(
+                    "{}",
+                    &format!("{}", column_info.character_maximum_length.unwrap_or(1)),
+                );
+                Content::String(StringContent::Pattern(
+                    RegexContent::pattern(pattern).context("pattern will always compile")?,
+                ))
+            }
+            "" => bail!(
+                "We haven't implemented a converter for column {} without a declared type",
+                column_info.column_name
+            ),
+            _ if data_type.contains("blob") => {
+                bail!("We haven't implemented a converter for {}", data_type)
+            }
+            // Everything else, e.g. `REAL`, `NUMERIC` or `DECIMAL`, has real or numeric affinity
+            _ => Content::Number(NumberContent::F64(F64::Range(RangeStep::default()))),
+        };
+
+        Ok(content)
+    }
+
+    fn get_columns_info_query(&self) -> &str {
+        r#"SELECT name, cid + 1, "notnull" = 0 AND pk = 0, type
+            FROM pragma_table_info(?)
+            ORDER BY cid"#
+    }
+}
+
+impl TryFrom<SqliteRow> for ColumnInfo {
+    type Error = anyhow::Error;
+
+    fn try_from(row: SqliteRow) -> Result<Self, Self::Error> {
+        let (data_type, character_maximum_length) =
+            split_declared_type(&row.try_get::<String, usize>(3)?);
+
+        Ok(ColumnInfo {
+            column_name: row.try_get::<String, usize>(0)?,
+            ordinal_position: row.try_get::<i32, usize>(1)?,
+            is_nullable: row.try_get::<bool, usize>(2)?,
+            data_type,
+            character_maximum_length,
+            is_custom_type: false,
+            numeric_scale: None,
+            column_default: None,
+            enum_values: Vec::new(),
+            checks: Vec::new(),
+        })
+    }
+}
+
+/// Splits a declared column type like `VARCHAR(255)` into its lowercase name and its first
+/// argument, which is the maximum length for character types.
+fn split_declared_type(declared: &str) -> (String, Option<i32>) {
+    let declared = declared.trim().to_lowercase();
+
+    match declared.split_once('(') {
+        Some((name, arguments)) => {
+            let length = arguments
+                .trim_end_matches(')')
+                .split(',')
+                .next()
+                .and_then(|length| length.trim().parse().ok());
+            (name.trim().to_string(), length)
+        }
+        None => (declared, None),
+    }
+}
+
+impl TryFrom<SqliteRow> for PrimaryKey {
+    type Error = anyhow::Error;
+
+    fn try_from(row: SqliteRow) -> Result<Self, Self::Error> {
+        Ok(PrimaryKey {
+            column_name: row.try_get(0)?,
+            type_name: row.try_get(1)?,
+        })
+    }
+}
+
+impl TryFrom<SqliteRow> for UniqueKeyColumn {
+    type Error = anyhow::Error;
+
+    fn try_from(row: SqliteRow) -> Result<Self, Self::Error> {
+        Ok(UniqueKeyColumn {
+            key_name: row.try_get(0)?,
+            column_name: row.try_get(1)?,
+        })
+    }
+}
+
+impl TryFrom<SqliteRow> for ForeignKey {
+    type Error = anyhow::Error;
+
+    fn try_from(row: SqliteRow) -> Result<Self, Self::Error> {
+        Ok(ForeignKey {
+            from_table: row.try_get(0)?,
+            from_column: row.try_get(1)?,
+            to_table: row.try_get(2)?,
+            to_column: row.try_get(3)?,
+        })
+    }
+}
+
+impl TryFrom<SqliteRow> for ValueWrapper {
+    type Error = anyhow::Error;
+
+    fn try_from(row: SqliteRow) -> Result<Self, Self::Error> {
+        let mut kv = BTreeMap::new();
+
+        for column in row.columns() {
+            let value = try_match_value(&row, column).unwrap_or(Value::Null(()));
+            kv.insert(column.name().to_string(), value);
+        }
+
+        Ok(ValueWrapper(Value::Object(kv)))
+    }
+}
+
+fn try_match_value(row: &SqliteRow, column: &SqliteColumn) -> Result<Value> {
+    let raw = row.try_get_raw(column.ordinal())?;
+    if raw.is_null() {
+        return Ok(Value::Null(()));
+    }
+    let storage_class = raw.type_info().name().to_string();
+
+    let value = match (column.type_info().name(), storage_class.as_str()) {
+        ("BOOLEAN", "INTEGER") => Value::Bool(row.try_get::<bool, usize>(column.ordinal())?),
+        (_, "INTEGER") => Value::Number(Number::from(row.try_get::<i64, usize>(column.ordinal())?)),
+        (_, "REAL") => Value::Number(Number::from(row.try_get::<f64, usize>(column.ordinal())?)),
+        (_, "TEXT") => Value::String(row.try_get::<String, usize>(column.ordinal())?),
+        _ => {
+            bail!(
+                "Could not convert value. Converter not implemented for {}",
+                storage_class
+            );
+        }
+    };
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_declared_type;
+
+    #[test]
+    fn declared_types_are_split() {
+        assert_eq!(
+            split_declared_type("VARCHAR(255)"),
+            ("varchar".to_string(), Some(255))
+        );
+        assert_eq!(
+            split_declared_type("decimal(10, 2)"),
+            ("decimal".to_string(), Some(10))
+        );
+        assert_eq!(
+            split_declared_type(" Integer "),
+            ("integer".to_string(), None)
+        );
+    }
+}
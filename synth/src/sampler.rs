@@ -1,15 +1,117 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use indicatif::{ProgressBar, ProgressStyle};
-use rand::SeedableRng;
-use std::collections::BTreeMap;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::str::FromStr;
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::thread::JoinHandle;
+use synth_core::compile::{Address, NamespaceCompiler, Shard};
 use synth_core::graph::json::synth_val_to_json;
-use synth_core::{Graph, Namespace, Value};
+use synth_core::graph::IterNode;
+use synth_core::{Content, Graph, Namespace, Value};
 use synth_gen::prelude::*;
 
+/// The number of batches each thread of a [`ParallelSampler`] can get ahead of the exporter.
+const SHARD_BATCH_BUFFER: usize = 4;
+
+/// The most values a collection with a [`SampleSize::PerCollection`] size generates per round.
+const MAX_ROUND_LENGTH: usize = 1000;
+
+/// How many values to generate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SampleSize {
+    /// At least this many values, counted across every collection sampled.
+    Total(usize),
+    /// Exactly this many values for each of the named collections. Collections that are not named
+    /// are generated alongside them with the length given in their schema.
+    PerCollection(BTreeMap<String, usize>),
+}
+
+impl SampleSize {
+    fn total(&self) -> usize {
+        match self {
+            Self::Total(total) => *total,
+            Self::PerCollection(sizes) => sizes.values().sum(),
+        }
+    }
+
+    /// The share of `self` generated by `shard`.
+    fn shard(&self, shard: Shard) -> Self {
+        let split =
+            |size: usize| size / shard.count + usize::from(shard.index < size % shard.count);
+        match self {
+            Self::Total(total) => Self::Total(split(*total)),
+            Self::PerCollection(sizes) => Self::PerCollection(
+                sizes
+                    .iter()
+                    .map(|(name, size)| (name.clone(), split(*size)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl FromStr for SampleSize {
+    type Err = anyhow::Error;
+
+    /// Parses either a single number or a list of sizes like `users=1000,orders=50000`.
+    fn from_str(s: &str) -> Result<Self> {
+        if !s.contains('=') {
+            return Ok(Self::Total(s.trim().parse().with_context(|| {
+                format!("'{s}' is neither a number nor a list of collection sizes")
+            })?));
+        }
+
+        let mut sizes = BTreeMap::new();
+        for entry in s.split(',') {
+            let (name, size) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow!("expected `<collection>=<size>`, found '{}'", entry))?;
+            let size = size
+                .trim()
+                .parse()
+                .with_context(|| format!("invalid size for collection '{}'", name.trim()))?;
+            if sizes.insert(name.trim().to_string(), size).is_some() {
+                return Err(anyhow!(
+                    "the size of collection '{}' is given more than once",
+                    name.trim()
+                ));
+            }
+        }
+        Ok(Self::PerCollection(sizes))
+    }
+}
+
+impl Serialize for SampleSize {
+    // Collection names can be privacy sensitive, so only the total is ever serialized
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.total().serialize(serializer)
+    }
+}
+
+/// When to stop completing the model.
+enum Goal {
+    /// Once at least this many values were generated.
+    Values(usize),
+    /// After exactly this many rounds, in which the `sized` collections are given their lengths.
+    Rounds {
+        rounds: usize,
+        sized: BTreeSet<String>,
+    },
+}
+
 pub(crate) struct Sampler {
     graph: Graph,
+    goal: Goal,
+    total: usize,
 }
 
+/// The values generated for a single collection during one round of sampling. Array collections
+/// carry a `Value::Array` of the elements generated in that round.
+pub(crate) type SampleBatch = (String, Value);
+
 #[derive(Clone)]
 pub(crate) enum SamplerOutput {
     Namespace(Vec<(String, Value)>),
@@ -29,6 +131,82 @@ impl SamplerOutput {
     }
 }
 
+/// A lazy stream of [`SampleBatch`]es. Values are only generated as the stream is consumed, so
+/// exporters can write them out incrementally instead of holding the whole sample in memory.
+pub(crate) struct SampleStream {
+    collection: Option<String>,
+    batches: Box<dyn Iterator<Item = Result<SampleBatch>>>,
+    progress_bar: ProgressBar,
+}
+
+impl SampleStream {
+    /// The collection being sampled, or `None` when sampling the whole namespace.
+    pub(crate) fn collection(&self) -> Option<&str> {
+        self.collection.as_deref()
+    }
+
+    /// Calls `f` on every batch as it passes through the stream.
+    #[cfg(feature = "telemetry")]
+    pub(crate) fn inspect<F>(self, mut f: F) -> Self
+    where
+        F: FnMut(&SampleBatch) + 'static,
+    {
+        Self {
+            collection: self.collection,
+            batches: Box::new(self.batches.inspect(move |batch| {
+                if let Ok(batch) = batch {
+                    f(batch)
+                }
+            })),
+            progress_bar: self.progress_bar,
+        }
+    }
+
+    /// Drains the stream, merging the batches of every collection together. Only use this for
+    /// outputs that cannot be written incrementally.
+    pub(crate) fn collect_output(self) -> Result<SamplerOutput> {
+        match self.collection.clone() {
+            None => {
+                let mut out: Vec<(String, Value)> = Vec::new();
+                for batch in self {
+                    let (collection, value) = batch?;
+                    match out.iter_mut().find(|(name, _)| *name == collection) {
+                        Some((_, existing)) => merge_batch(existing, value),
+                        None => out.push((collection, value)),
+                    }
+                }
+                Ok(SamplerOutput::Namespace(out))
+            }
+            Some(name) => {
+                let mut out = Value::Array(vec![]);
+                for batch in self {
+                    merge_batch(&mut out, batch?.1);
+                }
+                Ok(SamplerOutput::Collection(name, out))
+            }
+        }
+    }
+}
+
+impl Iterator for SampleStream {
+    type Item = Result<SampleBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.batches.next();
+        if next.is_none() {
+            self.progress_bar.finish_and_clear();
+        }
+        next
+    }
+}
+
+fn merge_batch(into: &mut Value, batch: Value) {
+    match (into, batch) {
+        (Value::Array(to_extend), Value::Array(elements)) => to_extend.extend(elements),
+        (into, non_array) => *into = non_array,
+    }
+}
+
 fn sampler_progress_bar(target: u64) -> ProgressBar {
     let bar = ProgressBar::new(target);
     let style = ProgressStyle::default_bar()
@@ -38,160 +216,368 @@ fn sampler_progress_bar(target: u64) -> ProgressBar {
 }
 
 impl Sampler {
-    pub(crate) fn sample_seeded(
-        self,
-        collection_name: Option<String>,
-        target: usize,
-        seed: u64,
-    ) -> Result<SamplerOutput> {
-        let rng = rand::rngs::StdRng::seed_from_u64(seed);
-        let sample_strategy = SampleStrategy::new(collection_name, target);
-        sample_strategy.sample(self.graph, rng)
+    pub(crate) fn new(namespace: &Namespace, size: &SampleSize) -> Result<Self> {
+        Self::new_sharded(namespace, size, Shard::default())
     }
-}
 
-impl TryFrom<&Namespace> for Sampler {
-    type Error = anyhow::Error;
-    fn try_from(namespace: &Namespace) -> Result<Self> {
+    fn new_sharded(namespace: &Namespace, size: &SampleSize, shard: Shard) -> Result<Self> {
+        let (graph, goal) = match size {
+            SampleSize::Total(total) => (
+                Graph::from_namespace_sharded(namespace, shard)?,
+                Goal::Values(*total),
+            ),
+            SampleSize::PerCollection(sizes) => {
+                // Every round generates a slice of each sized collection together with everything
+                // it references, so that references never point outside of what is generated.
+                // Sized collections referred to by others are generated in full by the first
+                // round instead, and stay referenced by every round after it.
+                let referenced = NamespaceCompiler::new(namespace).referenced_collections()?;
+                let rounds = sizes
+                    .values()
+                    .map(|size| size.div_ceil(MAX_ROUND_LENGTH))
+                    .max()
+                    .unwrap_or_default()
+                    .max(1);
+
+                let mut compiler = NamespaceCompiler::new(namespace).with_shard(shard);
+                for (name, &size) in sizes {
+                    match namespace.get_collection(name)? {
+                        Content::Array(array) => {
+                            if let Some((field, ..)) = array.cardinality() {
+                                return Err(anyhow!(
+                                    "cannot set the size of collection '{}': it is given by the cardinality of '{}'",
+                                    name,
+                                    field
+                                ));
+                            }
+                        }
+                        _ => {
+                            return Err(anyhow!(
+                                "cannot set the size of collection '{}': it is not an array",
+                                name
+                            ))
+                        }
+                    }
+
+                    let persistent = referenced.contains(name);
+                    if persistent {
+                        if size == 0 {
+                            return Err(anyhow!(
+                                "cannot generate no values of collection '{}': other collections refer to it{}",
+                                name,
+                                if shard.is_single() {
+                                    ""
+                                } else {
+                                    ", and each thread generates its own share of it"
+                                }
+                            ));
+                        }
+                        compiler = compiler.with_persistent(name);
+                    }
+
+                    let lengths = (0..rounds).map(move |round| {
+                        let length = if persistent {
+                            usize::from(round == 0) * size
+                        } else {
+                            size / rounds + usize::from(round < size % rounds)
+                        };
+                        Value::Number((length as u64).into())
+                    });
+                    compiler = compiler.with_override(
+                        Address::new_root().into_at(name).into_at("length"),
+                        Graph::Iter(IterNode {
+                            iter: Box::new(lengths),
+                        }),
+                    );
+                }
+
+                let graph = compiler.compile().context("cannot compile the namespace")?;
+                let sized = sizes.keys().cloned().collect();
+                (graph, Goal::Rounds { rounds, sized })
+            }
+        };
+
         Ok(Self {
-            graph: Graph::from_namespace(namespace)?,
+            graph,
+            goal,
+            total: size.total(),
         })
     }
-}
 
-enum SampleStrategy {
-    Namespace(NamespaceSampleStrategy),
-    Collection(CollectionSampleStrategy),
-}
+    pub(crate) fn sample_seeded(self, collection_name: Option<String>, seed: u64) -> SampleStream {
+        let progress_bar = sampler_progress_bar(self.total as u64);
+        let rounds = self.into_rounds(collection_name.clone(), seed, progress_bar.clone());
 
-impl SampleStrategy {
-    fn new(collection_name: Option<String>, target: usize) -> Self {
-        match collection_name {
-            None => SampleStrategy::Namespace(NamespaceSampleStrategy { target }),
-            Some(name) => SampleStrategy::Collection(CollectionSampleStrategy { name, target }),
+        SampleStream {
+            collection: collection_name,
+            batches: Box::new(rounds),
+            progress_bar,
         }
     }
 
-    fn sample<R: Rng>(self, model: Graph, rng: R) -> Result<SamplerOutput> {
-        match self {
-            SampleStrategy::Namespace(nss) => Ok(SamplerOutput::Namespace(nss.sample(model, rng)?)),
-            SampleStrategy::Collection(css) => Ok(SamplerOutput::Collection(
-                css.name.clone(),
-                css.sample(model, rng)?,
-            )),
+    fn into_rounds(
+        self,
+        collection: Option<String>,
+        seed: u64,
+        progress_bar: ProgressBar,
+    ) -> SampleRounds {
+        let ordered: Vec<_> = self
+            .graph
+            .iter_ordered()
+            .map(|iter| iter.map(|s| s.to_string()).collect())
+            .unwrap_or_default();
+
+        SampleRounds {
+            model: self.graph.aggregate(),
+            rng: StdRng::seed_from_u64(seed),
+            collection,
+            ordered,
+            goal: self.goal,
+            rounds: 0,
+            generated: 0,
+            pending: VecDeque::new(),
+            progress_bar,
+            exhausted: false,
         }
     }
 }
 
-struct NamespaceSampleStrategy {
-    target: usize,
+/// Samples a namespace on several threads at once.
+///
+/// Every thread compiles its own [`Shard`] of the namespace, so that ids and unique values never
+/// collide between threads, and is seeded from the user's seed. Batches are merged in a fixed
+/// round-robin order over the shards, so the output only depends on the seed and the number of
+/// threads.
+pub(crate) struct ParallelSampler {
+    namespace: Namespace,
+    size: SampleSize,
+    threads: usize,
 }
 
-impl NamespaceSampleStrategy {
-    fn sample<R: Rng>(self, model: Graph, mut rng: R) -> Result<Vec<(String, Value)>> {
-        let mut generated = 0;
-        let mut out = BTreeMap::<String, Value>::new();
-        let progress_bar = sampler_progress_bar(self.target as u64);
+impl ParallelSampler {
+    pub(crate) fn new(namespace: &Namespace, size: &SampleSize, threads: usize) -> Result<Self> {
+        if threads == 0 {
+            return Err(anyhow!("the number of threads must be at least 1"));
+        }
 
-        let ordered: Vec<_> = model
-            .iter_ordered()
-            .map(|iter| iter.map(|s| s.to_string()).collect())
-            .unwrap_or_default();
+        // Surface compilation errors before any thread is started
+        let shard = Shard::new(0, threads)?;
+        Sampler::new_sharded(namespace, &size.shard(shard), shard)?;
 
-        let mut model = model.aggregate();
-
-        while generated < self.target {
-            // We populate `out` by walking through the collections in the generated
-            // namespace. We also keep track of the number of `Values` generated
-            // for the progress bar.
-            let round_start = generated;
-            let next = model.complete(&mut rng)?;
-            as_object(next)?
-                .into_iter()
-                .for_each(|(collection, value)| match value {
-                    Value::Array(elements) => {
-                        generated += elements.len();
-
-                        let entry = out
-                            .entry(collection)
-                            .or_insert_with(|| Value::Array(vec![]));
-
-                        if let Value::Array(to_extend) = entry {
-                            to_extend.extend(elements);
-                        }
-                    }
-                    non_array => {
-                        generated += 1;
-                        //out[&collection] = non_array;
-                        out.insert(collection, non_array);
-                    }
-                });
-            progress_bar.set_position(generated as u64);
-            if round_start == generated {
-                warn!("could not generate {} values: try modifying the schema to generate more data instead of the --size flag", self.target);
-                break;
-            }
-        }
+        Ok(Self {
+            namespace: namespace.clone(),
+            size: size.clone(),
+            threads,
+        })
+    }
+
+    pub(crate) fn sample_seeded(
+        self,
+        collection_name: Option<String>,
+        seed: u64,
+    ) -> Result<SampleStream> {
+        let progress_bar = sampler_progress_bar(self.size.total() as u64);
 
-        progress_bar.finish_and_clear();
+        let mut seeder = StdRng::seed_from_u64(seed);
+        let shards = (0..self.threads)
+            .map(|index| {
+                let shard = Shard::new(index, self.threads)?;
+                let shard_size = self.size.shard(shard);
+                let shard_seed = seeder.next_u64();
 
-        let mut ordered_out = Vec::new();
+                let namespace = self.namespace.clone();
+                let collection_name = collection_name.clone();
+                let progress_bar = progress_bar.clone();
+                let (sender, receiver) = sync_channel(SHARD_BATCH_BUFFER);
 
-        for name in ordered {
-            let value = out.remove(&name).unwrap();
-            ordered_out.push((name, value));
-        }
+                let handle = std::thread::Builder::new()
+                    .name(format!("sampler-{index}"))
+                    .spawn(move || {
+                        let sampler = match Sampler::new_sharded(&namespace, &shard_size, shard) {
+                            Ok(sampler) => sampler,
+                            Err(err) => {
+                                let _ = sender.send(Err(err));
+                                return;
+                            }
+                        };
+                        for batch in sampler.into_rounds(collection_name, shard_seed, progress_bar)
+                        {
+                            // The receiving end is gone when the export was interrupted
+                            if sender.send(batch).is_err() {
+                                return;
+                            }
+                        }
+                    })?;
 
-        ordered_out.extend(out);
+                Ok(ShardReceiver {
+                    index,
+                    receiver,
+                    handle: Some(handle),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-        Ok(ordered_out)
+        Ok(SampleStream {
+            collection: collection_name,
+            batches: Box::new(ShardedBatches { shards, next: 0 }),
+            progress_bar,
+        })
     }
 }
 
-struct CollectionSampleStrategy {
-    name: String,
-    target: usize,
+struct ShardReceiver {
+    index: usize,
+    receiver: Receiver<Result<SampleBatch>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+/// Merges the batches of every shard, taking one batch from each shard in turn.
+struct ShardedBatches {
+    shards: Vec<ShardReceiver>,
+    next: usize,
 }
 
-impl CollectionSampleStrategy {
-    fn sample<R: Rng>(self, model: Graph, mut rng: R) -> Result<Value> {
-        let mut out = Value::Array(vec![]);
-        let mut generated = 0;
-        let progress_bar = sampler_progress_bar(self.target as u64);
+impl Iterator for ShardedBatches {
+    type Item = Result<SampleBatch>;
 
-        let mut model = model.aggregate();
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.shards.is_empty() {
+            let position = self.next % self.shards.len();
+            let shard = &mut self.shards[position];
 
-        while generated < self.target {
-            let round_start = generated;
-            let next = model.complete(&mut rng)?;
-            let collection_value = as_object(next)?.remove(&self.name).ok_or_else(|| {
-                anyhow!(
-                    "generated namespace does not have a collection '{}'",
-                    self.name
-                )
-            })?;
-            match collection_value {
-                Value::Array(vec) => {
-                    generated += vec.len();
-                    if let Value::Array(to_extend) = &mut out {
-                        to_extend.extend(vec);
+            match shard.receiver.recv() {
+                Ok(batch) => {
+                    self.next = position + 1;
+                    return Some(batch);
+                }
+                Err(_) => {
+                    // The shard is done: only a panic would have cut it short
+                    let panicked = shard
+                        .handle
+                        .take()
+                        .map(|handle| handle.join().is_err())
+                        .unwrap_or_default();
+                    let index = shard.index;
+                    self.shards.remove(position);
+                    self.next = position;
+                    if panicked {
+                        self.shards.clear();
+                        return Some(Err(anyhow!(
+                            "the sampler thread for shard {} panicked",
+                            index
+                        )));
                     }
                 }
-                non_array => {
-                    generated += 1;
-                    out = non_array;
+            }
+        }
+        None
+    }
+}
+
+/// Completes the model one round at a time until its [`Goal`] is reached, yielding the
+/// collections of each round in dependency order.
+struct SampleRounds {
+    model: Aggregate<Graph>,
+    rng: StdRng,
+    collection: Option<String>,
+    ordered: Vec<String>,
+    goal: Goal,
+    rounds: usize,
+    generated: usize,
+    pending: VecDeque<SampleBatch>,
+    progress_bar: ProgressBar,
+    exhausted: bool,
+}
+
+impl SampleRounds {
+    fn next_round(&mut self) -> Result<()> {
+        let round_start = self.generated;
+        let mut next = as_object(self.model.complete(&mut self.rng)?)?;
+        self.rounds += 1;
+
+        let batches = match &self.collection {
+            None => {
+                // Collections that take part in the dependency order come first, in that order,
+                // followed by any others.
+                let mut batches = Vec::new();
+                for name in &self.ordered {
+                    if let Some(value) = next.remove(name) {
+                        batches.push((name.clone(), value));
+                    }
                 }
+                batches.extend(next);
+                batches
+            }
+            Some(name) => {
+                let value = next.remove(name).ok_or_else(|| {
+                    anyhow!("generated namespace does not have a collection '{}'", name)
+                })?;
+                vec![(name.clone(), value)]
             }
-            progress_bar.set_position(generated as u64);
-            if round_start == generated {
-                warn!("could not generate {} values for collection {}: try modifying the schema to generate more instead of using the --size flag", self.target, self.name);
-                break;
+        };
+
+        for (name, value) in batches {
+            self.push(name, value);
+        }
+
+        self.progress_bar.inc((self.generated - round_start) as u64);
+        if let Goal::Values(target) = self.goal {
+            if round_start == self.generated {
+                match &self.collection {
+                    None => warn!("could not generate {} values: try modifying the schema to generate more data instead of the --size flag", target),
+                    Some(name) => warn!("could not generate {} values for collection {}: try modifying the schema to generate more instead of using the --size flag", target, name),
+                }
+                self.exhausted = true;
             }
         }
 
-        progress_bar.finish_and_clear();
+        Ok(())
+    }
+
+    fn push(&mut self, collection: String, value: Value) {
+        let counted = match &self.goal {
+            Goal::Values(_) => true,
+            Goal::Rounds { sized, .. } => sized.contains(&collection),
+        };
+        if counted {
+            self.generated += count_values(&value);
+        }
+        self.pending.push_back((collection, value));
+    }
+
+    fn is_done(&self) -> bool {
+        match &self.goal {
+            Goal::Values(target) => self.exhausted || self.generated >= *target,
+            Goal::Rounds { rounds, .. } => self.exhausted || self.rounds >= *rounds,
+        }
+    }
+}
+
+impl Iterator for SampleRounds {
+    type Item = Result<SampleBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(batch) = self.pending.pop_front() {
+                return Some(Ok(batch));
+            }
+
+            if self.is_done() {
+                return None;
+            }
+
+            if let Err(err) = self.next_round() {
+                self.exhausted = true;
+                return Some(Err(err));
+            }
+        }
+    }
+}
 
-        Ok(out)
+fn count_values(value: &Value) -> usize {
+    match value {
+        Value::Array(elements) => elements.len(),
+        _ => 1,
     }
 }
 
@@ -204,3 +590,274 @@ fn as_object(sample: Value) -> Result<BTreeMap<String, Value>> {
         )),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn namespace() -> Namespace {
+        serde_json::from_value::<synth_core::Content>(serde_json::json!({
+            "type": "object",
+            "users": {
+                "type": "array",
+                "length": 2,
+                "content": {
+                    "type": "number",
+                    "subtype": "u64",
+                    "range": { "low": 0, "high": 1000, "step": 1 }
+                }
+            }
+        }))
+        .unwrap()
+        .into_namespace()
+        .unwrap()
+    }
+
+    #[test]
+    fn sample_stream_yields_one_batch_per_round() {
+        let batches = Sampler::new(&namespace(), &SampleSize::Total(5))
+            .unwrap()
+            .sample_seeded(None, 0)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(batches.len(), 3);
+        assert!(batches
+            .iter()
+            .all(|(name, value)| name == "users" && count_values(value) == 2));
+    }
+
+    #[test]
+    fn parallel_sampler_is_deterministic() {
+        let ns: Namespace = serde_json::from_value::<synth_core::Content>(serde_json::json!({
+            "type": "object",
+            "users": {
+                "type": "array",
+                "length": 3,
+                "content": {
+                    "type": "object",
+                    "id": { "type": "number", "subtype": "u64", "id": {} },
+                    "name": { "type": "string", "unique": true, "pattern": "[a-z]{2}" }
+                }
+            }
+        }))
+        .unwrap()
+        .into_namespace()
+        .unwrap();
+
+        let sample = || {
+            ParallelSampler::new(&ns, &SampleSize::Total(100), 4)
+                .unwrap()
+                .sample_seeded(None, 7)
+                .unwrap()
+                .collect_output()
+                .unwrap()
+                .into_json()
+        };
+
+        let output = sample();
+        assert_eq!(output, sample());
+
+        let users = output["users"].as_array().unwrap();
+        assert!(users.len() >= 100);
+        for field in ["id", "name"] {
+            let distinct: std::collections::HashSet<_> =
+                users.iter().map(|user| user[field].to_string()).collect();
+            assert_eq!(distinct.len(), users.len());
+        }
+    }
+
+    #[test]
+    fn sample_stream_collects_to_same_values() {
+        let sampler = || Sampler::new(&namespace(), &SampleSize::Total(5)).unwrap();
+
+        let streamed: Vec<Value> = sampler()
+            .sample_seeded(Some("users".to_string()), 0)
+            .flat_map(|batch| match batch.unwrap().1 {
+                Value::Array(elements) => elements,
+                _ => unreachable!(),
+            })
+            .collect();
+
+        match sampler()
+            .sample_seeded(Some("users".to_string()), 0)
+            .collect_output()
+            .unwrap()
+        {
+            SamplerOutput::Collection(name, Value::Array(collected)) => {
+                assert_eq!(name, "users");
+                assert_eq!(collected, streamed);
+            }
+            _ => panic!("expected a collection output"),
+        }
+    }
+
+    #[test]
+    fn per_collection_sizes_are_exact() {
+        let ns: Namespace = serde_json::from_value::<synth_core::Content>(serde_json::json!({
+            "type": "object",
+            "users": {
+                "type": "array",
+                "length": 3,
+                "content": {
+                    "type": "object",
+                    "id": { "type": "number", "subtype": "u64", "id": {} }
+                }
+            },
+            "orders": {
+                "type": "array",
+                "length": 1,
+                "content": {
+                    "type": "object",
+                    "user_id": { "type": "same_as", "ref": "users.content.id" }
+                }
+            }
+        }))
+        .unwrap()
+        .into_namespace()
+        .unwrap();
+
+        let size: SampleSize = "users=1500,orders=2345".parse().unwrap();
+        for threads in [1, 3] {
+            let output = ParallelSampler::new(&ns, &size, threads)
+                .unwrap()
+                .sample_seeded(None, 0)
+                .unwrap()
+                .collect_output()
+                .unwrap()
+                .into_json();
+
+            let users = output["users"].as_array().unwrap();
+            let orders = output["orders"].as_array().unwrap();
+            assert_eq!(users.len(), 1500);
+            assert_eq!(orders.len(), 2345);
+
+            let ids: std::collections::HashSet<_> =
+                users.iter().map(|user| user["id"].to_string()).collect();
+            assert!(orders
+                .iter()
+                .all(|order| ids.contains(&order["user_id"].to_string())));
+        }
+    }
+
+    #[test]
+    fn unique_references_are_kept_by_every_shard() {
+        let ns: Namespace = serde_json::from_value::<synth_core::Content>(serde_json::json!({
+            "type": "object",
+            "users": {
+                "type": "array",
+                "length": 1,
+                "content": {
+                    "type": "object",
+                    "id": { "type": "number", "subtype": "u64", "id": {} }
+                }
+            },
+            "profiles": {
+                "type": "array",
+                "length": 1,
+                "content": {
+                    "type": "object",
+                    "user_id": {
+                        "type": "unique",
+                        "content": { "type": "same_as", "ref": "users.content.id" }
+                    }
+                }
+            }
+        }))
+        .unwrap()
+        .into_namespace()
+        .unwrap();
+
+        let size: SampleSize = "users=400,profiles=200".parse().unwrap();
+        for threads in [1, 4] {
+            let output = ParallelSampler::new(&ns, &size, threads)
+                .unwrap()
+                .sample_seeded(None, 0)
+                .unwrap()
+                .collect_output()
+                .unwrap()
+                .into_json();
+
+            let ids: std::collections::HashSet<_> = output["users"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|user| user["id"].to_string())
+                .collect();
+            let user_ids: std::collections::HashSet<_> = output["profiles"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|profile| profile["user_id"].to_string())
+                .collect();
+            assert_eq!(user_ids.len(), 200);
+            assert!(user_ids.is_subset(&ids));
+        }
+    }
+
+    #[test]
+    fn small_referenced_collections_are_kept_for_every_round() {
+        let ns: Namespace = serde_json::from_value::<synth_core::Content>(serde_json::json!({
+            "type": "object",
+            "warehouses": {
+                "type": "array",
+                "length": 1,
+                "content": {
+                    "type": "object",
+                    "id": { "type": "number", "subtype": "u64", "id": {} }
+                }
+            },
+            "orders": {
+                "type": "array",
+                "length": 1,
+                "content": {
+                    "type": "object",
+                    "warehouse_id": { "type": "same_as", "ref": "warehouses.content.id" }
+                }
+            }
+        }))
+        .unwrap()
+        .into_namespace()
+        .unwrap();
+
+        let size: SampleSize = "orders=5000,warehouses=10".parse().unwrap();
+        for threads in [1, 3] {
+            let output = ParallelSampler::new(&ns, &size, threads)
+                .unwrap()
+                .sample_seeded(None, 0)
+                .unwrap()
+                .collect_output()
+                .unwrap()
+                .into_json();
+
+            let warehouses = output["warehouses"].as_array().unwrap();
+            let orders = output["orders"].as_array().unwrap();
+            assert_eq!(warehouses.len(), 10);
+            assert_eq!(orders.len(), 5000);
+
+            let ids: std::collections::HashSet<_> = warehouses
+                .iter()
+                .map(|warehouse| warehouse["id"].to_string())
+                .collect();
+            assert!(orders
+                .iter()
+                .all(|order| ids.contains(&order["warehouse_id"].to_string())));
+        }
+
+        let size: SampleSize = "orders=5000,warehouses=2".parse().unwrap();
+        // One of the threads would have no warehouse to refer to
+        assert!(ParallelSampler::new(&ns, &size, 3)
+            .unwrap()
+            .sample_seeded(None, 0)
+            .unwrap()
+            .collect_output()
+            .is_err());
+    }
+
+    #[test]
+    fn sample_size_from_str() {
+        assert!(matches!("10".parse(), Ok(SampleSize::Total(10))));
+        assert!("users=1,users=2".parse::<SampleSize>().is_err());
+        assert!("users=".parse::<SampleSize>().is_err());
+    }
+}
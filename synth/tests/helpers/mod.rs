@@ -1,5 +1,6 @@
 use anyhow::Result;
 use synth::cli::{self, GenerateCommand};
+use synth::sampler::SampleSize;
 
 /// Helper to capture and return any output for generate on a namespace
 pub async fn generate(namespace: &str) -> Result<String> {
@@ -12,9 +13,10 @@ pub async fn generate_scenario(namespace: &str, scenario: Option<String>) -> Res
         collection: None,
         scenario,
         random: false,
+        threads: 1,
         schema: None,
         seed: Some(5),
-        size: 10,
+        size: SampleSize::Total(10),
         to: "json:".to_string(),
     }))
     .await